@@ -0,0 +1,111 @@
+use axum::http::{request::Parts, HeaderValue};
+use reqwest::Url;
+use std::time::Duration;
+use tokio::sync::watch;
+use tower_http::cors::{AllowHeaders, AllowMethods, AllowOrigin, CorsLayer};
+use wot_config::{ConfigError, Settings};
+
+// Browser origins allowed to call the API, from `CORS_ALLOWED_ORIGINS`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum CorsOrigins {
+    // No cross-origin access; the default, as the frontend calls the API from its server
+    #[default]
+    None,
+    Any,
+    // Serialized origins such as `https://app.wot.id`
+    List(Vec<String>),
+}
+
+impl CorsOrigins {
+    // Comma-separated origins, or `*` for any
+    pub fn from_settings(settings: &Settings) -> Result<Self, ConfigError> {
+        let entries = settings.list("CORS_ALLOWED_ORIGINS");
+        if entries.is_empty() {
+            return Ok(CorsOrigins::None);
+        }
+        if entries.iter().any(|entry| entry == "*") {
+            return Ok(CorsOrigins::Any);
+        }
+        entries
+            .into_iter()
+            .map(|entry| {
+                let invalid = |reason: &str| ConfigError::Invalid {
+                    key: "CORS_ALLOWED_ORIGINS".to_string(),
+                    value: entry.clone(),
+                    reason: reason.to_string(),
+                };
+                let url = Url::parse(&entry).map_err(|e| invalid(&e.to_string()))?;
+                if !matches!(url.scheme(), "http" | "https") || url.path() != "/" || url.query().is_some() {
+                    return Err(invalid("expected an origin such as https://app.example.org"));
+                }
+                Ok(url.origin().ascii_serialization())
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(CorsOrigins::List)
+    }
+
+    fn allows(&self, origin: &HeaderValue) -> bool {
+        match self {
+            CorsOrigins::None => false,
+            CorsOrigins::Any => true,
+            CorsOrigins::List(origins) => origin.to_str().is_ok_and(|origin| origins.iter().any(|o| o == origin)),
+        }
+    }
+}
+
+// CORS for the whole API; consults the current origins on every request so a reload applies at once
+pub fn layer(origins: watch::Receiver<CorsOrigins>) -> CorsLayer {
+    CorsLayer::new()
+        .allow_origin(AllowOrigin::predicate(move |origin: &HeaderValue, _: &Parts| origins.borrow().allows(origin)))
+        .allow_methods(AllowMethods::mirror_request())
+        .allow_headers(AllowHeaders::mirror_request())
+        .max_age(Duration::from_secs(600))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::StatusCode, routing::get, Router};
+    use std::collections::HashMap;
+
+    fn origins(value: &str) -> Result<CorsOrigins, ConfigError> {
+        let vars = HashMap::from([("CORS_ALLOWED_ORIGINS".to_string(), value.to_string())]);
+        CorsOrigins::from_settings(&Settings::from_parts(Vec::new(), vars).unwrap())
+    }
+
+    #[test]
+    fn origins_are_parsed_and_normalized() {
+        assert_eq!(origins("").unwrap(), CorsOrigins::None);
+        assert_eq!(origins("https://a.example, *").unwrap(), CorsOrigins::Any);
+        assert_eq!(
+            origins("https://App.Example:443/, http://localhost:3000").unwrap(),
+            CorsOrigins::List(vec!["https://app.example".to_string(), "http://localhost:3000".to_string()])
+        );
+        for invalid in ["app.example", "ftp://app.example", "https://app.example/path", "https://app.example/?q=1"] {
+            assert!(matches!(origins(invalid), Err(ConfigError::Invalid { .. })), "{}", invalid);
+        }
+    }
+
+    // The layer reads the origins on every request, so a SIGHUP reload needs no restart
+    #[tokio::test]
+    async fn reloaded_origins_apply_to_the_next_request() {
+        let (sender, receiver) = watch::channel(CorsOrigins::None);
+        let app = Router::new().route("/", get(|| async { "ok" })).layer(layer(receiver));
+        let url = crate::testing::serve(app).await;
+        let allowed_origin = |origin: &'static str| {
+            let request = reqwest::Client::new().get(&url).header("Origin", origin);
+            async move {
+                let resp = request.send().await.unwrap();
+                assert_eq!(resp.status(), StatusCode::OK);
+                resp.headers().get("access-control-allow-origin").map(|v| v.to_str().unwrap().to_string())
+            }
+        };
+
+        assert_eq!(allowed_origin("https://app.wot.id").await, None);
+        sender.send_replace(CorsOrigins::List(vec!["https://app.wot.id".to_string()]));
+        assert_eq!(allowed_origin("https://app.wot.id").await.as_deref(), Some("https://app.wot.id"));
+        assert_eq!(allowed_origin("https://evil.example").await, None);
+        sender.send_replace(CorsOrigins::Any);
+        assert_eq!(allowed_origin("https://evil.example").await.as_deref(), Some("https://evil.example"));
+    }
+}
@@ -0,0 +1,385 @@
+use aes::cipher::{block_padding::Pkcs7, BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use aes::Aes256;
+use aes_kw::KekAes256;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD as BASE64URL, Engine};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::OsRng;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha512};
+use utoipa::ToSchema;
+use x25519_dalek::{PublicKey, StaticSecret};
+
+use super::DidCommError;
+
+pub const TYP_ENCRYPTED: &str = "application/didcomm-encrypted+json";
+pub const ALG_AUTHCRYPT: &str = "ECDH-1PU+A256KW";
+pub const ALG_ANONCRYPT: &str = "ECDH-ES+A256KW";
+pub const ENC_A256CBC_HS512: &str = "A256CBC-HS512";
+
+// X25519 key-agreement public key of a DID, identified by its verification method ID
+#[derive(Debug, Clone)]
+pub struct X25519Key {
+    pub kid: String,
+    pub public_key: PublicKey,
+}
+
+// X25519 key-agreement secret held by this service (the mediator's own key)
+pub struct LocalSecret {
+    pub kid: String,
+    secret: StaticSecret,
+}
+
+impl LocalSecret {
+    pub fn from_base64url(kid: String, secret: &str) -> Option<Self> {
+        let bytes: [u8; 32] = BASE64URL.decode(secret).ok()?.try_into().ok()?;
+        Some(Self { kid, secret: StaticSecret::from(bytes) })
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey::from(&self.secret)
+    }
+}
+
+// JWE in general JSON serialization, as DIDComm v2 requires for multiple recipients
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Jwe {
+    pub protected: String,
+    pub recipients: Vec<JweRecipient>,
+    pub iv: String,
+    pub ciphertext: String,
+    pub tag: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct JweRecipient {
+    pub header: RecipientHeader,
+    pub encrypted_key: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RecipientHeader {
+    pub kid: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProtectedHeader {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub typ: Option<String>,
+    pub alg: String,
+    pub enc: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub apu: Option<String>,
+    pub apv: String,
+    pub epk: EphemeralKey,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EphemeralKey {
+    pub kty: String,
+    pub crv: String,
+    pub x: String,
+}
+
+impl Jwe {
+    pub fn protected_header(&self) -> Result<ProtectedHeader, DidCommError> {
+        let bytes = BASE64URL
+            .decode(&self.protected)
+            .map_err(|e| DidCommError::Malformed(format!("protected header: {}", e)))?;
+        serde_json::from_slice(&bytes).map_err(|e| DidCommError::Malformed(format!("protected header: {}", e)))
+    }
+}
+
+// Anonymous encryption: recipients learn nothing about the sender
+pub fn pack_anoncrypt(plaintext: &[u8], recipients: &[X25519Key]) -> Result<Jwe, DidCommError> {
+    pack(plaintext, None, recipients)
+}
+
+// Authenticated encryption: recipients can tell the message came from `sender`'s key
+pub fn pack_authcrypt(plaintext: &[u8], sender: &LocalSecret, recipients: &[X25519Key]) -> Result<Jwe, DidCommError> {
+    pack(plaintext, Some(sender), recipients)
+}
+
+fn pack(plaintext: &[u8], sender: Option<&LocalSecret>, recipients: &[X25519Key]) -> Result<Jwe, DidCommError> {
+    if recipients.is_empty() {
+        return Err(DidCommError::NoRecipientKeys("message has no recipients".to_string()));
+    }
+    let ephemeral = StaticSecret::random_from_rng(OsRng);
+    let mut cek = [0u8; 64];
+    OsRng.fill_bytes(&mut cek);
+    let mut iv = [0u8; 16];
+    OsRng.fill_bytes(&mut iv);
+
+    let apv = recipients_digest(recipients.iter().map(|r| r.kid.as_str()));
+    let header = ProtectedHeader {
+        typ: Some(TYP_ENCRYPTED.to_string()),
+        alg: if sender.is_some() { ALG_AUTHCRYPT } else { ALG_ANONCRYPT }.to_string(),
+        enc: ENC_A256CBC_HS512.to_string(),
+        skid: sender.map(|s| s.kid.clone()),
+        apu: sender.map(|s| BASE64URL.encode(&s.kid)),
+        apv: BASE64URL.encode(&apv),
+        epk: EphemeralKey {
+            kty: "OKP".to_string(),
+            crv: "X25519".to_string(),
+            x: BASE64URL.encode(PublicKey::from(&ephemeral).as_bytes()),
+        },
+    };
+    let protected = BASE64URL.encode(serde_json::to_vec(&header).expect("header serializes to JSON"));
+
+    let (ciphertext, tag) = a256cbc_hs512_encrypt(&cek, &iv, protected.as_bytes(), plaintext);
+
+    let apu = sender.map(|s| s.kid.as_bytes().to_vec()).unwrap_or_default();
+    let recipients = recipients
+        .iter()
+        .map(|recipient| {
+            let mut z = ephemeral.diffie_hellman(&recipient.public_key).as_bytes().to_vec();
+            // ECDH-1PU: Z = Ze || Zs, and the KDF also commits to the content tag
+            let cc_tag = match sender {
+                Some(sender) => {
+                    z.extend_from_slice(sender.secret.diffie_hellman(&recipient.public_key).as_bytes());
+                    Some(tag.as_slice())
+                }
+                None => None,
+            };
+            let kek = concat_kdf::<32>(&z, &header.alg, &apu, &apv, cc_tag);
+            let encrypted_key = KekAes256::from(kek)
+                .wrap_vec(&cek)
+                .map_err(|e| DidCommError::Crypto(format!("key wrap failed: {}", e)))?;
+            Ok(JweRecipient {
+                header: RecipientHeader { kid: recipient.kid.clone() },
+                encrypted_key: BASE64URL.encode(encrypted_key),
+            })
+        })
+        .collect::<Result<Vec<_>, DidCommError>>()?;
+
+    Ok(Jwe {
+        protected,
+        recipients,
+        iv: BASE64URL.encode(iv),
+        ciphertext: BASE64URL.encode(ciphertext),
+        tag: BASE64URL.encode(tag),
+    })
+}
+
+// Decrypt a JWE addressed to `recipient`. Authcrypt messages need the sender's public key
+// (resolved from `skid`); anoncrypt messages take `None`.
+pub fn unpack(jwe: &Jwe, recipient: &LocalSecret, sender: Option<&X25519Key>) -> Result<Vec<u8>, DidCommError> {
+    let header = jwe.protected_header()?;
+    if header.enc != ENC_A256CBC_HS512 {
+        return Err(DidCommError::Unsupported(format!("content encryption {}", header.enc)));
+    }
+    let authenticated = match header.alg.as_str() {
+        ALG_AUTHCRYPT => true,
+        ALG_ANONCRYPT => false,
+        other => return Err(DidCommError::Unsupported(format!("key agreement {}", other))),
+    };
+    if header.epk.crv != "X25519" {
+        return Err(DidCommError::Unsupported(format!("curve {}", header.epk.crv)));
+    }
+
+    let entry = jwe
+        .recipients
+        .iter()
+        .find(|r| r.header.kid == recipient.kid)
+        .ok_or_else(|| DidCommError::NotARecipient(recipient.kid.clone()))?;
+
+    let apv = recipients_digest(jwe.recipients.iter().map(|r| r.header.kid.as_str()));
+    if decode(&header.apv, "apv")? != apv {
+        return Err(DidCommError::Malformed("apv does not match the recipient list".to_string()));
+    }
+
+    let epk: [u8; 32] = decode(&header.epk.x, "epk")?
+        .try_into()
+        .map_err(|_| DidCommError::Malformed("epk is not an X25519 key".to_string()))?;
+    let mut z = recipient.secret.diffie_hellman(&PublicKey::from(epk)).as_bytes().to_vec();
+    let tag = decode(&jwe.tag, "tag")?;
+
+    let apu = if authenticated {
+        let sender = sender.ok_or_else(|| DidCommError::UnknownSender("sender key not resolved".to_string()))?;
+        if header.skid.as_deref() != Some(sender.kid.as_str()) {
+            return Err(DidCommError::UnknownSender(format!("skid does not match {}", sender.kid)));
+        }
+        let apu = decode(header.apu.as_deref().unwrap_or_default(), "apu")?;
+        if apu != sender.kid.as_bytes() {
+            return Err(DidCommError::Malformed("apu does not match skid".to_string()));
+        }
+        z.extend_from_slice(recipient.secret.diffie_hellman(&sender.public_key).as_bytes());
+        apu
+    } else {
+        Vec::new()
+    };
+
+    let kek = concat_kdf::<32>(&z, &header.alg, &apu, &apv, authenticated.then_some(tag.as_slice()));
+    let cek: [u8; 64] = KekAes256::from(kek)
+        .unwrap_vec(&decode(&entry.encrypted_key, "encrypted_key")?)
+        .map_err(|_| DidCommError::Crypto("key unwrap failed".to_string()))?
+        .try_into()
+        .map_err(|_| DidCommError::Crypto("content key has the wrong length".to_string()))?;
+    let iv: [u8; 16] = decode(&jwe.iv, "iv")?
+        .try_into()
+        .map_err(|_| DidCommError::Malformed("iv must be 16 bytes".to_string()))?;
+
+    a256cbc_hs512_decrypt(&cek, &iv, jwe.protected.as_bytes(), &decode(&jwe.ciphertext, "ciphertext")?, &tag)
+}
+
+fn decode(value: &str, field: &str) -> Result<Vec<u8>, DidCommError> {
+    BASE64URL.decode(value).map_err(|e| DidCommError::Malformed(format!("{}: {}", field, e)))
+}
+
+// apv: SHA-256 over the sorted recipient kids joined with '.'
+fn recipients_digest<'a>(kids: impl Iterator<Item = &'a str>) -> Vec<u8> {
+    let mut kids: Vec<&str> = kids.collect();
+    kids.sort_unstable();
+    Sha256::digest(kids.join(".").as_bytes()).to_vec()
+}
+
+// Concat KDF (NIST SP 800-56A) with SHA-256 for an N-byte key, one hash round so N <= 32.
+// A256KW takes N = 32. ECDH-1PU in key wrapping mode appends the content tag to SuppPubInfo.
+fn concat_kdf<const N: usize>(z: &[u8], alg: &str, apu: &[u8], apv: &[u8], cc_tag: Option<&[u8]>) -> [u8; N] {
+    const { assert!(N <= 32) };
+    let mut hasher = Sha256::new();
+    hasher.update(1u32.to_be_bytes());
+    hasher.update(z);
+    for field in [alg.as_bytes(), apu, apv] {
+        hasher.update((field.len() as u32).to_be_bytes());
+        hasher.update(field);
+    }
+    hasher.update(((N * 8) as u32).to_be_bytes());
+    if let Some(tag) = cc_tag {
+        hasher.update((tag.len() as u32).to_be_bytes());
+        hasher.update(tag);
+    }
+    hasher.finalize()[..N].try_into().expect("N is at most the SHA-256 output size")
+}
+
+// AES_256_CBC_HMAC_SHA_512 (RFC 7518 section 5.2.5)
+fn a256cbc_hs512_encrypt(cek: &[u8; 64], iv: &[u8; 16], aad: &[u8], plaintext: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let (mac_key, enc_key) = cek.split_at(32);
+    let ciphertext = cbc::Encryptor::<Aes256>::new(enc_key.into(), iv.into()).encrypt_padded_vec_mut::<Pkcs7>(plaintext);
+    let tag = cbc_hs512_mac(mac_key, aad, iv, &ciphertext).finalize().into_bytes()[..32].to_vec();
+    (ciphertext, tag)
+}
+
+fn a256cbc_hs512_decrypt(
+    cek: &[u8; 64],
+    iv: &[u8; 16],
+    aad: &[u8],
+    ciphertext: &[u8],
+    tag: &[u8],
+) -> Result<Vec<u8>, DidCommError> {
+    let (mac_key, enc_key) = cek.split_at(32);
+    if tag.len() != 32 {
+        return Err(DidCommError::Crypto("authentication tag must be 32 bytes".to_string()));
+    }
+    cbc_hs512_mac(mac_key, aad, iv, ciphertext)
+        .verify_truncated_left(tag)
+        .map_err(|_| DidCommError::Crypto("authentication tag mismatch".to_string()))?;
+    cbc::Decryptor::<Aes256>::new(enc_key.into(), iv.into())
+        .decrypt_padded_vec_mut::<Pkcs7>(ciphertext)
+        .map_err(|_| DidCommError::Crypto("invalid padding".to_string()))
+}
+
+fn cbc_hs512_mac(mac_key: &[u8], aad: &[u8], iv: &[u8], ciphertext: &[u8]) -> Hmac<Sha512> {
+    let mut mac = Hmac::<Sha512>::new_from_slice(mac_key).expect("HMAC accepts keys of any length");
+    mac.update(aad);
+    mac.update(iv);
+    mac.update(ciphertext);
+    mac.update(&((aad.len() as u64) * 8).to_be_bytes());
+    mac
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aes_kw::KekAes128;
+
+    fn b64(value: &str) -> Vec<u8> {
+        BASE64URL.decode(value).unwrap()
+    }
+
+    // RFC 7518 Appendix B.3
+    #[test]
+    fn a256cbc_hs512_matches_rfc7518() {
+        let cek: [u8; 64] = std::array::from_fn(|i| i as u8);
+        let iv: [u8; 16] = hex::decode("1af38c2dc2b96ffdd86694092341bc04").unwrap().try_into().unwrap();
+        let aad = b"The second principle of Auguste Kerckhoffs";
+        let plaintext = b"A cipher system must not be required to be secret, and it must be able to fall into \
+            the hands of the enemy without inconvenience";
+        let (ciphertext, tag) = a256cbc_hs512_encrypt(&cek, &iv, aad, plaintext);
+        assert_eq!(
+            hex::encode(&ciphertext),
+            "4affaaadb78c31c5da4b1b590d10ffbd3dd8d5d302423526912da037ecbcc7bd822c301dd67c373bccb584ad3e9279c2\
+             e6d12a1374b77f077553df829410446b36ebd97066296ae6427ea75c2e0846a11a09ccf5370dc80bfecbad28c73f09b3\
+             a3b75e662a2594410ae496b2e2e6609e31e6e02cc837f053d21f37ff4f51950bbe2638d09dd7a4930930806d0703b1f6"
+        );
+        assert_eq!(hex::encode(&tag), "4dd3b4c088a7f45c216839645b2012bf2e6269a8c56a816dbc1b267761955bc5");
+        assert_eq!(a256cbc_hs512_decrypt(&cek, &iv, aad, &ciphertext, &tag).unwrap(), plaintext);
+
+        let mut forged = tag.clone();
+        forged[0] ^= 1;
+        assert!(a256cbc_hs512_decrypt(&cek, &iv, aad, &ciphertext, &forged).is_err());
+    }
+
+    // RFC 7518 Appendix C: ECDH-ES with A128GCM, from the shared secret Z
+    #[test]
+    fn concat_kdf_matches_rfc7518() {
+        let z = hex::decode("9e56d91d817135d372834283bf84269cfb316ea3da806a48f6daa7798cfe90c4").unwrap();
+        let key = concat_kdf::<16>(&z, "A128GCM", b"Alice", b"Bob", None);
+        assert_eq!(BASE64URL.encode(key), "VqqN6vgjbSBcIijNcacQGg");
+    }
+
+    // draft-madden-jose-ecdh-1pu-04 Appendix A: Z = Ze || Zs in direct key agreement mode
+    #[test]
+    fn ecdh_1pu_direct_matches_the_draft() {
+        let ze = hex::decode("9e56d91d817135d372834283bf84269cfb316ea3da806a48f6daa7798cfe90c4").unwrap();
+        let zs = hex::decode("e3ca3474384c9f62b30bfd4c688b3e7d4110a1b4badc3cc54ef7b81241efd50d").unwrap();
+        let key = concat_kdf::<32>(&[ze, zs].concat(), "A256GCM", b"Alice", b"Bob", None);
+        assert_eq!(hex::encode(key), "6caf13723d14850ad4b42cd6dde935bffd2fff00a9ba70de05c203a5e1722ca7");
+    }
+
+    // draft-madden-jose-ecdh-1pu-04 Appendix B: X25519 key wrapping with the content tag in the
+    // KDF, checked for Bob by unwrapping the published encrypted key
+    #[test]
+    fn ecdh_1pu_key_wrapping_matches_the_draft() {
+        let alice = LocalSecret::from_base64url("alice".to_string(), "i9KuFhSzEBsiv3PKVL5115OCdsqQai5nj_Flzfkw5jU").unwrap();
+        let bob = LocalSecret::from_base64url("bob".to_string(), "1gDirl_r_Y3-qUa3WXHgEXrrEHngWThU3c9zj9A2uBg").unwrap();
+        let ephemeral = LocalSecret::from_base64url("epk".to_string(), "x8EVZH4Fwk673_mUujnliJoSrLz0zYzzCWp5GUX2fc8").unwrap();
+        assert_eq!(BASE64URL.encode(alice.public_key().as_bytes()), "Knbm_BcdQr7WIoz-uqit9M0wbcfEr6y-9UfIZ8QnBD4");
+        assert_eq!(BASE64URL.encode(ephemeral.public_key().as_bytes()), "k9of_cpAajy0poW5gaixXGs9nHkwg1AFqUAFa39dyBc");
+
+        // The recipient's side: Ze from the ephemeral key, Zs from the sender's static key
+        let mut z = bob.secret.diffie_hellman(&ephemeral.public_key()).as_bytes().to_vec();
+        z.extend_from_slice(bob.secret.diffie_hellman(&alice.public_key()).as_bytes());
+        assert_eq!(
+            hex::encode(&z),
+            "32810896e0fe4d570ed1acfcedf67117dc194ed5daac21d8ff7af3244694897f\
+             2157612c9048edfae77cb2e4237140605967c05c7f77a48eeaf2cf29a5737c4a"
+        );
+
+        let tag = b64("HLb4fTlm8spGmij3RyOs2gJ4DpHM4hhVRwdF_hGb3WQ");
+        let kek = concat_kdf::<16>(&z, "ECDH-1PU+A128KW", b"Alice", b"Bob and Charlie", Some(&tag));
+        assert_eq!(hex::encode(kek), "df4c37a0668306a11e3d6b0074b5d8df");
+        let cek = KekAes128::from(kek)
+            .unwrap_vec(&b64(
+                "pOMVA9_PtoRe7xXW1139NzzN1UhiFoio8lGto9cf0t8PyU-sjNXH8-LIRLycq8CHJQbDwvQeU1cSl55cQ0hGezJu2N9IY0QN",
+            ))
+            .unwrap();
+        assert_eq!(cek, (0xc0..=0xffu8).rev().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn authcrypt_round_trips_and_binds_the_sender() {
+        let alice = LocalSecret::from_base64url("did:iota:0xa1#key-1".to_string(), &BASE64URL.encode([0xa1; 32])).unwrap();
+        let bob = LocalSecret::from_base64url("did:iota:0xb2#key-1".to_string(), &BASE64URL.encode([0xb2; 32])).unwrap();
+        let key = |secret: &LocalSecret| X25519Key { kid: secret.kid.clone(), public_key: secret.public_key() };
+
+        let jwe = pack_authcrypt(b"hello", &alice, &[key(&bob)]).unwrap();
+        assert_eq!(unpack(&jwe, &bob, Some(&key(&alice))).unwrap(), b"hello");
+
+        // Another key under the same kid cannot pass as the sender
+        let mallory = LocalSecret::from_base64url(alice.kid.clone(), &BASE64URL.encode([0xc3; 32])).unwrap();
+        assert!(matches!(unpack(&jwe, &bob, Some(&key(&mallory))), Err(DidCommError::Crypto(_))));
+    }
+}
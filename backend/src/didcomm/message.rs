@@ -0,0 +1,131 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::time::{SystemTime, UNIX_EPOCH};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+pub const TYP_PLAIN: &str = "application/didcomm-plain+json";
+
+pub const TRUST_PING: &str = "https://didcomm.org/trust-ping/2.0/ping";
+pub const TRUST_PING_RESPONSE: &str = "https://didcomm.org/trust-ping/2.0/ping-response";
+pub const BASIC_MESSAGE: &str = "https://didcomm.org/basicmessage/2.0/message";
+pub const FORWARD: &str = "https://didcomm.org/routing/2.0/forward";
+pub const MEDIATE_REQUEST: &str = "https://didcomm.org/coordinate-mediation/2.0/mediate-request";
+pub const MEDIATE_GRANT: &str = "https://didcomm.org/coordinate-mediation/2.0/mediate-grant";
+
+// DIDComm v2 plaintext message
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Message {
+    pub id: String,
+    #[serde(default = "plain_typ")]
+    pub typ: String,
+    #[serde(rename = "type")]
+    pub type_: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub to: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created_time: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_time: Option<u64>,
+    // `all` asks for the reply on the same HTTP response
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub return_route: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lang: Option<String>,
+    #[serde(default)]
+    pub body: Value,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<Attachment>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Attachment {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub media_type: Option<String>,
+    pub data: AttachmentData,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AttachmentData {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub json: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base64: Option<String>,
+}
+
+fn plain_typ() -> String {
+    TYP_PLAIN.to_string()
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+impl Message {
+    pub fn new(type_: &str, from: Option<String>, to: Vec<String>, body: Value) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            typ: plain_typ(),
+            type_: type_.to_string(),
+            from,
+            to,
+            thid: None,
+            created_time: Some(now_secs()),
+            expires_time: None,
+            return_route: None,
+            lang: None,
+            body,
+            attachments: Vec::new(),
+        }
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_time.is_some_and(|t| t < now_secs())
+    }
+
+    pub fn trust_ping(from: String, to: String, response_requested: bool) -> Self {
+        Self::new(TRUST_PING, Some(from), vec![to], json!({ "response_requested": response_requested }))
+    }
+
+    // Trust-ping response, threaded to the ping
+    pub fn ping_response(ping: &Message, from: String) -> Self {
+        let mut response = Self::new(TRUST_PING_RESPONSE, Some(from), ping.from.iter().cloned().collect(), json!({}));
+        response.thid = Some(ping.id.clone());
+        response
+    }
+
+    // Mediation grant, threaded to the request; `routing_did` is the mediator's own DID
+    pub fn mediate_grant(request: &Message, from: String) -> Self {
+        let routing_did = json!({ "routing_did": from });
+        let mut grant = Self::new(MEDIATE_GRANT, Some(from), request.from.iter().cloned().collect(), routing_did);
+        grant.thid = Some(request.id.clone());
+        grant
+    }
+
+    pub fn basic_message(from: String, to: String, content: String) -> Self {
+        let mut message = Self::new(BASIC_MESSAGE, Some(from), vec![to], json!({ "content": content }));
+        message.lang = Some("en".to_string());
+        message
+    }
+
+    // Routing wrapper: `packed` is the encrypted message for `next`, opaque to the mediator
+    pub fn forward(next: String, packed: Value) -> Self {
+        let mut message = Self::new(FORWARD, None, Vec::new(), json!({ "next": next }));
+        message.attachments.push(Attachment {
+            id: None,
+            media_type: Some(super::jwe::TYP_ENCRYPTED.to_string()),
+            data: AttachmentData { json: Some(packed), base64: None },
+        });
+        message
+    }
+
+    pub fn response_requested(&self) -> bool {
+        self.body.get("response_requested").and_then(Value::as_bool).unwrap_or(true)
+    }
+}
@@ -0,0 +1,850 @@
+use axum::{
+    body::Bytes,
+    extract::{ConnectInfo, Path, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD as BASE64URL, Engine};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use utoipa::ToSchema;
+use utoipa_axum::{router::OpenApiRouter, routes};
+use uuid::Uuid;
+use wot_id_types::WotDid;
+use x25519_dalek::PublicKey;
+
+use crate::identity_client::{signed_action_from_headers, verify_signed_action, IdentityClient, SignedAction};
+use crate::state::AppState;
+use crate::store::JsonStore;
+use crate::webhooks::TargetPolicy;
+
+pub mod jwe;
+pub mod message;
+
+pub use jwe::{Jwe, LocalSecret, X25519Key};
+pub use message::Message;
+
+// Packed messages held per DID until the recipient picks them up. A full queue refuses new
+// messages rather than dropping old ones, so a flood cannot wipe what is waiting.
+const QUEUE_LIMIT: usize = 1000;
+// Forwards are anonymous, so one source (the authenticated sender DID, else the client IP) may
+// only hold this many of a queue's messages and cannot fill it for everyone else
+const FORWARD_LIMIT: usize = 100;
+
+const ACTION_SEND: &str = "didcomm.send";
+const ACTION_LIST: &str = "didcomm.list";
+const ACTION_DELETE: &str = "didcomm.delete";
+
+#[derive(Debug)]
+pub enum DidCommError {
+    Malformed(String),
+    Unsupported(String),
+    Crypto(String),
+    NotARecipient(String),
+    UnknownSender(String),
+    NoRecipientKeys(String),
+    Resolution(String),
+    NotMediated(String),
+    QueueFull(String),
+    TooManyForwards(String),
+}
+
+impl std::fmt::Display for DidCommError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DidCommError::Malformed(detail) => write!(f, "malformed DIDComm message: {}", detail),
+            DidCommError::Unsupported(detail) => write!(f, "unsupported {}", detail),
+            DidCommError::Crypto(detail) => write!(f, "decryption failed: {}", detail),
+            DidCommError::NotARecipient(kid) => write!(f, "message is not encrypted to {}", kid),
+            DidCommError::UnknownSender(detail) => write!(f, "sender cannot be authenticated: {}", detail),
+            DidCommError::NoRecipientKeys(detail) => write!(f, "no X25519 key-agreement keys: {}", detail),
+            DidCommError::Resolution(detail) => write!(f, "failed to resolve keys: {}", detail),
+            DidCommError::NotMediated(did) => write!(f, "{} has no mediation grant", did),
+            DidCommError::QueueFull(did) => write!(f, "queue for {} is full", did),
+            DidCommError::TooManyForwards(did) => write!(f, "too many messages from this sender are waiting for {}", did),
+        }
+    }
+}
+
+impl std::error::Error for DidCommError {}
+
+impl DidCommError {
+    fn status(&self) -> StatusCode {
+        match self {
+            DidCommError::Resolution(_) => StatusCode::BAD_GATEWAY,
+            DidCommError::NotARecipient(_) => StatusCode::NOT_FOUND,
+            DidCommError::UnknownSender(_) | DidCommError::Crypto(_) => StatusCode::UNAUTHORIZED,
+            DidCommError::NotMediated(_) => StatusCode::FORBIDDEN,
+            DidCommError::QueueFull(_) | DidCommError::TooManyForwards(_) => StatusCode::TOO_MANY_REQUESTS,
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+fn did_of(kid: &str) -> &str {
+    kid.split_once('#').map_or(kid, |(did, _)| did)
+}
+
+// All X25519 key-agreement keys a DID publishes, resolved through identity-service
+pub async fn resolve_keys(identity: &IdentityClient, did: &str) -> Result<Vec<X25519Key>, DidCommError> {
+    let did = WotDid::parse(did).map_err(|e| DidCommError::Malformed(format!("{}: {}", did, e)))?;
+    let methods = identity
+        .resolve_key_agreement(&did)
+        .await
+        .map_err(|e| DidCommError::Resolution(e.to_string()))?;
+    let keys: Vec<X25519Key> = methods
+        .iter()
+        .filter(|m| m.crv.as_deref() == Some("X25519"))
+        .filter_map(|m| {
+            let bytes: [u8; 32] = BASE64URL.decode(&m.x).ok()?.try_into().ok()?;
+            Some(X25519Key { kid: m.id.clone(), public_key: PublicKey::from(bytes) })
+        })
+        .collect();
+    if keys.is_empty() {
+        return Err(DidCommError::NoRecipientKeys(did.to_string()));
+    }
+    Ok(keys)
+}
+
+// Encrypt `message` to every key-agreement key of every DID in `to`. With a `sender` key the
+// message is authcrypted (ECDH-1PU), otherwise anoncrypted (ECDH-ES).
+pub async fn pack(identity: &IdentityClient, message: &Message, sender: Option<&LocalSecret>) -> Result<Jwe, DidCommError> {
+    if let Some(sender) = sender
+        && message.from.as_deref() != Some(did_of(&sender.kid))
+    {
+        return Err(DidCommError::Malformed("`from` does not match the sender key".to_string()));
+    }
+    let mut recipients = Vec::new();
+    for did in &message.to {
+        recipients.extend(resolve_keys(identity, did).await?);
+    }
+    let plaintext = serde_json::to_vec(message).expect("message serializes to JSON");
+    match sender {
+        Some(sender) => jwe::pack_authcrypt(&plaintext, sender, &recipients),
+        None => jwe::pack_anoncrypt(&plaintext, &recipients),
+    }
+}
+
+pub struct Unpacked {
+    pub message: Message,
+    // Key the message was authcrypted with; `None` for anoncrypt
+    pub sender_kid: Option<String>,
+}
+
+// Decrypt a message addressed to `recipient`, resolving the sender's key for authcrypt
+pub async fn unpack(identity: &IdentityClient, jwe: &Jwe, recipient: &LocalSecret) -> Result<Unpacked, DidCommError> {
+    let header = jwe.protected_header()?;
+    let sender = match header.skid.as_deref().filter(|_| header.alg == jwe::ALG_AUTHCRYPT) {
+        Some(skid) => Some(
+            resolve_keys(identity, did_of(skid))
+                .await?
+                .into_iter()
+                .find(|key| key.kid == skid)
+                .ok_or_else(|| DidCommError::UnknownSender(format!("{} is not a key-agreement key", skid)))?,
+        ),
+        None => None,
+    };
+
+    let plaintext = jwe::unpack(jwe, recipient, sender.as_ref())?;
+    let message: Message = serde_json::from_slice(&plaintext)
+        .map_err(|e| DidCommError::Malformed(format!("plaintext: {}", e)))?;
+
+    // The encryption layer authenticates the key; the plaintext must claim the same DID
+    if let Some(sender) = &sender
+        && message.from.as_deref() != Some(did_of(&sender.kid))
+    {
+        return Err(DidCommError::UnknownSender("`from` does not match skid".to_string()));
+    }
+    if message.is_expired() {
+        return Err(DidCommError::Malformed("message has expired".to_string()));
+    }
+
+    Ok(Unpacked { message, sender_kid: sender.map(|s| s.kid) })
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct QueuedMessage {
+    pub id: String,
+    pub received_at_ms: u64,
+    pub message: Value,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Queued {
+    #[serde(flatten)]
+    entry: QueuedMessage,
+    // Who posted a forward, counted against FORWARD_LIMIT; never shown to the recipient
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    source: Option<String>,
+}
+
+// Queues and mediation grants, kept across restarts
+#[derive(Default, Serialize, Deserialize)]
+struct MediatorData {
+    queues: BTreeMap<String, VecDeque<Queued>>,
+    // DIDs granted mediation through coordinate-mediation; only they receive forwards
+    grants: BTreeSet<String>,
+}
+
+// Mediator: this backend's own DID and key, plus per-DID queues of messages routed through it
+pub struct Mediator {
+    pub did: String,
+    secret: LocalSecret,
+    data: JsonStore<MediatorData>,
+    // Endpoints `send` may post to, and the client that enforces it
+    endpoints: TargetPolicy,
+    client: reqwest::Client,
+}
+
+impl Mediator {
+    pub fn load(secret: LocalSecret, endpoints: TargetPolicy, path: impl Into<PathBuf>) -> Result<Self, anyhow::Error> {
+        let data = JsonStore::load(path)?;
+        tracing::info!(
+            "DIDComm mediator {} (X25519 key {})",
+            secret.kid,
+            BASE64URL.encode(secret.public_key().as_bytes())
+        );
+        Ok(Self {
+            did: did_of(&secret.kid).to_string(),
+            secret,
+            data,
+            client: endpoints.client(),
+            endpoints,
+        })
+    }
+
+    // Queue `message` for `did`; `source` is set for forwards and limits how many one sender holds
+    async fn enqueue(&self, did: &str, message: Value, source: Option<&str>) -> Result<String, DidCommError> {
+        let id = Uuid::new_v4().to_string();
+        let received_at_ms = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0);
+        let mut data = self.data.lock().await;
+        let queue = data.queues.entry(did.to_string()).or_default();
+        if queue.len() >= QUEUE_LIMIT {
+            tracing::warn!(target: "backend::didcomm", "Queue for {} is full; refusing a new message", did);
+            return Err(DidCommError::QueueFull(did.to_string()));
+        }
+        if let Some(source) = source
+            && queue.iter().filter(|m| m.source.as_deref() == Some(source)).count() >= FORWARD_LIMIT
+        {
+            tracing::warn!(target: "backend::didcomm", "{} already has {} forwards waiting for {}", source, FORWARD_LIMIT, did);
+            return Err(DidCommError::TooManyForwards(did.to_string()));
+        }
+        queue.push_back(Queued {
+            entry: QueuedMessage { id: id.clone(), received_at_ms, message },
+            source: source.map(str::to_string),
+        });
+        self.data.persist(&data).await;
+        Ok(id)
+    }
+
+    // Queue a forwarded message, but only for a DID this mediator agreed to mediate for
+    async fn enqueue_forward(&self, next: &str, message: Value, source: &str) -> Result<String, DidCommError> {
+        if !self.data.lock().await.grants.contains(next) {
+            return Err(DidCommError::NotMediated(next.to_string()));
+        }
+        self.enqueue(next, message, Some(source)).await
+    }
+
+    async fn grant(&self, did: &str) {
+        let mut data = self.data.lock().await;
+        if data.grants.insert(did.to_string()) {
+            self.data.persist(&data).await;
+        }
+    }
+
+    // Queue a basic message from the mediator DID for `to` to pick up; returns the queue entry ID
+    pub async fn notify(&self, identity: &IdentityClient, to: &str, content: String) -> Result<String, DidCommError> {
+        let message = Message::basic_message(self.did.clone(), to.to_string(), content);
+        let packed = pack(identity, &message, Some(&self.secret)).await?;
+        let packed = serde_json::to_value(&packed).expect("JWE serializes to JSON");
+        self.enqueue(to, packed, None).await
+    }
+}
+
+pub fn routes() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new()
+        .routes(routes!(receive))
+        .routes(routes!(send))
+        .routes(routes!(list_queued))
+        .routes(routes!(delete_queued))
+}
+
+fn mediator(state: &AppState) -> Result<&Mediator, (StatusCode, String)> {
+    state
+        .mediator
+        .as_deref()
+        .ok_or((StatusCode::SERVICE_UNAVAILABLE, "DIDComm mediator is not configured".to_string()))
+}
+
+fn didcomm_error(e: DidCommError) -> (StatusCode, String) {
+    tracing::warn!(target: "backend::didcomm", "Rejected DIDComm message: {}", e);
+    (e.status(), e.to_string())
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct SendClaims {
+    pub to: WotDid,
+    // `trust-ping` or `basic-message`
+    pub protocol: String,
+    #[serde(default)]
+    pub content: Option<String>,
+    // DIDComm endpoint to POST to: the recipient's own, or its mediator's
+    pub endpoint: String,
+    // DID of the recipient's mediator; the message is then wrapped in a forward
+    #[serde(default)]
+    pub mediator: Option<WotDid>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SendResponse {
+    pub id: String,
+    pub status: u16,
+    // Decrypted reply when the recipient answered on the same connection
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reply: Option<Message>,
+}
+
+// Send a message as the mediator DID, e.g. to ping another backend on localhost. Only the
+// mediator DID itself may sign the request, so nobody else can speak in its name.
+#[utoipa::path(
+    post,
+    path = "/api/v1/didcomm/send",
+    tag = "didcomm",
+    request_body(content = SignedAction, description = "`didcomm.send` action signed by the mediator DID, with `SendClaims`"),
+    responses(
+        (status = 200, body = SendResponse),
+        (status = 400, description = "Unsupported protocol, a malformed DID, or an endpoint that is not a public http(s) URL", body = String),
+        (status = 401, description = "Signature, action or freshness rejected", body = String),
+        (status = 403, description = "The signer is not the mediator DID", body = String),
+        (status = 502, description = "The endpoint could not be reached or rejected the message", body = String),
+        (status = 503, description = "DIDComm mediator is not configured", body = String),
+    )
+)]
+async fn send(
+    State(state): State<AppState>,
+    Json(payload): Json<SignedAction>,
+) -> Result<Json<SendResponse>, (StatusCode, String)> {
+    let mediator = mediator(&state)?;
+    let request: SendClaims = verify_signed_action(&state.identity, &payload, ACTION_SEND).await?;
+    if payload.signer.as_str() != mediator.did {
+        return Err((StatusCode::FORBIDDEN, format!("Only {} may send as the mediator", mediator.did)));
+    }
+    let endpoint = reqwest::Url::parse(&request.endpoint)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid endpoint: {}", e)))?;
+    mediator.endpoints.check(&endpoint).await.map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+
+    let mut message = match request.protocol.as_str() {
+        "trust-ping" => Message::trust_ping(mediator.did.clone(), request.to.to_string(), true),
+        "basic-message" => Message::basic_message(
+            mediator.did.clone(),
+            request.to.to_string(),
+            request.content.clone().unwrap_or_default(),
+        ),
+        other => return Err((StatusCode::BAD_REQUEST, format!("Unsupported protocol {}", other))),
+    };
+    // A reply can only come back inline when we talk to the recipient directly
+    if request.mediator.is_none() {
+        message.return_route = Some("all".to_string());
+    }
+    let id = message.id.clone();
+
+    let mut packed = pack(&state.identity, &message, Some(&mediator.secret)).await.map_err(didcomm_error)?;
+    if let Some(next_hop) = &request.mediator {
+        let mut forward = Message::forward(request.to.to_string(), serde_json::to_value(&packed).expect("JWE serializes to JSON"));
+        forward.to = vec![next_hop.to_string()];
+        packed = pack(&state.identity, &forward, None).await.map_err(didcomm_error)?;
+    }
+
+    let resp = mediator
+        .client
+        .post(endpoint)
+        .header(header::CONTENT_TYPE, jwe::TYP_ENCRYPTED)
+        .json(&packed)
+        .send()
+        .await
+        .map_err(|e| {
+            tracing::error!(target: "backend::didcomm", "Failed to reach {}: {}", request.endpoint, e);
+            (StatusCode::BAD_GATEWAY, format!("Failed to reach {}: {}", request.endpoint, e))
+        })?;
+    let status = resp.status();
+    if !status.is_success() {
+        let body = resp.text().await.unwrap_or_default();
+        return Err((StatusCode::BAD_GATEWAY, format!("{} answered {}: {}", request.endpoint, status, body)));
+    }
+
+    let is_packed_reply = resp
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with(jwe::TYP_ENCRYPTED));
+    let reply = if is_packed_reply {
+        let reply: Jwe = resp
+            .json()
+            .await
+            .map_err(|e| (StatusCode::BAD_GATEWAY, format!("Reply is not a DIDComm message: {}", e)))?;
+        Some(unpack(&state.identity, &reply, &mediator.secret).await.map_err(didcomm_error)?.message)
+    } else {
+        None
+    };
+
+    Ok(Json(SendResponse { id, status: status.as_u16(), reply }))
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ReceiveResponse {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub type_: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub queued_for: Option<String>,
+}
+
+// How the mediator answers a message that asks for a reply
+enum Reply {
+    // On the same HTTP response (`return_route: "all"`)
+    Inline(Response),
+    // Queued for the sender to pick up
+    Queued(String),
+}
+
+// The DID that authcrypted `message`; replies and grants go only to authenticated senders
+fn authenticated_sender<'a>(message: &Message, sender_kid: Option<&'a str>) -> Result<&'a str, (StatusCode, String)> {
+    sender_kid.map(did_of).ok_or((
+        StatusCode::BAD_REQUEST,
+        format!("{} requesting a response must be authcrypted", message.type_),
+    ))
+}
+
+// Encrypt `response` back to `from`, the authenticated sender of `message`
+async fn reply(
+    state: &AppState,
+    mediator: &Mediator,
+    message: &Message,
+    from: &str,
+    response: Message,
+) -> Result<Reply, (StatusCode, String)> {
+    let packed = pack(&state.identity, &response, Some(&mediator.secret)).await.map_err(didcomm_error)?;
+    if message.return_route.as_deref() == Some("all") {
+        return Ok(Reply::Inline(
+            (
+                [(header::CONTENT_TYPE, jwe::TYP_ENCRYPTED)],
+                serde_json::to_string(&packed).expect("JWE serializes to JSON"),
+            )
+                .into_response(),
+        ));
+    }
+    let packed = serde_json::to_value(&packed).expect("JWE serializes to JSON");
+    mediator.enqueue(from, packed, None).await.map_err(didcomm_error)?;
+    Ok(Reply::Queued(from.to_string()))
+}
+
+// Inbound endpoint. Accepts any JSON body so both `application/didcomm-encrypted+json` and
+// `application/json` work.
+#[utoipa::path(
+    post,
+    path = "/api/v1/didcomm",
+    tag = "didcomm",
+    request_body(content((Jwe = "application/didcomm-encrypted+json"), (Jwe = "application/json"))),
+    responses(
+        (status = 200, description = "Trust ping or mediation request answered on the same connection", body = Jwe, content_type = "application/didcomm-encrypted+json"),
+        (status = 202, description = "Message accepted, and queued if it is to be picked up", body = ReceiveResponse),
+        (status = 400, description = "Not a valid DIDComm message for this mediator, or a forward whose `next` is not a DID", body = String),
+        (status = 403, description = "Forward to a DID without a mediation grant", body = String),
+        (status = 429, description = "The destination queue is full, or this sender already has too many messages in it", body = String),
+        (status = 503, description = "DIDComm mediator is not configured", body = String),
+    )
+)]
+async fn receive(
+    State(state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    body: Bytes,
+) -> Result<Response, (StatusCode, String)> {
+    let mediator = mediator(&state)?;
+    let jwe: Jwe = serde_json::from_slice(&body)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Body is not an encrypted DIDComm message: {}", e)))?;
+    let Unpacked { message, sender_kid } = unpack(&state.identity, &jwe, &mediator.secret).await.map_err(didcomm_error)?;
+
+    let reply = match message.type_.as_str() {
+        message::FORWARD => {
+            let next = message
+                .body
+                .get("next")
+                .and_then(Value::as_str)
+                .ok_or((StatusCode::BAD_REQUEST, "forward message has no `next`".to_string()))?;
+            let next = WotDid::parse(did_of(next))
+                .map_err(|e| (StatusCode::BAD_REQUEST, format!("forward `next` is not a DID: {}", e)))?;
+            let packed = message
+                .attachments
+                .first()
+                .and_then(|a| a.data.json.clone())
+                .ok_or((StatusCode::BAD_REQUEST, "forward message has no JSON attachment".to_string()))?;
+            let source = sender_kid.as_deref().map_or_else(|| peer.ip().to_string(), |kid| did_of(kid).to_string());
+            mediator.enqueue_forward(next.as_str(), packed, &source).await.map_err(didcomm_error)?;
+            Some(Reply::Queued(next.to_string()))
+        }
+        message::MEDIATE_REQUEST => {
+            let requester = authenticated_sender(&message, sender_kid.as_deref())?;
+            let grant = Message::mediate_grant(&message, mediator.did.clone());
+            let answer = reply(&state, mediator, &message, requester, grant).await?;
+            tracing::info!(target: "backend::didcomm", "Granted mediation to {}", requester);
+            mediator.grant(requester).await;
+            Some(answer)
+        }
+        message::TRUST_PING if message.response_requested() => {
+            let from = authenticated_sender(&message, sender_kid.as_deref())?;
+            let response = Message::ping_response(&message, mediator.did.clone());
+            Some(reply(&state, mediator, &message, from, response).await?)
+        }
+        message::TRUST_PING => None,
+        message::BASIC_MESSAGE => {
+            tracing::info!(
+                target: "backend::didcomm",
+                "Basic message {} from {}",
+                message.id,
+                message.from.as_deref().unwrap_or("anonymous sender")
+            );
+            // Kept, still encrypted, in the mediator's own queue
+            let packed = serde_json::to_value(&jwe).expect("JWE serializes to JSON");
+            mediator.enqueue(&mediator.did, packed, None).await.map_err(didcomm_error)?;
+            Some(Reply::Queued(mediator.did.clone()))
+        }
+        other => return Err((StatusCode::BAD_REQUEST, format!("Unsupported message type {}", other))),
+    };
+
+    let queued_for = match reply {
+        Some(Reply::Inline(response)) => return Ok(response),
+        Some(Reply::Queued(did)) => Some(did),
+        None => None,
+    };
+    Ok((StatusCode::ACCEPTED, Json(ReceiveResponse { id: message.id, type_: message.type_, queued_for })).into_response())
+}
+
+// Pickup. The messages stay encrypted, but who writes to a DID and when is still private, so
+// listing needs the recipient's signature.
+#[utoipa::path(
+    get,
+    path = "/api/v1/didcomm/messages/{did}",
+    tag = "didcomm",
+    params(
+        ("did" = String, Path, description = "Recipient DID"),
+        ("x-wotid-signer" = String, Header, description = "The recipient DID"),
+        ("x-wotid-action" = String, Header, description = "Signed `didcomm.list` action"),
+    ),
+    responses(
+        (status = 200, body = Vec<QueuedMessage>),
+        (status = 400, description = "Malformed DID", body = String),
+        (status = 401, description = "Signature, action or freshness rejected", body = String),
+        (status = 403, description = "The signer is not the recipient", body = String),
+        (status = 503, description = "DIDComm mediator is not configured", body = String),
+    )
+)]
+async fn list_queued(
+    State(state): State<AppState>,
+    Path(did): Path<WotDid>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<QueuedMessage>>, (StatusCode, String)> {
+    let mediator = mediator(&state)?;
+    let signed = signed_action_from_headers(&headers)?;
+    let _: Value = verify_signed_action(&state.identity, &signed, ACTION_LIST).await?;
+    if signed.signer != did {
+        return Err((StatusCode::FORBIDDEN, format!("Only {} may list its messages", did)));
+    }
+    let data = mediator.data.lock().await;
+    Ok(Json(data.queues.get(did.as_str()).map(|q| q.iter().map(|m| m.entry.clone()).collect()).unwrap_or_default()))
+}
+
+#[derive(Deserialize)]
+struct DeleteClaims {
+    message_id: String,
+}
+
+// Removing a message needs the recipient's signature, so nobody else can empty its queue
+#[utoipa::path(
+    delete,
+    path = "/api/v1/didcomm/messages/{did}/{message_id}",
+    tag = "didcomm",
+    params(
+        ("did" = String, Path, description = "Recipient DID"),
+        ("message_id" = String, Path),
+        ("x-wotid-signer" = String, Header, description = "The recipient DID"),
+        ("x-wotid-action" = String, Header, description = "Signed `didcomm.delete` action naming `message_id`"),
+    ),
+    responses(
+        (status = 204, description = "Message removed from the queue"),
+        (status = 400, description = "Malformed DID", body = String),
+        (status = 401, description = "Signature, action or freshness rejected", body = String),
+        (status = 403, description = "The signer is not the recipient, or the action names another message", body = String),
+        (status = 404, description = "No such message", body = String),
+        (status = 503, description = "DIDComm mediator is not configured", body = String),
+    )
+)]
+async fn delete_queued(
+    State(state): State<AppState>,
+    Path((did, message_id)): Path<(WotDid, String)>,
+    headers: HeaderMap,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let mediator = mediator(&state)?;
+    let signed = signed_action_from_headers(&headers)?;
+    let claims: DeleteClaims = verify_signed_action(&state.identity, &signed, ACTION_DELETE).await?;
+    if signed.signer != did {
+        return Err((StatusCode::FORBIDDEN, format!("Only {} may remove its messages", did)));
+    }
+    if claims.message_id != message_id {
+        return Err((StatusCode::FORBIDDEN, "The signed action is for another message".to_string()));
+    }
+    let mut data = mediator.data.lock().await;
+    let queue = data.queues.get_mut(did.as_str()).ok_or((StatusCode::NOT_FOUND, format!("No messages for {}", did)))?;
+    let position = queue
+        .iter()
+        .position(|m| m.entry.id == message_id)
+        .ok_or((StatusCode::NOT_FOUND, format!("Message {} not found", message_id)))?;
+    queue.remove(position);
+    mediator.data.persist(&data).await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use wot_id_types::identity::{KeyAgreementMethod, KeyAgreementResponse};
+
+    use crate::testing::{serve, verify_action_route, TestDir};
+
+    struct Agent {
+        did: String,
+        kid: String,
+        secret: String,
+    }
+
+    impl Agent {
+        fn new(tag: u8) -> Self {
+            let did = format!("did:iota:0x{:02x}", tag);
+            Self { kid: format!("{}#key-1", did), did, secret: BASE64URL.encode([tag; 32]) }
+        }
+
+        fn local_secret(&self) -> LocalSecret {
+            LocalSecret::from_base64url(self.kid.clone(), &self.secret).unwrap()
+        }
+
+        // Stand-in for a DID-signed action: the fake identity service below takes the claims as is
+        fn sign(&self, claims: Value) -> SignedAction {
+            SignedAction { signer: self.did.parse().unwrap(), jws: claims.to_string() }
+        }
+    }
+
+    // Identity service publishing one X25519 key per agent and accepting any action whose
+    // claims name the expected action
+    async fn identity_service(agents: &[&Agent]) -> String {
+        let keys: Arc<HashMap<String, KeyAgreementMethod>> = Arc::new(
+            agents
+                .iter()
+                .map(|agent| {
+                    let public_key = agent.local_secret().public_key();
+                    let method = KeyAgreementMethod {
+                        id: agent.kid.clone(),
+                        kty: "OKP".to_string(),
+                        crv: Some("X25519".to_string()),
+                        alg: None,
+                        x: BASE64URL.encode(public_key.as_bytes()),
+                    };
+                    (agent.did.clone(), method)
+                })
+                .collect(),
+        );
+        let app = verify_action_route().route(
+            "/api/v1/identity/{did}/key-agreement",
+            axum::routing::get(move |Path(did): Path<String>| async move {
+                let method = keys.get(&did).cloned().ok_or(StatusCode::NOT_FOUND)?;
+                Ok::<_, StatusCode>(Json(KeyAgreementResponse { did: did.parse().unwrap(), methods: vec![method] }))
+            }),
+        );
+        serve(app).await
+    }
+
+    // A backend on localhost whose mediator is `agent`, with its stores in `dir`; returns its base URL
+    async fn backend(agent: &Agent, identity_url: &str, dir: &TestDir) -> String {
+        let state = crate::testing::app_state(
+            dir,
+            identity_url,
+            &[
+                ("DIDCOMM_MEDIATOR_KID", agent.kid.clone()),
+                ("DIDCOMM_MEDIATOR_SECRET", agent.secret.clone()),
+                ("DIDCOMM_ALLOW_PRIVATE_ENDPOINTS", "true".to_string()),
+            ],
+        );
+        let (router, _) = routes().split_for_parts();
+        serve(router.with_state(state)).await
+    }
+
+    // Pickup as `agent`, which signs a `didcomm.list` action for `did`
+    async fn list(base_url: &str, did: &str, agent: &Agent) -> reqwest::Response {
+        let signed = agent.sign(json!({ "action": ACTION_LIST }));
+        reqwest::Client::new()
+            .get(format!("{}/api/v1/didcomm/messages/{}", base_url, did))
+            .header(crate::identity_client::SIGNER_HEADER, signed.signer.to_string())
+            .header(crate::identity_client::ACTION_HEADER, signed.jws)
+            .send()
+            .await
+            .unwrap()
+    }
+
+    async fn post(url: String, body: &impl Serialize) -> reqwest::Response {
+        reqwest::Client::new().post(url).json(body).send().await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn trust_ping_and_basic_message_between_two_backends() {
+        let (alice, bob) = (Agent::new(0xa1), Agent::new(0xb2));
+        let identity = identity_service(&[&alice, &bob]).await;
+        let (alice_dir, bob_dir) = (TestDir::new("didcomm"), TestDir::new("didcomm"));
+        let alice_url = backend(&alice, &identity, &alice_dir).await;
+        let bob_url = backend(&bob, &identity, &bob_dir).await;
+        let bob_endpoint = format!("{}/api/v1/didcomm", bob_url);
+
+        let ping = alice.sign(json!({
+            "action": ACTION_SEND, "to": bob.did, "protocol": "trust-ping", "endpoint": bob_endpoint,
+        }));
+        let resp = post(format!("{}/api/v1/didcomm/send", alice_url), &ping).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let sent: Value = resp.json().await.unwrap();
+        assert_eq!(sent["reply"]["type"], message::TRUST_PING_RESPONSE);
+        assert_eq!(sent["reply"]["from"], bob.did.as_str());
+        assert_eq!(sent["reply"]["thid"], sent["id"]);
+
+        let hello = alice.sign(json!({
+            "action": ACTION_SEND, "to": bob.did, "protocol": "basic-message", "content": "hello", "endpoint": bob_endpoint,
+        }));
+        assert_eq!(post(format!("{}/api/v1/didcomm/send", alice_url), &hello).await.status(), StatusCode::OK);
+        let queued: Vec<Value> = list(&bob_url, &bob.did, &bob).await.json().await.unwrap();
+        assert_eq!(queued.len(), 1);
+        let jwe: Jwe = serde_json::from_value(queued[0]["message"].clone()).unwrap();
+        let client = IdentityClient::new(reqwest::Client::new(), identity.clone());
+        let Unpacked { message, sender_kid } = unpack(&client, &jwe, &bob.local_secret()).await.unwrap();
+        assert_eq!(message.body["content"], "hello");
+        assert_eq!(sender_kid.as_deref(), Some(alice.kid.as_str()));
+
+        // Only the mediator DID may send in its name
+        let impersonation = bob.sign(json!({
+            "action": ACTION_SEND, "to": bob.did, "protocol": "trust-ping", "endpoint": bob_endpoint,
+        }));
+        let resp = post(format!("{}/api/v1/didcomm/send", alice_url), &impersonation).await;
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn forwards_need_a_mediation_grant_and_pickups_need_the_recipient() {
+        let (alice, bob, carol) = (Agent::new(0xa1), Agent::new(0xb2), Agent::new(0xc3));
+        let identity = identity_service(&[&alice, &bob, &carol]).await;
+        let (alice_dir, bob_dir) = (TestDir::new("didcomm"), TestDir::new("didcomm"));
+        let alice_url = backend(&alice, &identity, &alice_dir).await;
+        let bob_url = backend(&bob, &identity, &bob_dir).await;
+        let bob_endpoint = format!("{}/api/v1/didcomm", bob_url);
+        let via_bob = alice.sign(json!({
+            "action": ACTION_SEND, "to": carol.did, "protocol": "basic-message", "content": "hi carol",
+            "endpoint": bob_endpoint, "mediator": bob.did,
+        }));
+
+        // Bob does not mediate for Carol yet, so the forward is refused
+        let resp = post(format!("{}/api/v1/didcomm/send", alice_url), &via_bob).await;
+        assert_eq!(resp.status(), StatusCode::BAD_GATEWAY);
+        assert!(resp.text().await.unwrap().contains("403"));
+
+        // Carol asks Bob for mediation and gets the grant on the same connection
+        let client = IdentityClient::new(reqwest::Client::new(), identity.clone());
+        let mut request = Message::new(message::MEDIATE_REQUEST, Some(carol.did.clone()), vec![bob.did.clone()], json!({}));
+        request.return_route = Some("all".to_string());
+        let packed = pack(&client, &request, Some(&carol.local_secret())).await.unwrap();
+        let resp = post(bob_endpoint.clone(), &packed).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let grant: Jwe = resp.json().await.unwrap();
+        let grant = unpack(&client, &grant, &carol.local_secret()).await.unwrap().message;
+        assert_eq!(grant.type_, message::MEDIATE_GRANT);
+        assert_eq!(grant.body["routing_did"], bob.did.as_str());
+
+        assert_eq!(post(format!("{}/api/v1/didcomm/send", alice_url), &via_bob).await.status(), StatusCode::OK);
+
+        // `next` must be a DID
+        let mut forward = Message::forward("did:iota:0xc3".to_string(), json!({}));
+        forward.to = vec![bob.did.clone()];
+        forward.body["next"] = json!("mailto:carol@example.com");
+        let resp = post(bob_endpoint.clone(), &pack(&client, &forward, None).await.unwrap()).await;
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        // Only Carol may see what is waiting for her, also after Bob restarts
+        let queue_url = format!("{}/api/v1/didcomm/messages/{}", bob_url, carol.did);
+        assert_eq!(reqwest::get(&queue_url).await.unwrap().status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(list(&bob_url, &carol.did, &alice).await.status(), StatusCode::FORBIDDEN);
+        let bob_url = backend(&bob, &identity, &bob_dir).await;
+        let queue_url = format!("{}/api/v1/didcomm/messages/{}", bob_url, carol.did);
+        let queued: Vec<Value> = list(&bob_url, &carol.did, &carol).await.json().await.unwrap();
+        assert_eq!(queued.len(), 1);
+        assert!(queued[0].get("source").is_none());
+        let queued_id = queued[0]["id"].as_str().unwrap();
+
+        // Removing it takes Carol's signature over that message ID
+        let delete = |agent: &Agent, message_id: &str| {
+            let signed = agent.sign(json!({ "action": ACTION_DELETE, "message_id": message_id }));
+            reqwest::Client::new()
+                .delete(format!("{}/{}", queue_url, queued_id))
+                .header(crate::identity_client::SIGNER_HEADER, signed.signer.to_string())
+                .header(crate::identity_client::ACTION_HEADER, signed.jws)
+                .send()
+        };
+        assert_eq!(delete(&alice, queued_id).await.unwrap().status(), StatusCode::FORBIDDEN);
+        assert_eq!(delete(&carol, "another").await.unwrap().status(), StatusCode::FORBIDDEN);
+        assert_eq!(delete(&carol, queued_id).await.unwrap().status(), StatusCode::NO_CONTENT);
+    }
+
+    fn local_mediator(dir: &TestDir) -> Mediator {
+        Mediator::load(Agent::new(0xa1).local_secret(), TargetPolicy { allow_private: false }, dir.path("didcomm.json")).unwrap()
+    }
+
+    #[tokio::test]
+    async fn full_queue_refuses_new_messages_and_keeps_old_ones() {
+        let dir = TestDir::new("didcomm");
+        let mediator = local_mediator(&dir);
+        let first = mediator.enqueue("did:iota:0xb2", json!(0), None).await.unwrap();
+        for i in 1..QUEUE_LIMIT {
+            mediator.enqueue("did:iota:0xb2", json!(i), None).await.unwrap();
+        }
+        assert!(matches!(mediator.enqueue("did:iota:0xb2", json!("flood"), None).await, Err(DidCommError::QueueFull(_))));
+        assert_eq!(mediator.data.lock().await.queues["did:iota:0xb2"].front().unwrap().entry.id, first);
+
+        assert!(matches!(
+            mediator.enqueue_forward("did:iota:0xc3", json!("unmediated"), "203.0.113.7").await,
+            Err(DidCommError::NotMediated(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn one_source_cannot_fill_a_queue_with_forwards() {
+        let dir = TestDir::new("didcomm");
+        let mediator = local_mediator(&dir);
+        mediator.grant("did:iota:0xc3").await;
+        for i in 0..FORWARD_LIMIT {
+            mediator.enqueue_forward("did:iota:0xc3", json!(i), "203.0.113.7").await.unwrap();
+        }
+        assert!(matches!(
+            mediator.enqueue_forward("did:iota:0xc3", json!("flood"), "203.0.113.7").await,
+            Err(DidCommError::TooManyForwards(_))
+        ));
+        mediator.enqueue_forward("did:iota:0xc3", json!("other"), "did:iota:0xa1").await.unwrap();
+
+        // Grants, queues and the per-source count survive a restart
+        drop(mediator);
+        let restarted = local_mediator(&dir);
+        assert_eq!(restarted.data.lock().await.queues["did:iota:0xc3"].len(), FORWARD_LIMIT + 1);
+        assert!(matches!(
+            restarted.enqueue_forward("did:iota:0xc3", json!("flood"), "203.0.113.7").await,
+            Err(DidCommError::TooManyForwards(_))
+        ));
+    }
+}
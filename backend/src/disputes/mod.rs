@@ -0,0 +1,893 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use utoipa::{IntoParams, ToSchema};
+use utoipa_axum::{router::OpenApiRouter, routes};
+use uuid::Uuid;
+use wot_id_types::WotDid;
+
+use crate::identity_client::{verify_signed_action, SignedAction};
+use crate::iota_rpc::RpcError;
+use crate::state::AppState;
+use crate::store::JsonStore;
+use crate::storage;
+use crate::trust::{Adjustment, TrustEdge, MAX_TRUST_LEVEL};
+
+pub const ACTION_FILE: &str = "dispute.file";
+pub const ACTION_EVIDENCE: &str = "dispute.evidence";
+pub const ACTION_VOTE: &str = "dispute.vote";
+
+// Arbiters are drawn from this many times as many top-trusted candidates
+const ARBITER_POOL_FACTOR: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DisputedKind {
+    TrustRelationship,
+    ClaimTrust,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DisputeStatus {
+    // Collecting evidence and arbiter votes
+    Open,
+    // A majority of arbiters voted; `remedy` is final
+    Resolved,
+    // Voting ended without a majority of arbiters; the input is left as it was
+    Expired,
+}
+
+// What arbiters can decide, mildest first. The median vote wins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Remedy {
+    // The disputed input stands
+    Dismiss,
+    // The input counts at the configured percentage in aggregation
+    DownWeight,
+    // The input is left out of aggregation
+    Suspend,
+}
+
+impl Remedy {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Dismiss => "dismiss",
+            Self::DownWeight => "down_weight",
+            Self::Suspend => "suspend",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DisputeRules {
+    pub arbiter_count: usize,
+    // Lowest aggregate trust within the dispute's context an arbiter needs, in trust.move units
+    pub min_arbiter_trust: u64,
+    pub voting_period: Duration,
+    // Weight a down-weighted input keeps, in percent
+    pub down_weight_pct: u8,
+}
+
+impl Default for DisputeRules {
+    fn default() -> Self {
+        Self {
+            arbiter_count: 3,
+            min_arbiter_trust: 2 * MAX_TRUST_LEVEL,
+            voting_period: Duration::from_secs(7 * 24 * 60 * 60),
+            down_weight_pct: 50,
+        }
+    }
+}
+
+// Evidence is stored in the blob store beforehand and referenced by its SHA-256
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EvidenceItem {
+    pub content_hash: String,
+    pub description: String,
+    pub submitted_by: String,
+    pub submitted_at_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ArbiterVote {
+    pub arbiter: String,
+    pub remedy: Remedy,
+    pub rationale: String,
+    // The arbiter's signed `dispute.vote` action
+    pub jws: String,
+    pub recorded_at_ms: u64,
+}
+
+// One step of a dispute's history; entries are only ever appended
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AuditEntry {
+    pub at_ms: u64,
+    // DID that caused the step; `None` for steps taken by the backend
+    pub actor: Option<String>,
+    pub event: String,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Dispute {
+    pub id: String,
+    // The disputed TrustRelationship or ClaimTrust
+    pub object_id: String,
+    pub kind: DisputedKind,
+    pub context: String,
+    // Subject of the attestation: the trust target, or the holder of the assessed credential
+    pub complainant: String,
+    // Author of the attestation: the trust source, or the claim verifier
+    pub respondent: String,
+    pub description: String,
+    // The complainant's signed `dispute.file` action
+    pub jws: String,
+    pub evidence: Vec<EvidenceItem>,
+    pub arbiters: Vec<String>,
+    // Earlier dispute by the same complainant over the same object; its ID seeds the arbiter draw
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refiled_from: Option<String>,
+    pub votes: Vec<ArbiterVote>,
+    pub status: DisputeStatus,
+    pub remedy: Option<Remedy>,
+    // What the resolution does to the input in aggregation
+    pub adjustment: Option<Adjustment>,
+    pub created_at_ms: u64,
+    pub voting_ends_ms: u64,
+    pub closed_at_ms: Option<u64>,
+    pub audit: Vec<AuditEntry>,
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+impl Dispute {
+    fn record(&mut self, actor: Option<&str>, event: &str, detail: String) {
+        tracing::info!(
+            target: "backend::audit",
+            dispute = %self.id,
+            object_id = %self.object_id,
+            actor = actor.unwrap_or("backend"),
+            "{}: {}", event, detail
+        );
+        self.audit.push(AuditEntry { at_ms: now_ms(), actor: actor.map(str::to_string), event: event.to_string(), detail });
+    }
+
+    fn is_party(&self, did: &str) -> bool {
+        self.complainant == did || self.respondent == did
+    }
+
+    // Median remedy of the cast votes; with an even count, the milder of the middle two
+    fn decision(&self) -> Option<Remedy> {
+        let mut remedies: Vec<Remedy> = self.votes.iter().map(|v| v.remedy).collect();
+        remedies.sort();
+        remedies.get(remedies.len().checked_sub(1)? / 2).copied()
+    }
+
+    // Close the dispute once every arbiter voted, or once voting ended. Returns whether it closed.
+    fn try_close(&mut self, now_ms: u64, rules: &DisputeRules) -> bool {
+        if self.status != DisputeStatus::Open
+            || (self.votes.len() < self.arbiters.len() && now_ms < self.voting_ends_ms)
+        {
+            return false;
+        }
+        if self.votes.len() * 2 > self.arbiters.len() {
+            let remedy = self.decision().expect("a majority has voted");
+            self.remedy = Some(remedy);
+            self.adjustment = match remedy {
+                Remedy::Dismiss => None,
+                Remedy::DownWeight => Some(Adjustment::DownWeighted { pct: rules.down_weight_pct }),
+                Remedy::Suspend => Some(Adjustment::Suspended),
+            };
+            self.status = DisputeStatus::Resolved;
+            let detail = format!("{} by {} of {} arbiters", remedy.as_str(), self.votes.len(), self.arbiters.len());
+            self.record(None, "resolved", detail);
+        } else {
+            self.status = DisputeStatus::Expired;
+            let detail = format!("only {} of {} arbiters voted", self.votes.len(), self.arbiters.len());
+            self.record(None, "expired", detail);
+        }
+        self.closed_at_ms = Some(now_ms);
+        true
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct DisputeData {
+    disputes: Vec<Dispute>,
+}
+
+// Disputes with their evidence, votes and audit trail, persisted as one JSON file
+pub struct DisputeStore {
+    data: JsonStore<DisputeData>,
+    pub rules: DisputeRules,
+}
+
+impl DisputeStore {
+    pub fn load(path: impl Into<PathBuf>, rules: DisputeRules) -> Result<Self, anyhow::Error> {
+        Ok(Self { data: JsonStore::load(path)?, rules })
+    }
+
+    // Adjustments in force, per disputed object
+    pub async fn adjustments(&self) -> HashMap<String, Adjustment> {
+        adjustments_in_force(&self.data.lock().await.disputes)
+    }
+}
+
+// The latest resolution of each object decides: a later dismissal lifts an earlier suspension or
+// down-weighting. Expired disputes change nothing.
+fn adjustments_in_force(disputes: &[Dispute]) -> HashMap<String, Adjustment> {
+    let mut resolved: Vec<&Dispute> = disputes.iter().filter(|d| d.status == DisputeStatus::Resolved).collect();
+    resolved.sort_by_key(|d| d.closed_at_ms);
+    let latest: HashMap<&str, Option<Adjustment>> =
+        resolved.into_iter().map(|d| (d.object_id.as_str(), d.adjustment)).collect();
+    latest.into_iter().filter_map(|(object_id, adjustment)| Some((object_id.to_string(), adjustment?))).collect()
+}
+
+pub fn routes() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new()
+        .routes(routes!(file_dispute, list_disputes))
+        .routes(routes!(get_dispute))
+        .routes(routes!(add_evidence))
+        .routes(routes!(cast_vote))
+        .routes(routes!(close_dispute))
+}
+
+fn not_found(id: &str) -> (StatusCode, String) {
+    (StatusCode::NOT_FOUND, format!("No dispute {}", id))
+}
+
+fn rpc_error(context: &str, e: RpcError) -> (StatusCode, String) {
+    tracing::error!(target: "backend::disputes", "{}: {}", context, e);
+    (StatusCode::BAD_GATEWAY, format!("{}: {}", context, e))
+}
+
+// The disputed input as read from chain
+struct DisputedObject {
+    kind: DisputedKind,
+    context: String,
+    subject: String,
+    author: String,
+}
+
+async fn disputed_object(state: &AppState, object_id: &str) -> Result<DisputedObject, (StatusCode, String)> {
+    let object = state
+        .rpc
+        .call("iota_getObject", json!([object_id, { "showContent": true }]))
+        .await
+        .map_err(|e| rpc_error("Failed to fetch disputed object", e))?;
+    let object_type = object.pointer("/data/content/type").and_then(Value::as_str).unwrap_or_default();
+    let fields = object.pointer("/data/content/fields").cloned().unwrap_or(Value::Null);
+    let text = |name: &str| fields.get(name).and_then(Value::as_str).unwrap_or_default().to_string();
+
+    let pkg = &state.config.wot_id_pkg_id;
+    if object_type == format!("{}::trust::TrustRelationship", pkg) {
+        Ok(DisputedObject {
+            kind: DisputedKind::TrustRelationship,
+            context: text("context"),
+            subject: text("target"),
+            author: text("source"),
+        })
+    } else if object_type == format!("{}::trust::ClaimTrust", pkg) {
+        let credential_id = text("credential_id");
+        let holder = credential_holder(state, &credential_id)
+            .await
+            .map_err(|e| rpc_error("Failed to look up the assessed credential", e))?
+            .ok_or_else(|| (StatusCode::UNPROCESSABLE_ENTITY, format!("Credential {} is not known", credential_id)))?;
+        Ok(DisputedObject {
+            kind: DisputedKind::ClaimTrust,
+            context: text("context"),
+            subject: holder,
+            author: text("verifier"),
+        })
+    } else {
+        Err((StatusCode::UNPROCESSABLE_ENTITY, format!("{} is not a TrustRelationship or ClaimTrust", object_id)))
+    }
+}
+
+// Holder DID of a credential, from its `CredentialIssued` event
+async fn credential_holder(state: &AppState, credential_id: &str) -> Result<Option<String>, RpcError> {
+    let event_type = format!("{}::credentials::CredentialIssued", state.config.wot_id_pkg_id);
+    let mut cursor = Value::Null;
+    loop {
+        let page = state
+            .rpc
+            .call("iotax_queryEvents", json!([{ "MoveEventType": event_type }, cursor, 50, true]))
+            .await?;
+        let found = page
+            .get("data")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|event| event.get("parsedJson"))
+            .find(|fields| fields.get("credential_id").and_then(Value::as_str) == Some(credential_id))
+            .and_then(|fields| fields.get("holder").and_then(Value::as_str))
+            .map(str::to_string);
+        if found.is_some() {
+            return Ok(found);
+        }
+        match page.get("nextCursor") {
+            Some(next) if page.get("hasNextPage").and_then(Value::as_bool).unwrap_or(false) => cursor = next.clone(),
+            _ => return Ok(None),
+        }
+    }
+}
+
+// Pick arbiters among the DIDs most trusted within `context`, leaving out the parties and anyone
+// with a direct relationship to them there. The top candidates are drawn in an order fixed by
+// the dispute ID, so the selection can be recomputed but not steered by the parties.
+fn select_arbiters(edges: &[TrustEdge], rules: &DisputeRules, dispute_id: &str, parties: [&str; 2]) -> Vec<String> {
+    let conflicted: HashSet<&str> = edges
+        .iter()
+        .filter(|e| parties.contains(&e.source.as_str()) || parties.contains(&e.target.as_str()))
+        .flat_map(|e| [e.source.as_str(), e.target.as_str()])
+        .chain(parties)
+        .collect();
+
+    let mut scores: HashMap<&str, u64> = HashMap::new();
+    for edge in edges.iter().filter(|e| e.source != e.target) {
+        *scores.entry(edge.target.as_str()).or_default() += edge.effective_value();
+    }
+    let mut pool: Vec<(&str, u64)> = scores
+        .into_iter()
+        .filter(|(did, score)| *score >= rules.min_arbiter_trust && !conflicted.contains(did))
+        .collect();
+    pool.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+    pool.truncate(rules.arbiter_count * ARBITER_POOL_FACTOR);
+    pool.sort_by_cached_key(|(did, _)| <[u8; 32]>::from(Sha256::digest(format!("{}|{}", dispute_id, did))));
+    pool.into_iter().take(rules.arbiter_count).map(|(did, _)| did.to_string()).collect()
+}
+
+// A complainant gets one resolution per object, so refiling after it is refused. After a dispute
+// expired without a majority it may be filed again, but the arbiters are drawn with the first
+// dispute's ID, so refiling cannot shop for another panel. Returns that ID, if any.
+fn refiled_from<'a>(
+    disputes: &'a [Dispute],
+    complainant: &str,
+    object_id: &str,
+) -> Result<Option<&'a str>, (StatusCode, String)> {
+    let mut earlier = disputes.iter().filter(|d| d.object_id == object_id && d.complainant == complainant).peekable();
+    let first = earlier.peek().map(|d| d.id.as_str());
+    if let Some(resolved) = earlier.find(|d| d.status == DisputeStatus::Resolved) {
+        return Err((StatusCode::CONFLICT, format!("{} was already resolved by dispute {}", object_id, resolved.id)));
+    }
+    Ok(first)
+}
+
+// Check that evidence is in the blob store and pin it for the life of the record
+async fn check_evidence(state: &AppState, content_hash: &str) -> Result<(), (StatusCode, String)> {
+    if !storage::is_valid_hash(content_hash) {
+        return Err((StatusCode::BAD_REQUEST, "content_hash must be a lowercase hex SHA-256".to_string()));
+    }
+    match state.blobs.get(content_hash).await {
+        Ok(Some(_)) => {}
+        Ok(None) => return Err((StatusCode::UNPROCESSABLE_ENTITY, format!("No evidence stored under {}", content_hash))),
+        Err(e) => {
+            tracing::error!(target: "backend::disputes", "Failed to read evidence blob {}: {}", content_hash, e);
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, "Failed to read evidence".to_string()));
+        }
+    }
+    if let Err(e) = state.blobs.pin(content_hash).await {
+        tracing::warn!(target: "backend::disputes", "Failed to pin evidence blob {}: {}", content_hash, e);
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct EvidenceClaims {
+    content_hash: String,
+    description: String,
+}
+
+#[derive(Deserialize)]
+struct FileClaims {
+    object_id: String,
+    description: String,
+    evidence: Vec<EvidenceClaims>,
+}
+
+#[derive(Deserialize)]
+struct AddEvidenceClaims {
+    dispute_id: String,
+    #[serde(flatten)]
+    evidence: EvidenceClaims,
+}
+
+#[derive(Deserialize)]
+struct VoteClaims {
+    dispute_id: String,
+    remedy: Remedy,
+    #[serde(default)]
+    rationale: String,
+}
+
+// The subject of a trust relationship or claim assessment disputes it
+#[utoipa::path(
+    post,
+    path = "/api/v1/disputes",
+    tag = "disputes",
+    request_body(content = SignedAction, description = "Signed `dispute.file` action"),
+    responses(
+        (status = 201, body = Dispute),
+        (status = 401, description = "Signature, action or freshness rejected", body = String),
+        (status = 403, description = "The signer is not the subject of the disputed object", body = String),
+        (status = 409, description = "The object already has an open dispute, or one of the complainant's was resolved", body = String),
+        (status = 422, description = "No evidence, or not enough neutral arbiters", body = String),
+    )
+)]
+async fn file_dispute(
+    State(state): State<AppState>,
+    Json(payload): Json<SignedAction>,
+) -> Result<(StatusCode, Json<Dispute>), (StatusCode, String)> {
+    let claims: FileClaims = verify_signed_action(&state.identity, &payload, ACTION_FILE).await?;
+    if claims.evidence.is_empty() {
+        return Err((StatusCode::UNPROCESSABLE_ENTITY, "A dispute needs at least one piece of evidence".to_string()));
+    }
+    let disputed = disputed_object(&state, &claims.object_id).await?;
+    if disputed.subject != payload.signer {
+        return Err((
+            StatusCode::FORBIDDEN,
+            format!("Only {}, the subject of {}, may dispute it", disputed.subject, claims.object_id),
+        ));
+    }
+    for item in &claims.evidence {
+        check_evidence(&state, &item.content_hash).await?;
+    }
+
+    let refiled = {
+        let data = state.disputes.data.lock().await;
+        refiled_from(&data.disputes, payload.signer.as_str(), &claims.object_id)?.map(str::to_string)
+    };
+    let id = Uuid::new_v4().to_string();
+    let rules = &state.disputes.rules;
+    let edges = state.trust.context_edges(&disputed.context).await;
+    let seed = refiled.as_deref().unwrap_or(&id);
+    let arbiters = select_arbiters(&edges, rules, seed, [&disputed.subject, &disputed.author]);
+    if arbiters.len() < rules.arbiter_count {
+        return Err((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!("Not enough neutral arbiters with sufficient trust in context '{}'", disputed.context),
+        ));
+    }
+
+    let now = now_ms();
+    let mut dispute = Dispute {
+        id,
+        object_id: claims.object_id,
+        kind: disputed.kind,
+        context: disputed.context,
+        complainant: payload.signer.into(),
+        respondent: disputed.author,
+        description: claims.description,
+        jws: payload.jws,
+        evidence: Vec::new(),
+        arbiters,
+        refiled_from: refiled,
+        votes: Vec::new(),
+        status: DisputeStatus::Open,
+        remedy: None,
+        adjustment: None,
+        created_at_ms: now,
+        voting_ends_ms: now + rules.voting_period.as_millis() as u64,
+        closed_at_ms: None,
+        audit: Vec::new(),
+    };
+    let complainant = dispute.complainant.clone();
+    dispute.record(Some(&complainant), "filed", format!("{} disputed", dispute.object_id));
+    if let Some(earlier) = dispute.refiled_from.clone() {
+        dispute.record(Some(&complainant), "refiled", format!("arbiters drawn as for {}", earlier));
+    }
+    for item in claims.evidence {
+        dispute.record(Some(&complainant), "evidence_added", item.content_hash.clone());
+        dispute.evidence.push(EvidenceItem {
+            content_hash: item.content_hash,
+            description: item.description,
+            submitted_by: complainant.clone(),
+            submitted_at_ms: now,
+        });
+    }
+    let arbiters = dispute.arbiters.join(", ");
+    dispute.record(None, "arbiters_selected", arbiters);
+
+    let mut data = state.disputes.data.lock().await;
+    if data.disputes.iter().any(|d| d.object_id == dispute.object_id && d.status == DisputeStatus::Open) {
+        return Err((StatusCode::CONFLICT, format!("{} already has an open dispute", dispute.object_id)));
+    }
+    // Resolved while this one was being prepared
+    refiled_from(&data.disputes, &dispute.complainant, &dispute.object_id)?;
+    data.disputes.push(dispute.clone());
+    state.disputes.data.persist(&data).await;
+    Ok((StatusCode::CREATED, Json(dispute)))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListDisputesQuery {
+    pub object_id: Option<String>,
+    // Disputes where this DID is a party or an arbiter
+    pub did: Option<WotDid>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/disputes",
+    tag = "disputes",
+    params(ListDisputesQuery),
+    responses((status = 200, body = Vec<Dispute>), (status = 400, description = "Malformed DID", body = String))
+)]
+async fn list_disputes(State(state): State<AppState>, Query(query): Query<ListDisputesQuery>) -> Json<Vec<Dispute>> {
+    let data = state.disputes.data.lock().await;
+    Json(
+        data.disputes
+            .iter()
+            .filter(|d| query.object_id.as_ref().is_none_or(|id| &d.object_id == id))
+            .filter(|d| query.did.as_ref().is_none_or(|did| d.is_party(did) || d.arbiters.iter().any(|arbiter| did == arbiter)))
+            .cloned()
+            .collect(),
+    )
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/disputes/{id}",
+    tag = "disputes",
+    params(("id" = String, Path)),
+    responses((status = 200, body = Dispute), (status = 404, body = String))
+)]
+async fn get_dispute(State(state): State<AppState>, Path(id): Path<String>) -> Result<Json<Dispute>, (StatusCode, String)> {
+    let data = state.disputes.data.lock().await;
+    data.disputes.iter().find(|d| d.id == id).cloned().map(Json).ok_or_else(|| not_found(&id))
+}
+
+// Either party may add evidence while the dispute is open
+#[utoipa::path(
+    post,
+    path = "/api/v1/disputes/{id}/evidence",
+    tag = "disputes",
+    params(("id" = String, Path)),
+    request_body(content = SignedAction, description = "Signed `dispute.evidence` action"),
+    responses(
+        (status = 200, body = Dispute),
+        (status = 401, description = "Signature, action or freshness rejected", body = String),
+        (status = 403, description = "The signer is not a party to the dispute", body = String),
+        (status = 404, body = String),
+        (status = 409, description = "The dispute is closed", body = String),
+    )
+)]
+async fn add_evidence(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(payload): Json<SignedAction>,
+) -> Result<Json<Dispute>, (StatusCode, String)> {
+    let claims: AddEvidenceClaims = verify_signed_action(&state.identity, &payload, ACTION_EVIDENCE).await?;
+    if claims.dispute_id != id {
+        return Err((StatusCode::BAD_REQUEST, "The signed evidence is for a different dispute".to_string()));
+    }
+    check_evidence(&state, &claims.evidence.content_hash).await?;
+
+    let mut data = state.disputes.data.lock().await;
+    let dispute = data.disputes.iter_mut().find(|d| d.id == id).ok_or_else(|| not_found(&id))?;
+    if !dispute.is_party(&payload.signer) {
+        return Err((StatusCode::FORBIDDEN, format!("{} is not a party to dispute {}", payload.signer, id)));
+    }
+    if dispute.status != DisputeStatus::Open {
+        return Err((StatusCode::CONFLICT, format!("Dispute {} is closed", id)));
+    }
+    if !dispute.evidence.iter().any(|e| e.content_hash == claims.evidence.content_hash) {
+        dispute.record(Some(payload.signer.as_str()), "evidence_added", claims.evidence.content_hash.clone());
+        dispute.evidence.push(EvidenceItem {
+            content_hash: claims.evidence.content_hash,
+            description: claims.evidence.description,
+            submitted_by: payload.signer.into(),
+            submitted_at_ms: now_ms(),
+        });
+    }
+    let dispute = dispute.clone();
+    state.disputes.data.persist(&data).await;
+    Ok(Json(dispute))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/disputes/{id}/votes",
+    tag = "disputes",
+    params(("id" = String, Path)),
+    request_body(content = SignedAction, description = "Signed `dispute.vote` action"),
+    responses(
+        (status = 200, body = Dispute),
+        (status = 401, description = "Signature, action or freshness rejected", body = String),
+        (status = 403, description = "The signer is not an arbiter of the dispute", body = String),
+        (status = 404, body = String),
+        (status = 409, description = "Voting has ended or the arbiter already voted", body = String),
+    )
+)]
+async fn cast_vote(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(payload): Json<SignedAction>,
+) -> Result<Json<Dispute>, (StatusCode, String)> {
+    let claims: VoteClaims = verify_signed_action(&state.identity, &payload, ACTION_VOTE).await?;
+    if claims.dispute_id != id {
+        return Err((StatusCode::BAD_REQUEST, "The signed vote is for a different dispute".to_string()));
+    }
+
+    let now = now_ms();
+    let mut data = state.disputes.data.lock().await;
+    let dispute = data.disputes.iter_mut().find(|d| d.id == id).ok_or_else(|| not_found(&id))?;
+    if !dispute.arbiters.iter().any(|arbiter| *arbiter == payload.signer) {
+        return Err((StatusCode::FORBIDDEN, format!("{} is not an arbiter of dispute {}", payload.signer, id)));
+    }
+    if dispute.status != DisputeStatus::Open || now >= dispute.voting_ends_ms {
+        return Err((StatusCode::CONFLICT, format!("Voting on dispute {} has ended", id)));
+    }
+    if dispute.votes.iter().any(|v| v.arbiter == payload.signer) {
+        return Err((StatusCode::CONFLICT, format!("{} has already voted", payload.signer)));
+    }
+
+    dispute.record(Some(payload.signer.as_str()), "vote_cast", claims.remedy.as_str().to_string());
+    dispute.votes.push(ArbiterVote {
+        arbiter: payload.signer.into(),
+        remedy: claims.remedy,
+        rationale: claims.rationale,
+        jws: payload.jws,
+        recorded_at_ms: now,
+    });
+    let closed = dispute.try_close(now, &state.disputes.rules);
+    let dispute = dispute.clone();
+    state.disputes.data.persist(&data).await;
+    drop(data);
+    if closed {
+        apply_outcome(&state, &dispute).await;
+    }
+    Ok(Json(dispute))
+}
+
+// Close a dispute whose voting period is over; anyone may trigger this
+#[utoipa::path(
+    post,
+    path = "/api/v1/disputes/{id}/close",
+    tag = "disputes",
+    params(("id" = String, Path)),
+    responses(
+        (status = 200, body = Dispute),
+        (status = 404, body = String),
+        (status = 409, description = "Voting is still open", body = String),
+    )
+)]
+async fn close_dispute(State(state): State<AppState>, Path(id): Path<String>) -> Result<Json<Dispute>, (StatusCode, String)> {
+    let mut data = state.disputes.data.lock().await;
+    let dispute = data.disputes.iter_mut().find(|d| d.id == id).ok_or_else(|| not_found(&id))?;
+    if dispute.status != DisputeStatus::Open {
+        return Ok(Json(dispute.clone()));
+    }
+    if !dispute.try_close(now_ms(), &state.disputes.rules) {
+        return Err((StatusCode::CONFLICT, format!("Voting on dispute {} is still open", id)));
+    }
+    let dispute = dispute.clone();
+    state.disputes.data.persist(&data).await;
+    drop(data);
+    apply_outcome(&state, &dispute).await;
+    Ok(Json(dispute))
+}
+
+// Same rule as `adjustments_in_force`: a resolution replaces whatever adjustment was in force,
+// so a dismissal lifts it, while an expiry leaves it in place. Disputes over one object never
+// overlap, so the one closing now is the latest.
+async fn apply_outcome(state: &AppState, dispute: &Dispute) {
+    if dispute.status == DisputeStatus::Resolved {
+        state.trust.set_adjustment(&dispute.object_id, dispute.adjustment).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::ChainEvent;
+    use crate::trust::TrustIndex;
+
+    fn dispute(arbiters: usize, votes: &[Remedy]) -> Dispute {
+        Dispute {
+            id: "d1".to_string(),
+            object_id: "0xrel".to_string(),
+            kind: DisputedKind::TrustRelationship,
+            context: "ops".to_string(),
+            complainant: "did:iota:tst:0xsubject".to_string(),
+            respondent: "did:iota:tst:0xauthor".to_string(),
+            description: String::new(),
+            jws: "file.jws".to_string(),
+            evidence: Vec::new(),
+            arbiters: (0..arbiters).map(|i| format!("did:iota:tst:0xarb{}", i)).collect(),
+            refiled_from: None,
+            votes: votes
+                .iter()
+                .enumerate()
+                .map(|(i, remedy)| ArbiterVote {
+                    arbiter: format!("did:iota:tst:0xarb{}", i),
+                    remedy: *remedy,
+                    rationale: String::new(),
+                    jws: format!("vote{}.jws", i),
+                    recorded_at_ms: 10,
+                })
+                .collect(),
+            status: DisputeStatus::Open,
+            remedy: None,
+            adjustment: None,
+            created_at_ms: 0,
+            voting_ends_ms: 1_000,
+            closed_at_ms: None,
+            audit: Vec::new(),
+        }
+    }
+
+    fn closed(object_id: &str, closed_at_ms: u64, votes: &[Remedy]) -> Dispute {
+        let mut d = dispute(3, votes);
+        d.object_id = object_id.to_string();
+        assert!(d.try_close(closed_at_ms.max(d.voting_ends_ms), &DisputeRules::default()));
+        d.closed_at_ms = Some(closed_at_ms);
+        d
+    }
+
+    async fn trust(index: &TrustIndex, object_id: &str, source: &str, target: &str, value: u64) {
+        index
+            .apply(&ChainEvent {
+                seq: 0,
+                kind: "TrustChanged".to_string(),
+                module: "trust".to_string(),
+                object_id: Some(object_id.to_string()),
+                dids: Vec::new(),
+                tx_digest: String::new(),
+                timestamp_ms: Some(1),
+                payload: json!({ "source": source, "target": target, "context": "ops", "new_value": value.to_string() }),
+            })
+            .await;
+    }
+
+    #[test]
+    fn decision_is_the_median_and_the_milder_middle_on_ties() {
+        use Remedy::{Dismiss, DownWeight, Suspend};
+        assert_eq!(dispute(3, &[]).decision(), None);
+        assert_eq!(dispute(3, &[Suspend, Dismiss, DownWeight]).decision(), Some(DownWeight));
+        assert_eq!(dispute(3, &[Suspend, Suspend, Dismiss]).decision(), Some(Suspend));
+        assert_eq!(dispute(4, &[Suspend, Dismiss]).decision(), Some(Dismiss));
+        assert_eq!(dispute(4, &[Suspend, Suspend, DownWeight, Dismiss]).decision(), Some(DownWeight));
+    }
+
+    #[test]
+    fn closes_early_once_every_arbiter_voted() {
+        let rules = DisputeRules::default();
+        let mut d = dispute(3, &[Remedy::Suspend, Remedy::Suspend]);
+        assert!(!d.try_close(999, &rules));
+        assert_eq!(d.status, DisputeStatus::Open);
+
+        let mut d = dispute(3, &[Remedy::Suspend, Remedy::Suspend, Remedy::DownWeight]);
+        assert!(d.try_close(500, &rules));
+        assert_eq!(d.status, DisputeStatus::Resolved);
+        assert_eq!(d.remedy, Some(Remedy::Suspend));
+        assert_eq!(d.adjustment, Some(Adjustment::Suspended));
+        assert_eq!(d.closed_at_ms, Some(500));
+        assert!(!d.try_close(2_000, &rules));
+    }
+
+    #[test]
+    fn majority_at_expiry_resolves() {
+        let mut d = dispute(3, &[Remedy::DownWeight, Remedy::Dismiss]);
+        let rules = DisputeRules { down_weight_pct: 30, ..DisputeRules::default() };
+        assert!(d.try_close(1_000, &rules));
+        assert_eq!(d.status, DisputeStatus::Resolved);
+        assert_eq!(d.remedy, Some(Remedy::Dismiss));
+        assert_eq!(d.adjustment, None);
+
+        let mut d = dispute(3, &[Remedy::DownWeight, Remedy::Suspend]);
+        assert!(d.try_close(1_000, &rules));
+        assert_eq!(d.adjustment, Some(Adjustment::DownWeighted { pct: 30 }));
+    }
+
+    #[test]
+    fn expires_without_a_majority() {
+        // Two of four is half, not a majority
+        let mut d = dispute(4, &[Remedy::Suspend, Remedy::Suspend]);
+        assert!(d.try_close(1_000, &DisputeRules::default()));
+        assert_eq!(d.status, DisputeStatus::Expired);
+        assert_eq!((d.remedy, d.adjustment), (None, None));
+        assert_eq!(d.audit.last().map(|e| e.event.as_str()), Some("expired"));
+
+        let mut d = dispute(3, &[]);
+        assert!(d.try_close(1_000, &DisputeRules::default()));
+        assert_eq!(d.status, DisputeStatus::Expired);
+    }
+
+    #[test]
+    fn latest_resolution_decides_the_adjustment() {
+        use Remedy::{Dismiss, Suspend};
+        let suspended = closed("0xa", 10, &[Suspend, Suspend, Suspend]);
+        let dismissed = closed("0xa", 20, &[Dismiss, Dismiss, Dismiss]);
+        let expired = closed("0xa", 30, &[Dismiss]);
+        assert_eq!(expired.status, DisputeStatus::Expired);
+
+        let in_force = adjustments_in_force(std::slice::from_ref(&suspended));
+        assert_eq!(in_force.get("0xa"), Some(&Adjustment::Suspended));
+        // A later dismissal lifts the suspension, whatever order the store holds them in
+        assert!(adjustments_in_force(&[dismissed.clone(), suspended.clone()]).is_empty());
+        // An expiry leaves the suspension in place
+        let in_force = adjustments_in_force(&[expired, suspended.clone()]);
+        assert_eq!(in_force.get("0xa"), Some(&Adjustment::Suspended));
+        // Suspended again after the dismissal
+        let resuspended = closed("0xa", 40, &[Suspend, Suspend, Dismiss]);
+        let in_force = adjustments_in_force(&[suspended, dismissed, resuspended, closed("0xb", 5, &[Suspend, Suspend])]);
+        assert_eq!(in_force.get("0xa"), Some(&Adjustment::Suspended));
+        assert_eq!(in_force.get("0xb"), Some(&Adjustment::Suspended));
+    }
+
+    #[tokio::test]
+    async fn arbiters_exclude_parties_and_their_neighbours() {
+        let index = TrustIndex::default();
+        let (subject, author) = ("did:iota:tst:0xsubject", "did:iota:tst:0xauthor");
+        // Everyone below is endorsed enough to arbitrate
+        for (i, did) in ["friend", "critic", "n1", "n2", "n3", subject, author].iter().enumerate() {
+            let did = if did.starts_with("did:") { did.to_string() } else { format!("did:iota:tst:0x{}", did) };
+            trust(&index, &format!("0xe{}a", i), "did:iota:tst:0xroot1", &did, MAX_TRUST_LEVEL).await;
+            trust(&index, &format!("0xe{}b", i), "did:iota:tst:0xroot2", &did, MAX_TRUST_LEVEL).await;
+        }
+        // The subject trusts `friend`; `critic` trusts the author
+        trust(&index, "0xf1", subject, "did:iota:tst:0xfriend", 1).await;
+        trust(&index, "0xf2", "did:iota:tst:0xcritic", author, 1).await;
+        let edges = index.context_edges("ops").await;
+
+        let rules = DisputeRules { arbiter_count: 3, ..DisputeRules::default() };
+        let mut arbiters = select_arbiters(&edges, &rules, "d1", [subject, author]);
+        arbiters.sort();
+        assert_eq!(arbiters, ["did:iota:tst:0xn1", "did:iota:tst:0xn2", "did:iota:tst:0xn3"]);
+
+        // Too few neutral candidates to fill the panel
+        let rules = DisputeRules { arbiter_count: 4, ..DisputeRules::default() };
+        assert_eq!(select_arbiters(&edges, &rules, "d1", [subject, author]).len(), 3);
+    }
+
+    #[tokio::test]
+    async fn arbiters_need_enough_trust_and_are_fixed_by_the_dispute() {
+        let index = TrustIndex::default();
+        for i in 0..12 {
+            let did = format!("did:iota:tst:0xc{:02}", i);
+            trust(&index, &format!("0xe{}a", i), "did:iota:tst:0xroot1", &did, MAX_TRUST_LEVEL).await;
+            // Only the first eight reach the default two-relationship minimum
+            if i < 8 {
+                trust(&index, &format!("0xe{}b", i), "did:iota:tst:0xroot2", &did, MAX_TRUST_LEVEL).await;
+            }
+        }
+        let edges = index.context_edges("ops").await;
+        let rules = DisputeRules::default();
+        let parties = ["did:iota:tst:0xsubject", "did:iota:tst:0xauthor"];
+
+        let first = select_arbiters(&edges, &rules, "d1", parties);
+        assert_eq!(first.len(), 3);
+        assert!(first.iter().all(|did| did.as_str() < "did:iota:tst:0xc08"));
+        assert_eq!(select_arbiters(&edges, &rules, "d1", parties), first);
+    }
+
+    #[test]
+    fn refiling_reuses_the_first_draw_and_stops_at_a_resolution() {
+        let complainant = "did:iota:tst:0xsubject";
+        let mut disputes = vec![closed("0xrel", 2_000, &[])];
+        assert_eq!(disputes[0].status, DisputeStatus::Expired);
+        assert_eq!(refiled_from(&disputes, complainant, "0xrel"), Ok(Some("d1")));
+        assert_eq!(refiled_from(&disputes, complainant, "0xother"), Ok(None));
+
+        let mut second = closed("0xrel", 3_000, &[Remedy::Suspend, Remedy::Suspend]);
+        second.id = "d2".to_string();
+        disputes.push(second);
+        let err = refiled_from(&disputes, complainant, "0xrel").unwrap_err();
+        assert_eq!(err.0, StatusCode::CONFLICT);
+        assert!(err.1.contains("d2"));
+    }
+}
@@ -0,0 +1,290 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD as BASE64URL, Engine};
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::ChaCha20Poly1305;
+use hkdf::Hkdf;
+use ml_kem::kem::{Decapsulate, Encapsulate};
+use ml_kem::{Ciphertext, Encoded, EncodedSizeUser, KemCore, MlKem768};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use x25519_dalek::{EphemeralSecret, PublicKey, StaticSecret};
+
+use super::ENC_C20P;
+
+pub const ALG_X25519_ML_KEM_768: &str = "X25519-ML-KEM-768";
+pub const ML_KEM_768: &str = "ML-KEM-768";
+pub const ML_KEM_768_PUBLIC_KEY_SIZE: usize = 1184;
+const KDF_INFO: &[u8] = b"wot.id/encryption/v2/X25519-ML-KEM-768";
+const TAG_SIZE: usize = 16;
+
+pub type EncapsulationKey = <MlKem768 as KemCore>::EncapsulationKey;
+pub type DecapsulationKey = <MlKem768 as KemCore>::DecapsulationKey;
+
+// ML-KEM-768 key-agreement key of a recipient DID (`publicKeyJwk` with `kty: AKP`)
+#[derive(Debug, Clone)]
+pub struct MlKemKey {
+    pub kid: String,
+    pub public_key: Vec<u8>,
+}
+
+// JWE flattened JSON serialization. Everything needed to decrypt, including the ML-KEM
+// ciphertext (`ek`), is in the integrity-protected header.
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct HybridEnvelope {
+    pub protected: String,
+    pub iv: String,
+    pub ciphertext: String,
+    pub tag: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ProtectedHeader {
+    alg: String,
+    enc: String,
+    kid: String,
+    pq_kid: String,
+    epk: EphemeralKey,
+    ek: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct EphemeralKey {
+    kty: String,
+    crv: String,
+    x: String,
+}
+
+#[derive(Debug)]
+pub enum OpenError {
+    Malformed(String),
+    Unsupported(String),
+    Decryption,
+}
+
+impl std::fmt::Display for OpenError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OpenError::Malformed(detail) => write!(f, "malformed envelope: {}", detail),
+            OpenError::Unsupported(detail) => write!(f, "unsupported {}", detail),
+            OpenError::Decryption => write!(f, "decryption failed"),
+        }
+    }
+}
+
+impl std::error::Error for OpenError {}
+
+// Encrypt to both keys: the content key stays secret unless X25519 *and* ML-KEM-768 are broken
+pub fn seal(x25519_kid: &str, x25519_public_key: &[u8; 32], ml_kem: &MlKemKey, plaintext: &[u8]) -> HybridEnvelope {
+    let encoded: Encoded<EncapsulationKey> = ml_kem
+        .public_key
+        .as_slice()
+        .try_into()
+        .expect("ML-KEM-768 key length is checked when the recipient key is parsed");
+    let (kem_ciphertext, kem_secret) = EncapsulationKey::from_bytes(&encoded)
+        .encapsulate(&mut OsRng)
+        .expect("ML-KEM encapsulation cannot fail");
+
+    let ephemeral = EphemeralSecret::random_from_rng(OsRng);
+    let epk = PublicKey::from(&ephemeral);
+    let dh_secret = ephemeral.diffie_hellman(&PublicKey::from(*x25519_public_key));
+
+    let header = ProtectedHeader {
+        alg: ALG_X25519_ML_KEM_768.to_string(),
+        enc: ENC_C20P.to_string(),
+        kid: x25519_kid.to_string(),
+        pq_kid: ml_kem.kid.clone(),
+        epk: EphemeralKey {
+            kty: "OKP".to_string(),
+            crv: "X25519".to_string(),
+            x: BASE64URL.encode(epk.as_bytes()),
+        },
+        ek: BASE64URL.encode(kem_ciphertext),
+    };
+    let protected = BASE64URL.encode(serde_json::to_vec(&header).expect("header serializes to JSON"));
+
+    let key = derive_key(&kem_secret, dh_secret.as_bytes(), epk.as_bytes(), x25519_public_key, &kem_ciphertext);
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let mut ciphertext = ChaCha20Poly1305::new(&key.into())
+        .encrypt(&nonce, Payload { msg: plaintext, aad: protected.as_bytes() })
+        .expect("ChaCha20-Poly1305 encryption cannot fail for in-memory buffers");
+    let tag = ciphertext.split_off(ciphertext.len() - TAG_SIZE);
+
+    HybridEnvelope {
+        protected,
+        iv: BASE64URL.encode(nonce),
+        ciphertext: BASE64URL.encode(ciphertext),
+        tag: BASE64URL.encode(tag),
+    }
+}
+
+// Recipients decrypt client-side; the backend only needs this for tests and tooling
+#[cfg_attr(not(test), allow(dead_code))]
+pub fn open(
+    envelope: &HybridEnvelope,
+    x25519_secret: &StaticSecret,
+    ml_kem_secret: &DecapsulationKey,
+) -> Result<Vec<u8>, OpenError> {
+    let decode = |value: &str, field: &str| {
+        BASE64URL.decode(value).map_err(|e| OpenError::Malformed(format!("{}: {}", field, e)))
+    };
+    let header: ProtectedHeader = serde_json::from_slice(&decode(&envelope.protected, "protected")?)
+        .map_err(|e| OpenError::Malformed(format!("protected header: {}", e)))?;
+    if header.alg != ALG_X25519_ML_KEM_768 {
+        return Err(OpenError::Unsupported(format!("alg {}", header.alg)));
+    }
+    if header.enc != ENC_C20P {
+        return Err(OpenError::Unsupported(format!("enc {}", header.enc)));
+    }
+
+    let epk: [u8; 32] = decode(&header.epk.x, "epk")?
+        .try_into()
+        .map_err(|_| OpenError::Malformed("epk is not an X25519 key".to_string()))?;
+    let kem_ciphertext: Ciphertext<MlKem768> = decode(&header.ek, "ek")?
+        .as_slice()
+        .try_into()
+        .map_err(|_| OpenError::Malformed("ek is not an ML-KEM-768 ciphertext".to_string()))?;
+
+    let kem_secret = ml_kem_secret.decapsulate(&kem_ciphertext).map_err(|_| OpenError::Decryption)?;
+    let dh_secret = x25519_secret.diffie_hellman(&PublicKey::from(epk));
+    let recipient_public_key = PublicKey::from(x25519_secret);
+    let key = derive_key(&kem_secret, dh_secret.as_bytes(), &epk, recipient_public_key.as_bytes(), &kem_ciphertext);
+
+    let nonce: [u8; 12] = decode(&envelope.iv, "iv")?
+        .try_into()
+        .map_err(|_| OpenError::Malformed("iv must be 12 bytes".to_string()))?;
+    let mut ciphertext = decode(&envelope.ciphertext, "ciphertext")?;
+    ciphertext.extend_from_slice(&decode(&envelope.tag, "tag")?);
+    ChaCha20Poly1305::new(&key.into())
+        .decrypt(&nonce.into(), Payload { msg: &ciphertext, aad: envelope.protected.as_bytes() })
+        .map_err(|_| OpenError::Decryption)
+}
+
+// HKDF-SHA256 over both shared secrets. The X25519 public keys are the salt, as in v1, and the
+// ML-KEM ciphertext is bound through its hash so neither half can be swapped out.
+fn derive_key(kem_secret: &[u8], dh_secret: &[u8], epk: &[u8], recipient_pk: &[u8], kem_ciphertext: &[u8]) -> [u8; 32] {
+    let mut ikm = Vec::with_capacity(64);
+    ikm.extend_from_slice(kem_secret);
+    ikm.extend_from_slice(dh_secret);
+    let mut salt = Vec::with_capacity(64);
+    salt.extend_from_slice(epk);
+    salt.extend_from_slice(recipient_pk);
+    let mut info = KDF_INFO.to_vec();
+    info.extend_from_slice(&Sha256::digest(kem_ciphertext));
+
+    let mut key = [0u8; 32];
+    Hkdf::<Sha256>::new(Some(&salt), &ikm)
+        .expand(&info, &mut key)
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ml_kem::B32;
+    use serde_json::Value;
+
+    // Vector produced by an independent ML-KEM/X25519/HKDF/ChaCha20-Poly1305 implementation
+    const KAT: &str = include_str!("../../fixtures/hybrid_kem_kat.json");
+
+    fn kat() -> Value {
+        serde_json::from_str(KAT).unwrap()
+    }
+
+    fn hex_field<const N: usize>(kat: &Value, name: &str) -> [u8; N] {
+        hex::decode(kat[name].as_str().unwrap()).unwrap().try_into().unwrap()
+    }
+
+    fn kat_keys(kat: &Value) -> (StaticSecret, DecapsulationKey, EncapsulationKey) {
+        let x25519 = StaticSecret::from(hex_field::<32>(kat, "x25519_secret"));
+        let d = B32::from(hex_field::<32>(kat, "ml_kem_seed_d"));
+        let z = B32::from(hex_field::<32>(kat, "ml_kem_seed_z"));
+        let (dk, ek) = MlKem768::generate_deterministic(&d, &z);
+        (x25519, dk, ek)
+    }
+
+    fn recipient(x25519: &StaticSecret, ek: &EncapsulationKey) -> ([u8; 32], MlKemKey) {
+        let ml_kem = MlKemKey { kid: "did:iota:tst:0x1#mlkem-1".to_string(), public_key: ek.as_bytes().to_vec() };
+        (PublicKey::from(x25519).to_bytes(), ml_kem)
+    }
+
+    #[test]
+    fn round_trip() {
+        let (dk, ek) = MlKem768::generate(&mut OsRng);
+        let x25519 = StaticSecret::random_from_rng(OsRng);
+        let (x25519_pk, ml_kem) = recipient(&x25519, &ek);
+
+        let envelope = seal("did:iota:tst:0x1#x25519-1", &x25519_pk, &ml_kem, b"attestation evidence");
+        assert_eq!(open(&envelope, &x25519, &dk).unwrap(), b"attestation evidence");
+
+        // The envelope survives the JSON round trip it makes through storage and the mailbox
+        let stored: HybridEnvelope = serde_json::from_slice(&serde_json::to_vec(&envelope).unwrap()).unwrap();
+        assert_eq!(open(&stored, &x25519, &dk).unwrap(), b"attestation evidence");
+    }
+
+    #[test]
+    fn wrong_keys_do_not_open() {
+        let (dk, ek) = MlKem768::generate(&mut OsRng);
+        let (other_dk, _) = MlKem768::generate(&mut OsRng);
+        let x25519 = StaticSecret::random_from_rng(OsRng);
+        let other_x25519 = StaticSecret::random_from_rng(OsRng);
+        let (x25519_pk, ml_kem) = recipient(&x25519, &ek);
+        let envelope = seal("did:iota:tst:0x1#x25519-1", &x25519_pk, &ml_kem, b"secret");
+
+        // Holding only one of the two private keys is not enough
+        assert!(matches!(open(&envelope, &x25519, &other_dk), Err(OpenError::Decryption)));
+        assert!(matches!(open(&envelope, &other_x25519, &dk), Err(OpenError::Decryption)));
+    }
+
+    #[test]
+    fn tampered_header_is_rejected() {
+        let (dk, ek) = MlKem768::generate(&mut OsRng);
+        let x25519 = StaticSecret::random_from_rng(OsRng);
+        let (x25519_pk, ml_kem) = recipient(&x25519, &ek);
+        let mut envelope = seal("did:iota:tst:0x1#x25519-1", &x25519_pk, &ml_kem, b"secret");
+
+        let mut header: Value = serde_json::from_slice(&BASE64URL.decode(&envelope.protected).unwrap()).unwrap();
+        header["kid"] = Value::String("did:iota:tst:0x2#x25519-1".to_string());
+        envelope.protected = BASE64URL.encode(header.to_string());
+        assert!(matches!(open(&envelope, &x25519, &dk), Err(OpenError::Decryption)));
+    }
+
+    #[test]
+    fn known_answer_key_generation() {
+        let kat = kat();
+        let (_, _, ek) = kat_keys(&kat);
+        assert_eq!(hex::encode(Sha256::digest(ek.as_bytes())), kat["ml_kem_public_key_sha256"].as_str().unwrap());
+    }
+
+    #[test]
+    fn known_answer_key_derivation() {
+        let kat = kat();
+        let (x25519, dk, _) = kat_keys(&kat);
+        let envelope: HybridEnvelope = serde_json::from_value(kat["envelope"].clone()).unwrap();
+        let header: ProtectedHeader = serde_json::from_slice(&BASE64URL.decode(&envelope.protected).unwrap()).unwrap();
+        let epk: [u8; 32] = BASE64URL.decode(&header.epk.x).unwrap().try_into().unwrap();
+        let kem_ciphertext = BASE64URL.decode(&header.ek).unwrap();
+        let encoded_ciphertext: Ciphertext<MlKem768> = kem_ciphertext.as_slice().try_into().unwrap();
+
+        let dh_secret = x25519.diffie_hellman(&PublicKey::from(epk));
+        assert_eq!(hex::encode(dh_secret.as_bytes()), kat["x25519_shared_secret"].as_str().unwrap());
+        let kem_secret = dk.decapsulate(&encoded_ciphertext).unwrap();
+        assert_eq!(hex::encode(kem_secret), kat["ml_kem_shared_secret"].as_str().unwrap());
+
+        let key = derive_key(
+            &kem_secret,
+            dh_secret.as_bytes(),
+            &epk,
+            PublicKey::from(&x25519).as_bytes(),
+            &kem_ciphertext,
+        );
+        assert_eq!(hex::encode(key), kat["content_key"].as_str().unwrap());
+    }
+
+    #[test]
+    fn known_answer_open() {
+        let kat = kat();
+        let (x25519, dk, _) = kat_keys(&kat);
+        let envelope: HybridEnvelope = serde_json::from_value(kat["envelope"].clone()).unwrap();
+        assert_eq!(open(&envelope, &x25519, &dk).unwrap(), kat["plaintext"].as_str().unwrap().as_bytes());
+    }
+}
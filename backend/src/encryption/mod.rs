@@ -0,0 +1,110 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD as BASE64URL, Engine};
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::ChaCha20Poly1305;
+use hkdf::Hkdf;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use utoipa::ToSchema;
+use x25519_dalek::{EphemeralSecret, PublicKey};
+
+use crate::identity_client::KeyAgreementMethod;
+
+pub mod hybrid;
+
+pub use hybrid::MlKemKey;
+
+pub const ALG_ECDH_ES_X25519: &str = "ECDH-ES+X25519";
+pub const ENC_C20P: &str = "C20P";
+const KDF_INFO: &[u8] = b"wot.id/encryption/v1";
+
+// Public key-agreement keys of a recipient DID, taken from its DID document
+#[derive(Debug, Clone)]
+pub struct RecipientKey {
+    pub kid: String,
+    pub public_key: [u8; 32],
+    // Post-quantum key; when present, envelopes use the hybrid KEM
+    pub ml_kem: Option<MlKemKey>,
+}
+
+impl RecipientKey {
+    // Pick the first X25519 method the recipient publishes, and its first ML-KEM-768 method if any
+    pub fn from_methods(methods: &[KeyAgreementMethod]) -> Option<Self> {
+        let ml_kem = methods.iter().filter(|m| m.alg.as_deref() == Some(hybrid::ML_KEM_768)).find_map(|m| {
+            let public_key = BASE64URL.decode(&m.x).ok()?;
+            (public_key.len() == hybrid::ML_KEM_768_PUBLIC_KEY_SIZE).then(|| MlKemKey { kid: m.id.clone(), public_key })
+        });
+        methods.iter().find(|m| m.crv.as_deref() == Some("X25519")).and_then(|m| {
+            let public_key = BASE64URL.decode(&m.x).ok()?.try_into().ok()?;
+            Some(Self { kid: m.id.clone(), public_key, ml_kem })
+        })
+    }
+}
+
+// Stored form of an encrypted payload: hybrid for recipients with an ML-KEM key, X25519-only otherwise
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(untagged)]
+pub enum Envelope {
+    Hybrid(hybrid::HybridEnvelope),
+    Classical(EncryptedEnvelope),
+}
+
+// Self-describing ciphertext envelope; fields follow JWE naming so clients can map it onto JOSE
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct EncryptedEnvelope {
+    pub alg: String,
+    pub enc: String,
+    pub kid: String,
+    pub epk: String,
+    pub iv: String,
+    pub ciphertext: String,
+}
+
+// Encrypt `plaintext` to the recipient, post-quantum hybrid whenever it publishes an ML-KEM-768 key
+pub fn seal(recipient: &RecipientKey, plaintext: &[u8]) -> Envelope {
+    match &recipient.ml_kem {
+        Some(ml_kem) => Envelope::Hybrid(hybrid::seal(&recipient.kid, &recipient.public_key, ml_kem, plaintext)),
+        None => {
+            tracing::warn!(
+                target: "backend::encryption",
+                "{} has no ML-KEM-768 key-agreement method; sealing with X25519 only",
+                recipient.kid
+            );
+            Envelope::Classical(seal_x25519(recipient, plaintext))
+        }
+    }
+}
+
+// Encrypt `plaintext` so that only the holder of the recipient's X25519 private key can read it
+fn seal_x25519(recipient: &RecipientKey, plaintext: &[u8]) -> EncryptedEnvelope {
+    let ephemeral = EphemeralSecret::random_from_rng(OsRng);
+    let epk = PublicKey::from(&ephemeral);
+    let shared = ephemeral.diffie_hellman(&PublicKey::from(recipient.public_key));
+
+    let key = derive_key(shared.as_bytes(), epk.as_bytes(), &recipient.public_key);
+    let cipher = ChaCha20Poly1305::new(&key.into());
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, Payload { msg: plaintext, aad: recipient.kid.as_bytes() })
+        .expect("ChaCha20-Poly1305 encryption cannot fail for in-memory buffers");
+
+    EncryptedEnvelope {
+        alg: ALG_ECDH_ES_X25519.to_string(),
+        enc: ENC_C20P.to_string(),
+        kid: recipient.kid.clone(),
+        epk: BASE64URL.encode(epk.as_bytes()),
+        iv: BASE64URL.encode(nonce),
+        ciphertext: BASE64URL.encode(ciphertext),
+    }
+}
+
+// Both public keys are bound into the KDF so an envelope cannot be replayed to another recipient
+fn derive_key(shared_secret: &[u8], epk: &[u8], recipient_pk: &[u8]) -> [u8; 32] {
+    let mut salt = Vec::with_capacity(64);
+    salt.extend_from_slice(epk);
+    salt.extend_from_slice(recipient_pk);
+    let mut key = [0u8; 32];
+    Hkdf::<Sha256>::new(Some(&salt), shared_secret)
+        .expand(KDF_INFO, &mut key)
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    key
+}
@@ -0,0 +1,855 @@
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Query, State,
+    },
+    http::{HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse,
+    },
+};
+use futures_util::{stream, Stream};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::convert::Infallible;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::{broadcast, watch};
+use utoipa::{IntoParams, ToSchema};
+use utoipa_axum::{router::OpenApiRouter, routes};
+use wot_config::{ConfigError, Settings};
+
+use crate::iota_rpc::IotaRpcClient;
+use crate::state::AppState;
+
+// Modules of the wot_id package whose events are streamed to clients
+pub const EVENT_MODULES: [&str; 5] = ["identity", "credentials", "trust", "mailbox", "governance"];
+
+// Fields of a Move event payload that carry DIDs, used for `did` filtering
+const DID_FIELDS: [&str; 8] = [
+    "did", "issuer", "holder", "source", "target", "actor", "sender_did", "recipient_did",
+];
+
+// A wot_id Move event as delivered to clients. `seq` is assigned by the hub and is the
+// cursor clients hand back on reconnect; the node poller saves it with its checkpoint, so
+// numbering continues across restarts.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ChainEvent {
+    pub seq: u64,
+    // Event struct name, e.g. `IdentityUpdated`
+    pub kind: String,
+    pub module: String,
+    pub object_id: Option<String>,
+    pub dids: Vec<String>,
+    pub tx_digest: String,
+    pub timestamp_ms: Option<u64>,
+    pub payload: Value,
+}
+
+impl ChainEvent {
+    // Build from an event object as returned by `iotax_queryEvents`; `seq` is filled in by the hub
+    pub fn from_node_event(event: &Value) -> Option<Self> {
+        let event_type = event.get("type")?.as_str()?;
+        // `<package>::<module>::<Name>`, possibly with type arguments
+        let mut parts = event_type.split('<').next()?.split("::");
+        let _package = parts.next()?;
+        let module = parts.next()?.to_string();
+        let kind = parts.next()?.to_string();
+
+        let payload = event.get("parsedJson").cloned().unwrap_or(Value::Null);
+        let dids = DID_FIELDS
+            .iter()
+            .filter_map(|field| payload.get(field).and_then(Value::as_str))
+            .map(str::to_string)
+            .collect();
+
+        Some(Self {
+            seq: 0,
+            kind,
+            module,
+            object_id: payload.get("object_id").and_then(Value::as_str).map(str::to_string),
+            dids,
+            tx_digest: event.pointer("/id/txDigest").and_then(Value::as_str).unwrap_or_default().to_string(),
+            timestamp_ms: event.get("timestampMs").and_then(|v| match v {
+                Value::String(s) => s.parse().ok(),
+                other => other.as_u64(),
+            }),
+            payload,
+        })
+    }
+}
+
+// Fan-out point between the ingest task and connected clients. Recent events are kept in a
+// ring buffer so a reconnecting client can resume from its last cursor.
+pub struct EventHub {
+    sender: broadcast::Sender<Arc<ChainEvent>>,
+    buffer: Mutex<HubBuffer>,
+    capacity: usize,
+    // Set on shutdown; ends every subscription and the ingest task
+    closed: watch::Sender<bool>,
+}
+
+struct HubBuffer {
+    next_seq: u64,
+    events: VecDeque<Arc<ChainEvent>>,
+}
+
+impl HubBuffer {
+    fn after(&self, cursor: u64) -> VecDeque<Arc<ChainEvent>> {
+        self.events.iter().filter(|e| e.seq > cursor).cloned().collect()
+    }
+
+    // A cursor can be resumed only if every event after it is still buffered
+    fn check(&self, cursor: u64) -> Result<(), CursorError> {
+        let last = self.next_seq - 1;
+        if cursor > last {
+            return Err(CursorError::Unknown { cursor, last });
+        }
+        let oldest = self.events.front().map(|e| e.seq).unwrap_or(self.next_seq);
+        if cursor < last && cursor + 1 < oldest {
+            return Err(CursorError::Expired { cursor, oldest });
+        }
+        Ok(())
+    }
+}
+
+// Why a client cannot resume from its cursor; it has to reconnect without one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CursorError {
+    // Ahead of every event this stream has issued
+    Unknown { cursor: u64, last: u64 },
+    // Events after it have left the buffer
+    Expired { cursor: u64, oldest: u64 },
+}
+
+impl fmt::Display for CursorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CursorError::Unknown { cursor, last } => {
+                write!(f, "Cursor {} was not issued by this stream (newest event is {}); reconnect without a cursor", cursor, last)
+            }
+            CursorError::Expired { cursor, oldest } => {
+                write!(f, "Events after cursor {} are no longer buffered (oldest is {}); reconnect without a cursor", cursor, oldest)
+            }
+        }
+    }
+}
+
+impl EventHub {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        Self {
+            sender,
+            buffer: Mutex::new(HubBuffer { next_seq: 1, events: VecDeque::with_capacity(capacity) }),
+            capacity: capacity.max(1),
+            closed: watch::Sender::new(false),
+        }
+    }
+
+    pub fn close(&self) {
+        self.closed.send_replace(true);
+    }
+
+    pub async fn closed(&self) {
+        let _ = self.closed.subscribe().wait_for(|&closed| closed).await;
+    }
+
+    // Sequence number the next event gets
+    pub fn next_seq(&self) -> u64 {
+        self.buffer.lock().expect("event buffer lock poisoned").next_seq
+    }
+
+    // Continue numbering from a saved checkpoint; never moves backwards
+    pub fn resume_at(&self, next_seq: u64) {
+        let mut buffer = self.buffer.lock().expect("event buffer lock poisoned");
+        buffer.next_seq = buffer.next_seq.max(next_seq);
+    }
+
+    pub fn publish(&self, mut event: ChainEvent) -> u64 {
+        let mut buffer = self.buffer.lock().expect("event buffer lock poisoned");
+        event.seq = buffer.next_seq;
+        buffer.next_seq += 1;
+        let event = Arc::new(event);
+        if buffer.events.len() == self.capacity {
+            buffer.events.pop_front();
+        }
+        buffer.events.push_back(event.clone());
+        // Sent under the lock so subscribers never see an event both in backlog and live
+        let _ = self.sender.send(event);
+        buffer.next_seq - 1
+    }
+
+    // Buffered events after `cursor`
+    fn since(&self, cursor: u64) -> VecDeque<Arc<ChainEvent>> {
+        self.buffer.lock().expect("event buffer lock poisoned").after(cursor)
+    }
+
+    // Live events only, for in-process consumers
+    pub fn subscribe_live(self: &Arc<Self>, filter: EventFilter) -> Subscription {
+        self.subscribe(None, filter).expect("a subscription without a cursor has nothing to resume")
+    }
+
+    // Everything still buffered, then live events, for in-process consumers that start
+    // alongside the ingest task
+    pub fn subscribe_buffered(self: &Arc<Self>, filter: EventFilter) -> Subscription {
+        let cursor = {
+            let buffer = self.buffer.lock().expect("event buffer lock poisoned");
+            buffer.events.front().map(|e| e.seq - 1).unwrap_or(buffer.next_seq - 1)
+        };
+        // Only fails if events were evicted in between; the live subscription still holds
+        self.subscribe(Some(cursor), filter.clone()).unwrap_or_else(|_| self.subscribe_live(filter))
+    }
+
+    // Live events, preceded by the buffered ones after `cursor`. A cursor that cannot be
+    // resumed without a gap is rejected rather than silently skipping events.
+    pub fn subscribe(self: &Arc<Self>, cursor: Option<u64>, filter: EventFilter) -> Result<Subscription, CursorError> {
+        let buffer = self.buffer.lock().expect("event buffer lock poisoned");
+        let backlog = match cursor {
+            Some(cursor) => {
+                buffer.check(cursor)?;
+                buffer.after(cursor)
+            }
+            None => VecDeque::new(),
+        };
+        // Subscribed under the lock, so nothing published in between is missed
+        let receiver = self.sender.subscribe();
+        drop(buffer);
+        Ok(Subscription {
+            hub: self.clone(),
+            receiver,
+            closed: self.closed.subscribe(),
+            backlog,
+            last_seq: cursor.unwrap_or(0),
+            filter,
+        })
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct EventFilter {
+    pub did: Option<String>,
+    pub object_id: Option<String>,
+    pub kinds: Vec<String>,
+}
+
+impl EventFilter {
+    fn matches(&self, event: &ChainEvent) -> bool {
+        self.did.as_ref().is_none_or(|did| event.dids.contains(did))
+            && self.object_id.as_ref().is_none_or(|id| event.object_id.as_ref() == Some(id))
+            && (self.kinds.is_empty() || self.kinds.contains(&event.kind))
+    }
+}
+
+// One client's view of the hub: buffered backlog first, then live events
+pub struct Subscription {
+    hub: Arc<EventHub>,
+    receiver: broadcast::Receiver<Arc<ChainEvent>>,
+    closed: watch::Receiver<bool>,
+    backlog: VecDeque<Arc<ChainEvent>>,
+    last_seq: u64,
+    filter: EventFilter,
+}
+
+impl Subscription {
+    // `None` once the hub is closed
+    pub async fn next(&mut self) -> Option<Arc<ChainEvent>> {
+        loop {
+            if *self.closed.borrow() {
+                return None;
+            }
+            let event = match self.backlog.pop_front() {
+                Some(event) => event,
+                None => tokio::select! {
+                    received = self.receiver.recv() => match received {
+                        Ok(event) => event,
+                        // A slow client fell behind the channel; catch up from the ring buffer
+                        Err(broadcast::error::RecvError::Lagged(_)) => {
+                            self.backlog = self.hub.since(self.last_seq);
+                            continue;
+                        }
+                        Err(broadcast::error::RecvError::Closed) => return None,
+                    },
+                    _ = self.closed.wait_for(|&closed| closed) => return None,
+                },
+            };
+            if event.seq <= self.last_seq {
+                continue;
+            }
+            self.last_seq = event.seq;
+            if self.filter.matches(&event) {
+                return Some(event);
+            }
+        }
+    }
+}
+
+// Where ingested events come from. `Fixture` replays a JSONL file of node events, one
+// `iotax_queryEvents` result object per line, and stands in for the node in tests and demos.
+pub enum EventSource {
+    Node { rpc: IotaRpcClient, package_id: String, poll_interval: Duration, checkpoint: CursorCheckpoint },
+    Fixture { path: String, interval: Duration },
+}
+
+impl EventSource {
+    // EVENT_SOURCE is `node` (default) or `fixture:<path>`
+    pub fn from_settings(settings: &Settings, state: &AppState) -> Result<Self, ConfigError> {
+        let poll_interval = Duration::from_millis(settings.parse_or("EVENT_POLL_INTERVAL_MS", 2000)?);
+        let source = settings.string_or("EVENT_SOURCE", "node");
+        match source.strip_prefix("fixture:") {
+            Some(path) => Ok(EventSource::Fixture { path: path.to_string(), interval: poll_interval }),
+            None if source == "node" => Ok(EventSource::Node {
+                rpc: state.rpc.clone(),
+                package_id: state.config.wot_id_pkg_id.clone(),
+                poll_interval,
+                checkpoint: CursorCheckpoint {
+                    path: settings.string_or("EVENT_CURSOR_PATH", "./data/event_cursors.json").into(),
+                },
+            }),
+            None => Err(ConfigError::Invalid {
+                key: "EVENT_SOURCE".to_string(),
+                value: source,
+                reason: "expected node or fixture:<path>".to_string(),
+            }),
+        }
+    }
+}
+
+// Node query cursors per module and the hub's next `seq`, saved so a restart resumes where the
+// last process stopped
+pub struct CursorCheckpoint {
+    path: PathBuf,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedCursors {
+    package_id: String,
+    cursors: HashMap<String, Value>,
+    #[serde(default)]
+    next_seq: u64,
+}
+
+impl CursorCheckpoint {
+    // Checkpoint saved for `package_id`; none after a package change
+    async fn load(&self, package_id: &str) -> Option<SavedCursors> {
+        let saved = match tokio::fs::read(&self.path).await {
+            Ok(bytes) => serde_json::from_slice::<SavedCursors>(&bytes).ok(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => {
+                tracing::warn!(target: "backend::events", "Failed to read event cursors {}: {}", self.path.display(), e);
+                None
+            }
+        };
+        saved.filter(|saved| saved.package_id == package_id)
+    }
+
+    async fn save(&self, package_id: &str, cursors: &[Value], next_seq: u64) {
+        let saved = SavedCursors {
+            package_id: package_id.to_string(),
+            next_seq,
+            cursors: EVENT_MODULES
+                .iter()
+                .zip(cursors)
+                .filter(|(_, cursor)| !cursor.is_null())
+                .map(|(module, cursor)| (module.to_string(), cursor.clone()))
+                .collect(),
+        };
+        let write = async {
+            if let Some(parent) = self.path.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            let tmp = self.path.with_extension("json.tmp");
+            tokio::fs::write(&tmp, serde_json::to_vec_pretty(&saved)?).await?;
+            tokio::fs::rename(&tmp, &self.path).await?;
+            Ok::<_, anyhow::Error>(())
+        };
+        if let Err(e) = write.await {
+            tracing::error!(target: "backend::events", "Failed to save event cursors {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+// Long-running ingest task: pull events from the source and publish them to the hub until it
+// is closed
+pub async fn ingest(source: EventSource, hub: Arc<EventHub>) {
+    match source {
+        EventSource::Node { rpc, package_id, poll_interval, checkpoint } => {
+            if package_id.is_empty() {
+                tracing::warn!(target: "backend::events", "WOT_ID_PKG_ID not set; on-chain event streaming is disabled");
+                return;
+            }
+            poll_node(&rpc, &package_id, poll_interval, &checkpoint, &hub).await;
+        }
+        EventSource::Fixture { path, interval } => {
+            tokio::select! {
+                () = replay_fixture(&path, interval, &hub) => {}
+                () = hub.closed() => {}
+            }
+        }
+    }
+}
+
+async fn poll_node(rpc: &IotaRpcClient, package_id: &str, poll_interval: Duration, checkpoint: &CursorCheckpoint, hub: &EventHub) {
+    // Resume from the checkpoint; without one, start at the current tip of each module so
+    // history is not re-broadcast
+    let mut saved = match checkpoint.load(package_id).await {
+        Some(saved) => {
+            hub.resume_at(saved.next_seq);
+            saved.cursors
+        }
+        None => HashMap::new(),
+    };
+    let resumed = !saved.is_empty();
+    let mut cursors: Vec<Value> = Vec::with_capacity(EVENT_MODULES.len());
+    for module in EVENT_MODULES {
+        let cursor = match saved.remove(module) {
+            Some(cursor) => cursor,
+            None => rpc
+                .call("iotax_queryEvents", json!([module_filter(package_id, module), null, 1, true]))
+                .await
+                .ok()
+                .and_then(|page| page.pointer("/data/0/id").cloned())
+                .unwrap_or(Value::Null),
+        };
+        cursors.push(cursor);
+    }
+    tracing::info!(
+        target: "backend::events",
+        "Streaming wot_id events from package {}{}",
+        package_id,
+        if resumed { ", resuming from saved cursors" } else { "" }
+    );
+
+    let mut interval = tokio::time::interval(poll_interval);
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            () = hub.closed() => break,
+        }
+        let before = cursors.clone();
+        let mut caught_up = true;
+        for (module, cursor) in EVENT_MODULES.iter().zip(cursors.iter_mut()) {
+            loop {
+                let page = match rpc
+                    .call("iotax_queryEvents", json!([module_filter(package_id, module), cursor, 50, false]))
+                    .await
+                {
+                    Ok(page) => page,
+                    Err(e) => {
+                        tracing::warn!(target: "backend::events", "Polling {} events failed: {}", module, e);
+                        caught_up = false;
+                        break;
+                    }
+                };
+                for event in page.get("data").and_then(Value::as_array).into_iter().flatten() {
+                    if let Some(event) = ChainEvent::from_node_event(event) {
+                        record_ingested(&event);
+                        hub.publish(event);
+                    }
+                }
+                if let Some(next) = page.get("nextCursor").filter(|c| !c.is_null()) {
+                    *cursor = next.clone();
+                }
+                if !page.get("hasNextPage").and_then(Value::as_bool).unwrap_or(false) {
+                    break;
+                }
+            }
+        }
+        // With no new events the lag gauge goes stale; alert on the age of the last full poll
+        if caught_up {
+            metrics::gauge!("events_last_poll_timestamp_seconds").set(now_ms() as f64 / 1000.0);
+        }
+        if cursors != before {
+            checkpoint.save(package_id, &cursors, hub.next_seq()).await;
+        }
+    }
+    checkpoint.save(package_id, &cursors, hub.next_seq()).await;
+    tracing::info!(target: "backend::events", "Event ingest stopped; cursors saved to {}", checkpoint.path.display());
+}
+
+// Indexer lag: how long after its checkpoint an event reached the hub
+fn record_ingested(event: &ChainEvent) {
+    metrics::counter!("events_ingested_total", "module" => event.module.clone()).increment(1);
+    if let Some(timestamp_ms) = event.timestamp_ms {
+        metrics::gauge!("events_ingest_lag_seconds").set(now_ms().saturating_sub(timestamp_ms) as f64 / 1000.0);
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+fn module_filter(package_id: &str, module: &str) -> Value {
+    json!({ "MoveEventModule": { "package": package_id, "module": module } })
+}
+
+async fn replay_fixture(path: &str, interval: Duration, hub: &EventHub) {
+    let contents = match tokio::fs::read_to_string(path).await {
+        Ok(contents) => contents,
+        Err(e) => {
+            tracing::error!(target: "backend::events", "Failed to read event fixture {}: {}", path, e);
+            return;
+        }
+    };
+    tracing::info!(target: "backend::events", "Replaying event fixture {}", path);
+    for (line_no, line) in contents.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+        match serde_json::from_str::<Value>(line).ok().as_ref().and_then(ChainEvent::from_node_event) {
+            Some(event) => {
+                hub.publish(event);
+            }
+            None => tracing::warn!(target: "backend::events", "Skipping malformed fixture line {} in {}", line_no + 1, path),
+        }
+        tokio::time::sleep(interval).await;
+    }
+}
+
+pub fn routes() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new().routes(routes!(events_ws)).routes(routes!(events_sse))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct EventQuery {
+    pub did: Option<String>,
+    pub object_id: Option<String>,
+    // Comma-separated event kinds, e.g. `IdentityUpdated,CredentialRevoked`
+    pub types: Option<String>,
+    // Resume after this sequence number
+    pub cursor: Option<u64>,
+}
+
+impl EventQuery {
+    fn filter(&self) -> EventFilter {
+        EventFilter {
+            did: self.did.clone(),
+            object_id: self.object_id.clone(),
+            kinds: self
+                .types
+                .as_deref()
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|k| !k.is_empty())
+                .map(str::to_string)
+                .collect(),
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/events/ws",
+    tag = "events",
+    params(EventQuery),
+    responses(
+        (status = 101, description = "WebSocket sending each matching event as a JSON `ChainEvent` text message"),
+        (status = 410, description = "The cursor cannot be resumed; reconnect without one", body = String),
+    )
+)]
+async fn events_ws(
+    State(state): State<AppState>,
+    Query(query): Query<EventQuery>,
+    upgrade: WebSocketUpgrade,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let subscription = state.events.subscribe(query.cursor, query.filter()).map_err(cursor_gone)?;
+    Ok(upgrade.on_upgrade(move |socket| stream_to_socket(socket, subscription)))
+}
+
+fn cursor_gone(e: CursorError) -> (StatusCode, String) {
+    (StatusCode::GONE, e.to_string())
+}
+
+async fn stream_to_socket(mut socket: WebSocket, mut subscription: Subscription) {
+    loop {
+        tokio::select! {
+            event = subscription.next() => {
+                let Some(event) = event else { break };
+                let text = serde_json::to_string(&*event).expect("event serializes to JSON");
+                if socket.send(Message::Text(text.into())).await.is_err() {
+                    break;
+                }
+            }
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                _ => {}
+            },
+        }
+    }
+}
+
+// SSE clients resume with either `?cursor=` or the standard `Last-Event-ID` header
+#[utoipa::path(
+    get,
+    path = "/api/v1/events/sse",
+    tag = "events",
+    params(EventQuery, ("Last-Event-ID" = Option<u64>, Header, description = "Resume after this sequence number")),
+    responses(
+        (
+            status = 200,
+            description = "Server-sent events named after the event kind, with `seq` as ID and a `ChainEvent` as data",
+            body = ChainEvent,
+            content_type = "text/event-stream",
+        ),
+        (status = 410, description = "The cursor cannot be resumed; reconnect without one", body = String),
+    )
+)]
+async fn events_sse(
+    State(state): State<AppState>,
+    Query(query): Query<EventQuery>,
+    headers: HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, (StatusCode, String)> {
+    let cursor = query.cursor.or_else(|| {
+        headers
+            .get("last-event-id")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse().ok())
+    });
+    let subscription = state.events.subscribe(cursor, query.filter()).map_err(cursor_gone)?;
+    let events = stream::unfold(subscription, |mut subscription| async move {
+        let event = subscription.next().await?;
+        let sse = Event::default()
+            .id(event.seq.to_string())
+            .event(event.kind.clone())
+            .json_data(&*event)
+            .expect("event serializes to JSON");
+        Some((Ok(sse), subscription))
+    });
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/events.jsonl");
+
+    // A hub with the five fixture events (seq 1..=5) ingested through the fixture source
+    async fn replayed(capacity: usize) -> Arc<EventHub> {
+        let hub = Arc::new(EventHub::new(capacity));
+        let source = EventSource::Fixture { path: FIXTURE.to_string(), interval: Duration::ZERO };
+        ingest(source, hub.clone()).await;
+        hub
+    }
+
+    async fn take(subscription: &mut Subscription, n: usize) -> Vec<(u64, String)> {
+        let mut events = Vec::new();
+        while events.len() < n {
+            let event = tokio::time::timeout(Duration::from_secs(1), subscription.next())
+                .await
+                .expect("event within a second")
+                .expect("hub open");
+            events.push((event.seq, event.kind.clone()));
+        }
+        events
+    }
+
+    fn kinds(events: &[(u64, String)]) -> Vec<&str> {
+        events.iter().map(|(_, kind)| kind.as_str()).collect()
+    }
+
+    fn live_event(kind: &str) -> ChainEvent {
+        ChainEvent::from_node_event(&json!({
+            "id": { "txDigest": "live" },
+            "type": format!("0x1::identity::{}", kind),
+            "parsedJson": { "object_id": "0x0b1e", "did": "did:iota:0xa11ce" },
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn replays_the_fixture_in_order() {
+        let hub = replayed(16).await;
+        let mut subscription = hub.subscribe(Some(0), EventFilter::default()).unwrap();
+        let events = take(&mut subscription, 5).await;
+        assert_eq!(events.iter().map(|(seq, _)| *seq).collect::<Vec<_>>(), vec![1, 2, 3, 4, 5]);
+        assert_eq!(
+            kinds(&events),
+            vec!["IdentityCreated", "IdentityUpdated", "CredentialIssued", "TrustChanged", "CredentialRevoked"]
+        );
+    }
+
+    #[tokio::test]
+    async fn resumes_after_cursor_then_follows_live_events() {
+        let hub = replayed(16).await;
+        let mut subscription = hub.subscribe(Some(3), EventFilter::default()).unwrap();
+        hub.publish(live_event("IdentityUpdated"));
+        let events = take(&mut subscription, 3).await;
+        assert_eq!(events.iter().map(|(seq, _)| *seq).collect::<Vec<_>>(), vec![4, 5, 6]);
+
+        // Without a cursor only live events are sent
+        let mut subscription = hub.subscribe(None, EventFilter::default()).unwrap();
+        hub.publish(live_event("IdentityCreated"));
+        assert_eq!(take(&mut subscription, 1).await, vec![(7, "IdentityCreated".to_string())]);
+    }
+
+    #[tokio::test]
+    async fn filters_by_did_object_and_kind() {
+        let hub = replayed(16).await;
+        let subscribe = |filter| hub.subscribe(Some(0), filter).unwrap();
+
+        let mut by_did = subscribe(EventFilter { did: Some("did:iota:0xb0b".to_string()), ..Default::default() });
+        assert_eq!(
+            kinds(&take(&mut by_did, 3).await),
+            vec!["CredentialIssued", "TrustChanged", "CredentialRevoked"]
+        );
+
+        let mut by_object = subscribe(EventFilter { object_id: Some("0xc4ed".to_string()), ..Default::default() });
+        assert_eq!(kinds(&take(&mut by_object, 2).await), vec!["CredentialIssued", "CredentialRevoked"]);
+
+        let query = EventQuery {
+            did: Some("did:iota:0xa11ce".to_string()),
+            object_id: None,
+            types: Some("IdentityUpdated, TrustChanged".to_string()),
+            cursor: Some(0),
+        };
+        let mut by_kind = subscribe(query.filter());
+        assert_eq!(kinds(&take(&mut by_kind, 2).await), vec!["IdentityUpdated", "TrustChanged"]);
+    }
+
+    #[tokio::test]
+    async fn rejects_cursors_that_cannot_be_resumed() {
+        // Only seq 3..=5 are still buffered
+        let hub = replayed(3).await;
+        assert!(matches!(
+            hub.subscribe(Some(6), EventFilter::default()),
+            Err(CursorError::Unknown { cursor: 6, last: 5 })
+        ));
+        assert!(matches!(
+            hub.subscribe(Some(1), EventFilter::default()),
+            Err(CursorError::Expired { cursor: 1, oldest: 3 })
+        ));
+        let mut subscription = hub.subscribe(Some(2), EventFilter::default()).unwrap();
+        assert_eq!(take(&mut subscription, 3).await.first().map(|(seq, _)| *seq), Some(3));
+        assert!(hub.subscribe(Some(5), EventFilter::default()).is_ok());
+    }
+
+    // Node with a growing list of `identity` events and none in the other modules. Records each
+    // query as (module, cursor, descending).
+    #[derive(Clone, Default)]
+    struct Chain {
+        identity_events: Arc<Mutex<Vec<Value>>>,
+        queries: Arc<Mutex<Vec<(String, Value, bool)>>>,
+    }
+
+    impl Chain {
+        fn emit(&self, tx_digest: &str) {
+            self.identity_events.lock().unwrap().push(json!({
+                "id": { "txDigest": tx_digest, "eventSeq": "0" },
+                "type": "0x1::identity::IdentityCreated",
+                "parsedJson": { "object_id": format!("0x{}", tx_digest), "did": "did:iota:0xa11ce" },
+            }));
+        }
+
+        async fn serve(&self) -> IotaRpcClient {
+            let chain = self.clone();
+            let app = axum::Router::new().route(
+                "/",
+                axum::routing::post(move |axum::Json(request): axum::Json<Value>| {
+                    let chain = chain.clone();
+                    async move {
+                        assert_eq!(request["method"], "iotax_queryEvents");
+                        let params = &request["params"];
+                        let module = params[0]["MoveEventModule"]["module"].as_str().unwrap().to_string();
+                        let (cursor, descending) = (params[1].clone(), params[3].as_bool().unwrap());
+                        chain.queries.lock().unwrap().push((module.clone(), cursor.clone(), descending));
+                        let events = if module == "identity" { chain.identity_events.lock().unwrap().clone() } else { Vec::new() };
+                        let data: Vec<Value> = if descending {
+                            events.last().cloned().into_iter().collect()
+                        } else {
+                            let after = events.iter().position(|event| event["id"] == cursor).map_or(0, |i| i + 1);
+                            events[after..].to_vec()
+                        };
+                        let next_cursor = data.last().map_or(Value::Null, |event| event["id"].clone());
+                        let result = json!({ "data": data, "nextCursor": next_cursor, "hasNextPage": false });
+                        axum::Json(json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }))
+                    }
+                }),
+            );
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            tokio::spawn(async move { axum::serve(listener, app).await });
+            IotaRpcClient::new(reqwest::Client::new(), format!("http://{}/", addr))
+        }
+
+        fn tip_queries(&self, module: &str) -> usize {
+            self.queries.lock().unwrap().iter().filter(|(m, _, descending)| m == module && *descending).count()
+        }
+    }
+
+    // Run the node ingest until the next event arrives, then shut it down as on SIGTERM
+    async fn ingest_one(rpc: IotaRpcClient, checkpoint_path: &std::path::Path, chain: &Chain, tx_digest: &str) -> (u64, String) {
+        let hub = Arc::new(EventHub::new(16));
+        let mut subscription = hub.subscribe(None, EventFilter::default()).unwrap();
+        let source = EventSource::Node {
+            rpc,
+            package_id: "0x1".to_string(),
+            poll_interval: Duration::from_millis(10),
+            checkpoint: CursorCheckpoint { path: checkpoint_path.to_path_buf() },
+        };
+        let task = tokio::spawn(ingest(source, hub.clone()));
+        // Let the ingest find its starting cursors before the event is emitted
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        chain.emit(tx_digest);
+        let event = tokio::time::timeout(Duration::from_secs(2), subscription.next()).await.unwrap().unwrap();
+        hub.close();
+        task.await.unwrap();
+        (event.seq, event.tx_digest.clone())
+    }
+
+    #[tokio::test]
+    async fn a_restart_resumes_from_the_saved_cursors() {
+        let dir = std::env::temp_dir().join(format!("wot-events-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("cursors.json");
+        let chain = Chain::default();
+        chain.emit("t1");
+
+        // Without a checkpoint, ingest starts at the tip: t1 is history and not broadcast
+        assert_eq!(ingest_one(chain.serve().await, &path, &chain, "t2").await, (1, "t2".to_string()));
+        assert_eq!(chain.tip_queries("identity"), 1);
+        let saved: Value = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!(saved["cursors"]["identity"]["txDigest"], "t2");
+        assert_eq!(saved["next_seq"], 2);
+
+        // Emitted while the backend was down; delivered after the restart, numbered after t2
+        chain.emit("t3");
+        let resumed = ingest_one(chain.serve().await, &path, &chain, "t4").await;
+        let _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(resumed, (2, "t3".to_string()));
+        assert_eq!(chain.tip_queries("identity"), 1);
+    }
+
+    #[tokio::test]
+    async fn unreadable_checkpoints_are_ignored() {
+        let dir = std::env::temp_dir().join(format!("wot-events-{}", uuid::Uuid::new_v4()));
+        let checkpoint = CursorCheckpoint { path: dir.join("cursors.json") };
+        assert!(checkpoint.load("0x1").await.is_none());
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(&checkpoint.path, "not json").unwrap();
+        let corrupt = checkpoint.load("0x1").await;
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(corrupt.is_none());
+    }
+
+    #[tokio::test]
+    async fn numbering_continues_from_the_checkpoint() {
+        let dir = std::env::temp_dir().join(format!("wot-events-{}", uuid::Uuid::new_v4()));
+        let checkpoint = CursorCheckpoint { path: dir.join("cursors.json") };
+        checkpoint.save("0x1", &[json!({ "txDigest": "a", "eventSeq": "0" })], 42).await;
+        let (saved, other_package) = (checkpoint.load("0x1").await, checkpoint.load("0x2").await);
+        let _ = std::fs::remove_dir_all(&dir);
+        let saved = saved.expect("checkpoint saved");
+        assert!(other_package.is_none());
+
+        // After a restart the buffer is empty: the last issued cursor resumes, older ones do not
+        let hub = Arc::new(EventHub::new(16));
+        hub.resume_at(saved.next_seq);
+        assert!(hub.subscribe(Some(41), EventFilter::default()).is_ok());
+        assert!(matches!(hub.subscribe(Some(40), EventFilter::default()), Err(CursorError::Expired { .. })));
+        assert_eq!(hub.publish(live_event("IdentityCreated")), 42);
+        hub.resume_at(1);
+        assert_eq!(hub.next_seq(), 43);
+    }
+}
@@ -0,0 +1,475 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use utoipa::{IntoParams, ToSchema};
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+use crate::iota_rpc::TransactionBlockBytes;
+use crate::state::AppState;
+use crate::storage::{self, StorageError};
+
+// Attestations have no `content_hash` field on-chain, so the hash rides along in `method`
+const ATTESTATION_HASH_SEPARATOR: &str = "#sha256:";
+
+pub fn routes() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new()
+        .routes(routes!(add_trust_evidence))
+        .routes(routes!(add_claim_attestation))
+        .routes(routes!(verify_evidence))
+        .routes(routes!(get_evidence))
+}
+
+// Evidence content is either a JSON document or an arbitrary file, base64-encoded
+#[derive(Deserialize, ToSchema)]
+pub struct EvidenceContent {
+    pub document: Option<Value>,
+    pub content_base64: Option<String>,
+}
+
+impl EvidenceContent {
+    pub(crate) fn into_bytes(self) -> Result<Vec<u8>, (StatusCode, String)> {
+        match (self.document, self.content_base64) {
+            (Some(doc), None) => serde_json::to_vec(&doc)
+                .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid evidence document: {}", e))),
+            (None, Some(encoded)) => BASE64
+                .decode(encoded)
+                .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid base64 evidence content: {}", e))),
+            _ => Err((
+                StatusCode::BAD_REQUEST,
+                "Exactly one of 'document' or 'content_base64' must be provided".to_string(),
+            )),
+        }
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct AddTrustEvidenceRequest {
+    pub signer: String,
+    pub identity_id: String,
+    pub relationship_id: String,
+    pub evidence_type: String,
+    pub weight: u64,
+    #[serde(flatten)]
+    pub content: EvidenceContent,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct AddClaimAttestationRequest {
+    pub signer: String,
+    pub attestor_identity_id: String,
+    pub claim_trust_id: String,
+    pub method: String,
+    pub strength: u64,
+    #[serde(flatten)]
+    pub content: EvidenceContent,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct EvidenceSubmissionResponse {
+    pub content_hash: String,
+    pub transaction: TransactionBlockBytes,
+}
+
+async fn store_content(state: &AppState, content: EvidenceContent) -> Result<String, (StatusCode, String)> {
+    let bytes = content.into_bytes()?;
+    state.blobs.put(&bytes).await.map_err(|e| {
+        tracing::error!(target: "backend::evidence", "Failed to store evidence blob: {}", e);
+        (StatusCode::INTERNAL_SERVER_ERROR, "Failed to store evidence".to_string())
+    })
+}
+
+// Store the evidence off-chain and build an `add_trust_evidence` transaction for the signer's wallet
+#[utoipa::path(
+    post,
+    path = "/api/v1/trust/evidence",
+    tag = "evidence",
+    request_body = AddTrustEvidenceRequest,
+    responses(
+        (status = 200, body = EvidenceSubmissionResponse),
+        (status = 400, body = String),
+        (status = 502, description = "The node could not build the transaction", body = String),
+    )
+)]
+async fn add_trust_evidence(
+    State(state): State<AppState>,
+    Json(payload): Json<AddTrustEvidenceRequest>,
+) -> Result<Json<EvidenceSubmissionResponse>, (StatusCode, String)> {
+    if payload.weight > 100 {
+        return Err((StatusCode::BAD_REQUEST, "Evidence weight must be between 0 and 100".to_string()));
+    }
+    let hash = store_content(&state, payload.content).await?;
+    let hash_bytes =
+        hex::decode(&hash).map_err(|_| (StatusCode::BAD_REQUEST, format!("Invalid content hash '{}'", hash)))?;
+
+    let transaction = state
+        .rpc
+        .move_call(
+            &payload.signer,
+            &state.config.wot_id_pkg_id,
+            "trust",
+            "add_trust_evidence",
+            vec![
+                json!(payload.identity_id),
+                json!(payload.relationship_id),
+                json!(payload.evidence_type.into_bytes()),
+                json!(hash_bytes),
+                json!(payload.weight.to_string()),
+            ],
+            state.config.gas_budget,
+        )
+        .await
+        .map_err(|e| {
+            tracing::error!(target: "backend::evidence", "Failed to build add_trust_evidence transaction: {}", e);
+            (StatusCode::BAD_GATEWAY, format!("Failed to build transaction: {}", e))
+        })?;
+
+    Ok(Json(EvidenceSubmissionResponse { content_hash: hash, transaction }))
+}
+
+// Store the attestation material off-chain and build an `add_claim_attestation` transaction
+#[utoipa::path(
+    post,
+    path = "/api/v1/claims/attestations",
+    tag = "evidence",
+    request_body = AddClaimAttestationRequest,
+    responses(
+        (status = 200, body = EvidenceSubmissionResponse),
+        (status = 400, body = String),
+        (status = 502, description = "The node could not build the transaction", body = String),
+    )
+)]
+async fn add_claim_attestation(
+    State(state): State<AppState>,
+    Json(payload): Json<AddClaimAttestationRequest>,
+) -> Result<Json<EvidenceSubmissionResponse>, (StatusCode, String)> {
+    if payload.strength > 100 {
+        return Err((StatusCode::BAD_REQUEST, "Attestation strength must be between 0 and 100".to_string()));
+    }
+    if payload.method.contains(ATTESTATION_HASH_SEPARATOR) {
+        return Err((StatusCode::BAD_REQUEST, "Attestation method must not contain a content hash".to_string()));
+    }
+    let hash = store_content(&state, payload.content).await?;
+    let method = format!("{}{}{}", payload.method, ATTESTATION_HASH_SEPARATOR, hash);
+
+    let transaction = state
+        .rpc
+        .move_call(
+            &payload.signer,
+            &state.config.wot_id_pkg_id,
+            "trust",
+            "add_claim_attestation",
+            vec![
+                json!(payload.claim_trust_id),
+                json!(payload.attestor_identity_id),
+                json!(method.into_bytes()),
+                json!(payload.strength.to_string()),
+            ],
+            state.config.gas_budget,
+        )
+        .await
+        .map_err(|e| {
+            tracing::error!(target: "backend::evidence", "Failed to build add_claim_attestation transaction: {}", e);
+            (StatusCode::BAD_GATEWAY, format!("Failed to build transaction: {}", e))
+        })?;
+
+    Ok(Json(EvidenceSubmissionResponse { content_hash: hash, transaction }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/evidence/{hash}",
+    tag = "evidence",
+    params(("hash" = String, Path, description = "Lowercase hex SHA-256 of the content")),
+    responses(
+        (status = 200, body = [u8], content_type = "application/octet-stream"),
+        (status = 400, body = String),
+        (status = 404, body = String),
+    )
+)]
+async fn get_evidence(
+    State(state): State<AppState>,
+    Path(hash): Path<String>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    if !storage::is_valid_hash(&hash) {
+        return Err((StatusCode::BAD_REQUEST, "hash must be a lowercase hex SHA-256".to_string()));
+    }
+    match state.blobs.get(&hash).await {
+        Ok(Some(bytes)) => Ok(([(header::CONTENT_TYPE, "application/octet-stream")], bytes)),
+        Ok(None) => Err((StatusCode::NOT_FOUND, format!("No evidence stored under {}", hash))),
+        Err(e) => {
+            tracing::error!(target: "backend::evidence", "Failed to read evidence blob {}: {}", hash, e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, "Failed to read evidence".to_string()))
+        }
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct VerifyEvidenceQuery {
+    pub object_id: String,
+    pub content_hash: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct VerifyEvidenceResponse {
+    pub object_id: String,
+    pub content_hash: String,
+    // The hash is referenced by the on-chain TrustRelationship or ClaimTrust
+    pub anchored: bool,
+    // The stored blob still hashes to the anchored value
+    pub content_matches: bool,
+}
+
+// Re-fetch evidence and check it against the hash anchored in a TrustRelationship or ClaimTrust
+#[utoipa::path(
+    get,
+    path = "/api/v1/evidence/verify",
+    tag = "evidence",
+    params(VerifyEvidenceQuery),
+    responses(
+        (status = 200, body = VerifyEvidenceResponse),
+        (status = 400, body = String),
+        (status = 502, description = "The object could not be fetched from the node", body = String),
+    )
+)]
+async fn verify_evidence(
+    State(state): State<AppState>,
+    Query(query): Query<VerifyEvidenceQuery>,
+) -> Result<Json<VerifyEvidenceResponse>, (StatusCode, String)> {
+    if !storage::is_valid_hash(&query.content_hash) {
+        return Err((StatusCode::BAD_REQUEST, "content_hash must be a lowercase hex SHA-256".to_string()));
+    }
+
+    let fields = state.rpc.get_object_fields(&query.object_id).await.map_err(|e| {
+        tracing::error!(target: "backend::evidence", "Failed to fetch object {}: {}", query.object_id, e);
+        (StatusCode::BAD_GATEWAY, format!("Failed to fetch object: {}", e))
+    })?;
+    let anchored = anchored_hashes(&fields).contains(&query.content_hash);
+
+    // The store re-hashes blobs on read, so a successful fetch means the content still matches
+    let content_matches = match state.blobs.get(&query.content_hash).await {
+        Ok(Some(_)) => true,
+        Ok(None) | Err(StorageError::IntegrityMismatch { .. }) => false,
+        Err(e) => {
+            tracing::error!(target: "backend::evidence", "Failed to read evidence blob {}: {}", query.content_hash, e);
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, "Failed to read evidence".to_string()));
+        }
+    };
+
+    Ok(Json(VerifyEvidenceResponse {
+        object_id: query.object_id,
+        content_hash: query.content_hash,
+        anchored,
+        content_matches,
+    }))
+}
+
+// Collect every content hash referenced by a TrustRelationship's evidence or a ClaimTrust's attestations
+fn anchored_hashes(fields: &Value) -> Vec<String> {
+    let mut hashes = Vec::new();
+
+    if let Some(evidence) = fields.get("evidence").and_then(Value::as_array) {
+        for entry in evidence {
+            let bytes: Option<Vec<u8>> = entry
+                .pointer("/fields/content_hash")
+                .and_then(|v| serde_json::from_value(v.clone()).ok());
+            if let Some(bytes) = bytes {
+                hashes.push(hex::encode(bytes));
+            }
+        }
+    }
+
+    if let Some(attestations) = fields.get("attestations").and_then(Value::as_array) {
+        for entry in attestations {
+            let method = entry.pointer("/fields/method").and_then(Value::as_str);
+            if let Some((_, hash)) = method.and_then(|m| m.rsplit_once(ATTESTATION_HASH_SEPARATOR)) {
+                hashes.push(hash.to_string());
+            }
+        }
+    }
+
+    hashes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, TestDir};
+    use std::sync::{Arc, Mutex};
+
+    const SIGNER: &str = "0x5";
+
+    // Node answering `unsafe_moveCall` with fixed transaction bytes, keeping the arguments of each
+    // call, and `iota_getObject` with `fields`
+    async fn fake_node(fields: Value) -> (String, Arc<Mutex<Vec<Value>>>) {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let recorded = calls.clone();
+        let app = axum::Router::new().route(
+            "/",
+            axum::routing::post(move |Json(request): Json<Value>| {
+                let (calls, fields) = (recorded.clone(), fields.clone());
+                async move {
+                    let result = match request["method"].as_str() {
+                        Some("unsafe_moveCall") => {
+                            calls.lock().unwrap().push(request["params"].clone());
+                            json!({ "txBytes": "dHg=" })
+                        }
+                        Some("iota_getObject") => json!({ "data": { "content": { "fields": fields } } }),
+                        other => panic!("unexpected RPC method {:?}", other),
+                    };
+                    Json(json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }))
+                }
+            }),
+        );
+        (testing::serve(app).await, calls)
+    }
+
+    struct Backend {
+        url: String,
+        client: reqwest::Client,
+        calls: Arc<Mutex<Vec<Value>>>,
+        _dir: TestDir,
+    }
+
+    impl Backend {
+        async fn start(object_fields: Value) -> Self {
+            let dir = TestDir::new("evidence");
+            let (node_url, calls) = fake_node(object_fields).await;
+            let state = testing::app_state(&dir, "http://127.0.0.1:1", &[("IOTA_NODE_URL", node_url)]);
+            let (router, _) = routes().split_for_parts();
+            Self { url: testing::serve(router.with_state(state)).await, client: reqwest::Client::new(), calls, _dir: dir }
+        }
+
+        async fn post(&self, path: &str, body: Value) -> reqwest::Response {
+            self.client.post(format!("{}{}", self.url, path)).json(&body).send().await.unwrap()
+        }
+
+        async fn get(&self, path: &str) -> reqwest::Response {
+            self.client.get(format!("{}{}", self.url, path)).send().await.unwrap()
+        }
+
+        async fn verify(&self, hash: &str) -> Value {
+            let resp = self.get(&format!("/api/v1/evidence/verify?object_id=0xrel&content_hash={}", hash)).await;
+            assert_eq!(resp.status(), StatusCode::OK);
+            resp.json().await.unwrap()
+        }
+    }
+
+    fn trust_evidence(content: Value) -> Value {
+        let mut body = json!({
+            "signer": SIGNER,
+            "identity_id": "0xid",
+            "relationship_id": "0xrel",
+            "evidence_type": "document",
+            "weight": 40,
+        });
+        body.as_object_mut().unwrap().extend(content.as_object().unwrap().clone());
+        body
+    }
+
+    #[tokio::test]
+    async fn trust_evidence_is_stored_and_its_hash_anchored() {
+        let backend = Backend::start(Value::Null).await;
+        let resp = backend.post("/api/v1/trust/evidence", trust_evidence(json!({ "content_base64": BASE64.encode(b"scan") }))).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let hash = resp.json::<Value>().await.unwrap()["content_hash"].as_str().unwrap().to_string();
+
+        let calls = backend.calls.lock().unwrap().clone();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0][3], "add_trust_evidence");
+        assert_eq!(calls[0][5][3], json!(hex::decode(&hash).unwrap()));
+
+        let stored = backend.get(&format!("/api/v1/evidence/{}", hash)).await;
+        assert_eq!(stored.status(), StatusCode::OK);
+        assert_eq!(&stored.bytes().await.unwrap()[..], b"scan");
+    }
+
+    #[tokio::test]
+    async fn malformed_evidence_is_rejected() {
+        let backend = Backend::start(Value::Null).await;
+        let mut too_heavy = trust_evidence(json!({ "document": { "a": 1 } }));
+        too_heavy["weight"] = json!(101);
+        for body in [
+            trust_evidence(json!({ "content_base64": "not base64!" })),
+            trust_evidence(json!({ "document": { "a": 1 }, "content_base64": "c2Nhbg==" })),
+            trust_evidence(json!({})),
+            too_heavy,
+        ] {
+            assert_eq!(backend.post("/api/v1/trust/evidence", body).await.status(), StatusCode::BAD_REQUEST);
+        }
+        assert!(backend.calls.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn attestation_method_carries_the_content_hash() {
+        let backend = Backend::start(Value::Null).await;
+        let attestation = |method: &str| {
+            json!({
+                "signer": SIGNER,
+                "attestor_identity_id": "0xatt",
+                "claim_trust_id": "0xclaim",
+                "method": method,
+                "strength": 80,
+                "document": { "checked": true },
+            })
+        };
+        let rejected = backend.post("/api/v1/claims/attestations", attestation("kyc#sha256:00")).await;
+        assert_eq!(rejected.status(), StatusCode::BAD_REQUEST);
+
+        let resp = backend.post("/api/v1/claims/attestations", attestation("kyc")).await;
+        assert_eq!(resp.status(), StatusCode::OK);
+        let hash = resp.json::<Value>().await.unwrap()["content_hash"].as_str().unwrap().to_string();
+        let method = backend.calls.lock().unwrap()[0][5][2].clone();
+        let method: Vec<u8> = serde_json::from_value(method).unwrap();
+        assert_eq!(String::from_utf8(method).unwrap(), format!("kyc#sha256:{}", hash));
+    }
+
+    #[tokio::test]
+    async fn verify_checks_the_anchor_and_the_stored_content() {
+        let scan_hash = hex::encode(storage::content_hash(b"scan"));
+        let fields = json!({ "evidence": [{ "fields": { "content_hash": hex::decode(&scan_hash).unwrap() } }] });
+        let backend = Backend::start(fields).await;
+
+        let before_upload = backend.verify(&scan_hash).await;
+        assert_eq!((before_upload["anchored"].clone(), before_upload["content_matches"].clone()), (json!(true), json!(false)));
+
+        backend.post("/api/v1/trust/evidence", trust_evidence(json!({ "content_base64": BASE64.encode(b"scan") }))).await;
+        let verified = backend.verify(&scan_hash).await;
+        assert_eq!((verified["anchored"].clone(), verified["content_matches"].clone()), (json!(true), json!(true)));
+
+        let other = hex::encode(storage::content_hash(b"other"));
+        assert_eq!(backend.verify(&other).await["anchored"], false);
+    }
+
+    #[tokio::test]
+    async fn malformed_hashes_are_rejected() {
+        let backend = Backend::start(Value::Null).await;
+        let uppercase = hex::encode_upper(storage::content_hash(b"scan"));
+        for path in [
+            format!("/api/v1/evidence/verify?object_id=0xrel&content_hash={}", uppercase),
+            "/api/v1/evidence/verify?object_id=0xrel&content_hash=abc".to_string(),
+            format!("/api/v1/evidence/{}", uppercase),
+            "/api/v1/evidence/not-a-hash".to_string(),
+        ] {
+            assert_eq!(backend.get(&path).await.status(), StatusCode::BAD_REQUEST, "{}", path);
+        }
+    }
+
+    #[test]
+    fn anchored_hashes_come_from_evidence_and_attestations() {
+        let fields = json!({
+            "evidence": [{ "fields": { "content_hash": [0xab, 0x01] } }, { "fields": {} }],
+            "attestations": [
+                { "fields": { "method": "kyc#sha256:cd02" } },
+                { "fields": { "method": "in-person" } },
+            ],
+        });
+        assert_eq!(anchored_hashes(&fields), vec!["ab01".to_string(), "cd02".to_string()]);
+    }
+}
@@ -0,0 +1,412 @@
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use futures_util::future::join_all;
+use reed_solomon_erasure::galois_8::ReedSolomon;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use utoipa_axum::{router::OpenApiRouter, routes};
+use wot_id_types::WotDid;
+
+use crate::encryption::{self, Envelope, RecipientKey};
+use crate::state::AppState;
+use crate::storage::{self, BlobStore, StorageError};
+
+pub const MANIFEST_VERSION: u32 = 1;
+
+// One storage location that receives at most one share of each fragmented blob.
+// `location` is the stable name recorded in manifests, e.g. `local:/srv/fragments/a`.
+#[derive(Clone)]
+pub struct FragmentStore {
+    pub location: String,
+    pub store: BlobStore,
+}
+
+// Records where the shares of one blob live. The manifest is replicated to every
+// fragment store so it survives the same backend losses as the shares themselves.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FragmentManifest {
+    pub version: u32,
+    pub owner_did: WotDid,
+    // SHA-256 of the (encrypted) blob before splitting
+    pub content_hash: String,
+    pub size: usize,
+    pub data_shards: usize,
+    pub parity_shards: usize,
+    pub shares: Vec<ShareLocation>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ShareLocation {
+    pub index: usize,
+    pub location: String,
+    pub hash: String,
+}
+
+#[derive(Debug)]
+pub enum FragmentError {
+    InvalidThreshold { threshold: usize, stores: usize },
+    NotEnoughShares { available: usize, required: usize },
+    ManifestNotFound,
+    IntegrityMismatch,
+    Coding(reed_solomon_erasure::Error),
+    Storage(StorageError),
+}
+
+impl std::fmt::Display for FragmentError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FragmentError::InvalidThreshold { threshold, stores } => write!(
+                f,
+                "threshold {} is invalid for {} fragment stores (need 1 <= threshold < stores)",
+                threshold, stores
+            ),
+            FragmentError::NotEnoughShares { available, required } => {
+                write!(f, "only {} of the {} required shares are available", available, required)
+            }
+            FragmentError::ManifestNotFound => write!(f, "manifest not found in any fragment store"),
+            FragmentError::IntegrityMismatch => write!(f, "recomposed blob does not match the manifest hash"),
+            FragmentError::Coding(e) => write!(f, "erasure coding failed: {:?}", e),
+            FragmentError::Storage(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for FragmentError {}
+
+impl From<StorageError> for FragmentError {
+    fn from(e: StorageError) -> Self {
+        FragmentError::Storage(e)
+    }
+}
+
+impl From<reed_solomon_erasure::Error> for FragmentError {
+    fn from(e: reed_solomon_erasure::Error) -> Self {
+        FragmentError::Coding(e)
+    }
+}
+
+// Split `blob` into one share per store with Reed-Solomon coding so that any `threshold`
+// shares rebuild it. Up to `stores - threshold` stores may fail to take their share; the
+// manifest lists only the shares that were stored. Returns the manifest and its content hash.
+pub async fn fragment(
+    stores: &[FragmentStore],
+    owner_did: &WotDid,
+    blob: &[u8],
+    threshold: usize,
+) -> Result<(FragmentManifest, String), FragmentError> {
+    let total = stores.len();
+    if threshold == 0 || threshold >= total {
+        return Err(FragmentError::InvalidThreshold { threshold, stores: total });
+    }
+    let parity = total - threshold;
+
+    let shard_len = blob.len().div_ceil(threshold).max(1);
+    let mut shards: Vec<Vec<u8>> = (0..total)
+        .map(|i| {
+            let start = (i * shard_len).min(blob.len());
+            let end = ((i + 1) * shard_len).min(blob.len());
+            let mut shard = if i < threshold { blob[start..end].to_vec() } else { Vec::new() };
+            shard.resize(shard_len, 0);
+            shard
+        })
+        .collect();
+    ReedSolomon::new(threshold, parity)?.encode(&mut shards)?;
+
+    let puts = stores.iter().zip(&shards).map(|(target, shard)| target.store.put(shard));
+    let mut shares = Vec::with_capacity(total);
+    for (index, (target, result)) in stores.iter().zip(join_all(puts).await).enumerate() {
+        match result {
+            Ok(hash) => shares.push(ShareLocation { index, location: target.location.clone(), hash }),
+            Err(e) => tracing::warn!(target: "backend::fragmentation", "Share {} not stored at {}: {}", index, target.location, e),
+        }
+    }
+    if shares.len() < threshold {
+        return Err(FragmentError::NotEnoughShares { available: shares.len(), required: threshold });
+    }
+
+    let manifest = FragmentManifest {
+        version: MANIFEST_VERSION,
+        owner_did: owner_did.clone(),
+        content_hash: hex::encode(storage::content_hash(blob)),
+        size: blob.len(),
+        data_shards: threshold,
+        parity_shards: parity,
+        shares,
+    };
+    let manifest_bytes = serde_json::to_vec(&manifest).expect("manifest serializes to JSON");
+
+    // Any one surviving copy is enough, so a store that is down only loses its share
+    let mut manifest_hash = None;
+    for result in join_all(stores.iter().map(|target| target.store.put(&manifest_bytes))).await {
+        match result {
+            Ok(hash) => manifest_hash = Some(hash),
+            Err(e) => tracing::warn!(target: "backend::fragmentation", "Failed to replicate manifest: {}", e),
+        }
+    }
+    let manifest_hash = manifest_hash.ok_or(FragmentError::NotEnoughShares { available: 0, required: 1 })?;
+
+    Ok((manifest, manifest_hash))
+}
+
+pub async fn load_manifest(stores: &[FragmentStore], manifest_hash: &str) -> Result<FragmentManifest, FragmentError> {
+    for target in stores {
+        match target.store.get(manifest_hash).await {
+            Ok(Some(bytes)) => {
+                if let Ok(manifest) = serde_json::from_slice(&bytes) {
+                    return Ok(manifest);
+                }
+            }
+            Ok(None) => {}
+            Err(e) => tracing::warn!(target: "backend::fragmentation", "Manifest read from {} failed: {}", target.location, e),
+        }
+    }
+    Err(FragmentError::ManifestNotFound)
+}
+
+// Rebuild a blob from whichever shares are still reachable
+pub async fn recompose(stores: &[FragmentStore], manifest: &FragmentManifest) -> Result<Vec<u8>, FragmentError> {
+    let fetches = manifest.shares.iter().map(|share| async move {
+        let target = stores.iter().find(|s| s.location == share.location)?;
+        match target.store.get(&share.hash).await {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                tracing::warn!(target: "backend::fragmentation", "Share {} at {} unavailable: {}", share.index, share.location, e);
+                None
+            }
+        }
+    });
+    let mut shards: Vec<Option<Vec<u8>>> = vec![None; manifest.data_shards + manifest.parity_shards];
+    for (share, bytes) in manifest.shares.iter().zip(join_all(fetches).await) {
+        if let Some(slot) = shards.get_mut(share.index) {
+            *slot = bytes;
+        }
+    }
+
+    let available = shards.iter().filter(|s| s.is_some()).count();
+    if available < manifest.data_shards {
+        return Err(FragmentError::NotEnoughShares { available, required: manifest.data_shards });
+    }
+
+    ReedSolomon::new(manifest.data_shards, manifest.parity_shards)?.reconstruct_data(&mut shards)?;
+    let mut blob: Vec<u8> = shards
+        .into_iter()
+        .take(manifest.data_shards)
+        .flat_map(|shard| shard.expect("data shards are present after reconstruction"))
+        .collect();
+    blob.truncate(manifest.size);
+
+    if hex::encode(storage::content_hash(&blob)) != manifest.content_hash {
+        return Err(FragmentError::IntegrityMismatch);
+    }
+    Ok(blob)
+}
+
+pub fn routes() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new()
+        .routes(routes!(fragment_blob))
+        .routes(routes!(recompose_blob))
+        .routes(routes!(get_manifest))
+}
+
+fn fragment_error(e: FragmentError) -> (StatusCode, String) {
+    let status = match &e {
+        FragmentError::InvalidThreshold { .. } => StatusCode::BAD_REQUEST,
+        FragmentError::ManifestNotFound => StatusCode::NOT_FOUND,
+        FragmentError::NotEnoughShares { .. } => StatusCode::SERVICE_UNAVAILABLE,
+        FragmentError::Storage(StorageError::TooLarge { .. }) => StatusCode::PAYLOAD_TOO_LARGE,
+        FragmentError::Storage(StorageError::Upload(_)) => StatusCode::BAD_REQUEST,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    if status.is_server_error() {
+        tracing::error!(target: "backend::fragmentation", "Fragmentation failed: {}", e);
+    }
+    (status, e.to_string())
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct FragmentQuery {
+    pub owner: WotDid,
+    pub threshold: Option<usize>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct FragmentResponse {
+    pub manifest_hash: String,
+    pub manifest: FragmentManifest,
+}
+
+// Seal the body to the owner's key-agreement key, then spread it over the fragment stores.
+// Without an explicit threshold a simple majority of stores is required to recompose.
+#[utoipa::path(
+    post,
+    path = "/api/v1/fragments",
+    tag = "fragmentation",
+    params(FragmentQuery),
+    request_body(content = [u8], content_type = "application/octet-stream"),
+    responses(
+        (status = 200, body = FragmentResponse),
+        (status = 400, description = "Threshold out of range, or a malformed owner DID", body = String),
+        (status = 413, description = "The blob exceeds the configured size limit", body = String),
+        (status = 422, description = "The owner has no X25519 key-agreement method", body = String),
+        (status = 502, body = String),
+    )
+)]
+async fn fragment_blob(
+    State(state): State<AppState>,
+    Query(query): Query<FragmentQuery>,
+    body: Body,
+) -> Result<Json<FragmentResponse>, (StatusCode, String)> {
+    let stores = state.fragment_stores.as_slice();
+    let threshold = query.threshold.unwrap_or(stores.len() / 2 + 1);
+
+    let methods = state.identity.resolve_key_agreement(&query.owner).await.map_err(|e| {
+        tracing::error!(target: "backend::fragmentation", "Failed to resolve key agreement for {}: {}", query.owner, e);
+        (StatusCode::BAD_GATEWAY, format!("Failed to resolve key agreement methods for {}", query.owner))
+    })?;
+    let recipient = RecipientKey::from_methods(&methods).ok_or_else(|| {
+        (StatusCode::UNPROCESSABLE_ENTITY, format!("{} has no X25519 key-agreement method", query.owner))
+    })?;
+
+    let plaintext = storage::collect_limited(body.into_data_stream(), state.blobs.max_size())
+        .await
+        .map_err(|e| fragment_error(e.into()))?;
+    let sealed = serde_json::to_vec(&encryption::seal(&recipient, &plaintext)).expect("envelope serializes to JSON");
+
+    let (manifest, manifest_hash) = fragment(stores, &query.owner, &sealed, threshold)
+        .await
+        .map_err(fragment_error)?;
+    Ok(Json(FragmentResponse { manifest_hash, manifest }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/fragments/{manifest_hash}",
+    tag = "fragmentation",
+    params(("manifest_hash" = String, Path)),
+    responses(
+        (status = 200, description = "The blob, still sealed to its owner", body = Envelope),
+        (status = 404, description = "No fragment store has the manifest", body = String),
+        (status = 503, description = "Too few shares are reachable", body = String),
+    )
+)]
+async fn recompose_blob(
+    State(state): State<AppState>,
+    Path(manifest_hash): Path<String>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let stores = state.fragment_stores.as_slice();
+    let manifest = load_manifest(stores, &manifest_hash).await.map_err(fragment_error)?;
+    let blob = recompose(stores, &manifest).await.map_err(fragment_error)?;
+    Ok(([(header::CONTENT_TYPE, "application/json")], blob))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/fragments/{manifest_hash}/manifest",
+    tag = "fragmentation",
+    params(("manifest_hash" = String, Path)),
+    responses((status = 200, body = FragmentManifest), (status = 404, body = String))
+)]
+async fn get_manifest(
+    State(state): State<AppState>,
+    Path(manifest_hash): Path<String>,
+) -> Result<Json<FragmentManifest>, (StatusCode, String)> {
+    load_manifest(state.fragment_stores.as_slice(), &manifest_hash)
+        .await
+        .map(Json)
+        .map_err(fragment_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::local::LocalStore;
+    use crate::storage::StorageBackend;
+    use std::path::PathBuf;
+
+    struct TestStores {
+        dir: PathBuf,
+        stores: Vec<FragmentStore>,
+    }
+
+    impl TestStores {
+        fn new(name: &str, count: usize) -> Self {
+            let dir = std::env::temp_dir().join(format!("wot-fragments-{}-{}", name, std::process::id()));
+            let stores = (0..count)
+                .map(|i| {
+                    let root = dir.join(i.to_string());
+                    FragmentStore {
+                        location: format!("local:{}", root.display()),
+                        store: BlobStore::new(StorageBackend::Local(LocalStore::new(root)), 1024 * 1024),
+                    }
+                })
+                .collect();
+            Self { dir, stores }
+        }
+
+        // The backend behind store `i` is gone: its directory is removed and replaced by a
+        // file, so reads find nothing and writes fail
+        fn lose(&self, i: usize) {
+            let root = self.dir.join(i.to_string());
+            let _ = std::fs::remove_dir_all(&root);
+            std::fs::create_dir_all(&self.dir).unwrap();
+            std::fs::write(&root, b"").unwrap();
+        }
+    }
+
+    impl Drop for TestStores {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    fn owner() -> WotDid {
+        "did:iota:0x1".parse().unwrap()
+    }
+
+    fn blob() -> Vec<u8> {
+        (0..10_000u32).map(|i| (i * 7 % 251) as u8).collect()
+    }
+
+    #[tokio::test]
+    async fn recomposes_after_losing_all_parity_backends() {
+        let test = TestStores::new("loss", 5);
+        let (manifest, manifest_hash) = fragment(&test.stores, &owner(), &blob(), 3).await.unwrap();
+        assert_eq!(manifest.shares.len(), 5);
+
+        // N - K = 2 backends lost, including ones holding data shards
+        test.lose(0);
+        test.lose(3);
+        let manifest = load_manifest(&test.stores, &manifest_hash).await.unwrap();
+        assert_eq!(recompose(&test.stores, &manifest).await.unwrap(), blob());
+
+        test.lose(4);
+        assert!(matches!(
+            recompose(&test.stores, &manifest).await,
+            Err(FragmentError::NotEnoughShares { available: 2, required: 3 })
+        ));
+    }
+
+    #[tokio::test]
+    async fn tolerates_failed_share_writes_up_to_parity() {
+        let test = TestStores::new("write", 5);
+        test.lose(1);
+        test.lose(2);
+        let (manifest, manifest_hash) = fragment(&test.stores, &owner(), &blob(), 3).await.unwrap();
+        let stored: Vec<usize> = manifest.shares.iter().map(|share| share.index).collect();
+        assert_eq!(stored, vec![0, 3, 4]);
+        let manifest = load_manifest(&test.stores, &manifest_hash).await.unwrap();
+        assert_eq!(recompose(&test.stores, &manifest).await.unwrap(), blob());
+
+        test.lose(3);
+        assert!(matches!(
+            fragment(&test.stores, &owner(), &blob(), 3).await,
+            Err(FragmentError::NotEnoughShares { available: 2, required: 3 })
+        ));
+    }
+}
@@ -0,0 +1,935 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use utoipa::ToSchema;
+use utoipa_axum::{router::OpenApiRouter, routes};
+use uuid::Uuid;
+
+use crate::identity_client::{verify_signed_action, SignedAction};
+use crate::iota_rpc::{IotaRpcClient, RpcError, TransactionBlockBytes};
+use crate::state::AppState;
+use crate::store::JsonStore;
+use crate::trust::MAX_TRUST_LEVEL;
+
+pub const ACTION_PROPOSE: &str = "governance.propose";
+pub const ACTION_VOTE: &str = "governance.vote";
+
+// How much a ballot counts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Weighting {
+    // Every DID that votes counts 1
+    OneDidOneVote,
+    // A voter counts the sum of the trust levels other DIDs place in them within the
+    // proposal's context, in trust.move units (0..=100000 per relationship)
+    TrustWeighted,
+}
+
+impl Weighting {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "one_did_one_vote" => Some(Self::OneDidOneVote),
+            "trust_weighted" => Some(Self::TrustWeighted),
+            _ => None,
+        }
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::OneDidOneVote => "one_did_one_vote",
+            Self::TrustWeighted => "trust_weighted",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Choice {
+    For,
+    Against,
+    Abstain,
+}
+
+// Limits every proposal must respect
+#[derive(Debug, Clone)]
+pub struct GovernanceRules {
+    // Used when a proposal does not name a weighting
+    pub default_weighting: Weighting,
+    pub min_voting_period: Duration,
+    pub max_voting_period: Duration,
+    // Lowest quorum a proposal may set, in votes for one-DID-one-vote
+    pub min_quorum_votes: u64,
+    // Lowest quorum a trust-weighted proposal may set, in trust.move units
+    pub min_quorum_trust: u64,
+}
+
+impl Default for GovernanceRules {
+    fn default() -> Self {
+        Self {
+            default_weighting: Weighting::OneDidOneVote,
+            min_voting_period: Duration::from_secs(24 * 60 * 60),
+            max_voting_period: Duration::from_secs(30 * 24 * 60 * 60),
+            min_quorum_votes: 3,
+            min_quorum_trust: 3 * MAX_TRUST_LEVEL,
+        }
+    }
+}
+
+impl GovernanceRules {
+    fn min_quorum(&self, weighting: Weighting) -> u64 {
+        match weighting {
+            Weighting::OneDidOneVote => self.min_quorum_votes,
+            Weighting::TrustWeighted => self.min_quorum_trust,
+        }
+    }
+}
+
+// A vote as recorded, with the voter's signed `governance.vote` action
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Ballot {
+    pub voter: String,
+    pub choice: Choice,
+    // Fixed when the ballot is cast, so later trust changes do not move the tally
+    pub weight: u64,
+    // `iat` of the signed action; a newer ballot from the same voter replaces an older one
+    pub signed_at: u64,
+    pub jws: String,
+    pub recorded_at_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Proposal {
+    pub id: String,
+    pub proposer: String,
+    pub title: String,
+    pub description: String,
+    pub implementation_plan: Option<String>,
+    // Governance context; trust-weighted votes count trust within it
+    pub context: String,
+    pub weighting: Weighting,
+    // Total weight (for, against and abstain) that must be cast for the result to count
+    pub quorum: u64,
+    // Percentage of the for + against weight that `for` must exceed to pass
+    pub threshold_pct: u8,
+    pub voting_starts_ms: u64,
+    pub voting_ends_ms: u64,
+    pub created_at_ms: u64,
+    // The proposer's signed `governance.propose` action; it commits to all of the above
+    pub jws: String,
+    pub ballots: Vec<Ballot>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    // Voting has not started yet
+    Pending,
+    // Voting is open; the tally is provisional
+    Open,
+    Passed,
+    Rejected,
+    NoQuorum,
+}
+
+impl Outcome {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Open => "open",
+            Self::Passed => "passed",
+            Self::Rejected => "rejected",
+            Self::NoQuorum => "no_quorum",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Tally {
+    pub votes_for: u64,
+    pub votes_against: u64,
+    pub votes_abstain: u64,
+    pub voters: u64,
+    pub quorum: u64,
+    pub quorum_reached: bool,
+    pub outcome: Outcome,
+    // SHA-256 of the proposer's JWS and of the ballot JWSs ordered by voter, as anchored on-chain
+    pub proposal_hash: String,
+    pub ballots_hash: String,
+}
+
+impl Proposal {
+    fn is_open(&self, now_ms: u64) -> bool {
+        (self.voting_starts_ms..self.voting_ends_ms).contains(&now_ms)
+    }
+
+    fn ballots_hash(&self) -> [u8; 32] {
+        let mut ballots: Vec<&Ballot> = self.ballots.iter().collect();
+        ballots.sort_by(|a, b| a.voter.cmp(&b.voter));
+        let jws: Vec<&str> = ballots.iter().map(|b| b.jws.as_str()).collect();
+        Sha256::digest(serde_json::to_vec(&jws).expect("ballots serialize to JSON")).into()
+    }
+
+    // Same rules as `governance::anchor_result`, which recomputes the outcome on-chain
+    pub fn tally(&self, now_ms: u64) -> Tally {
+        let weight_of = |choice: Choice| self.ballots.iter().filter(|b| b.choice == choice).map(|b| b.weight).sum::<u64>();
+        let (votes_for, votes_against, votes_abstain) =
+            (weight_of(Choice::For), weight_of(Choice::Against), weight_of(Choice::Abstain));
+        let quorum_reached = votes_for + votes_against + votes_abstain >= self.quorum;
+        let passes = u128::from(votes_for) * 100 > u128::from(self.threshold_pct) * u128::from(votes_for + votes_against);
+
+        let outcome = if now_ms < self.voting_starts_ms {
+            Outcome::Pending
+        } else if now_ms < self.voting_ends_ms {
+            Outcome::Open
+        } else if !quorum_reached {
+            Outcome::NoQuorum
+        } else if passes {
+            Outcome::Passed
+        } else {
+            Outcome::Rejected
+        };
+
+        Tally {
+            votes_for,
+            votes_against,
+            votes_abstain,
+            voters: self.ballots.len() as u64,
+            quorum: self.quorum,
+            quorum_reached,
+            outcome,
+            proposal_hash: hex::encode(Sha256::digest(self.jws.as_bytes())),
+            ballots_hash: hex::encode(self.ballots_hash()),
+        }
+    }
+}
+
+// The address holding the contract's `governance::AnchorCap`, and the cap itself. Only that
+// address can anchor results, and only its `ProposalResultAnchored` events are believed.
+#[derive(Debug, Clone)]
+pub struct Anchoring {
+    pub address: String,
+    pub cap_id: String,
+}
+
+// A `ProposalResultAnchored` event sent by the anchoring address
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct AnchorRecord {
+    object_id: String,
+    proposal_id: String,
+    proposal_hash: String,
+    ballots_hash: String,
+    votes_for: u64,
+    votes_against: u64,
+    votes_abstain: u64,
+}
+
+impl AnchorRecord {
+    // `event` as returned by `iotax_queryEvents`; `None` for events from other senders
+    fn from_event(event: &Value, address: &str) -> Option<Self> {
+        let sender = event.get("sender").and_then(Value::as_str)?;
+        if !sender.eq_ignore_ascii_case(address) {
+            return None;
+        }
+        let fields = event.get("parsedJson")?;
+        let text = |key: &str| fields.get(key).and_then(Value::as_str).map(str::to_string);
+        // u64s come as strings; vector<u8>s as arrays of numbers
+        let number = |key: &str| fields.get(key).and_then(|v| v.as_str().map_or(v.as_u64(), |s| s.parse().ok()));
+        let bytes = |key: &str| {
+            let bytes = fields.get(key)?.as_array()?.iter().map(|b| b.as_u64().and_then(|b| u8::try_from(b).ok()));
+            bytes.collect::<Option<Vec<u8>>>().map(hex::encode)
+        };
+        Some(Self {
+            object_id: text("object_id")?,
+            proposal_id: text("proposal_id")?,
+            proposal_hash: bytes("proposal_hash")?,
+            ballots_hash: bytes("ballots_hash")?,
+            votes_for: number("votes_for")?,
+            votes_against: number("votes_against")?,
+            votes_abstain: number("votes_abstain")?,
+        })
+    }
+
+    // Whether this anchors `tally` as computed here from the stored proposal and ballots
+    fn records(&self, proposal_id: &str, tally: &Tally) -> bool {
+        self.proposal_id == proposal_id
+            && self.proposal_hash == tally.proposal_hash
+            && self.ballots_hash == tally.ballots_hash
+            && (self.votes_for, self.votes_against, self.votes_abstain) == (tally.votes_for, tally.votes_against, tally.votes_abstain)
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct GovernanceData {
+    proposals: Vec<Proposal>,
+    // `iotax_queryEvents` cursor after the last `ProposalResultAnchored` event read, so each
+    // anchor request only reads events that are new since the previous one
+    #[serde(default)]
+    anchor_cursor: Value,
+    #[serde(default)]
+    anchors: Vec<AnchorRecord>,
+}
+
+// Proposals and their ballots, persisted as one JSON file
+pub struct GovernanceStore {
+    data: JsonStore<GovernanceData>,
+    pub rules: GovernanceRules,
+    // `None` disables anchoring
+    anchoring: Option<Anchoring>,
+    // Held while reading anchor events, so two requests do not read the same pages
+    anchor_scan: tokio::sync::Mutex<()>,
+}
+
+impl GovernanceStore {
+    pub fn load(path: impl Into<PathBuf>, rules: GovernanceRules, anchoring: Option<Anchoring>) -> Result<Self, anyhow::Error> {
+        Ok(Self { data: JsonStore::load(path)?, rules, anchoring, anchor_scan: tokio::sync::Mutex::new(()) })
+    }
+
+    async fn proposal(&self, id: &str) -> Option<Proposal> {
+        self.data.lock().await.proposals.iter().find(|p| p.id == id).cloned()
+    }
+
+    // Read the `ProposalResultAnchored` events emitted since the saved cursor and keep the
+    // anchoring address's
+    async fn sync_anchors(&self, rpc: &IotaRpcClient, package_id: &str, address: &str) -> Result<(), RpcError> {
+        let _scan = self.anchor_scan.lock().await;
+        let event_type = format!("{}::governance::ProposalResultAnchored", package_id);
+        let mut cursor = self.data.lock().await.anchor_cursor.clone();
+        loop {
+            let page = rpc.call("iotax_queryEvents", json!([{ "MoveEventType": event_type }, cursor, 50, false])).await?;
+            let records = page
+                .get("data")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(|event| AnchorRecord::from_event(event, address));
+            let mut data = self.data.lock().await;
+            for record in records {
+                if !data.anchors.iter().any(|a| a.object_id == record.object_id) {
+                    data.anchors.push(record);
+                }
+            }
+            if let Some(next) = page.get("nextCursor").filter(|c| !c.is_null()) {
+                data.anchor_cursor = next.clone();
+                cursor = next.clone();
+            }
+            self.data.persist(&data).await;
+            drop(data);
+            if !page.get("hasNextPage").and_then(Value::as_bool).unwrap_or(false) {
+                return Ok(());
+            }
+        }
+    }
+
+    // Object ID of the `GovernanceResult` anchoring `tally` for `proposal_id`, among the events read
+    async fn anchored_result(&self, proposal_id: &str, tally: &Tally) -> Option<String> {
+        let data = self.data.lock().await;
+        data.anchors.iter().find(|a| a.records(proposal_id, tally)).map(|a| a.object_id.clone())
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+pub fn routes() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new()
+        .routes(routes!(create_proposal, list_proposals))
+        .routes(routes!(get_proposal))
+        .routes(routes!(cast_vote))
+        .routes(routes!(get_tally))
+        .routes(routes!(anchor_result))
+}
+
+fn not_found(id: &str) -> (StatusCode, String) {
+    (StatusCode::NOT_FOUND, format!("No proposal {}", id))
+}
+
+fn rpc_error(context: &str, e: RpcError) -> (StatusCode, String) {
+    tracing::error!(target: "backend::governance", "{}: {}", context, e);
+    (StatusCode::BAD_GATEWAY, format!("{}: {}", context, e))
+}
+
+#[derive(Deserialize)]
+struct ProposeClaims {
+    title: String,
+    description: String,
+    implementation_plan: Option<String>,
+    context: String,
+    weighting: Option<Weighting>,
+    quorum: Option<u64>,
+    #[serde(default = "default_threshold_pct")]
+    threshold_pct: u8,
+    // Defaults to now
+    voting_starts_ms: Option<u64>,
+    voting_ends_ms: u64,
+}
+
+fn default_threshold_pct() -> u8 {
+    50
+}
+
+#[derive(Deserialize)]
+struct VoteClaims {
+    iat: u64,
+    proposal_id: String,
+    choice: Choice,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ProposalView {
+    #[serde(flatten)]
+    pub proposal: Proposal,
+    pub tally: Tally,
+}
+
+impl From<Proposal> for ProposalView {
+    fn from(proposal: Proposal) -> Self {
+        Self { tally: proposal.tally(now_ms()), proposal }
+    }
+}
+
+// Quorum, threshold and voting period checked against `rules`; returns the weighting, quorum
+// and start of voting the proposal gets
+fn validate_proposal(
+    rules: &GovernanceRules,
+    claims: &ProposeClaims,
+    now: u64,
+) -> Result<(Weighting, u64, u64), (StatusCode, String)> {
+    let weighting = claims.weighting.unwrap_or(rules.default_weighting);
+    let quorum = claims.quorum.unwrap_or_else(|| rules.min_quorum(weighting));
+    if quorum < rules.min_quorum(weighting) {
+        return Err((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!("Quorum must be at least {} for {} proposals", rules.min_quorum(weighting), weighting.as_str()),
+        ));
+    }
+    if claims.threshold_pct > 100 {
+        return Err((StatusCode::UNPROCESSABLE_ENTITY, "threshold_pct must be between 0 and 100".to_string()));
+    }
+    if claims.title.trim().is_empty() || claims.context.trim().is_empty() {
+        return Err((StatusCode::UNPROCESSABLE_ENTITY, "Proposals need a title and a context".to_string()));
+    }
+    let voting_starts_ms = claims.voting_starts_ms.unwrap_or(now).max(now);
+    let period = Duration::from_millis(claims.voting_ends_ms.saturating_sub(voting_starts_ms));
+    if period < rules.min_voting_period || period > rules.max_voting_period {
+        return Err((
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!(
+                "Voting period must be between {}s and {}s",
+                rules.min_voting_period.as_secs(),
+                rules.max_voting_period.as_secs()
+            ),
+        ));
+    }
+
+    Ok((weighting, quorum, voting_starts_ms))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/governance/proposals",
+    tag = "governance",
+    request_body(content = SignedAction, description = "Signed `governance.propose` action"),
+    responses(
+        (status = 201, body = ProposalView),
+        (status = 401, description = "Signature, action or freshness rejected", body = String),
+        (status = 409, description = "This signed proposal was already submitted", body = String),
+        (status = 422, description = "Quorum, threshold or voting period outside the governance rules", body = String),
+    )
+)]
+async fn create_proposal(
+    State(state): State<AppState>,
+    Json(payload): Json<SignedAction>,
+) -> Result<(StatusCode, Json<ProposalView>), (StatusCode, String)> {
+    let claims: ProposeClaims = verify_signed_action(&state.identity, &payload, ACTION_PROPOSE).await?;
+    let now = now_ms();
+
+    let (weighting, quorum, voting_starts_ms) = validate_proposal(&state.governance.rules, &claims, now)?;
+
+    let proposal = Proposal {
+        id: Uuid::new_v4().to_string(),
+        proposer: payload.signer.into(),
+        title: claims.title,
+        description: claims.description,
+        implementation_plan: claims.implementation_plan,
+        context: claims.context,
+        weighting,
+        quorum,
+        threshold_pct: claims.threshold_pct,
+        voting_starts_ms,
+        voting_ends_ms: claims.voting_ends_ms,
+        created_at_ms: now,
+        jws: payload.jws,
+        ballots: Vec::new(),
+    };
+
+    let mut data = state.governance.data.lock().await;
+    // A replayed proposal action would otherwise open a duplicate proposal
+    if data.proposals.iter().any(|p| p.jws == proposal.jws) {
+        return Err((StatusCode::CONFLICT, "This signed proposal was already submitted".to_string()));
+    }
+    data.proposals.push(proposal.clone());
+    state.governance.data.persist(&data).await;
+    tracing::info!(
+        target: "backend::governance",
+        "Proposal {} by {} opened in context '{}' ({})",
+        proposal.id, proposal.proposer, proposal.context, proposal.weighting.as_str()
+    );
+    Ok((StatusCode::CREATED, Json(proposal.into())))
+}
+
+#[utoipa::path(get, path = "/api/v1/governance/proposals", tag = "governance", responses((status = 200, body = Vec<ProposalView>)))]
+async fn list_proposals(State(state): State<AppState>) -> Json<Vec<ProposalView>> {
+    let data = state.governance.data.lock().await;
+    Json(data.proposals.iter().cloned().map(ProposalView::from).collect())
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/governance/proposals/{id}",
+    tag = "governance",
+    params(("id" = String, Path)),
+    responses((status = 200, body = ProposalView), (status = 404, body = String))
+)]
+async fn get_proposal(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<ProposalView>, (StatusCode, String)> {
+    state.governance.proposal(&id).await.map(|p| Json(p.into())).ok_or_else(|| not_found(&id))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/governance/proposals/{id}/tally",
+    tag = "governance",
+    params(("id" = String, Path)),
+    responses((status = 200, body = Tally), (status = 404, body = String))
+)]
+async fn get_tally(State(state): State<AppState>, Path(id): Path<String>) -> Result<Json<Tally>, (StatusCode, String)> {
+    state.governance.proposal(&id).await.map(|p| Json(p.tally(now_ms()))).ok_or_else(|| not_found(&id))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/governance/proposals/{id}/votes",
+    tag = "governance",
+    params(("id" = String, Path)),
+    request_body(content = SignedAction, description = "Signed `governance.vote` action"),
+    responses(
+        (status = 200, body = Ballot),
+        (status = 401, description = "Signature, action or freshness rejected", body = String),
+        (status = 403, description = "The voter holds no trust in the proposal's context", body = String),
+        (status = 404, body = String),
+        (status = 409, description = "Voting is not open, or a newer ballot is recorded", body = String),
+    )
+)]
+async fn cast_vote(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(payload): Json<SignedAction>,
+) -> Result<Json<Ballot>, (StatusCode, String)> {
+    let claims: VoteClaims = verify_signed_action(&state.identity, &payload, ACTION_VOTE).await?;
+    if claims.proposal_id != id {
+        return Err((StatusCode::BAD_REQUEST, "The signed vote is for a different proposal".to_string()));
+    }
+    let proposal = state.governance.proposal(&id).await.ok_or_else(|| not_found(&id))?;
+    if !proposal.is_open(now_ms()) {
+        return Err((StatusCode::CONFLICT, format!("Voting on proposal {} is not open", id)));
+    }
+
+    let weight = match proposal.weighting {
+        Weighting::OneDidOneVote => 1,
+        Weighting::TrustWeighted => state.trust.score(&payload.signer, &proposal.context).await,
+    };
+    if weight == 0 {
+        return Err((
+            StatusCode::FORBIDDEN,
+            format!("{} holds no trust in context '{}' and cannot vote", payload.signer, proposal.context),
+        ));
+    }
+    let ballot = Ballot {
+        voter: payload.signer.into(),
+        choice: claims.choice,
+        weight,
+        signed_at: claims.iat,
+        jws: payload.jws,
+        recorded_at_ms: now_ms(),
+    };
+
+    let mut data = state.governance.data.lock().await;
+    let proposal = data.proposals.iter_mut().find(|p| p.id == id).ok_or_else(|| not_found(&id))?;
+    let recorded = record_ballot(proposal, ballot)?;
+    state.governance.data.persist(&data).await;
+    Ok(Json(recorded))
+}
+
+// A voter's newer ballot replaces their earlier one; resubmitting the same ballot is a no-op
+fn record_ballot(proposal: &mut Proposal, ballot: Ballot) -> Result<Ballot, (StatusCode, String)> {
+    match proposal.ballots.iter_mut().find(|b| b.voter == ballot.voter) {
+        Some(existing) if existing.jws == ballot.jws => return Ok(existing.clone()),
+        // Rejects replays of a voter's earlier ballot after they changed their vote
+        Some(existing) if existing.signed_at >= ballot.signed_at => {
+            return Err((StatusCode::CONFLICT, "A newer ballot from this voter is already recorded".to_string()));
+        }
+        Some(existing) => *existing = ballot.clone(),
+        None => proposal.ballots.push(ballot.clone()),
+    }
+    Ok(ballot)
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct AnchorRequest {
+    // Address that signs and pays for the transaction; must be the one holding the `AnchorCap`
+    pub signer: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AnchorResponse {
+    pub tally: Tally,
+    pub transaction: TransactionBlockBytes,
+}
+
+// Once voting has ended, return an unsigned `governance::anchor_result` transaction recording
+// the tally and the hashes of the signed proposal and ballots
+#[utoipa::path(
+    post,
+    path = "/api/v1/governance/proposals/{id}/anchor",
+    tag = "governance",
+    params(("id" = String, Path)),
+    request_body = AnchorRequest,
+    responses(
+        (status = 200, body = AnchorResponse),
+        (status = 403, description = "The signer does not hold the anchor capability", body = String),
+        (status = 404, body = String),
+        (status = 409, description = "Voting has not ended, or the result is already anchored", body = String),
+        (status = 502, body = String),
+        (status = 503, description = "Result anchoring is not configured", body = String),
+    )
+)]
+async fn anchor_result(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(payload): Json<AnchorRequest>,
+) -> Result<Json<AnchorResponse>, (StatusCode, String)> {
+    let anchoring = state
+        .governance
+        .anchoring
+        .as_ref()
+        .ok_or_else(|| (StatusCode::SERVICE_UNAVAILABLE, "Result anchoring is not configured".to_string()))?;
+    if !payload.signer.eq_ignore_ascii_case(&anchoring.address) {
+        return Err((StatusCode::FORBIDDEN, format!("Only {} can anchor results", anchoring.address)));
+    }
+    let proposal = state.governance.proposal(&id).await.ok_or_else(|| not_found(&id))?;
+    let tally = proposal.tally(now_ms());
+    if matches!(tally.outcome, Outcome::Pending | Outcome::Open) {
+        return Err((StatusCode::CONFLICT, format!("Voting on proposal {} has not ended", id)));
+    }
+    state
+        .governance
+        .sync_anchors(&state.rpc, &state.config.wot_id_pkg_id, &anchoring.address)
+        .await
+        .map_err(|e| rpc_error("Failed to look up anchored results", e))?;
+    if let Some(object_id) = state.governance.anchored_result(&id, &tally).await {
+        return Err((StatusCode::CONFLICT, format!("Proposal {} is already anchored as {}", id, object_id)));
+    }
+
+    let arguments = vec![
+        json!(anchoring.cap_id),
+        json!(id.clone().into_bytes()),
+        json!(Sha256::digest(proposal.jws.as_bytes()).to_vec()),
+        json!(proposal.ballots_hash().to_vec()),
+        json!(proposal.weighting.as_str().as_bytes()),
+        json!(tally.votes_for.to_string()),
+        json!(tally.votes_against.to_string()),
+        json!(tally.votes_abstain.to_string()),
+        json!(tally.voters.to_string()),
+        json!(proposal.quorum.to_string()),
+        json!(proposal.threshold_pct),
+        json!(proposal.voting_ends_ms.to_string()),
+        // The shared Clock object
+        json!("0x6"),
+    ];
+    let transaction = state
+        .rpc
+        .move_call(&payload.signer, &state.config.wot_id_pkg_id, "governance", "anchor_result", arguments, state.config.gas_budget)
+        .await
+        .map_err(|e| rpc_error("Failed to build anchor_result transaction", e))?;
+    tracing::info!(target: "backend::governance", "Built anchor transaction for proposal {} ({})", id, tally.outcome.as_str());
+
+    Ok(Json(AnchorResponse { tally, transaction }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+
+    fn proposal(quorum: u64, threshold_pct: u8) -> Proposal {
+        Proposal {
+            id: "p1".to_string(),
+            proposer: "did:iota:tst:0xa".to_string(),
+            title: "Raise the gas budget".to_string(),
+            description: String::new(),
+            implementation_plan: None,
+            context: "ops".to_string(),
+            weighting: Weighting::OneDidOneVote,
+            quorum,
+            threshold_pct,
+            voting_starts_ms: 1_000,
+            voting_ends_ms: 2_000,
+            created_at_ms: 500,
+            jws: "proposal.jws".to_string(),
+            ballots: Vec::new(),
+        }
+    }
+
+    fn ballot(voter: &str, choice: Choice, signed_at: u64) -> Ballot {
+        Ballot {
+            voter: voter.to_string(),
+            choice,
+            weight: 1,
+            signed_at,
+            jws: format!("{}.{}.jws", voter, signed_at),
+            recorded_at_ms: 1_500,
+        }
+    }
+
+    fn with_votes(quorum: u64, threshold_pct: u8, choices: &[Choice]) -> Proposal {
+        let mut p = proposal(quorum, threshold_pct);
+        p.ballots = choices.iter().enumerate().map(|(i, c)| ballot(&format!("did:iota:tst:0x{}", i), *c, 1)).collect();
+        p
+    }
+
+    fn claims(quorum: Option<u64>, threshold_pct: u8, voting_ends_ms: u64) -> ProposeClaims {
+        ProposeClaims {
+            title: "Raise the gas budget".to_string(),
+            description: String::new(),
+            implementation_plan: None,
+            context: "ops".to_string(),
+            weighting: None,
+            quorum,
+            threshold_pct,
+            voting_starts_ms: None,
+            voting_ends_ms,
+        }
+    }
+
+    #[test]
+    fn outcome_follows_the_voting_window() {
+        let p = with_votes(1, 50, &[Choice::For]);
+        assert_eq!(p.tally(999).outcome, Outcome::Pending);
+        assert_eq!(p.tally(1_000).outcome, Outcome::Open);
+        assert_eq!(p.tally(1_999).outcome, Outcome::Open);
+        assert_eq!(p.tally(2_000).outcome, Outcome::Passed);
+    }
+
+    #[test]
+    fn abstentions_count_towards_quorum_only() {
+        let p = with_votes(3, 50, &[Choice::For, Choice::Abstain]);
+        let tally = p.tally(2_000);
+        assert!(!tally.quorum_reached);
+        assert_eq!(tally.outcome, Outcome::NoQuorum);
+
+        let p = with_votes(3, 50, &[Choice::For, Choice::Abstain, Choice::Abstain]);
+        let tally = p.tally(2_000);
+        assert!(tally.quorum_reached);
+        assert_eq!((tally.votes_for, tally.votes_against, tally.votes_abstain, tally.voters), (1, 0, 2, 3));
+        assert_eq!(tally.outcome, Outcome::Passed);
+    }
+
+    // `for` must exceed the threshold; landing exactly on it rejects
+    #[test]
+    fn threshold_is_strict() {
+        use Choice::{Against, For};
+        assert_eq!(with_votes(2, 50, &[For, Against]).tally(2_000).outcome, Outcome::Rejected);
+        assert_eq!(with_votes(3, 50, &[For, For, Against]).tally(2_000).outcome, Outcome::Passed);
+        // 2/3 is 66.67%: passes 66, fails 67
+        assert_eq!(with_votes(3, 66, &[For, For, Against]).tally(2_000).outcome, Outcome::Passed);
+        assert_eq!(with_votes(3, 67, &[For, For, Against]).tally(2_000).outcome, Outcome::Rejected);
+        assert_eq!(with_votes(1, 100, &[For]).tally(2_000).outcome, Outcome::Rejected);
+        assert_eq!(with_votes(1, 0, &[For]).tally(2_000).outcome, Outcome::Passed);
+        // Abstentions alone reach quorum but nothing exceeds a 0% threshold of zero
+        assert_eq!(with_votes(1, 0, &[Choice::Abstain]).tally(2_000).outcome, Outcome::Rejected);
+    }
+
+    #[test]
+    fn trust_weights_do_not_overflow() {
+        let mut p = proposal(1, 50);
+        p.weighting = Weighting::TrustWeighted;
+        p.ballots = vec![ballot("did:iota:tst:0x1", Choice::For, 1), ballot("did:iota:tst:0x2", Choice::Against, 1)];
+        p.ballots[0].weight = u64::MAX / 2;
+        p.ballots[1].weight = u64::MAX / 2 - 1;
+        assert_eq!(p.tally(2_000).outcome, Outcome::Passed);
+    }
+
+    #[test]
+    fn ballots_hash_ignores_arrival_order() {
+        let mut p = with_votes(1, 50, &[Choice::For, Choice::Against]);
+        let hash = p.tally(2_000).ballots_hash;
+        p.ballots.reverse();
+        assert_eq!(p.tally(2_000).ballots_hash, hash);
+    }
+
+    #[test]
+    fn newer_ballot_replaces_older_one() {
+        let mut p = proposal(1, 50);
+        record_ballot(&mut p, ballot("did:iota:tst:0x1", Choice::For, 10)).unwrap();
+        record_ballot(&mut p, ballot("did:iota:tst:0x1", Choice::Against, 11)).unwrap();
+        assert_eq!(p.ballots.len(), 1);
+        assert_eq!(p.ballots[0].choice, Choice::Against);
+
+        record_ballot(&mut p, ballot("did:iota:tst:0x2", Choice::For, 5)).unwrap();
+        assert_eq!(p.ballots.len(), 2);
+    }
+
+    #[test]
+    fn resubmitted_ballot_is_a_no_op() {
+        let mut p = proposal(1, 50);
+        let first = record_ballot(&mut p, ballot("did:iota:tst:0x1", Choice::For, 10)).unwrap();
+        let mut again = ballot("did:iota:tst:0x1", Choice::For, 10);
+        again.recorded_at_ms = 1_900;
+        let recorded = record_ballot(&mut p, again).unwrap();
+        assert_eq!(recorded.recorded_at_ms, first.recorded_at_ms);
+        assert_eq!(p.ballots.len(), 1);
+    }
+
+    // Replaying the earlier ballot after a change of vote, or a different one signed in the
+    // same second, must not displace the recorded ballot
+    #[test]
+    fn older_or_same_age_ballot_conflicts() {
+        let mut p = proposal(1, 50);
+        record_ballot(&mut p, ballot("did:iota:tst:0x1", Choice::For, 10)).unwrap();
+        record_ballot(&mut p, ballot("did:iota:tst:0x1", Choice::Against, 11)).unwrap();
+
+        let replay = record_ballot(&mut p, ballot("did:iota:tst:0x1", Choice::For, 10)).unwrap_err();
+        assert_eq!(replay.0, StatusCode::CONFLICT);
+        let mut same_second = ballot("did:iota:tst:0x1", Choice::Abstain, 11);
+        same_second.jws = "other.jws".to_string();
+        assert_eq!(record_ballot(&mut p, same_second).unwrap_err().0, StatusCode::CONFLICT);
+        assert_eq!(p.ballots[0].choice, Choice::Against);
+    }
+
+    #[test]
+    fn proposal_defaults_come_from_the_rules() {
+        let rules = GovernanceRules::default();
+        let (weighting, quorum, starts) = validate_proposal(&rules, &claims(None, 50, 100 + 2 * DAY_MS), 100).unwrap();
+        assert_eq!(weighting, Weighting::OneDidOneVote);
+        assert_eq!(quorum, rules.min_quorum_votes);
+        assert_eq!(starts, 100);
+
+        let mut trust = claims(None, 50, 100 + 2 * DAY_MS);
+        trust.weighting = Some(Weighting::TrustWeighted);
+        assert_eq!(validate_proposal(&rules, &trust, 100).unwrap().1, rules.min_quorum_trust);
+
+        // A start in the past is moved up to now
+        let mut backdated = claims(None, 50, 100 + 2 * DAY_MS);
+        backdated.voting_starts_ms = Some(1);
+        assert_eq!(validate_proposal(&rules, &backdated, 100).unwrap().2, 100);
+    }
+
+    #[test]
+    fn proposals_outside_the_rules_are_rejected() {
+        let rules = GovernanceRules::default();
+        let now = 100;
+        let rejected = |claims: ProposeClaims| validate_proposal(&rules, &claims, now).unwrap_err().0;
+
+        assert_eq!(rejected(claims(Some(2), 50, now + 2 * DAY_MS)), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(rejected(claims(None, 101, now + 2 * DAY_MS)), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(rejected(claims(None, 50, now + DAY_MS - 1)), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(rejected(claims(None, 50, now + 30 * DAY_MS + 1)), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(rejected(claims(None, 50, 0)), StatusCode::UNPROCESSABLE_ENTITY);
+        let mut untitled = claims(None, 50, now + 2 * DAY_MS);
+        untitled.title = "  ".to_string();
+        assert_eq!(rejected(untitled), StatusCode::UNPROCESSABLE_ENTITY);
+        let mut no_context = claims(None, 50, now + 2 * DAY_MS);
+        no_context.context = String::new();
+        assert_eq!(rejected(no_context), StatusCode::UNPROCESSABLE_ENTITY);
+
+        // The limits themselves are inclusive
+        assert!(validate_proposal(&rules, &claims(Some(3), 100, now + DAY_MS), now).is_ok());
+        assert!(validate_proposal(&rules, &claims(None, 0, now + 30 * DAY_MS), now).is_ok());
+    }
+
+    const ANCHORER: &str = "0x00000000000000000000000000000000000000000000000000000000000000a1";
+
+    fn anchor_event(sender: &str, object_id: &str, tally: &Tally) -> Value {
+        let bytes = |hex_hash: &str| hex::decode(hex_hash).unwrap();
+        json!({
+            "sender": sender,
+            "parsedJson": {
+                "object_id": object_id,
+                "proposal_id": "p1",
+                "proposal_hash": bytes(&tally.proposal_hash),
+                "ballots_hash": bytes(&tally.ballots_hash),
+                "outcome": tally.outcome.as_str(),
+                "votes_for": tally.votes_for.to_string(),
+                "votes_against": tally.votes_against.to_string(),
+                "votes_abstain": tally.votes_abstain.to_string(),
+                "anchored_by": sender,
+            },
+        })
+    }
+
+    #[test]
+    fn only_matching_anchors_from_the_anchoring_address_count() {
+        let tally = with_votes(1, 50, &[Choice::For]).tally(3_000);
+        let record = AnchorRecord::from_event(&anchor_event(&ANCHORER.to_uppercase().replace("0X", "0x"), "0xr1", &tally), ANCHORER);
+        assert!(record.unwrap().records("p1", &tally));
+
+        assert_eq!(AnchorRecord::from_event(&anchor_event("0xbad", "0xr2", &tally), ANCHORER), None);
+
+        // A forged "passed" for a proposal that was rejected here
+        let rejected = with_votes(1, 50, &[Choice::Against]).tally(3_000);
+        let forged = AnchorRecord::from_event(&anchor_event(ANCHORER, "0xr3", &tally), ANCHORER).unwrap();
+        assert!(!forged.records("p1", &rejected));
+        assert!(!forged.records("p2", &tally));
+    }
+
+    #[tokio::test]
+    async fn anchor_events_are_read_once_from_the_saved_cursor() {
+        let tally = with_votes(1, 50, &[Choice::For]).tally(3_000);
+        let pages = Arc::new(vec![
+            json!({ "data": [anchor_event("0xbad", "0xforged", &tally)], "nextCursor": { "eventSeq": "1" }, "hasNextPage": true }),
+            json!({ "data": [anchor_event(ANCHORER, "0xr1", &tally)], "nextCursor": { "eventSeq": "2" }, "hasNextPage": false }),
+            json!({ "data": [], "nextCursor": null, "hasNextPage": false }),
+        ]);
+        let cursors = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = cursors.clone();
+        let app = axum::Router::new().route(
+            "/",
+            axum::routing::post(move |Json(request): Json<Value>| async move {
+                assert_eq!(request["method"], "iotax_queryEvents");
+                let mut seen = seen.lock().unwrap();
+                seen.push(request["params"][1].clone());
+                let page = pages[(seen.len() - 1).min(2)].clone();
+                Json(json!({ "jsonrpc": "2.0", "id": request["id"], "result": page }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        let rpc = IotaRpcClient::new(reqwest::Client::new(), format!("http://{}/", addr));
+
+        let dir = std::env::temp_dir().join(format!("wot-governance-{}", Uuid::new_v4()));
+        let path = dir.join("governance.json");
+        let store = GovernanceStore::load(&path, GovernanceRules::default(), None).unwrap();
+        store.sync_anchors(&rpc, "0xpkg", ANCHORER).await.unwrap();
+        assert_eq!(store.anchored_result("p1", &tally).await.as_deref(), Some("0xr1"));
+
+        // A restarted store resumes after the last event instead of rereading every page
+        let store = GovernanceStore::load(&path, GovernanceRules::default(), None).unwrap();
+        store.sync_anchors(&rpc, "0xpkg", ANCHORER).await.unwrap();
+        assert_eq!(*cursors.lock().unwrap(), vec![Value::Null, json!({ "eventSeq": "1" }), json!({ "eventSeq": "2" })]);
+        assert_eq!(store.data.lock().await.anchors.len(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
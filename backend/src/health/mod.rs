@@ -1,136 +1,548 @@
-use axum::{routing::get, Json, Router};
-use serde::{Deserialize, Serialize};
-use std::time::Duration;
-use reqwest::Client;
-use serde_json::json;
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct HealthStatus {
-    pub status: String,
-    pub components: ComponentStatus,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ComponentStatus {
-    pub backend: String,
-    pub identity_service: Option<String>,
-    pub iota_node: Option<String>,
-}
-
-pub fn routes() -> Router {
-    tracing::info!("[health::routes] Health routes function called.");
-    Router::new().route("/health", get(health_check))
-}
-
-// Overall health check handler
-#[axum::debug_handler]
-async fn health_check() -> Json<HealthStatus> {
-    tracing::info!("[health::health_check] Detailed health_check function called.");
-    let client = Client::new();
-    
-    // Check identity service
-    let identity_status = check_identity_service(&client).await;
-    
-    // Check IOTA node
-    let iota_status = check_iota_node().await;
-    
-    // Overall status is OK only if all checked components report "ok"
-    let overall_status = if identity_status.as_deref() == Some("ok") 
-                           && iota_status.as_deref() == Some("ok") {
-        "ok".to_string()
-    } else {
-        // If any component is None or not "ok", the system is degraded or has an error.
-        // We can refine this further if specific error states from components need different overall statuses.
-        if identity_status.is_none() || iota_status.is_none() {
-            "degraded (component missing)".to_string()
-        } else {
-            "error (component failure)".to_string()
-        }
-    };
-    
-    Json(HealthStatus {
-        status: overall_status,
-        components: ComponentStatus {
-            backend: "ok".to_string(),
-            identity_service: identity_status,
-            iota_node: iota_status,
-        },
-    })
+use axum::{extract::State, http::StatusCode, Json};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::RwLock;
+use utoipa::ToSchema;
+use utoipa_axum::{router::OpenApiRouter, routes};
+use wot_config::{ConfigError, Settings};
+
+use crate::iota_rpc::RpcError;
+use crate::lifecycle::Shutdown;
+use crate::state::AppState;
+
+pub const IOTA_NODE: &str = "iota_node";
+pub const IDENTITY_SERVICE: &str = "identity_service";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProbeStatus {
+    Up,
+    // Answering, but not as it should, e.g. lagging behind the network
+    Degraded,
+    Down,
+    // Not probed yet, or not within the cache TTL
+    Unknown,
 }
 
-// Check identity service health
-async fn check_identity_service(client: &Client) -> Option<String> {
-    let identity_url = std::env::var("IDENTITY_SERVICE_URL")
-        .unwrap_or_else(|_| "http://127.0.0.1:8081".to_string());
-    
-    match client
-        .get(format!("{}/health", identity_url))
-        .timeout(Duration::from_secs(5))
-        .send()
-        .await
-    {
-        Ok(response) => {
-            if response.status().is_success() {
-                Some("ok".to_string())
-            } else {
-                Some("error".to_string())
-            }
+// Why a probe failed
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case", tag = "kind")]
+pub enum ProbeError {
+    // No answer within the probe timeout
+    Timeout,
+    // Connection refused, DNS failure and the like
+    Unreachable { detail: String },
+    HttpStatus { status: u16 },
+    // The node answered with a JSON-RPC error
+    Rpc { code: i64, message: String },
+    Malformed { detail: String },
+    // The node is on a different network than `IOTA_CHAIN_ID`
+    WrongChain { expected: String, actual: String },
+    // Protocol version outside `IOTA_MIN_PROTOCOL_VERSION`..=`IOTA_MAX_PROTOCOL_VERSION`
+    UnsupportedProtocol { version: u64, min: Option<u64>, max: Option<u64> },
+    // Latest checkpoint older than `HEALTH_MAX_CHECKPOINT_LAG_SECS`
+    CheckpointLag { lag_secs: u64, max_lag_secs: u64 },
+}
+
+impl From<RpcError> for ProbeError {
+    fn from(e: RpcError) -> Self {
+        match e {
+            RpcError::Transport(e) if e.is_timeout() => ProbeError::Timeout,
+            RpcError::Transport(e) => ProbeError::Unreachable { detail: e.to_string() },
+            RpcError::Status(status) => ProbeError::HttpStatus { status },
+            RpcError::Node { code, message } => ProbeError::Rpc { code, message },
+            RpcError::MalformedResponse(detail) => ProbeError::Malformed { detail },
         }
-        Err(_) => None,
     }
 }
 
-// Check IOTA node health using JSON-RPC rpc.discover method
-async fn check_iota_node() -> Option<String> {
-    let node_url_str = match std::env::var("IOTA_NODE_URL") {
-        Ok(url) => {
-            tracing::info!(target: "backend::health::iota_node", "IOTA_NODE_URL for JSON-RPC: {}", url);
-            url
+impl From<reqwest::Error> for ProbeError {
+    fn from(e: reqwest::Error) -> Self {
+        if e.is_timeout() {
+            ProbeError::Timeout
+        } else {
+            ProbeError::Unreachable { detail: e.to_string() }
         }
-        Err(e) => {
-            tracing::error!(target: "backend::health::iota_node", "IOTA_NODE_URL environment variable not set: {:?}", e);
-            return Some("error_env_var_missing".to_string());
+    }
+}
+
+// Latest probe result of one dependency
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ComponentHealth {
+    pub status: ProbeStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_checked_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_success_ms: Option<u64>,
+    pub consecutive_failures: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ProbeError>,
+    // What the last successful probe saw, e.g. the node's latest checkpoint
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<Value>,
+}
+
+// A probe that got an answer; `degraded` says what is wrong with it, if anything
+#[derive(Debug, Default)]
+struct Probed {
+    details: Option<Value>,
+    degraded: Option<ProbeError>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct HealthReport {
+    // `down` if any component is down or not probed yet, else `degraded` if any component is
+    pub status: ProbeStatus,
+    pub uptime_secs: u64,
+    pub components: BTreeMap<&'static str, ComponentHealth>,
+}
+
+// Dependency health, refreshed by the `probe` task; requests only read the cached results so
+// health checks never reach the dependencies themselves
+pub struct HealthMonitor {
+    started: Instant,
+    // Results older than this are reported as unknown, so a stalled probe task fails readiness
+    ttl: Duration,
+    components: RwLock<BTreeMap<&'static str, ComponentHealth>>,
+}
+
+impl HealthMonitor {
+    pub fn new(names: &[&'static str], ttl: Duration) -> Self {
+        let unknown = ComponentHealth {
+            status: ProbeStatus::Unknown,
+            latency_ms: None,
+            last_checked_ms: None,
+            last_success_ms: None,
+            consecutive_failures: 0,
+            error: None,
+            details: None,
+        };
+        Self {
+            started: Instant::now(),
+            ttl,
+            components: RwLock::new(names.iter().map(|&name| (name, unknown.clone())).collect()),
         }
-    };
-
-    let client = reqwest::Client::new();
-    let payload = json!({
-        "jsonrpc": "2.0",
-        "id": 1,
-        "method": "rpc.discover",
-        "params": {}
-    });
-
-    tracing::info!(target: "backend::health::iota_node", "Attempting JSON-RPC 'rpc.discover' to {}", node_url_str);
-
-    match client.post(&node_url_str).json(&payload).send().await {
-        Ok(response) => {
-            if response.status().is_success() {
-                match response.text().await {
-                    Ok(text) => {
-                        if !text.is_empty() {
-                            tracing::info!(target: "backend::health::iota_node", "IOTA node JSON-RPC 'rpc.discover' successful.");
-                            Some("ok".to_string())
-                        } else {
-                            tracing::error!(target: "backend::health::iota_node", "IOTA node JSON-RPC 'rpc.discover' returned success status but empty body.");
-                            Some("error_rpc_empty_response".to_string())
-                        }
+    }
+
+    async fn record(&self, name: &'static str, result: Result<Probed, ProbeError>, latency: Duration) {
+        let now = now_ms();
+        let mut components = self.components.write().await;
+        let Some(component) = components.get_mut(name) else {
+            return;
+        };
+        component.latency_ms = Some(latency.as_millis() as u64);
+        component.last_checked_ms = Some(now);
+        match result {
+            Ok(probed) => {
+                let status = if probed.degraded.is_some() { ProbeStatus::Degraded } else { ProbeStatus::Up };
+                match &probed.degraded {
+                    Some(error) if component.status != ProbeStatus::Degraded => {
+                        tracing::warn!(target: "backend::health", "{} is degraded: {:?}", name, error)
                     }
-                    Err(e) => {
-                        tracing::error!(target: "backend::health::iota_node", "IOTA node JSON-RPC 'rpc.discover' failed to read response body: {:?}", e);
-                        Some("error_rpc_read_body_failed".to_string())
+                    None if component.status != ProbeStatus::Up && component.status != ProbeStatus::Unknown => {
+                        tracing::info!(target: "backend::health", "{} is up again", name)
                     }
+                    _ => {}
+                }
+                component.status = status;
+                component.last_success_ms = Some(now);
+                component.consecutive_failures = 0;
+                component.error = probed.degraded;
+                component.details = probed.details;
+            }
+            Err(error) => {
+                if component.status != ProbeStatus::Down {
+                    tracing::warn!(target: "backend::health", "{} is down: {:?}", name, error);
                 }
-            } else {
-                let status = response.status();
-                let error_text = response.text().await.unwrap_or_else(|_| "Failed to read error body".to_string());
-                tracing::error!(target: "backend::health::iota_node", "IOTA node JSON-RPC 'rpc.discover' failed with status: {}. Body (truncated): {}", status, error_text.chars().take(200).collect::<String>());
-                Some(format!("error_rpc_failed_status_{}", status.as_u16()))
+                component.status = ProbeStatus::Down;
+                component.consecutive_failures += 1;
+                component.error = Some(error);
+            }
+        }
+    }
+
+    pub async fn report(&self) -> HealthReport {
+        self.report_at(now_ms()).await
+    }
+
+    async fn report_at(&self, now_ms: u64) -> HealthReport {
+        let mut components = self.components.read().await.clone();
+        let ttl_ms = self.ttl.as_millis() as u64;
+        for component in components.values_mut() {
+            if component.last_checked_ms.is_some_and(|checked| now_ms.saturating_sub(checked) > ttl_ms) {
+                component.status = ProbeStatus::Unknown;
             }
         }
-        Err(e) => {
-            tracing::error!(target: "backend::health::iota_node", "IOTA node JSON-RPC 'rpc.discover' request failed: {:?}", e);
-            Some("error_rpc_request_failed".to_string())
+        let statuses = || components.values().map(|c| c.status);
+        let status = if statuses().any(|s| matches!(s, ProbeStatus::Down | ProbeStatus::Unknown)) {
+            ProbeStatus::Down
+        } else if statuses().any(|s| s == ProbeStatus::Degraded) {
+            ProbeStatus::Degraded
+        } else {
+            ProbeStatus::Up
+        };
+        HealthReport {
+            status,
+            uptime_secs: self.started.elapsed().as_secs(),
+            components,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ProbeConfig {
+    pub interval: Duration,
+    pub timeout: Duration,
+    // Chain identifier of the network the node must be on; not checked when unset
+    pub chain_id: Option<String>,
+    pub min_protocol_version: Option<u64>,
+    pub max_protocol_version: Option<u64>,
+    // Latest checkpoint age beyond which the node counts as degraded
+    pub max_checkpoint_lag: Duration,
+}
+
+impl ProbeConfig {
+    pub fn from_settings(settings: &Settings) -> Result<Self, ConfigError> {
+        let positive = |name: &str, default: u64| settings.parse_or(name, default).map(|v| if v > 0 { v } else { default });
+        Ok(Self {
+            interval: Duration::from_secs(positive("HEALTH_PROBE_INTERVAL_SECS", 10)?),
+            timeout: Duration::from_millis(positive("HEALTH_PROBE_TIMEOUT_MS", 3000)?),
+            chain_id: settings.var("IOTA_CHAIN_ID"),
+            min_protocol_version: settings.parse("IOTA_MIN_PROTOCOL_VERSION")?,
+            max_protocol_version: settings.parse("IOTA_MAX_PROTOCOL_VERSION")?,
+            max_checkpoint_lag: Duration::from_secs(positive("HEALTH_MAX_CHECKPOINT_LAG_SECS", 60)?),
+        })
+    }
+
+    // How long a probe result stays valid: three missed rounds
+    pub fn cache_ttl(&self) -> Duration {
+        self.interval * 3 + self.timeout
+    }
+}
+
+// Long-running task: probe every dependency once per interval and cache the results
+pub async fn probe(state: AppState, config: ProbeConfig, shutdown: Shutdown) {
+    let mut interval = tokio::time::interval(config.interval);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            () = shutdown.requested() => return,
         }
+        tokio::join!(
+            run_probe(&state, IOTA_NODE, config.timeout, probe_node(&state, &config)),
+            run_probe(&state, IDENTITY_SERVICE, config.timeout, probe_identity_service(&state)),
+        );
+    }
+}
+
+async fn run_probe(
+    state: &AppState,
+    name: &'static str,
+    timeout: Duration,
+    probe: impl Future<Output = Result<Probed, ProbeError>>,
+) {
+    let started = Instant::now();
+    let result = tokio::time::timeout(timeout, probe).await.unwrap_or(Err(ProbeError::Timeout));
+    state.health.record(name, result, started.elapsed()).await;
+}
+
+#[derive(Debug, Serialize)]
+struct NodeDetails {
+    chain_id: String,
+    protocol_version: u64,
+    checkpoint: u64,
+    checkpoint_timestamp_ms: u64,
+    lag_secs: u64,
+}
+
+// Reachable is not enough: the node must be on the expected network, speak a supported
+// protocol version and have a recent checkpoint
+async fn probe_node(state: &AppState, config: &ProbeConfig) -> Result<Probed, ProbeError> {
+    let (chain_id, protocol, checkpoint) = tokio::try_join!(
+        state.rpc.call("iota_getChainIdentifier", json!([])),
+        state.rpc.call("iota_getProtocolConfig", json!([])),
+        state.rpc.call("iota_getLatestCheckpointSequenceNumber", json!([])),
+    )?;
+    let chain_id = chain_id.as_str().ok_or_else(|| malformed("chain identifier"))?.to_string();
+    let protocol_version = protocol.get("protocolVersion").and_then(as_u64).ok_or_else(|| malformed("protocol version"))?;
+    let checkpoint = as_u64(&checkpoint).ok_or_else(|| malformed("checkpoint sequence number"))?;
+    let checkpoint_timestamp_ms = state
+        .rpc
+        .call("iota_getCheckpoint", json!([checkpoint.to_string()]))
+        .await?
+        .get("timestampMs")
+        .and_then(as_u64)
+        .ok_or_else(|| malformed("checkpoint timestamp"))?;
+
+    if let Some(expected) = &config.chain_id
+        && *expected != chain_id
+    {
+        return Err(ProbeError::WrongChain { expected: expected.clone(), actual: chain_id });
+    }
+    if config.min_protocol_version.is_some_and(|min| protocol_version < min)
+        || config.max_protocol_version.is_some_and(|max| protocol_version > max)
+    {
+        return Err(ProbeError::UnsupportedProtocol {
+            version: protocol_version,
+            min: config.min_protocol_version,
+            max: config.max_protocol_version,
+        });
+    }
+
+    let lag_secs = now_ms().saturating_sub(checkpoint_timestamp_ms) / 1000;
+    let max_lag_secs = config.max_checkpoint_lag.as_secs();
+    let details = NodeDetails { chain_id, protocol_version, checkpoint, checkpoint_timestamp_ms, lag_secs };
+    Ok(Probed {
+        details: Some(serde_json::to_value(details).expect("node details serialize to JSON")),
+        degraded: (lag_secs > max_lag_secs).then_some(ProbeError::CheckpointLag { lag_secs, max_lag_secs }),
+    })
+}
+
+// u64 values are rendered as strings by the node
+fn as_u64(value: &Value) -> Option<u64> {
+    match value {
+        Value::String(s) => s.parse().ok(),
+        other => other.as_u64(),
+    }
+}
+
+fn malformed(what: &str) -> ProbeError {
+    ProbeError::Malformed { detail: format!("missing or invalid {}", what) }
+}
+
+async fn probe_identity_service(state: &AppState) -> Result<Probed, ProbeError> {
+    let status = state.identity.readiness().await?;
+    if status.is_success() {
+        Ok(Probed::default())
+    } else {
+        Err(ProbeError::HttpStatus { status: status.as_u16() })
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+pub fn routes() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new().routes(routes!(livez)).routes(routes!(readyz)).routes(routes!(health))
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct LivenessResponse {
+    pub status: ProbeStatus,
+    pub uptime_secs: u64,
+}
+
+// The process is serving requests; dependencies are not consulted
+#[utoipa::path(get, path = "/livez", tag = "health", responses((status = 200, body = LivenessResponse)))]
+async fn livez(State(state): State<AppState>) -> Json<LivenessResponse> {
+    Json(LivenessResponse {
+        status: ProbeStatus::Up,
+        uptime_secs: state.health.started.elapsed().as_secs(),
+    })
+}
+
+// 503 until every dependency's last probe got an answer, and again once that answer is older
+// than the cache TTL; a degraded dependency still serves
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    tag = "health",
+    responses(
+        (status = 200, description = "Every dependency answered", body = HealthReport),
+        (status = 503, description = "A dependency is down or not probed recently", body = HealthReport),
+    )
+)]
+async fn readyz(State(state): State<AppState>) -> (StatusCode, Json<HealthReport>) {
+    let report = state.health.report().await;
+    let code = if report.status == ProbeStatus::Down { StatusCode::SERVICE_UNAVAILABLE } else { StatusCode::OK };
+    (code, Json(report))
+}
+
+// Same report as `/readyz`, always with 200, for dashboards
+#[utoipa::path(get, path = "/health", tag = "health", responses((status = 200, body = HealthReport)))]
+async fn health(State(state): State<AppState>) -> Json<HealthReport> {
+    Json(state.health.report().await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, serve, TestDir};
+
+    const PROBED: Duration = Duration::from_millis(5);
+    const CHAIN: &str = "6364aad5";
+
+    // Node on `CHAIN` at `protocol_version` whose latest checkpoint is `age_secs` old
+    async fn node(protocol_version: u64, age_secs: u64) -> String {
+        let timestamp_ms = now_ms() - age_secs * 1000;
+        serve(axum::Router::new().route(
+            "/",
+            axum::routing::post(move |Json(request): Json<Value>| async move {
+                let result = match request["method"].as_str().unwrap() {
+                    "iota_getChainIdentifier" => json!(CHAIN),
+                    "iota_getProtocolConfig" => json!({ "protocolVersion": protocol_version.to_string() }),
+                    "iota_getLatestCheckpointSequenceNumber" => json!("4242"),
+                    "iota_getCheckpoint" => {
+                        assert_eq!(request["params"][0], "4242");
+                        json!({ "sequenceNumber": "4242", "timestampMs": timestamp_ms.to_string() })
+                    }
+                    method => panic!("unexpected call to {}", method),
+                };
+                Json(json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }))
+            }),
+        ))
+        .await
+    }
+
+    fn config() -> ProbeConfig {
+        ProbeConfig {
+            interval: Duration::from_secs(10),
+            timeout: Duration::from_secs(3),
+            chain_id: Some(CHAIN.to_string()),
+            min_protocol_version: Some(5),
+            max_protocol_version: Some(7),
+            max_checkpoint_lag: Duration::from_secs(60),
+        }
+    }
+
+    async fn probe_node_at(node_url: &str, config: &ProbeConfig) -> Result<Probed, ProbeError> {
+        let dir = TestDir::new("health-node");
+        let state = testing::app_state(&dir, "http://127.0.0.1:1", &[("IOTA_NODE_URL", node_url.to_string())]);
+        probe_node(&state, config).await
+    }
+
+    fn state(dir: &TestDir) -> AppState {
+        testing::app_state(dir, "http://127.0.0.1:1", &[])
+    }
+
+    async fn readiness(state: &AppState) -> (StatusCode, ProbeStatus) {
+        let (code, Json(report)) = readyz(State(state.clone())).await;
+        (code, report.status)
+    }
+
+    #[tokio::test]
+    async fn not_ready_until_every_dependency_answered() {
+        let dir = TestDir::new("health-ready");
+        let state = state(&dir);
+        assert_eq!(readiness(&state).await, (StatusCode::SERVICE_UNAVAILABLE, ProbeStatus::Down));
+
+        state.health.record(IOTA_NODE, Ok(Probed::default()), PROBED).await;
+        assert_eq!(readiness(&state).await, (StatusCode::SERVICE_UNAVAILABLE, ProbeStatus::Down));
+
+        state.health.record(IDENTITY_SERVICE, Ok(Probed::default()), PROBED).await;
+        assert_eq!(readiness(&state).await, (StatusCode::OK, ProbeStatus::Up));
+    }
+
+    #[tokio::test]
+    async fn a_down_dependency_fails_readiness_and_a_degraded_one_does_not() {
+        let dir = TestDir::new("health-down");
+        let state = state(&dir);
+        let lagging = Probed { details: None, degraded: Some(ProbeError::CheckpointLag { lag_secs: 120, max_lag_secs: 60 }) };
+        state.health.record(IOTA_NODE, Ok(lagging), PROBED).await;
+        state.health.record(IDENTITY_SERVICE, Ok(Probed::default()), PROBED).await;
+        assert_eq!(readiness(&state).await, (StatusCode::OK, ProbeStatus::Degraded));
+
+        state.health.record(IDENTITY_SERVICE, Err(ProbeError::HttpStatus { status: 503 }), PROBED).await;
+        assert_eq!(readiness(&state).await, (StatusCode::SERVICE_UNAVAILABLE, ProbeStatus::Down));
+        let report = state.health.report().await;
+        assert_eq!(report.components[IDENTITY_SERVICE].consecutive_failures, 1);
+        assert!(report.components[IDENTITY_SERVICE].last_success_ms.is_some());
+
+        // Recovers with the next successful probe
+        state.health.record(IDENTITY_SERVICE, Ok(Probed::default()), PROBED).await;
+        assert_eq!(readiness(&state).await.0, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn results_older_than_the_ttl_are_unknown() {
+        let monitor = HealthMonitor::new(&[IOTA_NODE], Duration::from_secs(33));
+        monitor.record(IOTA_NODE, Ok(Probed::default()), PROBED).await;
+        let checked = monitor.report().await.components[IOTA_NODE].last_checked_ms.unwrap();
+
+        let fresh = monitor.report_at(checked + 33_000).await;
+        assert_eq!(fresh.status, ProbeStatus::Up);
+        let stale = monitor.report_at(checked + 33_001).await;
+        assert_eq!(stale.status, ProbeStatus::Down);
+        assert_eq!(stale.components[IOTA_NODE].status, ProbeStatus::Unknown);
+        // The last result is still shown
+        assert_eq!(stale.components[IOTA_NODE].last_success_ms, Some(checked));
+    }
+
+    #[test]
+    fn ttl_covers_three_missed_rounds() {
+        let settings = Settings::from_parts(
+            Vec::new(),
+            [("HEALTH_PROBE_INTERVAL_SECS", "10"), ("HEALTH_PROBE_TIMEOUT_MS", "3000")]
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        )
+        .unwrap();
+        assert_eq!(ProbeConfig::from_settings(&settings).unwrap().cache_ttl(), Duration::from_secs(33));
+    }
+
+    #[tokio::test]
+    async fn a_current_node_on_the_expected_chain_is_up() {
+        let probed = probe_node_at(&node(6, 2).await, &config()).await.unwrap();
+        assert!(probed.degraded.is_none());
+        let details = probed.details.unwrap();
+        assert_eq!(details["chain_id"], CHAIN);
+        assert_eq!(details["protocol_version"], 6);
+        assert_eq!(details["checkpoint"], 4242);
+        assert!(details["lag_secs"].as_u64().unwrap() <= 3);
+    }
+
+    #[tokio::test]
+    async fn checkpoint_lag_degrades_the_node() {
+        let url = node(6, 120).await;
+        let probed = probe_node_at(&url, &config()).await.unwrap();
+        let Some(ProbeError::CheckpointLag { lag_secs, max_lag_secs: 60 }) = probed.degraded else {
+            panic!("expected checkpoint lag, got {:?}", probed.degraded);
+        };
+        assert!((120..=123).contains(&lag_secs));
+
+        // Within the configured lag it is up
+        let patient = ProbeConfig { max_checkpoint_lag: Duration::from_secs(300), ..config() };
+        assert!(probe_node_at(&url, &patient).await.unwrap().degraded.is_none());
+    }
+
+    #[tokio::test]
+    async fn a_node_on_another_chain_is_down() {
+        let other = ProbeConfig { chain_id: Some("35834a8a".to_string()), ..config() };
+        let result = probe_node_at(&node(6, 2).await, &other).await;
+        assert!(matches!(
+            result,
+            Err(ProbeError::WrongChain { expected, actual }) if expected == "35834a8a" && actual == CHAIN
+        ));
+
+        // Not checked without `IOTA_CHAIN_ID`
+        let any = ProbeConfig { chain_id: None, ..config() };
+        assert!(probe_node_at(&node(6, 2).await, &any).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn protocol_version_must_be_within_the_bounds() {
+        for version in [5, 7] {
+            assert!(probe_node_at(&node(version, 2).await, &config()).await.is_ok(), "version {}", version);
+        }
+        for version in [4, 8] {
+            let result = probe_node_at(&node(version, 2).await, &config()).await;
+            assert!(
+                matches!(result, Err(ProbeError::UnsupportedProtocol { version: v, min: Some(5), max: Some(7) }) if v == version),
+                "version {}",
+                version
+            );
+        }
+
+        // Each bound is optional
+        let unbounded = ProbeConfig { min_protocol_version: None, max_protocol_version: None, ..config() };
+        assert!(probe_node_at(&node(1, 2).await, &unbounded).await.is_ok());
     }
 }
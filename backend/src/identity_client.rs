@@ -1,6 +1,17 @@
+use axum::http::{HeaderMap, StatusCode};
 use reqwest::Client;
-use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
 use anyhow::Result;
+use tracing::Instrument;
+use wot_id_types::identity::{KeyAgreementResponse, VerifyActionRequest, VerifyActionResponse};
+use wot_id_types::WotDid;
+
+pub use wot_id_types::identity::{KeyAgreementMethod, SignedAction};
+
+// Requests without a body (GET, DELETE) carry their signed action in these headers
+pub const SIGNER_HEADER: &str = "x-wotid-signer";
+pub const ACTION_HEADER: &str = "x-wotid-action";
 
 // Client for communicating with the identity-service
 pub struct IdentityClient {
@@ -8,51 +19,109 @@ pub struct IdentityClient {
     base_url: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct CreateDidRequest {
-    // Empty for now, can be extended with optional parameters later
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct CreateDidResponse {
-    pub did: String,
-    pub document: String,
+// Result of verifying a DID-signed workflow action
+#[derive(Debug)]
+pub enum ActionVerification {
+    // Signature, action name and freshness check out; carries the signed claims
+    Verified(Value),
+    // The identity service refused the action, with its reason
+    Rejected(String),
 }
 
 impl IdentityClient {
-    pub fn new() -> Self {
-        let base_url = std::env::var("IDENTITY_SERVICE_URL")
-            .unwrap_or_else(|_| "http://127.0.0.1:8081".to_string());
-        
-        Self {
-            client: Client::new(),
-            base_url,
-        }
+    // `client` carries the TLS settings for the identity service; see `tls::identity_http_client`
+    pub fn new(client: Client, base_url: String) -> Self {
+        Self { client, base_url }
     }
     
-    // Check if the identity service is healthy
-    pub async fn health_check(&self) -> Result<bool> {
+    // Send a request in a client span, passing the trace on to the identity service
+    async fn send(&self, request: reqwest::RequestBuilder, operation: &'static str) -> reqwest::Result<reqwest::Response> {
+        let span = tracing::info_span!("identity_service", otel.kind = "client", operation);
+        request.headers(wot_telemetry::trace_headers(&span)).send().instrument(span).await
+    }
+
+    // Status of the identity service's readiness probe; not traced, as probes run constantly
+    pub async fn readiness(&self) -> reqwest::Result<reqwest::StatusCode> {
         let resp = self.client
-            .get(format!("{}/health", self.base_url))
+            .get(format!("{}/readyz", self.base_url))
             .send()
             .await?;
-        
-        Ok(resp.status().is_success())
+
+        Ok(resp.status())
     }
     
-    // Create a new DID via the identity service
-    pub async fn create_did(&self, _request: CreateDidRequest) -> Result<CreateDidResponse> {
-        let resp = self.client
-            .post(format!("{}/create-did", self.base_url))
-            .json(&_request)
-            .send()
-            .await?;
+    // Fetch the key-agreement methods of a DID for encrypting data to it
+    pub async fn resolve_key_agreement(&self, did: &WotDid) -> Result<Vec<KeyAgreementMethod>> {
+        let request = self.client
+            .get(format!("{}/api/v1/identity/{}/key-agreement", self.base_url, did));
+        let resp = self.send(request, "resolve_key_agreement").await?;
         
         if resp.status().is_success() {
-            let did_response = resp.json::<CreateDidResponse>().await?;
-            Ok(did_response)
+            Ok(resp.json::<KeyAgreementResponse>().await?.methods)
+        } else {
+            anyhow::bail!("Failed to resolve key agreement methods for {}: {}", did, resp.status())
+        }
+    }
+
+    // Verify a workflow action (`{"action": ..., "iat": ..., "jti": ..., ...}`) signed by `signer`'s DID
+    pub async fn verify_action(&self, signer: &WotDid, jws: &str, action: &str) -> Result<ActionVerification> {
+        let body = VerifyActionRequest { signer: signer.clone(), jws: jws.to_string(), action: action.to_string() };
+        let request = self.client
+            .post(format!("{}/api/v1/identity/verify-action", self.base_url))
+            .json(&body);
+        let resp = self.send(request, "verify_action").await?;
+
+        let status = resp.status();
+        if status.is_success() {
+            Ok(ActionVerification::Verified(resp.json::<VerifyActionResponse>().await?.claims))
+        } else if status.is_client_error() {
+            Ok(ActionVerification::Rejected(resp.text().await.unwrap_or_else(|_| status.to_string())))
         } else {
-            anyhow::bail!("Failed to create DID: {}", resp.status())
+            anyhow::bail!("Failed to verify {} action by {}: {}", action, signer, status)
         }
     }
 }
+
+// Have the identity service check the signature, action name, freshness and unused `jti`, then
+// decode the claims
+pub async fn verify_signed_action<T: DeserializeOwned>(
+    identity: &IdentityClient,
+    request: &SignedAction,
+    action: &str,
+) -> std::result::Result<T, (StatusCode, String)> {
+    let record = |outcome: &'static str| {
+        metrics::counter!("action_verifications_total", "action" => action.to_string(), "outcome" => outcome).increment(1);
+    };
+    let claims = match identity.verify_action(&request.signer, &request.jws, action).await {
+        Ok(ActionVerification::Verified(claims)) => claims,
+        Ok(ActionVerification::Rejected(reason)) => {
+            record("rejected");
+            return Err((StatusCode::UNAUTHORIZED, reason));
+        }
+        Err(e) => {
+            record("unavailable");
+            tracing::error!(target: "backend::identity", "Failed to verify {} action: {}", action, e);
+            return Err((StatusCode::BAD_GATEWAY, format!("Failed to verify the signed {} action", action)));
+        }
+    };
+    let claims = serde_json::from_value(claims).map_err(|e| {
+        record("invalid_claims");
+        (StatusCode::BAD_REQUEST, format!("Invalid {} claims: {}", action, e))
+    })?;
+    record("verified");
+    Ok(claims)
+}
+
+// The signed action of a request without a body, from `SIGNER_HEADER` and `ACTION_HEADER`
+pub fn signed_action_from_headers(headers: &HeaderMap) -> std::result::Result<SignedAction, (StatusCode, String)> {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .ok_or_else(|| (StatusCode::UNAUTHORIZED, format!("Missing {} header", name)))
+    };
+    let signer = header(SIGNER_HEADER)?
+        .parse()
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid {} header: {}", SIGNER_HEADER, e)))?;
+    Ok(SignedAction { signer, jws: header(ACTION_HEADER)?.to_string() })
+}
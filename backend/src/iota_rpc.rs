@@ -0,0 +1,221 @@
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::time::{Duration, Instant};
+use tracing::Instrument;
+
+// Thin JSON-RPC client for the IOTA node, shared by every module that talks to the ledger
+#[derive(Clone)]
+pub struct IotaRpcClient {
+    client: Client,
+    node_url: String,
+}
+
+#[derive(Debug)]
+pub enum RpcError {
+    Transport(reqwest::Error),
+    Status(u16),
+    Node { code: i64, message: String },
+    MalformedResponse(String),
+}
+
+impl std::fmt::Display for RpcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RpcError::Transport(e) => write!(f, "request to IOTA node failed: {}", e),
+            RpcError::Status(status) => write!(f, "IOTA node returned HTTP status {}", status),
+            RpcError::Node { code, message } => write!(f, "IOTA node error {}: {}", code, message),
+            RpcError::MalformedResponse(detail) => write!(f, "malformed JSON-RPC response: {}", detail),
+        }
+    }
+}
+
+impl std::error::Error for RpcError {}
+
+impl RpcError {
+    // Short label for metrics
+    pub fn kind(&self) -> &'static str {
+        match self {
+            RpcError::Transport(_) => "transport",
+            RpcError::Status(_) => "status",
+            RpcError::Node { .. } => "node",
+            RpcError::MalformedResponse(_) => "malformed",
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RpcResponse {
+    result: Option<Value>,
+    error: Option<RpcErrorBody>,
+}
+
+#[derive(Deserialize)]
+struct RpcErrorBody {
+    code: i64,
+    message: String,
+}
+
+// Unsigned transaction returned by `unsafe_moveCall`; the controller's wallet signs and submits it
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionBlockBytes {
+    pub tx_bytes: String,
+    #[serde(default)]
+    pub gas: Value,
+    #[serde(default)]
+    pub input_objects: Value,
+}
+
+impl IotaRpcClient {
+    pub fn new(client: Client, node_url: String) -> Self {
+        Self { client, node_url }
+    }
+
+    pub async fn call(&self, method: &str, params: Value) -> Result<Value, RpcError> {
+        let started = Instant::now();
+        let span = tracing::info_span!("iota_rpc", otel.kind = "client", rpc.method = method);
+        let result = self.send(method, params).instrument(span).await;
+        metrics::histogram!("iota_rpc_duration_seconds", "method" => method.to_string())
+            .record(started.elapsed().as_secs_f64());
+        if let Err(e) = &result {
+            metrics::counter!("iota_rpc_errors_total", "method" => method.to_string(), "kind" => e.kind()).increment(1);
+        }
+        result
+    }
+
+    async fn send(&self, method: &str, params: Value) -> Result<Value, RpcError> {
+        let payload = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": method,
+            "params": params,
+        });
+
+        let response = self
+            .client
+            .post(&self.node_url)
+            .json(&payload)
+            .timeout(Duration::from_secs(10))
+            .send()
+            .await
+            .map_err(RpcError::Transport)?;
+
+        if !response.status().is_success() {
+            return Err(RpcError::Status(response.status().as_u16()));
+        }
+
+        let body: RpcResponse = response.json().await.map_err(RpcError::Transport)?;
+        if let Some(err) = body.error {
+            return Err(RpcError::Node { code: err.code, message: err.message });
+        }
+        body.result
+            .ok_or_else(|| RpcError::MalformedResponse(format!("'{}' returned neither result nor error", method)))
+    }
+
+    // Build (but do not sign) a Move call transaction with `signer` as sender
+    pub async fn move_call(
+        &self,
+        signer: &str,
+        package_id: &str,
+        module: &str,
+        function: &str,
+        arguments: Vec<Value>,
+        gas_budget: u64,
+    ) -> Result<TransactionBlockBytes, RpcError> {
+        let result = self
+            .call(
+                "unsafe_moveCall",
+                json!([
+                    signer,
+                    package_id,
+                    module,
+                    function,
+                    [],
+                    arguments,
+                    null,
+                    gas_budget.to_string(),
+                    null
+                ]),
+            )
+            .await?;
+        serde_json::from_value(result).map_err(|e| RpcError::MalformedResponse(e.to_string()))
+    }
+
+    // Fetch an object with its Move fields
+    pub async fn get_object_fields(&self, object_id: &str) -> Result<Value, RpcError> {
+        let result = self
+            .call("iota_getObject", json!([object_id, { "showContent": true }]))
+            .await?;
+        result
+            .pointer("/data/content/fields")
+            .cloned()
+            .ok_or_else(|| RpcError::MalformedResponse(format!("object {} has no Move content", object_id)))
+    }
+
+    // Objects owned by `owner` (an address or object ID) of the given Move struct type, with content
+    pub async fn get_owned_objects(&self, owner: &str, struct_type: &str) -> Result<Vec<Value>, RpcError> {
+        let mut objects = Vec::new();
+        let mut cursor = Value::Null;
+        loop {
+            let page = self
+                .call(
+                    "iotax_getOwnedObjects",
+                    json!([
+                        owner,
+                        { "filter": { "StructType": struct_type }, "options": { "showContent": true } },
+                        cursor,
+                        50
+                    ]),
+                )
+                .await?;
+            objects.extend(page.get("data").and_then(Value::as_array).into_iter().flatten().cloned());
+            match page.get("nextCursor") {
+                Some(next) if page.get("hasNextPage").and_then(Value::as_bool).unwrap_or(false) => cursor = next.clone(),
+                _ => return Ok(objects),
+            }
+        }
+    }
+
+    // Object IDs stored as dynamic object fields of `parent`
+    pub async fn get_dynamic_object_ids(&self, parent: &str) -> Result<Vec<String>, RpcError> {
+        let mut ids = Vec::new();
+        let mut cursor = Value::Null;
+        loop {
+            let page = self.call("iotax_getDynamicFields", json!([parent, cursor, 50])).await?;
+            ids.extend(
+                page.get("data")
+                    .and_then(Value::as_array)
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|field| field.get("objectId").and_then(Value::as_str))
+                    .map(str::to_string),
+            );
+            match page.get("nextCursor") {
+                Some(next) if page.get("hasNextPage").and_then(Value::as_bool).unwrap_or(false) => cursor = next.clone(),
+                _ => return Ok(ids),
+            }
+        }
+    }
+
+    // Value of the dynamic field of `parent` named by a Move `String`, or `None` if there is none
+    pub async fn get_string_keyed_field(&self, parent: &str, key: &str) -> Result<Option<Value>, RpcError> {
+        let result = self
+            .call("iotax_getDynamicFieldObject", json!([parent, { "type": "0x1::string::String", "value": key }]))
+            .await?;
+        if result.get("error").is_some() {
+            return Ok(None);
+        }
+        Ok(result.pointer("/data/content/fields/value").cloned())
+    }
+
+    pub async fn multi_get_objects(&self, object_ids: &[String]) -> Result<Vec<Value>, RpcError> {
+        if object_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let result = self
+            .call("iota_multiGetObjects", json!([object_ids, { "showContent": true, "showOwner": true }]))
+            .await?;
+        serde_json::from_value(result).map_err(|e| RpcError::MalformedResponse(e.to_string()))
+    }
+}
@@ -0,0 +1,157 @@
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::watch;
+use wot_config::{ConfigError, Settings};
+use wot_telemetry::LogFilter;
+
+use crate::cors::CorsOrigins;
+use crate::trust::decay::DecayPolicy;
+
+// Set once on SIGTERM or SIGINT; long-running tasks stop at their next await point
+#[derive(Clone)]
+pub struct Shutdown(watch::Receiver<bool>);
+
+impl Shutdown {
+    pub fn channel() -> (watch::Sender<bool>, Self) {
+        let (sender, receiver) = watch::channel(false);
+        (sender, Self(receiver))
+    }
+
+    pub async fn requested(&self) {
+        let _ = self.0.clone().wait_for(|&requested| requested).await;
+    }
+}
+
+// Resolves with the name of the first SIGTERM or SIGINT
+pub async fn terminate_signal() -> &'static str {
+    let mut sigterm = signal(SignalKind::terminate()).expect("SIGTERM handler can be installed");
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => "SIGINT",
+        _ = sigterm.recv() => "SIGTERM",
+    }
+}
+
+// Settings applied again on SIGHUP; all others need a restart. As the environment of a running
+// process is fixed, changes take effect through the config file (or values it does not override).
+pub struct Reloadable {
+    log_filter: LogFilter,
+    pub decay_policy: watch::Sender<DecayPolicy>,
+    pub cors_origins: watch::Sender<CorsOrigins>,
+}
+
+impl Reloadable {
+    pub fn new(log_filter: LogFilter, settings: &Settings) -> Result<Self, ConfigError> {
+        let reloadable = Self {
+            log_filter,
+            decay_policy: watch::Sender::new(DecayPolicy::from_settings(settings)?),
+            cors_origins: watch::Sender::new(CorsOrigins::from_settings(settings)?),
+        };
+        reloadable.set_log_filter(settings)?;
+        Ok(reloadable)
+    }
+
+    fn set_log_filter(&self, settings: &Settings) -> Result<(), ConfigError> {
+        let directives = settings.string_or("RUST_LOG", "info");
+        self.log_filter.set(&directives).map_err(|reason| ConfigError::Invalid {
+            key: "RUST_LOG".to_string(),
+            value: directives,
+            reason,
+        })
+    }
+
+    // Everything is validated before anything is applied, so a bad file changes nothing
+    fn apply(&self, settings: &Settings) -> Result<(), ConfigError> {
+        let decay_policy = DecayPolicy::from_settings(settings)?;
+        let cors_origins = CorsOrigins::from_settings(settings)?;
+        self.set_log_filter(settings)?;
+        self.decay_policy.send_if_modified(|current| replace(current, decay_policy));
+        self.cors_origins.send_if_modified(|current| replace(current, cors_origins));
+        Ok(())
+    }
+}
+
+fn replace<T: PartialEq>(current: &mut T, new: T) -> bool {
+    let changed = *current != new;
+    *current = new;
+    changed
+}
+
+// Long-running task: on each SIGHUP, re-read the settings and apply the reloadable ones
+pub async fn reload_on_hangup(reloadable: Reloadable, shutdown: Shutdown) {
+    let mut hangups = signal(SignalKind::hangup()).expect("SIGHUP handler can be installed");
+    loop {
+        tokio::select! {
+            Some(()) = hangups.recv() => {}
+            () = shutdown.requested() => return,
+        }
+        match Settings::load().and_then(|settings| reloadable.apply(&settings)) {
+            Ok(()) => tracing::info!(target: "backend::lifecycle", "Configuration reloaded"),
+            Err(e) => tracing::error!(target: "backend::lifecycle", "Configuration reload failed, keeping current settings: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    fn settings(vars: &[(&str, &str)]) -> Settings {
+        let vars: HashMap<String, String> = vars.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect();
+        Settings::from_parts(Vec::new(), vars).unwrap()
+    }
+
+    const RELOADED: [(&str, &str); 3] = [
+        ("RUST_LOG", "backend=debug,warn"),
+        ("TRUST_DECAY_HALF_LIFE_DAYS", "30"),
+        ("CORS_ALLOWED_ORIGINS", "https://app.wot.id/"),
+    ];
+
+    #[test]
+    fn reload_applies_log_level_decay_policy_and_cors() {
+        let (_layer, log_filter) = LogFilter::new("info");
+        let reloadable = Reloadable::new(log_filter.clone(), &settings(&[])).unwrap();
+        let (decay_policy, cors_origins) = (reloadable.decay_policy.subscribe(), reloadable.cors_origins.subscribe());
+        assert_eq!(reloadable.decay_policy.borrow().half_life, None);
+
+        reloadable.apply(&settings(&RELOADED)).unwrap();
+        assert_eq!(log_filter.current().as_deref(), Some("backend=debug,warn"));
+        assert!(decay_policy.has_changed().unwrap());
+        assert_eq!(decay_policy.borrow().half_life, Some(Duration::from_secs(30 * 86_400)));
+        assert!(cors_origins.has_changed().unwrap());
+        assert_eq!(*cors_origins.borrow(), CorsOrigins::List(vec!["https://app.wot.id".to_string()]));
+    }
+
+    #[test]
+    fn unchanged_settings_do_not_notify() {
+        let (_layer, log_filter) = LogFilter::new("info");
+        let reloadable = Reloadable::new(log_filter, &settings(&RELOADED)).unwrap();
+        let (decay_policy, cors_origins) = (reloadable.decay_policy.subscribe(), reloadable.cors_origins.subscribe());
+        reloadable.apply(&settings(&RELOADED)).unwrap();
+        assert!(!decay_policy.has_changed().unwrap());
+        assert!(!cors_origins.has_changed().unwrap());
+    }
+
+    #[test]
+    fn an_invalid_reload_changes_nothing() {
+        let (_layer, log_filter) = LogFilter::new("info");
+        let reloadable = Reloadable::new(log_filter.clone(), &settings(&[])).unwrap();
+        let (decay_policy, cors_origins) = (reloadable.decay_policy.subscribe(), reloadable.cors_origins.subscribe());
+        let before = log_filter.current();
+
+        for (key, bad) in [
+            ("RUST_LOG", "backend=loud"),
+            ("TRUST_DECAY_HALF_LIFE_DAYS", "a month"),
+            ("CORS_ALLOWED_ORIGINS", "https://app.wot.id/login"),
+        ] {
+            let mut vars = RELOADED.to_vec();
+            vars.retain(|(k, _)| *k != key);
+            vars.push((key, bad));
+            let err = reloadable.apply(&settings(&vars)).unwrap_err();
+            assert!(matches!(&err, ConfigError::Invalid { key: k, .. } if k == key), "{}: {}", key, err);
+        }
+        assert_eq!(log_filter.current(), before);
+        assert!(!decay_policy.has_changed().unwrap());
+        assert!(!cors_origins.has_changed().unwrap());
+    }
+}
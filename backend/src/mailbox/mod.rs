@@ -0,0 +1,395 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use utoipa::{IntoParams, ToSchema};
+use utoipa_axum::{router::OpenApiRouter, routes};
+use wot_id_types::WotDid;
+
+use crate::encryption::{self, Envelope, RecipientKey};
+use crate::evidence::EvidenceContent;
+use crate::iota_rpc::{IotaRpcClient, RpcError, TransactionBlockBytes};
+use crate::state::AppState;
+
+// Mirrors MAX_PAYLOAD_SIZE in mailbox.move
+pub const MAX_PAYLOAD_SIZE: usize = 16384;
+
+// Client for the on-chain `wot_id::mailbox` contract. Messages are sealed to the recipient's
+// key-agreement method here; every transaction is returned unsigned for the caller's wallet.
+pub fn routes() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new()
+        .routes(routes!(create_mailbox, find_mailbox))
+        .routes(routes!(send_message))
+        .routes(routes!(list_messages))
+        .routes(routes!(delete_message))
+        .routes(routes!(claim_message))
+}
+
+fn rpc_error(context: &str, e: RpcError) -> (StatusCode, String) {
+    tracing::error!(target: "backend::mailbox", "{}: {}", context, e);
+    (StatusCode::BAD_GATEWAY, format!("{}: {}", context, e))
+}
+
+async fn build_call(
+    state: &AppState,
+    signer: &str,
+    function: &str,
+    arguments: Vec<Value>,
+) -> Result<TransactionBlockBytes, (StatusCode, String)> {
+    state
+        .rpc
+        .move_call(signer, &state.config.wot_id_pkg_id, "mailbox", function, arguments, state.config.gas_budget)
+        .await
+        .map_err(|e| rpc_error(&format!("Failed to build {} transaction", function), e))
+}
+
+// The shared MailboxDirectory, without which mailboxes cannot be created or found by DID
+fn directory_id(state: &AppState) -> Result<&str, (StatusCode, String)> {
+    state.config.mailbox_directory_id.as_deref().ok_or_else(|| {
+        (StatusCode::SERVICE_UNAVAILABLE, "MAILBOX_DIRECTORY_ID is not configured".to_string())
+    })
+}
+
+// Find a DID's current mailbox in the on-chain directory
+async fn mailbox_for_did(rpc: &IotaRpcClient, directory_id: &str, did: &str) -> Result<Option<String>, RpcError> {
+    let value = rpc.get_string_keyed_field(directory_id, did).await?;
+    Ok(value.as_ref().and_then(Value::as_str).map(str::to_string))
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct MailboxTransactionResponse {
+    pub transaction: TransactionBlockBytes,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateMailboxRequest {
+    pub signer: String,
+    pub identity_id: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/mailboxes",
+    tag = "mailbox",
+    request_body = CreateMailboxRequest,
+    responses(
+        (status = 200, body = MailboxTransactionResponse),
+        (status = 502, description = "The node could not build the transaction", body = String),
+        (status = 503, description = "The mailbox directory is not configured", body = String),
+    )
+)]
+async fn create_mailbox(
+    State(state): State<AppState>,
+    Json(payload): Json<CreateMailboxRequest>,
+) -> Result<Json<MailboxTransactionResponse>, (StatusCode, String)> {
+    let arguments = vec![json!(directory_id(&state)?), json!(payload.identity_id)];
+    let transaction = build_call(&state, &payload.signer, "create_mailbox", arguments).await?;
+    Ok(Json(MailboxTransactionResponse { transaction }))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct FindMailboxQuery {
+    pub did: WotDid,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct FindMailboxResponse {
+    pub did: WotDid,
+    pub mailbox_id: String,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/mailboxes",
+    tag = "mailbox",
+    params(FindMailboxQuery),
+    responses(
+        (status = 200, body = FindMailboxResponse),
+        (status = 400, description = "Malformed DID", body = String),
+        (status = 404, description = "The DID has no mailbox", body = String),
+        (status = 502, body = String),
+        (status = 503, description = "The mailbox directory is not configured", body = String),
+    )
+)]
+async fn find_mailbox(
+    State(state): State<AppState>,
+    Query(query): Query<FindMailboxQuery>,
+) -> Result<Json<FindMailboxResponse>, (StatusCode, String)> {
+    match mailbox_for_did(&state.rpc, directory_id(&state)?, &query.did).await {
+        Ok(Some(mailbox_id)) => Ok(Json(FindMailboxResponse { did: query.did, mailbox_id })),
+        Ok(None) => Err((StatusCode::NOT_FOUND, format!("{} has no mailbox", query.did))),
+        Err(e) => Err(rpc_error("Failed to look up mailbox", e)),
+    }
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct SendMessageRequest {
+    pub signer: String,
+    // The sender's Identity object; the signer must be one of its controllers
+    pub sender_identity_id: String,
+    pub recipient_did: WotDid,
+    // Looked up from the recipient DID when omitted
+    pub mailbox_id: Option<String>,
+    #[serde(flatten)]
+    pub content: EvidenceContent,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SendMessageResponse {
+    pub mailbox_id: String,
+    pub recipient_kid: String,
+    pub transaction: TransactionBlockBytes,
+}
+
+// Seal the message to the recipient's key-agreement method and build `send_message`
+#[utoipa::path(
+    post,
+    path = "/api/v1/mailboxes/messages",
+    tag = "mailbox",
+    request_body = SendMessageRequest,
+    responses(
+        (status = 200, body = SendMessageResponse),
+        (status = 404, description = "The recipient has no mailbox", body = String),
+        (status = 413, description = "The sealed message exceeds the mailbox limit", body = String),
+        (status = 422, description = "The recipient has no X25519 key-agreement method", body = String),
+        (status = 502, body = String),
+        (status = 503, description = "No mailbox_id was given and the mailbox directory is not configured", body = String),
+    )
+)]
+async fn send_message(
+    State(state): State<AppState>,
+    Json(payload): Json<SendMessageRequest>,
+) -> Result<Json<SendMessageResponse>, (StatusCode, String)> {
+    let plaintext = payload.content.into_bytes()?;
+
+    let methods = state.identity.resolve_key_agreement(&payload.recipient_did).await.map_err(|e| {
+        tracing::error!(target: "backend::mailbox", "Failed to resolve key agreement for {}: {}", payload.recipient_did, e);
+        (StatusCode::BAD_GATEWAY, format!("Failed to resolve key agreement methods for {}", payload.recipient_did))
+    })?;
+    let recipient = RecipientKey::from_methods(&methods).ok_or_else(|| {
+        (StatusCode::UNPROCESSABLE_ENTITY, format!("{} has no X25519 key-agreement method", payload.recipient_did))
+    })?;
+    let sealed = serde_json::to_vec(&encryption::seal(&recipient, &plaintext)).expect("envelope serializes to JSON");
+    if sealed.len() > MAX_PAYLOAD_SIZE {
+        return Err((
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("Encrypted message is {} bytes; the mailbox limit is {}", sealed.len(), MAX_PAYLOAD_SIZE),
+        ));
+    }
+
+    let mailbox_id = match payload.mailbox_id {
+        Some(id) => id,
+        None => mailbox_for_did(&state.rpc, directory_id(&state)?, &payload.recipient_did)
+            .await
+            .map_err(|e| rpc_error("Failed to look up mailbox", e))?
+            .ok_or_else(|| (StatusCode::NOT_FOUND, format!("{} has no mailbox", payload.recipient_did)))?,
+    };
+
+    let transaction = build_call(
+        &state,
+        &payload.signer,
+        "send_message",
+        vec![json!(mailbox_id), json!(payload.sender_identity_id), json!(sealed)],
+    )
+    .await?;
+
+    Ok(Json(SendMessageResponse { mailbox_id, recipient_kid: recipient.kid, transaction }))
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct MessageView {
+    pub message_id: String,
+    pub sender_did: String,
+    pub recipient_did: String,
+    pub sent_epoch: Option<u64>,
+    // The sealed envelope; only the recipient's key-agreement key opens it
+    pub envelope: Option<Envelope>,
+}
+
+impl MessageView {
+    fn from_object(object: &Value) -> Option<Self> {
+        let data = object.get("data").unwrap_or(object);
+        let fields = data.pointer("/content/fields")?;
+        let text = |name: &str| fields.get(name).and_then(Value::as_str).unwrap_or_default().to_string();
+        // vector<u8> is rendered as a number array, or as base64 by some node versions
+        let payload = match fields.get("payload") {
+            Some(Value::Array(bytes)) => bytes.iter().map(|b| b.as_u64().map(|b| b as u8)).collect::<Option<Vec<u8>>>(),
+            Some(Value::String(encoded)) => BASE64.decode(encoded).ok(),
+            _ => None,
+        };
+        Some(Self {
+            message_id: data.get("objectId").and_then(Value::as_str)?.to_string(),
+            sender_did: text("sender_did"),
+            recipient_did: text("recipient_did"),
+            sent_epoch: fields.get("sent").and_then(|v| match v {
+                Value::String(s) => s.parse().ok(),
+                other => other.as_u64(),
+            }),
+            envelope: payload.and_then(|bytes| serde_json::from_slice(&bytes).ok()),
+        })
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ListMessagesResponse {
+    pub mailbox_id: String,
+    // Transferred to the mailbox but not yet claimed
+    pub pending: Vec<MessageView>,
+    // Claimed into the mailbox's dynamic object fields
+    pub claimed: Vec<MessageView>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/mailboxes/{mailbox_id}/messages",
+    tag = "mailbox",
+    params(("mailbox_id" = String, Path)),
+    responses((status = 200, body = ListMessagesResponse), (status = 502, body = String))
+)]
+async fn list_messages(
+    State(state): State<AppState>,
+    Path(mailbox_id): Path<String>,
+) -> Result<Json<ListMessagesResponse>, (StatusCode, String)> {
+    let message_type = format!("{}::mailbox::MessageObject", state.config.wot_id_pkg_id);
+    let pending = state
+        .rpc
+        .get_owned_objects(&mailbox_id, &message_type)
+        .await
+        .map_err(|e| rpc_error("Failed to list pending messages", e))?;
+    let claimed_ids = state
+        .rpc
+        .get_dynamic_object_ids(&mailbox_id)
+        .await
+        .map_err(|e| rpc_error("Failed to list claimed messages", e))?;
+    let claimed = state
+        .rpc
+        .multi_get_objects(&claimed_ids)
+        .await
+        .map_err(|e| rpc_error("Failed to fetch claimed messages", e))?;
+
+    Ok(Json(ListMessagesResponse {
+        mailbox_id,
+        pending: pending.iter().filter_map(MessageView::from_object).collect(),
+        claimed: claimed.iter().filter_map(MessageView::from_object).collect(),
+    }))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct MailboxOwnerRequest {
+    pub signer: String,
+    // The caller's MailboxOwnerCap object
+    pub cap_id: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/mailboxes/{mailbox_id}/messages/{message_id}/claim",
+    tag = "mailbox",
+    params(("mailbox_id" = String, Path), ("message_id" = String, Path)),
+    request_body = MailboxOwnerRequest,
+    responses((status = 200, body = MailboxTransactionResponse), (status = 502, body = String))
+)]
+async fn claim_message(
+    State(state): State<AppState>,
+    Path((mailbox_id, message_id)): Path<(String, String)>,
+    Json(payload): Json<MailboxOwnerRequest>,
+) -> Result<Json<MailboxTransactionResponse>, (StatusCode, String)> {
+    let transaction = build_call(
+        &state,
+        &payload.signer,
+        "claim_message",
+        vec![json!(mailbox_id), json!(payload.cap_id), json!(message_id)],
+    )
+    .await?;
+    Ok(Json(MailboxTransactionResponse { transaction }))
+}
+
+// Pending messages are rejected straight from the queue; claimed ones are removed from the mailbox
+#[utoipa::path(
+    delete,
+    path = "/api/v1/mailboxes/{mailbox_id}/messages/{message_id}",
+    tag = "mailbox",
+    params(("mailbox_id" = String, Path), ("message_id" = String, Path)),
+    request_body = MailboxOwnerRequest,
+    responses((status = 200, body = MailboxTransactionResponse), (status = 502, body = String))
+)]
+async fn delete_message(
+    State(state): State<AppState>,
+    Path((mailbox_id, message_id)): Path<(String, String)>,
+    Json(payload): Json<MailboxOwnerRequest>,
+) -> Result<Json<MailboxTransactionResponse>, (StatusCode, String)> {
+    let object = state
+        .rpc
+        .call("iota_getObject", json!([message_id, { "showOwner": true }]))
+        .await
+        .map_err(|e| rpc_error("Failed to fetch message", e))?;
+    let owner = object.pointer("/data/owner/AddressOwner").and_then(Value::as_str);
+    let function = if owner == Some(mailbox_id.as_str()) { "reject_message" } else { "delete_message" };
+
+    let transaction = build_call(
+        &state,
+        &payload.signer,
+        function,
+        vec![json!(mailbox_id), json!(payload.cap_id), json!(message_id)],
+    )
+    .await?;
+    Ok(Json(MailboxTransactionResponse { transaction }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Minimal node answering `iotax_getDynamicFieldObject` for a directory holding one DID
+    async fn fake_node() -> IotaRpcClient {
+        let app = axum::Router::new().route(
+            "/",
+            axum::routing::post(|Json(request): Json<Value>| async move {
+                assert_eq!(request["method"], "iotax_getDynamicFieldObject");
+                assert_eq!(request["params"][0], "0xd1");
+                assert_eq!(request["params"][1]["type"], "0x1::string::String");
+                let result = if request["params"][1]["value"] == "did:iota:0xa11ce" {
+                    json!({ "data": { "objectId": "0xf1", "content": { "fields": { "name": "did:iota:0xa11ce", "value": "0xb0x" } } } })
+                } else {
+                    json!({ "error": { "code": "dynamicFieldNotFound", "parent_object_id": "0xd1" } })
+                };
+                Json(json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        IotaRpcClient::new(reqwest::Client::new(), format!("http://{}/", addr))
+    }
+
+    #[tokio::test]
+    async fn mailbox_is_found_through_the_directory() {
+        let rpc = fake_node().await;
+        assert_eq!(mailbox_for_did(&rpc, "0xd1", "did:iota:0xa11ce").await.unwrap().as_deref(), Some("0xb0x"));
+        assert_eq!(mailbox_for_did(&rpc, "0xd1", "did:iota:0xb0b").await.unwrap(), None);
+    }
+
+    #[test]
+    fn message_view_reads_both_payload_encodings() {
+        let envelope = serde_json::to_vec(&json!({ "unknown": "shape" })).unwrap();
+        for payload in [json!(envelope), json!(BASE64.encode(&envelope))] {
+            let object = json!({ "data": { "objectId": "0xm1", "content": { "fields": {
+                "sender_did": "did:iota:0xb0b",
+                "recipient_did": "did:iota:0xa11ce",
+                "payload": payload,
+                "sent": "7",
+            } } } });
+            let view = MessageView::from_object(&object).unwrap();
+            assert_eq!(view.message_id, "0xm1");
+            assert_eq!(view.sender_did, "did:iota:0xb0b");
+            assert_eq!(view.recipient_did, "did:iota:0xa11ce");
+            assert_eq!(view.sent_epoch, Some(7));
+        }
+        assert!(MessageView::from_object(&json!({ "data": { "objectId": "0xm1" } })).is_none());
+    }
+}
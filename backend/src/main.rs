@@ -1,37 +1,140 @@
 use std::net::SocketAddr;
-use std::env;
+use std::time::Duration;
 use dotenvy::dotenv; // Keep this if you are only using the dotenv function directly
+use wot_config::{ConfigError, Settings, SharedConfig};
 
+mod cors;
+mod didcomm;
+mod disputes;
+mod encryption;
+mod events;
+mod evidence;
+mod fragmentation;
+mod governance;
 mod health;
+mod identity_client;
+mod iota_rpc;
+mod lifecycle;
+mod mailbox;
+mod openapi;
+mod state;
+mod storage;
+mod store;
+mod telemetry;
+#[cfg(test)]
+mod testing;
+mod tls;
+mod trust;
+mod webhooks;
 
 #[tokio::main]
 async fn main() {
+    // Load .env file first so it can configure logging and trace export
+    let dotenv_result = dotenvy::dotenv();
+
     // Initialize tracing subscriber
-    tracing_subscriber::fmt::init();
+    let (tracer_provider, log_filter) = wot_telemetry::init("backend", "info");
 
-    // Load .env file
-    match dotenvy::dotenv() { // Use dotenvy::dotenv() here
+    match dotenv_result {
         Ok(path) => tracing::info!(".env file loaded successfully from {:?}", path),
         Err(e) => tracing::error!("Failed to load .env file: {}", e),
     }
 
-    match env::var("IOTA_NODE_URL") {
-        Ok(val) => tracing::info!("IOTA_NODE_URL in main after dotenv: {}", val),
-        Err(_) => tracing::error!("IOTA_NODE_URL NOT FOUND in main after dotenv"),
+    // Installed first so metrics recorded during startup are kept
+    let metrics = telemetry::install_recorder();
+
+    // Config file, environment and command line, validated before anything starts
+    let settings = Settings::load().unwrap_or_else(config_error);
+    let shared = SharedConfig::from_settings(&settings).unwrap_or_else(config_error);
+    // Before anything is opened or created, such as the store files `build_app_state` loads
+    if settings.print_requested() {
+        print!("{}", settings.render());
+        return;
+    }
+    // Shared clients and configuration for the API modules
+    let app_state = state::build_app_state(&settings, &shared).unwrap_or_else(config_error);
+    let event_source = events::EventSource::from_settings(&settings, &app_state).unwrap_or_else(config_error);
+    let probe_config = health::ProbeConfig::from_settings(&settings).unwrap_or_else(config_error);
+    // Log level, trust decay policy and CORS origins can change on SIGHUP
+    let reloadable = lifecycle::Reloadable::new(log_filter, &settings).unwrap_or_else(config_error);
+    let tls_config = tls::server_config(&settings).unwrap_or_else(config_error);
+    let drain_timeout = Duration::from_secs(settings.parse_or("SHUTDOWN_DRAIN_TIMEOUT_SECS", 30).unwrap_or_else(config_error));
+    for (key, source) in settings.unused() {
+        tracing::warn!("{} from the {} is not used by the backend", key, source);
+    }
+    tracing::info!("Using network {} via {}", shared.network, shared.iota_node_url);
+
+    let addr = SocketAddr::from(([0, 0, 0, 0], shared.backend_port));
+
+    // Background tasks stop on shutdown, checkpointing whatever they need to resume
+    let (shutdown_trigger, shutdown) = lifecycle::Shutdown::channel();
+    let mut tasks = tokio::task::JoinSet::new();
+    // Stream wot_id events from the node (or a fixture) to connected clients
+    tasks.spawn(events::ingest(event_source, app_state.events.clone()));
+    tasks.spawn(health::probe(app_state.clone(), probe_config, shutdown.clone()));
+    tasks.spawn(webhooks::dispatch(app_state.clone(), shutdown.clone()));
+    tasks.spawn(trust::index(app_state.clone(), shutdown.clone()));
+    tasks.spawn(trust::decay::run(
+        app_state.clone(),
+        trust::decay::NodeClock::new(app_state.rpc.clone()),
+        reloadable.decay_policy.subscribe(),
+        shutdown.clone(),
+    ));
+    let cors_origins = reloadable.cors_origins.subscribe();
+    tokio::spawn(lifecycle::reload_on_hangup(reloadable, shutdown.clone()));
+    let events = app_state.events.clone();
+
+    // Set up the application router; the OpenAPI document is built from the same routes
+    let (api_routes, api) = openapi::router(metrics).split_for_parts();
+    let app = api_routes
+        .with_state(app_state)
+        .merge(openapi::docs(api))
+        .layer(cors::layer(cors_origins))
+        .layer(axum::middleware::from_fn(telemetry::track_http));
+
+    let scheme = if tls_config.is_some() { "https" } else { "http" };
+    println!("[wot.id backend] Rust backend running at {}://{}", scheme, addr);
+    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
+    let draining = shutdown.clone();
+    let server = async move {
+        let stopped = async move { draining.requested().await };
+        match tls_config {
+            Some(config) => wot_tls::serve(listener, config, app, axum::extract::ConnectInfo::<SocketAddr>, stopped).await,
+            None => axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+                .with_graceful_shutdown(stopped)
+                .await
+                .unwrap(),
+        }
+    };
+
+    // Stop accepting connections on SIGTERM/SIGINT and give in-flight requests the drain timeout to finish
+    let drain = async {
+        let signal = lifecycle::terminate_signal().await;
+        tracing::info!("{} received, draining for up to {:?}", signal, drain_timeout);
+        shutdown_trigger.send_replace(true);
+        events.close();
+        tokio::time::sleep(drain_timeout).await;
+    };
+    tokio::select! {
+        () = server => {}
+        () = drain => tracing::warn!("Drain timeout reached, dropping remaining connections"),
     }
 
-    // Get backend port from environment or use default
-    let port = env::var("BACKEND_PORT").unwrap_or_else(|_| "8080".to_string());
-    let addr_str = format!("0.0.0.0:{}", port);
-    let addr = addr_str.parse::<SocketAddr>().expect("Invalid BACKEND_PORT format");
+    // Also reached if the server stopped on its own; background tasks get their own drain timeout
+    shutdown_trigger.send_replace(true);
+    events.close();
+    if tokio::time::timeout(drain_timeout, tasks.join_all()).await.is_err() {
+        tracing::warn!("Background tasks did not stop within {:?}", drain_timeout);
+    }
 
-    // Set up the application router with health routes
-    tracing::info!("[main] About to define 'app' using health::routes().");
-    let app = health::routes();
-    tracing::info!("[main] 'app' defined. Router should be using health::routes().");
+    if let Some(provider) = tracer_provider
+        && let Err(e) = provider.shutdown()
+    {
+        eprintln!("Failed to flush traces: {}", e);
+    }
+}
 
-    println!("[wot.id backend] Rust backend running at http://{}", addr);
-    axum::serve(tokio::net::TcpListener::bind(addr).await.unwrap(), app)
-        .await
-        .unwrap();
+fn config_error<T>(e: ConfigError) -> T {
+    tracing::error!("Invalid configuration: {}", e);
+    std::process::exit(2);
 }
@@ -0,0 +1,88 @@
+use metrics_exporter_prometheus::PrometheusHandle;
+use utoipa::OpenApi;
+use utoipa_axum::router::OpenApiRouter;
+use utoipa_swagger_ui::SwaggerUi;
+
+use crate::state::AppState;
+use crate::{didcomm, disputes, events, evidence, fragmentation, governance, health, mailbox, storage, telemetry, trust, webhooks};
+
+// Version of the HTTP API, not of the crate. Any change to the generated document needs a bump
+// here, and `openapi.json` regenerated (see the test below).
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "wot.id backend",
+        version = "6.2.2",
+        description = "Trust graph, evidence, storage, messaging, governance and dispute APIs of the wot.id backend"
+    ),
+    tags(
+        (name = "health", description = "Liveness and dependency health"),
+        (name = "trust", description = "Aggregated trust scores from the on-chain trust graph"),
+        (name = "evidence", description = "Off-chain evidence anchored in trust relationships and claim attestations"),
+        (name = "storage", description = "Content-addressed blob store"),
+        (name = "fragmentation", description = "Erasure-coded blobs spread over several stores"),
+        (name = "mailbox", description = "On-chain mailboxes with sealed messages"),
+        (name = "didcomm", description = "DIDComm v2 mediator"),
+        (name = "events", description = "Live wot_id chain events"),
+        (name = "webhooks", description = "Chain event delivery to registered URLs"),
+        (name = "governance", description = "Proposals and trust-weighted voting"),
+        (name = "disputes", description = "Disputes over trust relationships and claim assessments"),
+        (name = "telemetry", description = "Prometheus metrics"),
+    )
+)]
+pub struct ApiDoc;
+
+// Every route of the backend, each documented by its handler's annotation
+pub fn router(metrics: PrometheusHandle) -> OpenApiRouter<AppState> {
+    let mut doc = ApiDoc::openapi();
+    // utoipa fills in the crate's `license`, which is not declared
+    doc.info.license = None;
+    OpenApiRouter::with_openapi(doc)
+        .merge(health::routes())
+        .merge(didcomm::routes())
+        .merge(disputes::routes())
+        .merge(evidence::routes())
+        .merge(events::routes())
+        .merge(mailbox::routes())
+        .merge(storage::routes())
+        .merge(fragmentation::routes())
+        .merge(governance::routes())
+        .merge(trust::routes())
+        .merge(webhooks::routes())
+        .merge(telemetry::routes(metrics))
+}
+
+// The document at `/openapi.json`, browsable at `/swagger-ui`
+pub fn docs(api: utoipa::openapi::OpenApi) -> SwaggerUi {
+    SwaggerUi::new("/swagger-ui").url("/openapi.json", api)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use metrics_exporter_prometheus::PrometheusBuilder;
+    use serde_json::Value;
+    use std::path::Path;
+
+    // `openapi.json` is the published document. When the generated one differs, its version must
+    // differ too; `UPDATE_OPENAPI=1 cargo test` then rewrites the file.
+    #[test]
+    fn spec_changes_come_with_a_version_bump() {
+        let (_, api) = router(PrometheusBuilder::new().build_recorder().handle()).split_for_parts();
+        let generated = serde_json::to_value(&api).unwrap();
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("openapi.json");
+        let published: Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        if generated == published {
+            return;
+        }
+        assert_ne!(
+            generated["info"]["version"], published["info"]["version"],
+            "The OpenAPI document changed; bump `version` in src/openapi.rs"
+        );
+        assert!(
+            std::env::var_os("UPDATE_OPENAPI").is_some(),
+            "openapi.json is outdated; regenerate it with UPDATE_OPENAPI=1 cargo test"
+        );
+        std::fs::write(&path, api.to_pretty_json().unwrap() + "\n").unwrap();
+    }
+}
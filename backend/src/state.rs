@@ -0,0 +1,222 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::didcomm::{LocalSecret, Mediator};
+use crate::disputes::{DisputeRules, DisputeStore};
+use crate::events::EventHub;
+use crate::fragmentation::FragmentStore;
+use crate::governance::{Anchoring, GovernanceRules, GovernanceStore, Weighting};
+use crate::health::{self, HealthMonitor};
+use crate::identity_client::IdentityClient;
+use crate::iota_rpc::IotaRpcClient;
+use crate::storage::{ipfs::IpfsStore, local::LocalStore, BlobOwners, BlobStore, StorageBackend};
+use crate::tls;
+use crate::trust::{decay::RenewalLog, TrustIndex};
+use crate::webhooks::{RetryPolicy, TargetPolicy, WebhookStore};
+use wot_config::{ConfigError, Settings, SharedConfig};
+
+#[derive(Clone)]
+pub struct AppState {
+    pub rpc: IotaRpcClient,
+    pub blobs: BlobStore,
+    pub blob_owners: Arc<BlobOwners>,
+    pub identity: Arc<IdentityClient>,
+    pub fragment_stores: Arc<Vec<FragmentStore>>,
+    pub events: Arc<EventHub>,
+    pub webhooks: Arc<WebhookStore>,
+    pub trust: Arc<TrustIndex>,
+    pub renewals: Arc<RenewalLog>,
+    pub governance: Arc<GovernanceStore>,
+    pub disputes: Arc<DisputeStore>,
+    pub health: Arc<HealthMonitor>,
+    // DIDComm mediator; `None` unless its DID and key are configured
+    pub mediator: Option<Arc<Mediator>>,
+    pub config: Arc<BackendConfig>,
+}
+
+#[derive(Debug)]
+pub struct BackendConfig {
+    pub wot_id_pkg_id: String,
+    pub gas_budget: u64,
+    // Shared `mailbox::MailboxDirectory` object; mailbox lookups by DID need it
+    pub mailbox_directory_id: Option<String>,
+}
+
+pub fn build_app_state(settings: &Settings, shared: &SharedConfig) -> Result<AppState, ConfigError> {
+    let wot_id_pkg_id = settings.object_id("WOT_ID_PKG_ID")?.unwrap_or_else(|| {
+        tracing::warn!("WOT_ID_PKG_ID not set; transactions against the wot_id package will fail");
+        String::new()
+    });
+    let gas_budget = settings.parse_or("GAS_BUDGET", 10_000_000)?;
+    let mailbox_directory_id = settings.object_id("MAILBOX_DIRECTORY_ID")?;
+    let max_blob_size = settings.parse_or("BLOB_MAX_SIZE", 10 * 1024 * 1024)?;
+    let event_buffer_size = settings.parse_or("EVENT_BUFFER_SIZE", 1024)?;
+
+    let http = reqwest::Client::new();
+
+    let storage_backend = match settings.string_or("BLOB_STORE_BACKEND", "local").as_str() {
+        "ipfs" => format!("ipfs:{}", settings.url("IPFS_API_URL")?.map_or("http://127.0.0.1:5001".to_string(), String::from)),
+        "local" => format!("local:{}", settings.string_or("BLOB_STORE_DIR", "./data/blobs")),
+        other => {
+            return Err(ConfigError::Invalid {
+                key: "BLOB_STORE_BACKEND".to_string(),
+                value: other.to_string(),
+                reason: "expected local or ipfs".to_string(),
+            });
+        }
+    };
+    tracing::info!("Using blob storage {}", storage_backend);
+    let storage_backend = storage_backend_from_spec(&storage_backend, &http).ok_or_else(|| ConfigError::Invalid {
+        key: "BLOB_STORE_BACKEND".to_string(),
+        value: storage_backend.clone(),
+        reason: "expected local:<dir> or ipfs:<api url>".to_string(),
+    })?;
+
+    // Comma-separated `local:<dir>` / `ipfs:<api url>` locations, one share each
+    let fragment_stores = settings
+        .list("FRAGMENT_STORES")
+        .into_iter()
+        .map(|spec| match storage_backend_from_spec(&spec, &http) {
+            Some(backend) => Ok(FragmentStore {
+                store: BlobStore::new(backend, max_blob_size),
+                location: spec,
+            }),
+            None => Err(ConfigError::Invalid {
+                key: "FRAGMENT_STORES".to_string(),
+                value: spec,
+                reason: "expected local:<dir> or ipfs:<api url>".to_string(),
+            }),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if fragment_stores.len() < 2 {
+        tracing::warn!("Fewer than two FRAGMENT_STORES configured; data fragmentation is unavailable");
+    }
+
+    let blob_owners_path = settings.string_or("BLOB_OWNERS_PATH", "./data/blob-owners.json");
+    let blob_owners = BlobOwners::load(&blob_owners_path).map_err(store_error(&blob_owners_path))?;
+
+    let webhook_retry = RetryPolicy {
+        max_attempts: settings.parse_or("WEBHOOK_MAX_ATTEMPTS", 6)?,
+        initial_backoff: Duration::from_millis(settings.parse_or("WEBHOOK_INITIAL_BACKOFF_MS", 1000)?),
+        max_backoff: Duration::from_secs(300),
+    };
+    let webhook_store_path = settings.string_or("WEBHOOK_STORE_PATH", "./data/webhooks.json");
+    let webhook_targets = TargetPolicy { allow_private: settings.parse_or("WEBHOOK_ALLOW_PRIVATE_TARGETS", false)? };
+    let webhooks = WebhookStore::load(&webhook_store_path, webhook_retry, webhook_targets)
+        .map_err(store_error(&webhook_store_path))?;
+
+    let defaults = GovernanceRules::default();
+    let governance_rules = GovernanceRules {
+        default_weighting: match settings.var("GOVERNANCE_WEIGHTING") {
+            Some(value) => Weighting::parse(&value).ok_or_else(|| ConfigError::Invalid {
+                key: "GOVERNANCE_WEIGHTING".to_string(),
+                value,
+                reason: "expected one_did_one_vote or trust_weighted".to_string(),
+            })?,
+            None => defaults.default_weighting,
+        },
+        min_voting_period: settings
+            .parse("GOVERNANCE_MIN_VOTING_PERIOD_SECS")?
+            .map_or(defaults.min_voting_period, Duration::from_secs),
+        max_voting_period: settings
+            .parse("GOVERNANCE_MAX_VOTING_PERIOD_SECS")?
+            .map_or(defaults.max_voting_period, Duration::from_secs),
+        min_quorum_votes: settings.parse_or("GOVERNANCE_MIN_QUORUM_VOTES", defaults.min_quorum_votes)?,
+        min_quorum_trust: settings.parse_or("GOVERNANCE_MIN_QUORUM_TRUST", defaults.min_quorum_trust)?,
+    };
+    // The address holding `governance::AnchorCap` and the cap's object ID, together or neither
+    let anchoring = match (settings.object_id("GOVERNANCE_ANCHOR_ADDRESS")?, settings.object_id("GOVERNANCE_ANCHOR_CAP_ID")?) {
+        (Some(address), Some(cap_id)) => Some(Anchoring { address, cap_id }),
+        (None, None) => {
+            tracing::warn!("GOVERNANCE_ANCHOR_ADDRESS/GOVERNANCE_ANCHOR_CAP_ID not set; governance results cannot be anchored");
+            None
+        }
+        (Some(_), None) => return Err(ConfigError::Missing("GOVERNANCE_ANCHOR_CAP_ID".to_string())),
+        (None, Some(_)) => return Err(ConfigError::Missing("GOVERNANCE_ANCHOR_ADDRESS".to_string())),
+    };
+    let governance_store_path = settings.string_or("GOVERNANCE_STORE_PATH", "./data/governance.json");
+    let governance =
+        GovernanceStore::load(&governance_store_path, governance_rules, anchoring).map_err(store_error(&governance_store_path))?;
+
+    let defaults = DisputeRules::default();
+    let dispute_rules = DisputeRules {
+        arbiter_count: settings.parse_or("DISPUTE_ARBITERS", defaults.arbiter_count)?,
+        min_arbiter_trust: settings.parse_or("DISPUTE_MIN_ARBITER_TRUST", defaults.min_arbiter_trust)?,
+        voting_period: settings
+            .parse("DISPUTE_VOTING_PERIOD_SECS")?
+            .map_or(defaults.voting_period, Duration::from_secs),
+        down_weight_pct: match settings.parse_or("DISPUTE_DOWN_WEIGHT_PCT", defaults.down_weight_pct)? {
+            pct if pct <= 100 => pct,
+            pct => {
+                return Err(ConfigError::Invalid {
+                    key: "DISPUTE_DOWN_WEIGHT_PCT".to_string(),
+                    value: pct.to_string(),
+                    reason: "must be at most 100".to_string(),
+                });
+            }
+        },
+    };
+    let dispute_store_path = settings.string_or("DISPUTE_STORE_PATH", "./data/disputes.json");
+    let disputes = DisputeStore::load(&dispute_store_path, dispute_rules).map_err(store_error(&dispute_store_path))?;
+
+    let renewals_path = settings.string_or("TRUST_RENEWALS_PATH", "./data/trust-renewals.json");
+    let renewals = RenewalLog::load(&renewals_path).map_err(store_error(&renewals_path))?;
+    // Cached probe results expire, so the monitor needs the probe timing
+    let probe_config = health::ProbeConfig::from_settings(settings)?;
+
+    let didcomm_endpoints = TargetPolicy { allow_private: settings.parse_or("DIDCOMM_ALLOW_PRIVATE_ENDPOINTS", false)? };
+    let didcomm_store_path = settings.string_or("DIDCOMM_STORE_PATH", "./data/didcomm.json");
+    // Key-agreement method ID (`did:iota:...#fragment`) and base64url X25519 secret of the mediator
+    let mediator = match (settings.var("DIDCOMM_MEDIATOR_KID"), settings.var("DIDCOMM_MEDIATOR_SECRET")) {
+        (Some(kid), Some(secret)) => match LocalSecret::from_base64url(kid, &secret) {
+            Some(secret) => Some(Arc::new(
+                Mediator::load(secret, didcomm_endpoints, &didcomm_store_path).map_err(store_error(&didcomm_store_path))?,
+            )),
+            None => {
+                tracing::error!("DIDCOMM_MEDIATOR_SECRET is not a base64url X25519 key; DIDComm is disabled");
+                None
+            }
+        },
+        _ => {
+            tracing::warn!("DIDCOMM_MEDIATOR_KID/DIDCOMM_MEDIATOR_SECRET not set; DIDComm routing is disabled");
+            None
+        }
+    };
+
+    Ok(AppState {
+        rpc: IotaRpcClient::new(http, shared.iota_node_url.to_string()),
+        blobs: BlobStore::new(storage_backend, max_blob_size),
+        blob_owners: Arc::new(blob_owners),
+        identity: Arc::new(IdentityClient::new(
+            tls::identity_http_client(settings)?,
+            shared.identity_service_url.as_str().trim_end_matches('/').to_string(),
+        )),
+        fragment_stores: Arc::new(fragment_stores),
+        events: Arc::new(EventHub::new(event_buffer_size)),
+        webhooks: Arc::new(webhooks),
+        trust: Arc::new(TrustIndex::default()),
+        renewals: Arc::new(renewals),
+        governance: Arc::new(governance),
+        disputes: Arc::new(disputes),
+        health: Arc::new(HealthMonitor::new(&[health::IOTA_NODE, health::IDENTITY_SERVICE], probe_config.cache_ttl())),
+        mediator,
+        config: Arc::new(BackendConfig {
+            wot_id_pkg_id,
+            gas_budget,
+            mailbox_directory_id,
+        }),
+    })
+}
+
+// A store file that exists but cannot be read or parsed stops startup like a bad setting
+fn store_error(path: &str) -> impl FnOnce(anyhow::Error) -> ConfigError {
+    move |e| ConfigError::File { path: path.to_string(), reason: e.to_string() }
+}
+
+fn storage_backend_from_spec(spec: &str, http: &reqwest::Client) -> Option<StorageBackend> {
+    match spec.split_once(':')? {
+        ("local", dir) => Some(StorageBackend::Local(LocalStore::new(dir))),
+        ("ipfs", api_url) => Some(StorageBackend::Ipfs(IpfsStore::new(http.clone(), api_url.to_string()))),
+        _ => None,
+    }
+}
@@ -0,0 +1,140 @@
+use reqwest::{multipart, Client};
+use serde::Deserialize;
+
+use super::{StorageError, StoredBlob};
+
+// Talks to an IPFS node through the Kubo-compatible HTTP RPC API (`/api/v0/...`).
+// Blobs are stored as raw blocks, so their CIDv1 wraps exactly the SHA-256 we use as key.
+#[derive(Clone)]
+pub struct IpfsStore {
+    client: Client,
+    api_url: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct BlockPutResponse {
+    key: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct IpfsErrorBody {
+    message: String,
+}
+
+impl IpfsStore {
+    pub fn new(client: Client, api_url: String) -> Self {
+        Self { client, api_url: api_url.trim_end_matches('/').to_string() }
+    }
+
+    pub async fn put(&self, hash: &str, bytes: Vec<u8>) -> Result<StoredBlob, StorageError> {
+        let size = bytes.len();
+        let form = multipart::Form::new().part("file", multipart::Part::bytes(bytes));
+        let response = self
+            .client
+            .post(format!(
+                "{}/api/v0/block/put?cid-codec=raw&mhtype=sha2-256&allow-big-block=true",
+                self.api_url
+            ))
+            .multipart(form)
+            .send()
+            .await
+            .map_err(|e| StorageError::Backend(e.to_string()))?;
+        let response = check(response).await?;
+        let put: BlockPutResponse = response
+            .json()
+            .await
+            .map_err(|e| StorageError::Backend(e.to_string()))?;
+
+        if put.key != cid_for_hash(hash) {
+            return Err(StorageError::IntegrityMismatch { expected: cid_for_hash(hash), actual: put.key });
+        }
+        Ok(StoredBlob { hash: hash.to_string(), size })
+    }
+
+    pub async fn get(&self, hash: &str) -> Result<Option<Vec<u8>>, StorageError> {
+        match self.rpc("block/get", hash).await {
+            Ok(response) => {
+                let bytes = response.bytes().await.map_err(|e| StorageError::Backend(e.to_string()))?;
+                Ok(Some(bytes.to_vec()))
+            }
+            Err(StorageError::NotFound) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub async fn pin(&self, hash: &str) -> Result<(), StorageError> {
+        self.rpc("pin/add", hash).await.map(|_| ())
+    }
+
+    pub async fn unpin(&self, hash: &str) -> Result<(), StorageError> {
+        match self.rpc("pin/rm", hash).await {
+            Ok(_) => Ok(()),
+            // Unpinning something that is not pinned is not an error for callers
+            Err(StorageError::Backend(message)) if message.contains("not pinned") => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub async fn delete(&self, hash: &str) -> Result<(), StorageError> {
+        self.rpc("block/rm", hash).await.map(|_| ())
+    }
+
+    async fn rpc(&self, command: &str, hash: &str) -> Result<reqwest::Response, StorageError> {
+        let response = self
+            .client
+            .post(format!("{}/api/v0/{}?arg={}", self.api_url, command, cid_for_hash(hash)))
+            .send()
+            .await
+            .map_err(|e| StorageError::Backend(e.to_string()))?;
+        check(response).await
+    }
+}
+
+// Map Kubo's error bodies onto storage errors
+async fn check(response: reqwest::Response) -> Result<reqwest::Response, StorageError> {
+    if response.status().is_success() {
+        return Ok(response);
+    }
+    let status = response.status();
+    let message = match response.json::<IpfsErrorBody>().await {
+        Ok(body) => body.message,
+        Err(_) => format!("IPFS API returned status {}", status),
+    };
+    if message.contains("not found") {
+        Err(StorageError::NotFound)
+    } else if message.contains("pinned") && !message.contains("not pinned") {
+        Err(StorageError::Pinned)
+    } else {
+        Err(StorageError::Backend(message))
+    }
+}
+
+// CIDv1, raw codec, sha2-256 multihash, multibase base32 ("b" prefix)
+pub fn cid_for_hash(hash: &str) -> String {
+    let digest = hex::decode(hash).unwrap_or_default();
+    let mut cid = vec![0x01, 0x55, 0x12, 0x20];
+    cid.extend_from_slice(&digest);
+    format!("b{}", base32_lower(&cid))
+}
+
+// RFC 4648 base32, lowercase, no padding
+fn base32_lower(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+    let mut out = String::with_capacity((bytes.len() * 8).div_ceil(5));
+    let mut buffer = 0u16;
+    let mut bits = 0;
+    for &byte in bytes {
+        buffer = (buffer << 8) | byte as u16;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+    out
+}
@@ -0,0 +1,156 @@
+use bytes::Bytes;
+use futures_util::{Stream, StreamExt};
+use sha2::{Digest, Sha256};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::io::AsyncWriteExt;
+
+use super::{StorageError, StoredBlob};
+
+static UPLOAD_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+// Blobs live at `<root>/<hash>`; a `<root>/<hash>.pin` marker protects a blob from deletion
+#[derive(Clone)]
+pub struct LocalStore {
+    root: PathBuf,
+}
+
+impl LocalStore {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    // Stream the upload to a temp file while hashing it, then move it under its hash
+    pub async fn put_stream<S, E>(&self, mut stream: S, max_size: usize) -> Result<StoredBlob, StorageError>
+    where
+        S: Stream<Item = Result<Bytes, E>> + Unpin,
+        E: std::fmt::Display,
+    {
+        tokio::fs::create_dir_all(&self.root).await?;
+        let tmp = self.root.join(format!(
+            "upload-{}-{}.tmp",
+            std::process::id(),
+            UPLOAD_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let stored = self.write_and_move(&tmp, &mut stream, max_size).await;
+        if stored.is_err() {
+            // Gone already once renamed; otherwise the partial upload must not linger
+            let _ = tokio::fs::remove_file(&tmp).await;
+        }
+        stored
+    }
+
+    async fn write_and_move<S, E>(&self, tmp: &Path, stream: &mut S, max_size: usize) -> Result<StoredBlob, StorageError>
+    where
+        S: Stream<Item = Result<Bytes, E>> + Unpin,
+        E: std::fmt::Display,
+    {
+        let mut file = tokio::fs::File::create(tmp).await?;
+        let mut hasher = Sha256::new();
+        let mut size = 0usize;
+
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|e| StorageError::Upload(e.to_string()))?;
+            size += chunk.len();
+            if size > max_size {
+                return Err(StorageError::TooLarge { limit: max_size });
+            }
+            hasher.update(&chunk);
+            file.write_all(&chunk).await?;
+        }
+        file.sync_all().await?;
+        drop(file);
+
+        let hash = hex::encode(hasher.finalize());
+        let path = self.path_for(&hash);
+        if tokio::fs::try_exists(&path).await? {
+            tokio::fs::remove_file(tmp).await?;
+        } else {
+            tokio::fs::rename(tmp, &path).await?;
+        }
+        Ok(StoredBlob { hash, size })
+    }
+
+    pub async fn get(&self, hash: &str) -> Result<Option<Vec<u8>>, StorageError> {
+        match tokio::fs::read(self.path_for(hash)).await {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub async fn pin(&self, hash: &str) -> Result<(), StorageError> {
+        if !tokio::fs::try_exists(self.path_for(hash)).await? {
+            return Err(StorageError::NotFound);
+        }
+        tokio::fs::write(self.pin_path_for(hash), b"").await?;
+        Ok(())
+    }
+
+    pub async fn unpin(&self, hash: &str) -> Result<(), StorageError> {
+        match tokio::fs::remove_file(self.pin_path_for(hash)).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub async fn delete(&self, hash: &str) -> Result<(), StorageError> {
+        if tokio::fs::try_exists(self.pin_path_for(hash)).await? {
+            return Err(StorageError::Pinned);
+        }
+        match tokio::fs::remove_file(self.path_for(hash)).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Err(StorageError::NotFound),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn path_for(&self, hash: &str) -> PathBuf {
+        self.root.join(hash)
+    }
+
+    fn pin_path_for(&self, hash: &str) -> PathBuf {
+        self.root.join(format!("{}.pin", hash))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestDir(PathBuf);
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn leftovers(dir: &Path) -> Vec<String> {
+        std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn failed_uploads_leave_no_temp_file() {
+        let dir = TestDir(std::env::temp_dir().join(format!("wot-local-store-{}", std::process::id())));
+        let store = LocalStore::new(&dir.0);
+
+        let chunks = vec![Ok::<_, String>(Bytes::from_static(b"0123456789")), Ok(Bytes::from_static(b"abc"))];
+        let too_large = store.put_stream(futures_util::stream::iter(chunks), 12).await;
+        assert!(matches!(too_large, Err(StorageError::TooLarge { limit: 12 })));
+
+        let chunks = vec![Ok(Bytes::from_static(b"partial")), Err("connection reset".to_string())];
+        let broken = store.put_stream(futures_util::stream::iter(chunks), 1024).await;
+        assert!(matches!(broken, Err(StorageError::Upload(_))));
+        assert!(leftovers(&dir.0).is_empty());
+
+        let chunks = vec![Ok::<_, String>(Bytes::from_static(b"complete"))];
+        let stored = store.put_stream(futures_util::stream::iter(chunks), 1024).await.unwrap();
+        assert_eq!(leftovers(&dir.0), vec![stored.hash]);
+    }
+}
@@ -0,0 +1,585 @@
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use bytes::Bytes;
+use futures_util::{stream, Stream, StreamExt};
+use serde::{de::IgnoredAny, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::io;
+use std::path::PathBuf;
+use utoipa::{IntoParams, ToSchema};
+use utoipa_axum::{router::OpenApiRouter, routes};
+use wot_id_types::WotDid;
+
+use crate::encryption::{self, RecipientKey};
+use crate::identity_client::{signed_action_from_headers, verify_signed_action};
+use crate::state::AppState;
+use crate::store::JsonStore;
+
+pub mod ipfs;
+pub mod local;
+
+const ACTION_UPLOAD: &str = "blob.upload";
+const ACTION_PIN: &str = "blob.pin";
+const ACTION_UNPIN: &str = "blob.unpin";
+const ACTION_DELETE: &str = "blob.delete";
+
+// Content-addressed off-chain blob store. Blobs are keyed by the hex SHA-256 of their
+// bytes, which is also the value anchored on-chain as `content_hash`.
+#[derive(Clone)]
+pub struct BlobStore {
+    backend: StorageBackend,
+    max_size: usize,
+}
+
+#[derive(Clone)]
+pub enum StorageBackend {
+    Local(local::LocalStore),
+    Ipfs(ipfs::IpfsStore),
+}
+
+#[derive(Debug, Serialize)]
+pub struct StoredBlob {
+    pub hash: String,
+    pub size: usize,
+}
+
+#[derive(Debug)]
+pub enum StorageError {
+    NotFound,
+    Pinned,
+    TooLarge { limit: usize },
+    IntegrityMismatch { expected: String, actual: String },
+    Upload(String),
+    Backend(String),
+    Io(io::Error),
+}
+
+impl std::fmt::Display for StorageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StorageError::NotFound => write!(f, "blob not found"),
+            StorageError::Pinned => write!(f, "blob is pinned"),
+            StorageError::TooLarge { limit } => write!(f, "blob exceeds the size limit of {} bytes", limit),
+            StorageError::IntegrityMismatch { expected, actual } => {
+                write!(f, "stored content does not match its address: expected {}, got {}", expected, actual)
+            }
+            StorageError::Upload(e) => write!(f, "upload failed: {}", e),
+            StorageError::Backend(e) => write!(f, "storage backend error: {}", e),
+            StorageError::Io(e) => write!(f, "storage I/O error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {}
+
+impl From<io::Error> for StorageError {
+    fn from(e: io::Error) -> Self {
+        StorageError::Io(e)
+    }
+}
+
+impl StorageError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            StorageError::NotFound => StatusCode::NOT_FOUND,
+            StorageError::Pinned => StatusCode::CONFLICT,
+            StorageError::TooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            StorageError::Upload(_) => StatusCode::BAD_REQUEST,
+            StorageError::Backend(_) => StatusCode::BAD_GATEWAY,
+            StorageError::IntegrityMismatch { .. } | StorageError::Io(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+pub fn content_hash(bytes: &[u8]) -> [u8; 32] {
+    Sha256::digest(bytes).into()
+}
+
+pub fn is_valid_hash(hash: &str) -> bool {
+    hash.len() == 64 && hash.bytes().all(|b| b.is_ascii_hexdigit() && !b.is_ascii_uppercase())
+}
+
+impl BlobStore {
+    pub fn new(backend: StorageBackend, max_size: usize) -> Self {
+        Self { backend, max_size }
+    }
+
+    pub fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    // Store a blob and return its hex content hash. Storing the same bytes twice is a no-op.
+    pub async fn put(&self, bytes: &[u8]) -> Result<String, StorageError> {
+        let chunk: Result<Bytes, io::Error> = Ok(Bytes::copy_from_slice(bytes));
+        Ok(self.put_stream(stream::iter([chunk])).await?.hash)
+    }
+
+    pub async fn put_stream<S, E>(&self, stream: S) -> Result<StoredBlob, StorageError>
+    where
+        S: Stream<Item = Result<Bytes, E>> + Unpin,
+        E: std::fmt::Display,
+    {
+        match &self.backend {
+            StorageBackend::Local(store) => store.put_stream(stream, self.max_size).await,
+            StorageBackend::Ipfs(store) => {
+                // Raw IPFS blocks are written in one request, so the upload is bounded in memory
+                let bytes = collect_limited(stream, self.max_size).await?;
+                let hash = hex::encode(content_hash(&bytes));
+                store.put(&hash, bytes).await
+            }
+        }
+    }
+
+    // Fetch a blob and verify that it still hashes to its address
+    pub async fn get(&self, hash: &str) -> Result<Option<Vec<u8>>, StorageError> {
+        if !is_valid_hash(hash) {
+            return Ok(None);
+        }
+        let bytes = match &self.backend {
+            StorageBackend::Local(store) => store.get(hash).await?,
+            StorageBackend::Ipfs(store) => store.get(hash).await?,
+        };
+        match bytes {
+            Some(bytes) => {
+                let actual = hex::encode(content_hash(&bytes));
+                if actual != hash {
+                    tracing::error!(target: "backend::storage", "Blob {} failed hash verification (got {})", hash, actual);
+                    return Err(StorageError::IntegrityMismatch { expected: hash.to_string(), actual });
+                }
+                Ok(Some(bytes))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub async fn pin(&self, hash: &str) -> Result<(), StorageError> {
+        if !is_valid_hash(hash) {
+            return Err(StorageError::NotFound);
+        }
+        match &self.backend {
+            StorageBackend::Local(store) => store.pin(hash).await,
+            StorageBackend::Ipfs(store) => store.pin(hash).await,
+        }
+    }
+
+    pub async fn unpin(&self, hash: &str) -> Result<(), StorageError> {
+        if !is_valid_hash(hash) {
+            return Err(StorageError::NotFound);
+        }
+        match &self.backend {
+            StorageBackend::Local(store) => store.unpin(hash).await,
+            StorageBackend::Ipfs(store) => store.unpin(hash).await,
+        }
+    }
+
+    // Pinned blobs must be unpinned before they can be deleted
+    pub async fn delete(&self, hash: &str) -> Result<(), StorageError> {
+        if !is_valid_hash(hash) {
+            return Err(StorageError::NotFound);
+        }
+        match &self.backend {
+            StorageBackend::Local(store) => store.delete(hash).await,
+            StorageBackend::Ipfs(store) => store.delete(hash).await,
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct OwnerData {
+    // Blob hash to the DIDs that uploaded it
+    owners: BTreeMap<String, Vec<String>>,
+}
+
+// Who uploaded each blob, persisted as one JSON file. The same bytes can be uploaded by several
+// DIDs; each of them may pin, unpin or delete the blob, and deleting only drops that DID's
+// claim until the last one goes. Blobs stored by the backend itself (evidence) have no owner.
+pub struct BlobOwners {
+    data: JsonStore<OwnerData>,
+}
+
+impl BlobOwners {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self, anyhow::Error> {
+        Ok(Self { data: JsonStore::load(path)? })
+    }
+
+    async fn add(&self, hash: &str, did: &str) {
+        let mut data = self.data.lock().await;
+        let owners = data.owners.entry(hash.to_string()).or_default();
+        if !owners.iter().any(|owner| owner == did) {
+            owners.push(did.to_string());
+            self.data.persist(&data).await;
+        }
+    }
+
+    async fn ensure_owner(&self, hash: &str, did: &str) -> Result<(), (StatusCode, String)> {
+        let data = self.data.lock().await;
+        if data.owners.get(hash).is_some_and(|owners| owners.iter().any(|owner| owner == did)) {
+            return Ok(());
+        }
+        Err((StatusCode::FORBIDDEN, format!("{} did not upload blob {}", did, hash)))
+    }
+
+    // Drop `did`'s claim on `hash`, deleting the bytes from `blobs` when it was the last one
+    async fn release(&self, blobs: &BlobStore, hash: &str, did: &str) -> Result<(), (StatusCode, String)> {
+        let mut data = self.data.lock().await;
+        let owners = data
+            .owners
+            .get_mut(hash)
+            .filter(|owners| owners.iter().any(|owner| owner == did))
+            .ok_or_else(|| (StatusCode::FORBIDDEN, format!("{} did not upload blob {}", did, hash)))?;
+        if owners.len() == 1 {
+            blobs.delete(hash).await.map_err(storage_error)?;
+            data.owners.remove(hash);
+        } else {
+            owners.retain(|owner| owner != did);
+        }
+        self.data.persist(&data).await;
+        Ok(())
+    }
+}
+
+pub async fn collect_limited<S, E>(mut stream: S, max_size: usize) -> Result<Vec<u8>, StorageError>
+where
+    S: Stream<Item = Result<Bytes, E>> + Unpin,
+    E: std::fmt::Display,
+{
+    let mut bytes = Vec::new();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| StorageError::Upload(e.to_string()))?;
+        if bytes.len() + chunk.len() > max_size {
+            return Err(StorageError::TooLarge { limit: max_size });
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(bytes)
+}
+
+pub fn routes() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new()
+        .routes(routes!(upload_blob))
+        .routes(routes!(download_blob, delete_blob))
+        .routes(routes!(pin_blob, unpin_blob))
+}
+
+fn storage_error(e: StorageError) -> (StatusCode, String) {
+    if matches!(e.status_code(), StatusCode::INTERNAL_SERVER_ERROR | StatusCode::BAD_GATEWAY) {
+        tracing::error!(target: "backend::storage", "Storage operation failed: {}", e);
+    }
+    (e.status_code(), e.to_string())
+}
+
+// Names the blob a pin, unpin or delete action is for, so it cannot be replayed against another
+#[derive(Deserialize)]
+struct BlobClaims {
+    hash: String,
+}
+
+// Verify the signed action in the request headers and return its signer. Without `hash` the
+// action may carry any claims.
+async fn blob_action(
+    state: &AppState,
+    headers: &HeaderMap,
+    action: &str,
+    hash: Option<&str>,
+) -> Result<String, (StatusCode, String)> {
+    let signed = signed_action_from_headers(headers)?;
+    match hash {
+        Some(hash) => {
+            let claims: BlobClaims = verify_signed_action(&state.identity, &signed, action).await?;
+            if claims.hash != hash {
+                return Err((StatusCode::FORBIDDEN, "The signed action is for another blob".to_string()));
+            }
+        }
+        None => {
+            let IgnoredAny = verify_signed_action(&state.identity, &signed, action).await?;
+        }
+    }
+    Ok(signed.signer.to_string())
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct UploadQuery {
+    // Encrypt the blob to this DID's key-agreement method before storing it
+    pub encrypt_to: Option<WotDid>,
+    #[serde(default)]
+    pub pin: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct UploadResponse {
+    pub hash: String,
+    pub size: usize,
+    pub encrypted: bool,
+    pub pinned: bool,
+}
+
+// Streams the raw request body into the store and records the signer as an uploader.
+// Encrypted uploads are buffered (up to the size limit) because the envelope is sealed over
+// the whole plaintext.
+#[utoipa::path(
+    post,
+    path = "/api/v1/blobs",
+    tag = "storage",
+    params(
+        UploadQuery,
+        ("x-wotid-signer" = String, Header, description = "DID of the uploader"),
+        ("x-wotid-action" = String, Header, description = "Signed `blob.upload` action"),
+    ),
+    request_body(content = [u8], content_type = "application/octet-stream"),
+    responses(
+        (status = 200, body = UploadResponse),
+        (status = 400, description = "Malformed `encrypt_to` DID", body = String),
+        (status = 401, description = "Missing headers, or signature, action or freshness rejected", body = String),
+        (status = 413, description = "The blob exceeds the configured size limit", body = String),
+        (status = 422, description = "`encrypt_to` has no X25519 key-agreement method", body = String),
+        (status = 502, description = "The storage backend or identity service failed", body = String),
+    )
+)]
+async fn upload_blob(
+    State(state): State<AppState>,
+    Query(query): Query<UploadQuery>,
+    headers: HeaderMap,
+    body: Body,
+) -> Result<Json<UploadResponse>, (StatusCode, String)> {
+    let uploader = blob_action(&state, &headers, ACTION_UPLOAD, None).await?;
+    let stored = match &query.encrypt_to {
+        Some(did) => {
+            let methods = state.identity.resolve_key_agreement(did).await.map_err(|e| {
+                tracing::error!(target: "backend::storage", "Failed to resolve key agreement for {}: {}", did, e);
+                (StatusCode::BAD_GATEWAY, format!("Failed to resolve key agreement methods for {}", did))
+            })?;
+            let recipient = RecipientKey::from_methods(&methods).ok_or_else(|| {
+                (StatusCode::UNPROCESSABLE_ENTITY, format!("{} has no X25519 key-agreement method", did))
+            })?;
+            let plaintext = collect_limited(body.into_data_stream(), state.blobs.max_size())
+                .await
+                .map_err(storage_error)?;
+            let envelope = encryption::seal(&recipient, &plaintext);
+            let sealed = serde_json::to_vec(&envelope).expect("envelope serializes to JSON");
+            let hash = state.blobs.put(&sealed).await.map_err(storage_error)?;
+            StoredBlob { hash, size: sealed.len() }
+        }
+        None => state
+            .blobs
+            .put_stream(body.into_data_stream())
+            .await
+            .map_err(storage_error)?,
+    };
+
+    state.blob_owners.add(&stored.hash, &uploader).await;
+    if query.pin {
+        state.blobs.pin(&stored.hash).await.map_err(storage_error)?;
+    }
+
+    Ok(Json(UploadResponse {
+        hash: stored.hash,
+        size: stored.size,
+        encrypted: query.encrypt_to.is_some(),
+        pinned: query.pin,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/blobs/{hash}",
+    tag = "storage",
+    params(("hash" = String, Path, description = "Lowercase hex SHA-256 of the blob")),
+    responses(
+        (status = 200, body = [u8], content_type = "application/octet-stream"),
+        (status = 404, body = String),
+        (status = 500, description = "The stored bytes no longer match the hash", body = String),
+    )
+)]
+async fn download_blob(
+    State(state): State<AppState>,
+    Path(hash): Path<String>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    match state.blobs.get(&hash).await.map_err(storage_error)? {
+        Some(bytes) => Ok(([(header::CONTENT_TYPE, "application/octet-stream")], bytes)),
+        None => Err((StatusCode::NOT_FOUND, format!("No blob stored under {}", hash))),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/blobs/{hash}/pin",
+    tag = "storage",
+    params(
+        ("hash" = String, Path),
+        ("x-wotid-signer" = String, Header, description = "DID of an uploader"),
+        ("x-wotid-action" = String, Header, description = "Signed `blob.pin` action naming `hash`"),
+    ),
+    responses(
+        (status = 204, description = "Pinned"),
+        (status = 401, description = "Missing headers, or signature, action or freshness rejected", body = String),
+        (status = 403, description = "The signer did not upload the blob, or the action names another blob", body = String),
+        (status = 404, body = String),
+    )
+)]
+async fn pin_blob(
+    State(state): State<AppState>,
+    Path(hash): Path<String>,
+    headers: HeaderMap,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let signer = blob_action(&state, &headers, ACTION_PIN, Some(&hash)).await?;
+    state.blob_owners.ensure_owner(&hash, &signer).await?;
+    state.blobs.pin(&hash).await.map_err(storage_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/blobs/{hash}/pin",
+    tag = "storage",
+    params(
+        ("hash" = String, Path),
+        ("x-wotid-signer" = String, Header, description = "DID of an uploader"),
+        ("x-wotid-action" = String, Header, description = "Signed `blob.unpin` action naming `hash`"),
+    ),
+    responses(
+        (status = 204, description = "Unpinned"),
+        (status = 401, description = "Missing headers, or signature, action or freshness rejected", body = String),
+        (status = 403, description = "The signer did not upload the blob, or the action names another blob", body = String),
+        (status = 404, body = String),
+    )
+)]
+async fn unpin_blob(
+    State(state): State<AppState>,
+    Path(hash): Path<String>,
+    headers: HeaderMap,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let signer = blob_action(&state, &headers, ACTION_UNPIN, Some(&hash)).await?;
+    state.blob_owners.ensure_owner(&hash, &signer).await?;
+    state.blobs.unpin(&hash).await.map_err(storage_error)?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Drops the signer's claim on the blob; the bytes are deleted with the last uploader's
+#[utoipa::path(
+    delete,
+    path = "/api/v1/blobs/{hash}",
+    tag = "storage",
+    params(
+        ("hash" = String, Path),
+        ("x-wotid-signer" = String, Header, description = "DID of an uploader"),
+        ("x-wotid-action" = String, Header, description = "Signed `blob.delete` action naming `hash`"),
+    ),
+    responses(
+        (status = 204, description = "Deleted, or the signer's claim dropped while others keep the blob"),
+        (status = 401, description = "Missing headers, or signature, action or freshness rejected", body = String),
+        (status = 403, description = "The signer did not upload the blob, or the action names another blob", body = String),
+        (status = 404, body = String),
+        (status = 409, description = "The blob is pinned", body = String),
+    )
+)]
+async fn delete_blob(
+    State(state): State<AppState>,
+    Path(hash): Path<String>,
+    headers: HeaderMap,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let signer = blob_action(&state, &headers, ACTION_DELETE, Some(&hash)).await?;
+    state.blob_owners.release(&state.blobs, &hash, &signer).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::identity_client::{ACTION_HEADER, SIGNER_HEADER};
+    use crate::testing::{self, TestDir};
+    use serde_json::json;
+
+    const ALICE: &str = "did:iota:0xa11ce";
+    const BOB: &str = "did:iota:0xb0b";
+
+    struct Backend {
+        url: String,
+        client: reqwest::Client,
+        _dir: TestDir,
+    }
+
+    impl Backend {
+        async fn start() -> Self {
+            let dir = TestDir::new("storage");
+            let state = testing::app_state(&dir, &testing::identity_service().await, &[]);
+            let (router, _) = routes().split_for_parts();
+            Self { url: testing::serve(router.with_state(state)).await, client: reqwest::Client::new(), _dir: dir }
+        }
+
+        fn signed(&self, request: reqwest::RequestBuilder, signer: &str, claims: serde_json::Value) -> reqwest::RequestBuilder {
+            let action = testing::sign(signer, claims);
+            request.header(SIGNER_HEADER, action.signer.as_str()).header(ACTION_HEADER, action.jws)
+        }
+
+        async fn upload(&self, signer: &str, bytes: &'static [u8]) -> String {
+            let request = self.client.post(format!("{}/api/v1/blobs", self.url)).body(bytes);
+            let resp = self.signed(request, signer, json!({ "action": ACTION_UPLOAD })).send().await.unwrap();
+            assert_eq!(resp.status(), StatusCode::OK);
+            resp.json::<serde_json::Value>().await.unwrap()["hash"].as_str().unwrap().to_string()
+        }
+
+        async fn delete(&self, signer: &str, hash: &str) -> StatusCode {
+            let request = self.client.delete(format!("{}/api/v1/blobs/{}", self.url, hash));
+            self.signed(request, signer, json!({ "action": ACTION_DELETE, "hash": hash })).send().await.unwrap().status()
+        }
+
+        async fn exists(&self, hash: &str) -> bool {
+            let resp = self.client.get(format!("{}/api/v1/blobs/{}", self.url, hash)).send().await.unwrap();
+            resp.status() == StatusCode::OK
+        }
+    }
+
+    #[tokio::test]
+    async fn unsigned_requests_are_rejected() {
+        let backend = Backend::start().await;
+        let upload = backend.client.post(format!("{}/api/v1/blobs", backend.url)).body("data").send().await.unwrap();
+        assert_eq!(upload.status(), StatusCode::UNAUTHORIZED);
+
+        let hash = backend.upload(ALICE, b"data").await;
+        let blob = format!("{}/api/v1/blobs/{}", backend.url, hash);
+        for request in [backend.client.put(format!("{}/pin", blob)), backend.client.delete(format!("{}/pin", blob)), backend.client.delete(&blob)] {
+            assert_eq!(request.send().await.unwrap().status(), StatusCode::UNAUTHORIZED);
+        }
+        assert!(backend.exists(&hash).await);
+    }
+
+    #[tokio::test]
+    async fn only_an_uploader_can_pin_or_delete() {
+        let backend = Backend::start().await;
+        let hash = backend.upload(ALICE, b"alice's data").await;
+        let pin = |signer: &str, claims| {
+            let request = backend.client.put(format!("{}/api/v1/blobs/{}/pin", backend.url, hash));
+            backend.signed(request, signer, claims).send()
+        };
+        assert_eq!(pin(BOB, json!({ "action": ACTION_PIN, "hash": hash })).await.unwrap().status(), StatusCode::FORBIDDEN);
+        assert_eq!(backend.delete(BOB, &hash).await, StatusCode::FORBIDDEN);
+        assert!(backend.exists(&hash).await);
+
+        // An action signed for another blob does not carry over
+        let other = backend.upload(ALICE, b"other data").await;
+        assert_eq!(pin(ALICE, json!({ "action": ACTION_PIN, "hash": other })).await.unwrap().status(), StatusCode::FORBIDDEN);
+
+        assert_eq!(pin(ALICE, json!({ "action": ACTION_PIN, "hash": hash })).await.unwrap().status(), StatusCode::NO_CONTENT);
+        assert_eq!(backend.delete(ALICE, &hash).await, StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn shared_blob_is_deleted_with_its_last_uploader() {
+        let backend = Backend::start().await;
+        let hash = backend.upload(ALICE, b"common").await;
+        assert_eq!(backend.upload(BOB, b"common").await, hash);
+
+        assert_eq!(backend.delete(ALICE, &hash).await, StatusCode::NO_CONTENT);
+        assert!(backend.exists(&hash).await);
+        assert_eq!(backend.delete(ALICE, &hash).await, StatusCode::FORBIDDEN);
+        assert_eq!(backend.delete(BOB, &hash).await, StatusCode::NO_CONTENT);
+        assert!(!backend.exists(&hash).await);
+    }
+}
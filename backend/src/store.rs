@@ -0,0 +1,70 @@
+use serde::{de::DeserializeOwned, Serialize};
+use std::path::PathBuf;
+use tokio::sync::{Mutex, MutexGuard};
+
+// State persisted as one JSON file, shared by the governance, dispute and webhook stores.
+// A missing file starts empty; writes go through a temp file so a crash never leaves half a file.
+pub struct JsonStore<T> {
+    path: PathBuf,
+    data: Mutex<T>,
+}
+
+impl<T: Serialize + DeserializeOwned + Default> JsonStore<T> {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self, anyhow::Error> {
+        let path = path.into();
+        let data = match std::fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => T::default(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self { path, data: Mutex::new(data) })
+    }
+
+    pub async fn lock(&self) -> MutexGuard<'_, T> {
+        self.data.lock().await
+    }
+
+    // Write the locked state back; callers pass the guard they mutated. Failures are logged and
+    // the in-memory state stays authoritative until the next successful write.
+    pub async fn persist(&self, data: &T) {
+        let write = async {
+            if let Some(parent) = self.path.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            let tmp = self.path.with_extension("json.tmp");
+            tokio::fs::write(&tmp, serde_json::to_vec_pretty(data)?).await?;
+            tokio::fs::rename(&tmp, &self.path).await?;
+            Ok::<_, anyhow::Error>(())
+        };
+        if let Err(e) = write.await {
+            tracing::error!(target: "backend::store", "Failed to persist {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[tokio::test]
+    async fn state_survives_a_reload() {
+        let dir = std::env::temp_dir().join(format!("wot-store-{}", uuid::Uuid::new_v4()));
+        let path = dir.join("nested").join("store.json");
+
+        let store: JsonStore<BTreeMap<String, u32>> = JsonStore::load(&path).unwrap();
+        assert!(store.lock().await.is_empty());
+        let mut data = store.lock().await;
+        data.insert("votes".to_string(), 3);
+        store.persist(&data).await;
+        drop(data);
+
+        let reloaded: JsonStore<BTreeMap<String, u32>> = JsonStore::load(&path).unwrap();
+        assert_eq!(reloaded.lock().await.get("votes"), Some(&3));
+        assert!(!path.with_extension("json.tmp").exists());
+
+        std::fs::write(&path, b"not json").unwrap();
+        assert!(JsonStore::<BTreeMap<String, u32>>::load(&path).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
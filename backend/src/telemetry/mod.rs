@@ -0,0 +1,65 @@
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use std::time::Instant;
+use tracing::Instrument;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use utoipa_axum::{router::OpenApiRouter, routes};
+
+// Latency buckets in seconds, shared by HTTP and node RPC histograms
+const LATENCY_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+// Install the global Prometheus recorder; metrics recorded before this are dropped
+pub fn install_recorder() -> PrometheusHandle {
+    PrometheusBuilder::new()
+        .set_buckets_for_metric(Matcher::Suffix("duration_seconds".to_string()), LATENCY_BUCKETS)
+        .expect("latency buckets are not empty")
+        .install_recorder()
+        .expect("Prometheus recorder is installed once")
+}
+
+pub fn routes<S: Clone + Send + Sync + 'static>(handle: PrometheusHandle) -> OpenApiRouter<S> {
+    OpenApiRouter::new().routes(routes!(render)).with_state(handle)
+}
+
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "telemetry",
+    responses((status = 200, description = "Prometheus text exposition format", body = String, content_type = "text/plain"))
+)]
+async fn render(State(handle): State<PrometheusHandle>) -> String {
+    handle.render()
+}
+
+// Middleware: run each request in a server span continuing the caller's `traceparent`, and
+// count requests and record their latency per route template, so path parameters such as
+// DIDs do not become labels
+pub async fn track_http(request: Request, next: Next) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or_else(|| "unmatched".to_string(), |path| path.as_str().to_string());
+    let method = request.method().to_string();
+    let span = tracing::info_span!(
+        "http_request",
+        otel.name = format!("{} {}", method, route),
+        otel.kind = "server",
+        http.request.method = %method,
+        http.route = %route,
+        http.response.status_code = tracing::field::Empty,
+    );
+    let _ = span.set_parent(wot_telemetry::remote_context(request.headers()));
+    let started = Instant::now();
+    let response = next.run(request).instrument(span.clone()).await;
+    span.record("http.response.status_code", response.status().as_u16());
+
+    let status = response.status().as_u16().to_string();
+    metrics::histogram!("http_request_duration_seconds", "method" => method.clone(), "route" => route.clone())
+        .record(started.elapsed().as_secs_f64());
+    metrics::counter!("http_requests_total", "method" => method, "route" => route, "status" => status).increment(1);
+    response
+}
@@ -0,0 +1,85 @@
+// Shared pieces of the handler tests: a throwaway data directory, a server on a free local
+// port, and an identity service stand-in that accepts any action whose JWS is its claims
+use axum::{http::StatusCode, routing::post, Json, Router};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use wot_config::{Settings, SharedConfig};
+use wot_id_types::identity::{VerifyActionRequest, VerifyActionResponse};
+
+use crate::identity_client::SignedAction;
+use crate::state::AppState;
+
+pub struct TestDir(pub PathBuf);
+
+impl TestDir {
+    pub fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("wot-{}-{}", name, uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+
+    pub fn path(&self, file: &str) -> String {
+        self.0.join(file).display().to_string()
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+// Serve `app` on 127.0.0.1 and return its base URL
+pub async fn serve(app: Router) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await });
+    format!("http://{}", addr)
+}
+
+pub fn verify_action_route() -> Router {
+    Router::new().route(
+        "/api/v1/identity/verify-action",
+        post(|Json(request): Json<VerifyActionRequest>| async move {
+            let claims: Value = serde_json::from_str(&request.jws).map_err(|_| StatusCode::BAD_REQUEST)?;
+            if claims["action"] != request.action.as_str() {
+                return Err(StatusCode::UNAUTHORIZED);
+            }
+            Ok(Json(VerifyActionResponse { signer: request.signer, claims }))
+        }),
+    )
+}
+
+// Identity service that only verifies actions; returns its base URL
+pub async fn identity_service() -> String {
+    serve(verify_action_route()).await
+}
+
+// Stand-in for an action signed by `did`, for use with `verify_action_route`
+pub fn sign(did: &str, claims: Value) -> SignedAction {
+    SignedAction { signer: did.parse().unwrap(), jws: claims.to_string() }
+}
+
+// App state with every store in `dir`, talking to `identity_url` and no node. `env` adds or
+// overrides settings.
+pub fn app_state(dir: &TestDir, identity_url: &str, env: &[(&str, String)]) -> AppState {
+    let mut vars: HashMap<String, String> = [
+        ("IOTA_NODE_URL", "http://127.0.0.1:1".to_string()),
+        ("IDENTITY_SERVICE_URL", identity_url.to_string()),
+        ("BLOB_STORE_DIR", dir.path("blobs")),
+        ("BLOB_OWNERS_PATH", dir.path("blob-owners.json")),
+        ("WEBHOOK_STORE_PATH", dir.path("webhooks.json")),
+        ("GOVERNANCE_STORE_PATH", dir.path("governance.json")),
+        ("DISPUTE_STORE_PATH", dir.path("disputes.json")),
+        ("DIDCOMM_STORE_PATH", dir.path("didcomm.json")),
+        ("TRUST_RENEWALS_PATH", dir.path("trust-renewals.json")),
+    ]
+    .into_iter()
+    .map(|(key, value)| (key.to_string(), value))
+    .collect();
+    vars.extend(env.iter().map(|(key, value)| (key.to_string(), value.clone())));
+    let settings = Settings::from_parts(Vec::new(), vars).unwrap();
+    crate::state::build_app_state(&settings, &SharedConfig::from_settings(&settings).unwrap()).unwrap()
+}
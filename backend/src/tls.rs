@@ -0,0 +1,191 @@
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, pem::PemObject};
+use rustls::{ClientConfig, RootCertStore, ServerConfig};
+use std::sync::Arc;
+use wot_config::{ConfigError, Settings};
+
+// Certificate chain and private key of one end of a connection, as PEM files
+struct KeyPair {
+    cert_path: String,
+    key_path: String,
+}
+
+impl KeyPair {
+    // `<prefix>_CERT_PATH` and `<prefix>_KEY_PATH` together, or neither
+    fn from_settings(settings: &Settings, prefix: &str) -> Result<Option<Self>, ConfigError> {
+        let cert_key = format!("{}_CERT_PATH", prefix);
+        let key_key = format!("{}_KEY_PATH", prefix);
+        match (settings.var(&cert_key), settings.var(&key_key)) {
+            (Some(cert_path), Some(key_path)) => Ok(Some(Self { cert_path, key_path })),
+            (None, None) => Ok(None),
+            (Some(_), None) => Err(ConfigError::Missing(key_key)),
+            (None, Some(_)) => Err(ConfigError::Missing(cert_key)),
+        }
+    }
+
+    fn load(&self) -> Result<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>), ConfigError> {
+        let certs = CertificateDer::pem_file_iter(&self.cert_path)
+            .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| file_error(&self.cert_path, e))?;
+        if certs.is_empty() {
+            return Err(file_error(&self.cert_path, "no certificates found"));
+        }
+        let key = PrivateKeyDer::from_pem_file(&self.key_path).map_err(|e| file_error(&self.key_path, e))?;
+        Ok((certs, key))
+    }
+}
+
+fn file_error(path: &str, reason: impl ToString) -> ConfigError {
+    ConfigError::File { path: path.into(), reason: reason.to_string() }
+}
+
+// CA certificates from a PEM file
+fn load_roots(path: &str) -> Result<RootCertStore, ConfigError> {
+    let mut roots = RootCertStore::empty();
+    for cert in CertificateDer::pem_file_iter(path).map_err(|e| file_error(path, e))? {
+        roots.add(cert.map_err(|e| file_error(path, e))?).map_err(|e| file_error(path, e))?;
+    }
+    if roots.is_empty() {
+        return Err(file_error(path, "no certificates found"));
+    }
+    Ok(roots)
+}
+
+// Always ring, so the result does not depend on which rustls features other crates enable
+fn provider() -> Arc<rustls::crypto::CryptoProvider> {
+    Arc::new(rustls::crypto::ring::default_provider())
+}
+
+// TLS for the backend's own listener, from `BACKEND_TLS_CERT_PATH` and `BACKEND_TLS_KEY_PATH`;
+// `None` serves plain HTTP
+pub fn server_config(settings: &Settings) -> Result<Option<Arc<ServerConfig>>, ConfigError> {
+    let Some(key_pair) = KeyPair::from_settings(settings, "BACKEND_TLS")? else {
+        return Ok(None);
+    };
+    let (certs, key) = key_pair.load()?;
+    let mut config = ServerConfig::builder_with_provider(provider())
+        .with_safe_default_protocol_versions()
+        .and_then(|builder| builder.with_no_client_auth().with_single_cert(certs, key))
+        .map_err(|e| file_error(&key_pair.cert_path, e))?;
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(Some(Arc::new(config)))
+}
+
+// HTTP client for the identity service. `IDENTITY_SERVICE_CA_PATH` names the CA its certificate is
+// checked against; with `BACKEND_CLIENT_CERT_PATH` and `BACKEND_CLIENT_KEY_PATH` the backend also
+// presents a client certificate, for identity services that require mutual TLS.
+pub fn identity_http_client(settings: &Settings) -> Result<reqwest::Client, ConfigError> {
+    let ca_path = settings.var("IDENTITY_SERVICE_CA_PATH");
+    let key_pair = KeyPair::from_settings(settings, "BACKEND_CLIENT")?;
+    let Some(ca_path) = ca_path else {
+        if key_pair.is_some() {
+            return Err(ConfigError::Missing("IDENTITY_SERVICE_CA_PATH".to_string()));
+        }
+        return Ok(reqwest::Client::new());
+    };
+    let builder = ClientConfig::builder_with_provider(provider())
+        .with_safe_default_protocol_versions()
+        .map_err(|e| file_error(&ca_path, e))?
+        .with_root_certificates(load_roots(&ca_path)?);
+    let config = match key_pair {
+        Some(key_pair) => {
+            let (certs, key) = key_pair.load()?;
+            builder.with_client_auth_cert(certs, key).map_err(|e| file_error(&key_pair.cert_path, e))?
+        }
+        None => builder.with_no_client_auth(),
+    };
+    reqwest::Client::builder()
+        .use_preconfigured_tls(config)
+        .build()
+        .map_err(|e| file_error(&ca_path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, extract::ConnectInfo, routing::get};
+    use rcgen::{BasicConstraints, Certificate, CertificateParams, ExtendedKeyUsagePurpose, IsCa};
+    use std::collections::HashMap;
+    use std::net::SocketAddr;
+    use std::path::PathBuf;
+    use tokio::net::TcpListener;
+
+    // A CA with a server certificate for `localhost` and a client certificate, written as PEM files
+    struct TestPki {
+        dir: PathBuf,
+    }
+
+    impl TestPki {
+        fn generate(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("wot-tls-{}-{}", name, std::process::id()));
+            std::fs::create_dir_all(&dir).unwrap();
+            let mut ca_params = CertificateParams::new(Vec::new());
+            ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+            let ca = Certificate::from_params(ca_params).unwrap();
+            let server = Certificate::from_params(CertificateParams::new(vec!["localhost".to_string()])).unwrap();
+            let mut client_params = CertificateParams::new(Vec::new());
+            client_params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ClientAuth];
+            let client = Certificate::from_params(client_params).unwrap();
+
+            let write = |file: &str, pem: String| std::fs::write(dir.join(file), pem).unwrap();
+            write("ca.pem", ca.serialize_pem().unwrap());
+            write("server.pem", server.serialize_pem_with_signer(&ca).unwrap());
+            write("server.key", server.serialize_private_key_pem());
+            write("client.pem", client.serialize_pem_with_signer(&ca).unwrap());
+            write("client.key", client.serialize_private_key_pem());
+            Self { dir }
+        }
+
+        fn settings(&self, entries: &[(&str, &str)]) -> Settings {
+            let env: HashMap<String, String> = entries
+                .iter()
+                .map(|(key, file)| (key.to_string(), self.dir.join(file).to_string_lossy().into_owned()))
+                .collect();
+            Settings::from_parts(Vec::new(), env).unwrap()
+        }
+    }
+
+    impl Drop for TestPki {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    #[tokio::test]
+    async fn serves_https_and_presents_client_certificate() {
+        let pki = TestPki::generate("serve");
+        let server = pki.settings(&[("BACKEND_TLS_CERT_PATH", "server.pem"), ("BACKEND_TLS_KEY_PATH", "server.key")]);
+        let config = server_config(&server).unwrap().expect("TLS is configured");
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let app = Router::new().route("/", get(|ConnectInfo(peer): ConnectInfo<SocketAddr>| async move { peer.ip().to_string() }));
+        let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+        let served = tokio::spawn(wot_tls::serve(listener, config, app, ConnectInfo, async move {
+            stopped.await.ok();
+        }));
+
+        let url = format!("https://localhost:{}/", port);
+        let client = pki.settings(&[
+            ("IDENTITY_SERVICE_CA_PATH", "ca.pem"),
+            ("BACKEND_CLIENT_CERT_PATH", "client.pem"),
+            ("BACKEND_CLIENT_KEY_PATH", "client.key"),
+        ]);
+        let body = identity_http_client(&client).unwrap().get(&url).send().await.unwrap().text().await.unwrap();
+        assert_eq!(body, "127.0.0.1");
+
+        // Without the CA the server certificate is not trusted
+        let untrusted = pki.settings(&[]);
+        assert!(identity_http_client(&untrusted).unwrap().get(&url).send().await.is_err());
+
+        stop.send(()).unwrap();
+        served.await.unwrap();
+    }
+
+    #[test]
+    fn key_pair_needs_both_files() {
+        let pki = TestPki::generate("pair");
+        let partial = pki.settings(&[("BACKEND_CLIENT_CERT_PATH", "client.pem"), ("IDENTITY_SERVICE_CA_PATH", "ca.pem")]);
+        assert!(matches!(identity_http_client(&partial), Err(ConfigError::Missing(key)) if key == "BACKEND_CLIENT_KEY_PATH"));
+        let no_ca = pki.settings(&[("BACKEND_CLIENT_CERT_PATH", "client.pem"), ("BACKEND_CLIENT_KEY_PATH", "client.key")]);
+        assert!(matches!(identity_http_client(&no_ca), Err(ConfigError::Missing(key)) if key == "IDENTITY_SERVICE_CA_PATH"));
+    }
+}
@@ -0,0 +1,382 @@
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+use std::future::Future;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::watch;
+use wot_config::{ConfigError, Settings};
+
+use crate::iota_rpc::{IotaRpcClient, RpcError};
+use crate::lifecycle::Shutdown;
+use crate::state::AppState;
+use crate::store::JsonStore;
+
+use super::UNDECAYED;
+
+// Objects per `iota_multiGetObjects` request when loading expiries
+const OBJECT_BATCH: usize = 50;
+
+// Wall-clock time and the chain's current epoch; expiry is in epochs, decay is in milliseconds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChainTime {
+    pub now_ms: u64,
+    pub epoch: u64,
+}
+
+// Source of `ChainTime` for the sweep; the node in production, fixed values in tests
+pub trait Clock: Send + Sync {
+    fn now(&self) -> impl Future<Output = Result<ChainTime, RpcError>> + Send;
+}
+
+pub struct NodeClock {
+    rpc: IotaRpcClient,
+}
+
+impl NodeClock {
+    pub fn new(rpc: IotaRpcClient) -> Self {
+        Self { rpc }
+    }
+}
+
+impl Clock for NodeClock {
+    async fn now(&self) -> Result<ChainTime, RpcError> {
+        let system_state = self.rpc.call("iotax_getLatestIotaSystemState", json!([])).await?;
+        let epoch = match system_state.get("epoch") {
+            Some(Value::String(s)) => s.parse().ok(),
+            Some(other) => other.as_u64(),
+            None => None,
+        }
+        .ok_or_else(|| RpcError::MalformedResponse("system state without epoch".to_string()))?;
+        let now_ms = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0);
+        Ok(ChainTime { now_ms, epoch })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecayPolicy {
+    // How often the sweep runs
+    pub interval: Duration,
+    // Age at which an input counts half; `None` disables decay
+    pub half_life: Option<Duration>,
+    // Decay never takes an input below this share of its weight
+    pub floor_permille: u32,
+    // Notify the trusting DID this many epochs before expiry; `None` disables notices
+    pub renewal_notice_epochs: Option<u64>,
+}
+
+impl Default for DecayPolicy {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(3600),
+            half_life: None,
+            floor_permille: 0,
+            renewal_notice_epochs: None,
+        }
+    }
+}
+
+impl DecayPolicy {
+    pub fn from_settings(settings: &Settings) -> Result<Self, ConfigError> {
+        let var = |name: &str| settings.parse::<u64>(name);
+        let defaults = Self::default();
+        Ok(Self {
+            interval: var("TRUST_SWEEP_INTERVAL_SECS")?
+                .filter(|&secs| secs > 0)
+                .map_or(defaults.interval, Duration::from_secs),
+            half_life: var("TRUST_DECAY_HALF_LIFE_DAYS")?
+                .filter(|&days| days > 0)
+                .map(|days| Duration::from_secs(days * 86_400)),
+            floor_permille: var("TRUST_DECAY_FLOOR_PCT")?.map_or(defaults.floor_permille, |pct| pct.min(100) as u32 * 10),
+            renewal_notice_epochs: var("TRUST_RENEWAL_NOTICE_EPOCHS")?,
+        })
+    }
+
+    // Share of an input's weight left `age_ms` after its last update: halved every half-life
+    pub fn retained_permille(&self, age_ms: u64) -> u32 {
+        let Some(half_life) = self.half_life else {
+            return UNDECAYED;
+        };
+        let half_lives = age_ms as f64 / half_life.as_millis() as f64;
+        let retained = (f64::from(UNDECAYED) * 0.5f64.powf(half_lives)) as u32;
+        retained.clamp(self.floor_permille.min(UNDECAYED), UNDECAYED)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RenewalNotice {
+    pub object_id: String,
+    pub source: String,
+    pub target: String,
+    pub context: String,
+    pub expires_epoch: u64,
+}
+
+// Renewal notices already sent, by relationship and the expiry they announced. The trust index
+// is rebuilt on every start, so without this each restart would send every due notice again.
+pub struct RenewalLog {
+    sent: JsonStore<BTreeSet<(String, u64)>>,
+}
+
+impl RenewalLog {
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self, anyhow::Error> {
+        Ok(Self { sent: JsonStore::load(path)? })
+    }
+
+    // Record `notices` and return those not sent before. Entries whose expiry has passed are
+    // dropped, since the sweep no longer reports those relationships.
+    async fn record(&self, notices: Vec<RenewalNotice>, epoch: u64) -> Vec<RenewalNotice> {
+        let mut sent = self.sent.lock().await;
+        let before = sent.len();
+        sent.retain(|&(_, expires)| expires >= epoch);
+        let pruned = sent.len() != before;
+        let new: Vec<RenewalNotice> = notices
+            .into_iter()
+            .filter(|notice| sent.insert((notice.object_id.clone(), notice.expires_epoch)))
+            .collect();
+        if pruned || !new.is_empty() {
+            self.sent.persist(&sent).await;
+        }
+        new
+    }
+}
+
+// What one sweep changed; all zero and empty when nothing did
+#[derive(Debug, Default)]
+pub struct SweepReport {
+    pub expired: usize,
+    pub decayed: usize,
+    pub renewal_notices: Vec<RenewalNotice>,
+}
+
+// Long-running task: periodically expire and decay the trust index and send renewal notices.
+// The policy may be replaced while running (SIGHUP); a new interval applies from the next sweep.
+pub async fn run<C: Clock>(state: AppState, clock: C, mut policies: watch::Receiver<DecayPolicy>, shutdown: Shutdown) {
+    let mut policy = policies.borrow_and_update().clone();
+    if policy.half_life.is_none() {
+        tracing::info!(target: "backend::trust", "TRUST_DECAY_HALF_LIFE_DAYS not set; trust decay is disabled");
+    }
+    let mut interval = tokio::time::interval(policy.interval);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            Ok(()) = policies.changed() => {
+                policy = policies.borrow_and_update().clone();
+                tracing::info!(target: "backend::trust", "Trust decay policy updated: {:?}", policy);
+                if policy.interval != interval.period() {
+                    interval = tokio::time::interval_at(tokio::time::Instant::now() + policy.interval, policy.interval);
+                    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+                }
+                continue;
+            }
+            () = shutdown.requested() => return,
+        }
+        match run_once(&state, &clock, &policy).await {
+            Ok(report) if report.expired > 0 || report.decayed > 0 || !report.renewal_notices.is_empty() => {
+                tracing::info!(
+                    target: "backend::trust",
+                    "Trust sweep: {} expired, {} decayed, {} renewal notices",
+                    report.expired,
+                    report.decayed,
+                    report.renewal_notices.len()
+                );
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!(target: "backend::trust", "Trust sweep failed: {}", e),
+        }
+    }
+}
+
+pub async fn run_once<C: Clock>(state: &AppState, clock: &C, policy: &DecayPolicy) -> Result<SweepReport, RpcError> {
+    load_expiries(state).await?;
+    let time = clock.now().await?;
+    let mut report = state.trust.sweep(time, policy).await;
+    report.renewal_notices = state.renewals.record(std::mem::take(&mut report.renewal_notices), time.epoch).await;
+    for notice in &report.renewal_notices {
+        send_renewal_notice(state, notice).await;
+    }
+    Ok(report)
+}
+
+// `expires` is only set at creation, so each relationship is read from chain once
+async fn load_expiries(state: &AppState) -> Result<(), RpcError> {
+    let pending = state.trust.unknown_expiry().await;
+    for batch in pending.chunks(OBJECT_BATCH) {
+        for object in state.rpc.multi_get_objects(batch).await? {
+            let data = object.get("data").unwrap_or(&object);
+            let Some(object_id) = data.get("objectId").and_then(Value::as_str) else {
+                continue;
+            };
+            if let Some(fields) = data.pointer("/content/fields") {
+                state.trust.set_expiry(object_id, option_u64(fields.get("expires"))).await;
+            }
+        }
+    }
+    Ok(())
+}
+
+// `Option<u64>` as rendered by the node: null, the bare value, or `{ "vec": [value] }`
+fn option_u64(value: Option<&Value>) -> Option<u64> {
+    match value? {
+        Value::String(s) => s.parse().ok(),
+        Value::Object(wrapper) => option_u64(wrapper.get("vec")?.as_array()?.first()),
+        other => other.as_u64(),
+    }
+}
+
+async fn send_renewal_notice(state: &AppState, notice: &RenewalNotice) {
+    let Some(mediator) = &state.mediator else {
+        tracing::warn!(
+            target: "backend::trust",
+            "Trust relationship {} expires at epoch {}; no DIDComm mediator configured to notify {}",
+            notice.object_id,
+            notice.expires_epoch,
+            notice.source
+        );
+        return;
+    };
+    let content = format!(
+        "Your trust relationship {} in {} (context \"{}\") expires at epoch {}. Renew it to keep it counting.",
+        notice.object_id, notice.target, notice.context, notice.expires_epoch
+    );
+    match mediator.notify(&state.identity, &notice.source, content).await {
+        Ok(_) => tracing::info!(target: "backend::trust", "Sent renewal notice for {} to {}", notice.object_id, notice.source),
+        Err(e) => tracing::warn!(
+            target: "backend::trust",
+            "Failed to send renewal notice for {} to {}: {}",
+            notice.object_id,
+            notice.source,
+            e
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::ChainEvent;
+    use crate::testing::TestDir;
+    use crate::trust::TrustIndex;
+
+    const DAY_MS: u64 = 86_400_000;
+
+    struct FixedClock(ChainTime);
+
+    impl Clock for FixedClock {
+        async fn now(&self) -> Result<ChainTime, RpcError> {
+            Ok(self.0)
+        }
+    }
+
+    fn trust_changed(object_id: &str, value: u64, timestamp_ms: u64) -> ChainEvent {
+        ChainEvent::from_node_event(&json!({
+            "type": "0x1::trust::TrustChanged",
+            "timestampMs": timestamp_ms.to_string(),
+            "parsedJson": {
+                "object_id": object_id,
+                "source": "did:iota:alice",
+                "target": "did:iota:bob",
+                "context": "code-review",
+                "previous_value": "0",
+                "new_value": value.to_string(),
+                "change": "created",
+            },
+        }))
+        .unwrap()
+    }
+
+    async fn index_with(expires_epoch: Option<u64>) -> TrustIndex {
+        let index = TrustIndex::default();
+        index.apply(&trust_changed("0xa", 80_000, 0)).await;
+        index.set_expiry("0xa", expires_epoch).await;
+        index
+    }
+
+    async fn sweep_at<C: Clock>(index: &TrustIndex, clock: &C, policy: &DecayPolicy) -> SweepReport {
+        index.sweep(clock.now().await.unwrap(), policy).await
+    }
+
+    #[tokio::test]
+    async fn expired_relationships_stop_counting_once() {
+        let index = index_with(Some(10)).await;
+        let policy = DecayPolicy::default();
+
+        let report = sweep_at(&index, &FixedClock(ChainTime { now_ms: 0, epoch: 10 }), &policy).await;
+        assert_eq!(report.expired, 0);
+        assert_eq!(index.score("did:iota:bob", "code-review").await, 80_000);
+
+        let clock = FixedClock(ChainTime { now_ms: 0, epoch: 11 });
+        assert_eq!(sweep_at(&index, &clock, &policy).await.expired, 1);
+        assert_eq!(index.score("did:iota:bob", "code-review").await, 0);
+        assert_eq!(sweep_at(&index, &clock, &policy).await.expired, 0);
+    }
+
+    #[tokio::test]
+    async fn decay_halves_weight_per_half_life_down_to_floor() {
+        let index = index_with(None).await;
+        let policy = DecayPolicy {
+            half_life: Some(Duration::from_millis(30 * DAY_MS)),
+            floor_permille: 200,
+            ..DecayPolicy::default()
+        };
+
+        let clock = FixedClock(ChainTime { now_ms: 30 * DAY_MS, epoch: 1 });
+        assert_eq!(sweep_at(&index, &clock, &policy).await.decayed, 1);
+        assert_eq!(index.score("did:iota:bob", "code-review").await, 40_000);
+        assert_eq!(sweep_at(&index, &clock, &policy).await.decayed, 0);
+
+        let clock = FixedClock(ChainTime { now_ms: 365 * DAY_MS, epoch: 1 });
+        sweep_at(&index, &clock, &policy).await;
+        assert_eq!(index.score("did:iota:bob", "code-review").await, 16_000);
+    }
+
+    // A backend with its stores in `dir` and relationship 0xa expiring at `expires_epoch`, as
+    // after a (re)start
+    async fn started(dir: &TestDir, expires_epoch: u64) -> AppState {
+        let state = crate::testing::app_state(dir, "http://127.0.0.1:1", &[]);
+        state.trust.apply(&trust_changed("0xa", 80_000, 0)).await;
+        state.trust.set_expiry("0xa", Some(expires_epoch)).await;
+        state
+    }
+
+    #[tokio::test]
+    async fn renewal_notice_is_sent_once_across_restarts() {
+        let dir = TestDir::new("trust-decay");
+        let policy = DecayPolicy { renewal_notice_epochs: Some(2), ..DecayPolicy::default() };
+        let state = started(&dir, 10).await;
+
+        let report = run_once(&state, &FixedClock(ChainTime { now_ms: 0, epoch: 7 }), &policy).await.unwrap();
+        assert!(report.renewal_notices.is_empty());
+
+        let clock = FixedClock(ChainTime { now_ms: 0, epoch: 8 });
+        let report = run_once(&state, &clock, &policy).await.unwrap();
+        assert_eq!(report.renewal_notices.len(), 1);
+        assert_eq!(report.renewal_notices[0].source, "did:iota:alice");
+        assert_eq!(report.renewal_notices[0].expires_epoch, 10);
+        assert!(run_once(&state, &clock, &policy).await.unwrap().renewal_notices.is_empty());
+
+        // The restarted backend rebuilds its index but remembers the notice
+        drop(state);
+        let state = started(&dir, 10).await;
+        assert!(run_once(&state, &clock, &policy).await.unwrap().renewal_notices.is_empty());
+    }
+
+    #[tokio::test]
+    async fn no_renewal_notice_after_expiry() {
+        let index = index_with(Some(10)).await;
+        let policy = DecayPolicy { renewal_notice_epochs: Some(2), ..DecayPolicy::default() };
+
+        let report = sweep_at(&index, &FixedClock(ChainTime { now_ms: 0, epoch: 11 }), &policy).await;
+        assert_eq!(report.expired, 1);
+        assert!(report.renewal_notices.is_empty());
+    }
+
+    #[test]
+    fn reads_move_option_expiry() {
+        assert_eq!(option_u64(Some(&json!({ "vec": ["42"] }))), Some(42));
+        assert_eq!(option_u64(Some(&json!({ "vec": [] }))), None);
+        assert_eq!(option_u64(Some(&json!("7"))), Some(7));
+        assert_eq!(option_u64(Some(&Value::Null)), None);
+    }
+}
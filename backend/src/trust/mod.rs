@@ -0,0 +1,423 @@
+use axum::{
+    extract::{Path, Query, State},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+use utoipa::{IntoParams, ToSchema};
+use utoipa_axum::{router::OpenApiRouter, routes};
+use wot_id_types::{TrustValue, WotDid};
+
+use crate::events::{ChainEvent, EventFilter};
+use crate::iota_rpc::RpcError;
+use crate::lifecycle::Shutdown;
+use crate::state::AppState;
+
+pub mod decay;
+
+use decay::{ChainTime, DecayPolicy, RenewalNotice, SweepReport};
+
+// Highest trust level of a single relationship, for scaling aggregate thresholds
+pub const MAX_TRUST_LEVEL: u64 = TrustValue::MAX.get();
+
+// Full weight for `retained_permille`
+const UNDECAYED: u32 = 1000;
+
+// How a resolved dispute changes the weight of a disputed relationship or claim assessment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case", tag = "kind")]
+pub enum Adjustment {
+    // Left out of aggregation entirely
+    Suspended,
+    // Counted at `pct` percent of its weight
+    DownWeighted { pct: u8 },
+}
+
+impl Adjustment {
+    // Share of the input's weight that still counts, in percent
+    pub fn weight_pct(adjustment: Option<Self>) -> u64 {
+        match adjustment {
+            None => 100,
+            Some(Self::Suspended) => 0,
+            Some(Self::DownWeighted { pct }) => u64::from(pct.min(100)),
+        }
+    }
+}
+
+// Latest known state of one `TrustRelationship`, taken from its `TrustChanged` events
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct TrustEdge {
+    pub object_id: String,
+    pub source: String,
+    pub target: String,
+    pub context: String,
+    pub value: TrustValue,
+    pub updated_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub adjustment: Option<Adjustment>,
+    // `TrustRelationship.expires`, an epoch; read from the object by the decay job
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expires_epoch: Option<u64>,
+    #[serde(skip)]
+    expiry_known: bool,
+    // Cleared by the decay job once the relationship has expired
+    pub active: bool,
+    // Weight left after time decay, in permille; set by the decay job
+    pub retained_permille: u32,
+}
+
+// Latest known state of one `ClaimTrust`, taken from its `ClaimTrustChanged` events
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ClaimAssessment {
+    pub object_id: String,
+    pub credential_id: String,
+    pub claim_name: String,
+    pub value: TrustValue,
+    pub updated_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub adjustment: Option<Adjustment>,
+    // Weight left after time decay, in permille; set by the decay job
+    pub retained_permille: u32,
+}
+
+// u64 fields of parsed Move events are rendered as strings
+fn u64_field(payload: &Value, name: &str) -> Option<u64> {
+    match payload.get(name)? {
+        Value::String(s) => s.parse().ok(),
+        other => other.as_u64(),
+    }
+}
+
+impl TrustEdge {
+    fn from_event(event: &ChainEvent) -> Option<Self> {
+        if event.kind != "TrustChanged" {
+            return None;
+        }
+        let text = |name: &str| event.payload.get(name).and_then(Value::as_str).map(str::to_string);
+        Some(Self {
+            object_id: event.object_id.clone()?,
+            source: text("source")?,
+            target: text("target")?,
+            context: text("context")?,
+            value: u64_field(&event.payload, "new_value").and_then(|v| TrustValue::new(v).ok())?,
+            updated_ms: event.timestamp_ms.unwrap_or(0),
+            adjustment: None,
+            expires_epoch: None,
+            expiry_known: false,
+            active: true,
+            retained_permille: UNDECAYED,
+        })
+    }
+
+    // Trust level after expiry, decay and any dispute adjustment
+    pub fn effective_value(&self) -> u64 {
+        if !self.active {
+            return 0;
+        }
+        self.value.get() * Adjustment::weight_pct(self.adjustment) / 100 * u64::from(self.retained_permille) / 1000
+    }
+}
+
+impl ClaimAssessment {
+    fn from_event(event: &ChainEvent) -> Option<Self> {
+        if event.kind != "ClaimTrustChanged" {
+            return None;
+        }
+        let text = |name: &str| event.payload.get(name).and_then(Value::as_str).map(str::to_string);
+        Some(Self {
+            object_id: event.object_id.clone()?,
+            credential_id: text("credential_id")?,
+            claim_name: text("claim_name")?,
+            value: u64_field(&event.payload, "trust_value").and_then(|v| TrustValue::new(v).ok())?,
+            updated_ms: event.timestamp_ms.unwrap_or(0),
+            adjustment: None,
+            retained_permille: UNDECAYED,
+        })
+    }
+}
+
+// Off-chain index of trust relationships and claim assessments, kept current from `TrustChanged`
+// and `ClaimTrustChanged` events. Aggregate scores (e.g. governance vote weights) are computed
+// from it rather than from chain reads, with dispute adjustments applied.
+#[derive(Default)]
+pub struct TrustIndex {
+    edges: RwLock<HashMap<String, TrustEdge>>,
+    claims: RwLock<HashMap<String, ClaimAssessment>>,
+    // By object ID of the disputed TrustRelationship or ClaimTrust
+    adjustments: RwLock<HashMap<String, Adjustment>>,
+}
+
+impl TrustIndex {
+    pub async fn apply(&self, event: &ChainEvent) {
+        // The backfill and the live stream overlap; an older event never overwrites a newer one
+        if let Some(mut edge) = TrustEdge::from_event(event) {
+            let mut edges = self.edges.write().await;
+            match edges.get(&edge.object_id) {
+                Some(existing) if existing.updated_ms > edge.updated_ms => {}
+                existing => {
+                    // Expiry is fixed at creation; an update resets decay but not expiry
+                    if let Some(existing) = existing {
+                        edge.expires_epoch = existing.expires_epoch;
+                        edge.expiry_known = existing.expiry_known;
+                        edge.active = existing.active;
+                    }
+                    edges.insert(edge.object_id.clone(), edge);
+                }
+            }
+        } else if let Some(claim) = ClaimAssessment::from_event(event) {
+            let mut claims = self.claims.write().await;
+            if claims.get(&claim.object_id).is_none_or(|existing| existing.updated_ms <= claim.updated_ms) {
+                claims.insert(claim.object_id.clone(), claim);
+            }
+        }
+    }
+
+    // Record the outcome of a resolved dispute over a relationship or claim assessment; `None`
+    // lifts an earlier adjustment
+    pub async fn set_adjustment(&self, object_id: &str, adjustment: Option<Adjustment>) {
+        let mut adjustments = self.adjustments.write().await;
+        match adjustment {
+            Some(adjustment) => adjustments.insert(object_id.to_string(), adjustment),
+            None => adjustments.remove(object_id),
+        };
+    }
+
+    // Every relationship within `context`, with adjustments filled in
+    pub async fn context_edges(&self, context: &str) -> Vec<TrustEdge> {
+        let adjustments = self.adjustments.read().await;
+        let edges = self.edges.read().await;
+        edges
+            .values()
+            .filter(|e| e.context == context)
+            .map(|e| TrustEdge { adjustment: adjustments.get(&e.object_id).copied(), ..e.clone() })
+            .collect()
+    }
+
+    // Relationships from other DIDs to `target` within `context`
+    pub async fn incoming(&self, target: &str, context: &str) -> Vec<TrustEdge> {
+        let mut edges = self.context_edges(context).await;
+        edges.retain(|e| e.target == target && e.source != target);
+        edges
+    }
+
+    // Sum of the trust levels other DIDs place in `target` within `context`, in trust.move units
+    pub async fn score(&self, target: &str, context: &str) -> u64 {
+        self.incoming(target, context).await.iter().map(TrustEdge::effective_value).sum()
+    }
+
+    // Assessments of one claim of a credential, with adjustments filled in
+    pub async fn claim_assessments(&self, credential_id: &str, claim_name: &str) -> Vec<ClaimAssessment> {
+        let adjustments = self.adjustments.read().await;
+        let claims = self.claims.read().await;
+        claims
+            .values()
+            .filter(|c| c.credential_id == credential_id && c.claim_name == claim_name)
+            .map(|c| ClaimAssessment { adjustment: adjustments.get(&c.object_id).copied(), ..c.clone() })
+            .collect()
+    }
+
+    // Weighted mean of a claim's assessments; down-weighted ones count less, suspended ones not at all
+    pub async fn claim_score(&self, credential_id: &str, claim_name: &str) -> Option<u64> {
+        let assessments = self.claim_assessments(credential_id, claim_name).await;
+        let weights: u64 = assessments.iter().map(|c| Adjustment::weight_pct(c.adjustment)).sum();
+        let weighted: u64 = assessments
+            .iter()
+            .map(|c| c.value.get() * Adjustment::weight_pct(c.adjustment) * u64::from(c.retained_permille) / 1000)
+            .sum();
+        (weights > 0).then(|| weighted / weights)
+    }
+
+    // Relationships whose expiry has not been read from chain yet
+    pub async fn unknown_expiry(&self) -> Vec<String> {
+        let edges = self.edges.read().await;
+        edges.values().filter(|e| !e.expiry_known).map(|e| e.object_id.clone()).collect()
+    }
+
+    pub async fn set_expiry(&self, object_id: &str, expires_epoch: Option<u64>) {
+        if let Some(edge) = self.edges.write().await.get_mut(object_id) {
+            edge.expires_epoch = expires_epoch;
+            edge.expiry_known = true;
+        }
+    }
+
+    // Mark expired relationships inactive, recompute decay from each input's last update and
+    // collect renewal notices that are due. State is derived from `time` alone, so running it
+    // again for the same time changes nothing. A relationship is reported on every sweep inside
+    // its notice window; `decay::RenewalLog` keeps that to one notice.
+    pub async fn sweep(&self, time: ChainTime, policy: &DecayPolicy) -> SweepReport {
+        let mut report = SweepReport::default();
+        let mut edges = self.edges.write().await;
+        for edge in edges.values_mut() {
+            let expired = edge.expires_epoch.is_some_and(|expires| time.epoch > expires);
+            if edge.active == expired {
+                edge.active = !expired;
+                if expired {
+                    report.expired += 1;
+                }
+            }
+            let retained = policy.retained_permille(time.now_ms.saturating_sub(edge.updated_ms));
+            if retained != edge.retained_permille {
+                edge.retained_permille = retained;
+                report.decayed += 1;
+            }
+            if let (Some(notice_epochs), Some(expires)) = (policy.renewal_notice_epochs, edge.expires_epoch)
+                && !expired
+                && time.epoch.saturating_add(notice_epochs) >= expires
+            {
+                report.renewal_notices.push(RenewalNotice {
+                    object_id: edge.object_id.clone(),
+                    source: edge.source.clone(),
+                    target: edge.target.clone(),
+                    context: edge.context.clone(),
+                    expires_epoch: expires,
+                });
+            }
+        }
+        drop(edges);
+
+        let mut claims = self.claims.write().await;
+        for claim in claims.values_mut() {
+            let retained = policy.retained_permille(time.now_ms.saturating_sub(claim.updated_ms));
+            if retained != claim.retained_permille {
+                claim.retained_permille = retained;
+                report.decayed += 1;
+            }
+        }
+        report
+    }
+}
+
+// Long-running task: apply recorded dispute outcomes, load the trust event history from the
+// node, then follow the hub. The index is rebuilt on every start, so shutdown just stops it.
+pub async fn index(state: AppState, shutdown: Shutdown) {
+    // Subscribed before the backfill so nothing published meanwhile is missed
+    let mut subscription = state.events.subscribe_buffered(EventFilter::default());
+    for (object_id, adjustment) in state.disputes.adjustments().await {
+        state.trust.set_adjustment(&object_id, Some(adjustment)).await;
+    }
+    if !state.config.wot_id_pkg_id.is_empty() {
+        let backfilled = tokio::select! {
+            backfilled = backfill(&state) => backfilled,
+            () = shutdown.requested() => return,
+        };
+        match backfilled {
+            Ok(count) => tracing::info!(target: "backend::trust", "Indexed {} historical trust events", count),
+            Err(e) => tracing::warn!(target: "backend::trust", "Trust index backfill failed: {}", e),
+        }
+    }
+    loop {
+        let event = tokio::select! {
+            event = subscription.next() => event,
+            () = shutdown.requested() => return,
+        };
+        let Some(event) = event else { return };
+        state.trust.apply(&event).await;
+    }
+}
+
+async fn backfill(state: &AppState) -> Result<usize, RpcError> {
+    let mut count = 0;
+    for kind in ["TrustChanged", "ClaimTrustChanged"] {
+        count += backfill_kind(state, kind).await?;
+    }
+    Ok(count)
+}
+
+async fn backfill_kind(state: &AppState, kind: &str) -> Result<usize, RpcError> {
+    let event_type = format!("{}::trust::{}", state.config.wot_id_pkg_id, kind);
+    let mut cursor = Value::Null;
+    let mut count = 0;
+    loop {
+        let page = state
+            .rpc
+            .call("iotax_queryEvents", json!([{ "MoveEventType": event_type }, cursor, 50, false]))
+            .await?;
+        for event in page.get("data").and_then(Value::as_array).into_iter().flatten() {
+            if let Some(event) = ChainEvent::from_node_event(event) {
+                state.trust.apply(&event).await;
+                count += 1;
+            }
+        }
+        match page.get("nextCursor") {
+            Some(next) if page.get("hasNextPage").and_then(Value::as_bool).unwrap_or(false) => cursor = next.clone(),
+            _ => return Ok(count),
+        }
+    }
+}
+
+pub fn routes() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new().routes(routes!(trust_score)).routes(routes!(claim_score))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TrustScoreQuery {
+    pub context: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct TrustScoreResponse {
+    pub did: WotDid,
+    pub context: String,
+    pub score: u64,
+    pub relationships: Vec<TrustEdge>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/trust/{did}/score",
+    tag = "trust",
+    params(("did" = String, Path, description = "Trust target"), TrustScoreQuery),
+    responses(
+        (status = 200, body = TrustScoreResponse),
+        (status = 400, description = "Malformed DID", body = String),
+    )
+)]
+async fn trust_score(
+    State(state): State<AppState>,
+    Path(did): Path<WotDid>,
+    Query(query): Query<TrustScoreQuery>,
+) -> Json<TrustScoreResponse> {
+    let relationships = state.trust.incoming(&did, &query.context).await;
+    Json(TrustScoreResponse {
+        score: relationships.iter().map(TrustEdge::effective_value).sum(),
+        did,
+        context: query.context,
+        relationships,
+    })
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ClaimScoreQuery {
+    pub credential_id: String,
+    pub claim_name: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ClaimScoreResponse {
+    pub credential_id: String,
+    pub claim_name: String,
+    // `None` when no assessment counts
+    pub score: Option<u64>,
+    pub assessments: Vec<ClaimAssessment>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/trust/claims/score",
+    tag = "trust",
+    params(ClaimScoreQuery),
+    responses((status = 200, body = ClaimScoreResponse))
+)]
+async fn claim_score(State(state): State<AppState>, Query(query): Query<ClaimScoreQuery>) -> Json<ClaimScoreResponse> {
+    let assessments = state.trust.claim_assessments(&query.credential_id, &query.claim_name).await;
+    Json(ClaimScoreResponse {
+        score: state.trust.claim_score(&query.credential_id, &query.claim_name).await,
+        credential_id: query.credential_id,
+        claim_name: query.claim_name,
+        assessments,
+    })
+}
@@ -0,0 +1,821 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    Json,
+};
+use hmac::{Hmac, Mac};
+use serde::{de::IgnoredAny, Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+use tokio::task::JoinSet;
+use utoipa::ToSchema;
+use utoipa_axum::{router::OpenApiRouter, routes};
+use uuid::Uuid;
+use wot_id_types::WotDid;
+
+use crate::events::{ChainEvent, EventFilter};
+use crate::identity_client::{signed_action_from_headers, verify_signed_action, SignedAction};
+use crate::lifecycle::Shutdown;
+use crate::state::AppState;
+use crate::store::JsonStore;
+
+mod target;
+
+pub use target::TargetPolicy;
+
+const ACTION_REGISTER: &str = "webhook.register";
+const ACTION_LIST: &str = "webhook.list";
+const ACTION_READ: &str = "webhook.read";
+const ACTION_DELETE: &str = "webhook.delete";
+const ACTION_REDELIVER: &str = "webhook.redeliver";
+
+pub const SIGNATURE_HEADER: &str = "x-wotid-signature";
+pub const TIMESTAMP_HEADER: &str = "x-wotid-timestamp";
+pub const EVENT_HEADER: &str = "x-wotid-event";
+pub const DELIVERY_HEADER: &str = "x-wotid-delivery";
+
+// Delivery log entries kept across all webhooks
+const DELIVERY_LOG_SIZE: usize = 1000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Webhook {
+    pub id: String,
+    // DID that registered the webhook; only it can see or change it
+    #[serde(default)]
+    pub owner: String,
+    pub url: String,
+    // Shared HMAC key; never returned by the API after registration
+    pub secret: String,
+    // Event kinds to deliver, e.g. `CredentialRevoked`; empty means all
+    pub event_types: Vec<String>,
+    // Only deliver events involving one of these DIDs; empty means all
+    pub dids: Vec<String>,
+    pub created_at_ms: u64,
+}
+
+impl Webhook {
+    fn matches(&self, event: &ChainEvent) -> bool {
+        (self.event_types.is_empty() || self.event_types.contains(&event.kind))
+            && (self.dids.is_empty() || event.dids.iter().any(|did| self.dids.contains(did)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryOutcome {
+    Delivered,
+    Retrying,
+    DeadLettered,
+}
+
+// One delivery attempt
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DeliveryAttempt {
+    pub delivery_id: String,
+    pub webhook_id: String,
+    pub event_seq: u64,
+    pub event_kind: String,
+    pub attempt: u32,
+    pub status_code: Option<u16>,
+    pub error: Option<String>,
+    pub outcome: DeliveryOutcome,
+    pub at_ms: u64,
+}
+
+// A delivery that exhausted its retries; kept until redelivered or the webhook is removed
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct DeadLetter {
+    pub delivery_id: String,
+    pub webhook_id: String,
+    pub event: ChainEvent,
+    pub attempts: u32,
+    pub last_error: String,
+    pub failed_at_ms: u64,
+}
+
+// A delivery that is neither delivered nor dead-lettered yet. It is checkpointed with every
+// attempt, so retries pending at shutdown resume on the next start.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PendingDelivery {
+    delivery_id: String,
+    webhook_id: String,
+    event: ChainEvent,
+    // Attempts made so far
+    attempts: u32,
+    // When the next attempt is due
+    due_at_ms: u64,
+}
+
+impl PendingDelivery {
+    fn new(webhook_id: &str, delivery_id: String, event: ChainEvent) -> Self {
+        Self { delivery_id, webhook_id: webhook_id.to_string(), event, attempts: 0, due_at_ms: now_ms() }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct WebhookData {
+    webhooks: Vec<Webhook>,
+    deliveries: VecDeque<DeliveryAttempt>,
+    dead_letters: Vec<DeadLetter>,
+    #[serde(default)]
+    pending: Vec<PendingDelivery>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    // Exponential backoff before attempt `attempt + 1`
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+}
+
+// Webhook registrations, delivery log and dead-letter queue, persisted as one JSON file
+pub struct WebhookStore {
+    data: JsonStore<WebhookData>,
+    pub retry: RetryPolicy,
+    pub targets: TargetPolicy,
+    client: reqwest::Client,
+    // Checkpointed deliveries for `dispatch` to run; it takes the receiver when it starts
+    queue: mpsc::UnboundedSender<PendingDelivery>,
+    queued: Mutex<Option<mpsc::UnboundedReceiver<PendingDelivery>>>,
+}
+
+impl WebhookStore {
+    pub fn load(path: impl Into<PathBuf>, retry: RetryPolicy, targets: TargetPolicy) -> Result<Self, anyhow::Error> {
+        let (queue, queued) = mpsc::unbounded_channel();
+        Ok(Self {
+            data: JsonStore::load(path)?,
+            retry,
+            targets,
+            client: targets.client(),
+            queue,
+            queued: Mutex::new(Some(queued)),
+        })
+    }
+
+    // Checkpoint a new delivery, then hand it to `dispatch`
+    async fn enqueue(&self, pending: PendingDelivery) {
+        let mut data = self.data.lock().await;
+        data.pending.push(pending.clone());
+        self.data.persist(&data).await;
+        let _ = self.queue.send(pending);
+    }
+
+    // Deliveries a previous run left pending, minus those of webhooks removed since
+    async fn resumable(&self) -> Vec<PendingDelivery> {
+        let mut data = self.data.lock().await;
+        let before = data.pending.len();
+        let WebhookData { webhooks, pending, .. } = &mut *data;
+        pending.retain(|p| webhooks.iter().any(|w| w.id == p.webhook_id));
+        if data.pending.len() != before {
+            self.data.persist(&data).await;
+        }
+        data.pending.clone()
+    }
+
+    async fn matching(&self, event: &ChainEvent) -> Vec<Webhook> {
+        let data = self.data.lock().await;
+        data.webhooks.iter().filter(|w| w.matches(event)).cloned().collect()
+    }
+
+    async fn webhook(&self, id: &str) -> Option<Webhook> {
+        self.data.lock().await.webhooks.iter().find(|w| w.id == id).cloned()
+    }
+
+    // The webhook if `owner` registered it. Other owners' webhooks answer like missing ones,
+    // so their ids are not confirmed.
+    async fn owned(&self, id: &str, owner: &str) -> Result<Webhook, (StatusCode, String)> {
+        self.webhook(id)
+            .await
+            .filter(|w| w.owner == owner)
+            .ok_or_else(|| (StatusCode::NOT_FOUND, format!("No webhook {}", id)))
+    }
+
+    // Log an attempt and checkpoint the delivery: `next` when it will be retried, otherwise it is
+    // no longer pending
+    async fn record(&self, attempt: DeliveryAttempt, dead_letter: Option<DeadLetter>, next: Option<&PendingDelivery>) {
+        let mut data = self.data.lock().await;
+        if data.deliveries.len() == DELIVERY_LOG_SIZE {
+            data.deliveries.pop_front();
+        }
+        data.pending.retain(|p| p.delivery_id != attempt.delivery_id);
+        if let Some(next) = next {
+            data.pending.push(next.clone());
+        }
+        data.deliveries.push_back(attempt);
+        if let Some(dead_letter) = dead_letter {
+            data.dead_letters.push(dead_letter);
+        }
+        self.data.persist(&data).await;
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+// Hex HMAC-SHA256 over `<timestamp>.<body>`, sent as `sha256=<hex>`. Including the timestamp
+// lets receivers reject replays.
+pub fn sign_payload(secret: &str, timestamp: u64, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+// Long-running task: fan hub events out to matching webhooks and run their deliveries. On
+// shutdown it waits for the deliveries to stop; those not finished stay checkpointed.
+pub async fn dispatch(state: AppState, shutdown: Shutdown) {
+    let store = state.webhooks.clone();
+    let Some(mut queued) = store.queued.lock().expect("webhook queue lock").take() else {
+        tracing::error!(target: "backend::webhooks", "Webhook dispatch is already running");
+        return;
+    };
+    let mut deliveries = JoinSet::new();
+    let resumed = store.resumable().await;
+    if !resumed.is_empty() {
+        tracing::info!(target: "backend::webhooks", "Resuming {} pending webhook deliveries", resumed.len());
+    }
+    for pending in resumed {
+        deliveries.spawn(deliver(store.clone(), pending, shutdown.clone()));
+    }
+
+    let mut subscription = state.events.subscribe_live(EventFilter::default());
+    loop {
+        tokio::select! {
+            event = subscription.next() => {
+                let Some(event) = event else { break };
+                for webhook in store.matching(&event).await {
+                    let delivery_id = format!("{}-{}", webhook.id, event.seq);
+                    store.enqueue(PendingDelivery::new(&webhook.id, delivery_id, (*event).clone())).await;
+                }
+            }
+            Some(pending) = queued.recv() => {
+                deliveries.spawn(deliver(store.clone(), pending, shutdown.clone()));
+            }
+            Some(_) = deliveries.join_next() => {}
+            () = shutdown.requested() => break,
+        }
+    }
+    while deliveries.join_next().await.is_some() {}
+}
+
+// POST the event, without following redirects; see `TargetPolicy::client`
+async fn send(store: &WebhookStore, webhook: &Webhook, event: &ChainEvent, delivery_id: &str, body: &[u8]) -> (Option<u16>, Option<String>) {
+    let url = match reqwest::Url::parse(&webhook.url) {
+        Ok(url) => url,
+        Err(e) => return (None, Some(format!("invalid url: {}", e))),
+    };
+    if let Err(e) = store.targets.check_delivery(&url) {
+        return (None, Some(e));
+    }
+    let timestamp = now_ms() / 1000;
+    let result = store
+        .client
+        .post(url)
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .header(EVENT_HEADER, &event.kind)
+        .header(DELIVERY_HEADER, delivery_id)
+        .header(TIMESTAMP_HEADER, timestamp)
+        .header(SIGNATURE_HEADER, sign_payload(&webhook.secret, timestamp, body))
+        .body(body.to_vec())
+        .send()
+        .await;
+    match result {
+        Ok(response) if response.status().is_success() => (Some(response.status().as_u16()), None),
+        Ok(response) => (Some(response.status().as_u16()), Some(format!("receiver returned {}", response.status()))),
+        Err(e) => (None, Some(e.to_string())),
+    }
+}
+
+// Attempt a delivery until it succeeds or is dead-lettered. Shutdown interrupts it between or
+// during attempts; an interrupted attempt is made again on the next start.
+async fn deliver(store: Arc<WebhookStore>, mut pending: PendingDelivery, shutdown: Shutdown) {
+    let body = serde_json::to_vec(&pending.event).expect("event serializes to JSON");
+    let retry = &store.retry;
+
+    loop {
+        let wait = Duration::from_millis(pending.due_at_ms.saturating_sub(now_ms()));
+        let webhook = tokio::select! {
+            () = tokio::time::sleep(wait) => store.webhook(&pending.webhook_id).await,
+            () = shutdown.requested() => return,
+        };
+        // Stop retrying if the webhook was removed in the meantime; its pending deliveries went with it
+        let Some(webhook) = webhook else { return };
+        let (status_code, error) = tokio::select! {
+            sent = send(&store, &webhook, &pending.event, &pending.delivery_id, &body) => sent,
+            () = shutdown.requested() => return,
+        };
+        pending.attempts += 1;
+        let attempt = pending.attempts;
+        let exhausted = attempt >= retry.max_attempts;
+        let outcome = match (&error, exhausted) {
+            (None, _) => DeliveryOutcome::Delivered,
+            (Some(_), false) => DeliveryOutcome::Retrying,
+            (Some(_), true) => DeliveryOutcome::DeadLettered,
+        };
+        let dead_letter = (outcome == DeliveryOutcome::DeadLettered).then(|| DeadLetter {
+            delivery_id: pending.delivery_id.clone(),
+            webhook_id: webhook.id.clone(),
+            event: pending.event.clone(),
+            attempts: attempt,
+            last_error: error.clone().unwrap_or_default(),
+            failed_at_ms: now_ms(),
+        });
+        if dead_letter.is_some() {
+            tracing::warn!(
+                target: "backend::webhooks",
+                "Delivery {} to {} dead-lettered after {} attempts",
+                pending.delivery_id, webhook.url, attempt
+            );
+        }
+        let attempt_log = DeliveryAttempt {
+            delivery_id: pending.delivery_id.clone(),
+            webhook_id: webhook.id.clone(),
+            event_seq: pending.event.seq,
+            event_kind: pending.event.kind.clone(),
+            attempt,
+            status_code,
+            error,
+            outcome,
+            at_ms: now_ms(),
+        };
+        pending.due_at_ms = now_ms() + retry.backoff(attempt).as_millis() as u64;
+        let retrying = outcome == DeliveryOutcome::Retrying;
+        store.record(attempt_log, dead_letter, retrying.then_some(&pending)).await;
+        if !retrying {
+            return;
+        }
+    }
+}
+
+pub fn routes() -> OpenApiRouter<AppState> {
+    OpenApiRouter::new()
+        .routes(routes!(register_webhook, list_webhooks))
+        .routes(routes!(list_dead_letters))
+        .routes(routes!(redeliver))
+        .routes(routes!(get_webhook, delete_webhook))
+        .routes(routes!(list_deliveries))
+}
+
+#[derive(Deserialize)]
+struct RegisterClaims {
+    url: String,
+    secret: String,
+    #[serde(default)]
+    event_types: Vec<String>,
+    #[serde(default)]
+    dids: Vec<WotDid>,
+}
+
+// Names the webhook a read or delete action is for, so it cannot be replayed against another
+#[derive(Deserialize)]
+struct WebhookClaims {
+    webhook_id: String,
+}
+
+#[derive(Deserialize)]
+struct RedeliverClaims {
+    delivery_id: String,
+}
+
+// Webhook as returned by the API, without its secret
+#[derive(Serialize, ToSchema)]
+pub struct WebhookView {
+    pub id: String,
+    pub owner: String,
+    pub url: String,
+    pub event_types: Vec<String>,
+    pub dids: Vec<String>,
+    pub created_at_ms: u64,
+}
+
+impl From<&Webhook> for WebhookView {
+    fn from(webhook: &Webhook) -> Self {
+        Self {
+            id: webhook.id.clone(),
+            owner: webhook.owner.clone(),
+            url: webhook.url.clone(),
+            event_types: webhook.event_types.clone(),
+            dids: webhook.dids.clone(),
+            created_at_ms: webhook.created_at_ms,
+        }
+    }
+}
+
+// Verify the signed action carried in the request headers and return its signer
+async fn header_action<T: serde::de::DeserializeOwned>(
+    state: &AppState,
+    headers: &HeaderMap,
+    action: &str,
+) -> Result<(String, T), (StatusCode, String)> {
+    let signed = signed_action_from_headers(headers)?;
+    let claims = verify_signed_action(&state.identity, &signed, action).await?;
+    Ok((signed.signer.to_string(), claims))
+}
+
+fn ensure_same_webhook(claims: &WebhookClaims, id: &str) -> Result<(), (StatusCode, String)> {
+    if claims.webhook_id != id {
+        return Err((StatusCode::FORBIDDEN, "The signed action is for another webhook".to_string()));
+    }
+    Ok(())
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/webhooks",
+    tag = "webhooks",
+    request_body(
+        content = SignedAction,
+        description = "Signed `webhook.register` action with `url`, `secret`, `event_types` and `dids`; the signer owns the webhook"
+    ),
+    responses(
+        (status = 201, body = WebhookView),
+        (status = 400, description = "Invalid or non-public url, a secret shorter than 16 characters, or a malformed DID", body = String),
+        (status = 401, description = "Signature, action or freshness rejected", body = String),
+    )
+)]
+async fn register_webhook(
+    State(state): State<AppState>,
+    Json(payload): Json<SignedAction>,
+) -> Result<(StatusCode, Json<WebhookView>), (StatusCode, String)> {
+    let claims: RegisterClaims = verify_signed_action(&state.identity, &payload, ACTION_REGISTER).await?;
+    let url = reqwest::Url::parse(&claims.url)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid webhook url: {}", e)))?;
+    state.webhooks.targets.check(&url).await.map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    if claims.secret.len() < 16 {
+        return Err((StatusCode::BAD_REQUEST, "Webhook secret must be at least 16 characters".to_string()));
+    }
+
+    let webhook = Webhook {
+        id: Uuid::new_v4().to_string(),
+        owner: payload.signer.to_string(),
+        url: claims.url,
+        secret: claims.secret,
+        event_types: claims.event_types,
+        dids: claims.dids.into_iter().map(String::from).collect(),
+        created_at_ms: now_ms(),
+    };
+    let view = WebhookView::from(&webhook);
+    let mut data = state.webhooks.data.lock().await;
+    data.webhooks.push(webhook);
+    state.webhooks.data.persist(&data).await;
+    Ok((StatusCode::CREATED, Json(view)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/webhooks",
+    tag = "webhooks",
+    params(
+        ("x-wotid-signer" = String, Header, description = "DID of the owner"),
+        ("x-wotid-action" = String, Header, description = "Signed `webhook.list` action"),
+    ),
+    responses(
+        (status = 200, description = "The signer's webhooks", body = Vec<WebhookView>),
+        (status = 401, description = "Missing headers, or signature, action or freshness rejected", body = String),
+    )
+)]
+async fn list_webhooks(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<WebhookView>>, (StatusCode, String)> {
+    let (owner, IgnoredAny) = header_action(&state, &headers, ACTION_LIST).await?;
+    let data = state.webhooks.data.lock().await;
+    Ok(Json(data.webhooks.iter().filter(|w| w.owner == owner).map(WebhookView::from).collect()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/webhooks/{id}",
+    tag = "webhooks",
+    params(
+        ("id" = String, Path),
+        ("x-wotid-signer" = String, Header, description = "DID of the owner"),
+        ("x-wotid-action" = String, Header, description = "Signed `webhook.read` action naming `webhook_id`"),
+    ),
+    responses(
+        (status = 200, body = WebhookView),
+        (status = 401, description = "Missing headers, or signature, action or freshness rejected", body = String),
+        (status = 403, description = "The action names another webhook", body = String),
+        (status = 404, description = "No webhook with this id for the signer", body = String),
+    )
+)]
+async fn get_webhook(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<WebhookView>, (StatusCode, String)> {
+    let (owner, claims) = header_action(&state, &headers, ACTION_READ).await?;
+    ensure_same_webhook(&claims, &id)?;
+    let webhook = state.webhooks.owned(&id, &owner).await?;
+    Ok(Json(WebhookView::from(&webhook)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/webhooks/{id}",
+    tag = "webhooks",
+    params(
+        ("id" = String, Path),
+        ("x-wotid-signer" = String, Header, description = "DID of the owner"),
+        ("x-wotid-action" = String, Header, description = "Signed `webhook.delete` action naming `webhook_id`"),
+    ),
+    responses(
+        (status = 204, description = "Removed, with its dead letters and pending deliveries"),
+        (status = 401, description = "Missing headers, or signature, action or freshness rejected", body = String),
+        (status = 403, description = "The action names another webhook", body = String),
+        (status = 404, description = "No webhook with this id for the signer", body = String),
+    )
+)]
+async fn delete_webhook(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let (owner, claims) = header_action(&state, &headers, ACTION_DELETE).await?;
+    ensure_same_webhook(&claims, &id)?;
+    state.webhooks.owned(&id, &owner).await?;
+    let mut data = state.webhooks.data.lock().await;
+    data.webhooks.retain(|w| w.id != id);
+    data.dead_letters.retain(|d| d.webhook_id != id);
+    data.pending.retain(|p| p.webhook_id != id);
+    state.webhooks.data.persist(&data).await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Delivery log for one webhook, newest first
+#[utoipa::path(
+    get,
+    path = "/api/v1/webhooks/{id}/deliveries",
+    tag = "webhooks",
+    params(
+        ("id" = String, Path),
+        ("x-wotid-signer" = String, Header, description = "DID of the owner"),
+        ("x-wotid-action" = String, Header, description = "Signed `webhook.read` action naming `webhook_id`"),
+    ),
+    responses(
+        (status = 200, body = Vec<DeliveryAttempt>),
+        (status = 401, description = "Missing headers, or signature, action or freshness rejected", body = String),
+        (status = 403, description = "The action names another webhook", body = String),
+        (status = 404, description = "No webhook with this id for the signer", body = String),
+    )
+)]
+async fn list_deliveries(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<DeliveryAttempt>>, (StatusCode, String)> {
+    let (owner, claims) = header_action(&state, &headers, ACTION_READ).await?;
+    ensure_same_webhook(&claims, &id)?;
+    state.webhooks.owned(&id, &owner).await?;
+    let data = state.webhooks.data.lock().await;
+    Ok(Json(data.deliveries.iter().rev().filter(|d| d.webhook_id == id).cloned().collect()))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/webhooks/dead-letters",
+    tag = "webhooks",
+    params(
+        ("x-wotid-signer" = String, Header, description = "DID of the owner"),
+        ("x-wotid-action" = String, Header, description = "Signed `webhook.list` action"),
+    ),
+    responses(
+        (status = 200, description = "Dead letters of the signer's webhooks", body = Vec<DeadLetter>),
+        (status = 401, description = "Missing headers, or signature, action or freshness rejected", body = String),
+    )
+)]
+async fn list_dead_letters(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> Result<Json<Vec<DeadLetter>>, (StatusCode, String)> {
+    let (owner, IgnoredAny) = header_action(&state, &headers, ACTION_LIST).await?;
+    let data = state.webhooks.data.lock().await;
+    let owned = |webhook_id: &str| data.webhooks.iter().any(|w| w.id == webhook_id && w.owner == owner);
+    Ok(Json(data.dead_letters.iter().filter(|d| owned(&d.webhook_id)).cloned().collect()))
+}
+
+// Take a dead letter off the queue and run it through the normal retry schedule again
+#[utoipa::path(
+    post,
+    path = "/api/v1/webhooks/dead-letters/{delivery_id}/redeliver",
+    tag = "webhooks",
+    params(("delivery_id" = String, Path)),
+    request_body(content = SignedAction, description = "Signed `webhook.redeliver` action naming `delivery_id`"),
+    responses(
+        (status = 202, description = "Queued for delivery"),
+        (status = 401, description = "Signature, action or freshness rejected", body = String),
+        (status = 403, description = "The action names another delivery", body = String),
+        (status = 404, description = "No dead letter with this id for the signer's webhooks", body = String),
+    )
+)]
+async fn redeliver(
+    State(state): State<AppState>,
+    Path(delivery_id): Path<String>,
+    Json(payload): Json<SignedAction>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    let claims: RedeliverClaims = verify_signed_action(&state.identity, &payload, ACTION_REDELIVER).await?;
+    if claims.delivery_id != delivery_id {
+        return Err((StatusCode::FORBIDDEN, "The signed action is for another delivery".to_string()));
+    }
+    let owner = payload.signer.to_string();
+    let mut data = state.webhooks.data.lock().await;
+    // Dead letters of removed webhooks are removed with them, so the webhook is always found
+    let index = data
+        .dead_letters
+        .iter()
+        .position(|d| {
+            d.delivery_id == delivery_id && data.webhooks.iter().any(|w| w.id == d.webhook_id && w.owner == owner)
+        })
+        .ok_or_else(|| (StatusCode::NOT_FOUND, format!("No dead letter {}", delivery_id)))?;
+    let dead_letter = data.dead_letters.remove(index);
+    drop(data);
+    let pending = PendingDelivery::new(&dead_letter.webhook_id, dead_letter.delivery_id, dead_letter.event);
+    state.webhooks.enqueue(pending).await;
+    Ok(StatusCode::ACCEPTED)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct TestDir(PathBuf);
+
+    impl Drop for TestDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn store(dir: &TestDir, max_attempts: u32) -> Arc<WebhookStore> {
+        let retry = RetryPolicy {
+            max_attempts,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(2),
+        };
+        // Test receivers listen on loopback
+        let targets = TargetPolicy { allow_private: true };
+        Arc::new(WebhookStore::load(dir.0.join("webhooks.json"), retry, targets).unwrap())
+    }
+
+    fn webhook(url: String) -> Webhook {
+        Webhook {
+            id: "hook-1".to_string(),
+            owner: "did:iota:0xa11ce".to_string(),
+            url,
+            secret: "0123456789abcdef".to_string(),
+            event_types: Vec::new(),
+            dids: Vec::new(),
+            created_at_ms: 0,
+        }
+    }
+
+    fn event() -> ChainEvent {
+        ChainEvent::from_node_event(&serde_json::json!({
+            "id": { "txDigest": "tx" },
+            "type": "0x1::identity::IdentityCreated",
+            "parsedJson": { "object_id": "0x0b1e", "did": "did:iota:0xa11ce" },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn signature_matches_known_vector() {
+        // HMAC-SHA256 over `1760000000.{"seq":1}`
+        assert_eq!(
+            sign_payload("whsec_0123456789abcdef", 1760000000, br#"{"seq":1}"#),
+            "sha256=f55d88b5c58edd04846faabb2d379a78d7d10d86078161de3b575f7dabd799ff"
+        );
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        let retry = RetryPolicy {
+            max_attempts: 6,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(5),
+        };
+        let delays: Vec<u64> = (1..=5).map(|attempt| retry.backoff(attempt).as_secs()).collect();
+        assert_eq!(delays, vec![1, 2, 4, 5, 5]);
+        assert_eq!(retry.backoff(u32::MAX), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn register_claims_reject_malformed_dids() {
+        let claims = |did: &str| {
+            serde_json::from_value::<RegisterClaims>(serde_json::json!({
+                "url": "https://example.com/hook",
+                "secret": "0123456789abcdef",
+                "dids": [did],
+            }))
+        };
+        assert_eq!(claims("did:iota:0xa11ce").unwrap().dids, vec!["did:iota:0xa11ce".parse::<WotDid>().unwrap()]);
+        assert!(claims("did:iota:0xA11CE").is_err());
+        assert!(claims("alice").is_err());
+    }
+
+    #[tokio::test]
+    async fn failing_deliveries_are_retried_then_dead_lettered() {
+        let dir = TestDir(std::env::temp_dir().join(format!("wot-webhooks-{}", Uuid::new_v4())));
+        let store = store(&dir, 3);
+        // Nothing listens on port 1
+        let hook = webhook("http://127.0.0.1:1/hook".to_string());
+        store.data.lock().await.webhooks.push(hook.clone());
+
+        let (_trigger, shutdown) = Shutdown::channel();
+        deliver(store.clone(), PendingDelivery::new(&hook.id, "hook-1-1".to_string(), event()), shutdown).await;
+
+        let data = store.data.lock().await;
+        let outcomes: Vec<DeliveryOutcome> = data.deliveries.iter().map(|d| d.outcome).collect();
+        assert_eq!(
+            outcomes,
+            vec![DeliveryOutcome::Retrying, DeliveryOutcome::Retrying, DeliveryOutcome::DeadLettered]
+        );
+        assert_eq!(data.dead_letters.len(), 1);
+        assert_eq!((data.dead_letters[0].attempts, data.dead_letters[0].delivery_id.as_str()), (3, "hook-1-1"));
+        assert!(data.pending.is_empty());
+    }
+
+    #[tokio::test]
+    async fn retries_pending_at_shutdown_resume_after_a_restart() {
+        let dir = TestDir(std::env::temp_dir().join(format!("wot-webhooks-{}", Uuid::new_v4())));
+        let retry = RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_secs(3600),
+            max_backoff: Duration::from_secs(3600),
+        };
+        let load = || WebhookStore::load(dir.0.join("webhooks.json"), retry.clone(), TargetPolicy { allow_private: true }).unwrap();
+        let store = Arc::new(load());
+        let hook = webhook("http://127.0.0.1:1/hook".to_string());
+        store.data.lock().await.webhooks.push(hook.clone());
+        store.enqueue(PendingDelivery::new(&hook.id, "hook-1-1".to_string(), event())).await;
+
+        // The first attempt fails, then shutdown interrupts the hour-long backoff
+        let (trigger, shutdown) = Shutdown::channel();
+        let delivery = tokio::spawn(deliver(store.clone(), store.resumable().await.remove(0), shutdown));
+        while store.data.lock().await.deliveries.is_empty() {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        trigger.send_replace(true);
+        tokio::time::timeout(Duration::from_secs(5), delivery).await.unwrap().unwrap();
+
+        let restarted = load();
+        let resumed = restarted.resumable().await;
+        assert_eq!(resumed.len(), 1);
+        assert_eq!((resumed[0].delivery_id.as_str(), resumed[0].attempts), ("hook-1-1", 1));
+        assert!(resumed[0].due_at_ms > now_ms());
+    }
+
+    #[tokio::test]
+    async fn removed_webhooks_are_not_resumed() {
+        let dir = TestDir(std::env::temp_dir().join(format!("wot-webhooks-{}", Uuid::new_v4())));
+        let store = store(&dir, 3);
+        store.enqueue(PendingDelivery::new("hook-gone", "hook-gone-1".to_string(), event())).await;
+        assert!(store.resumable().await.is_empty());
+        assert!(store.data.lock().await.pending.is_empty());
+    }
+
+    #[tokio::test]
+    async fn redirects_are_not_followed() {
+        let followed = Arc::new(AtomicUsize::new(0));
+        let counter = followed.clone();
+        let app = axum::Router::new()
+            .route("/hook", axum::routing::post(|| async { axum::response::Redirect::temporary("/elsewhere") }))
+            .route(
+                "/elsewhere",
+                axum::routing::post(move || async move {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    StatusCode::OK
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let dir = TestDir(std::env::temp_dir().join(format!("wot-webhooks-{}", Uuid::new_v4())));
+        let store = store(&dir, 1);
+        let hook = webhook(format!("http://{}/hook", addr));
+        store.data.lock().await.webhooks.push(hook.clone());
+        let (_trigger, shutdown) = Shutdown::channel();
+        deliver(store.clone(), PendingDelivery::new(&hook.id, "hook-1-1".to_string(), event()), shutdown).await;
+
+        assert_eq!(followed.load(Ordering::SeqCst), 0);
+        let data = store.data.lock().await;
+        assert_eq!(data.deliveries[0].status_code, Some(307));
+        assert_eq!(data.deliveries[0].outcome, DeliveryOutcome::DeadLettered);
+    }
+}
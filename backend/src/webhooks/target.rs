@@ -0,0 +1,177 @@
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::Url;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+// Whether deliveries may reach `ip`: globally routable unicast addresses only, so a webhook
+// cannot point the backend at its own network (loopback, private ranges, cloud metadata)
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(mapped) => is_public_v4(mapped),
+            None => is_public_v6(ip),
+        },
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        || a == 0
+        // Carrier-grade NAT, 100.64.0.0/10
+        || (a == 100 && (64..128).contains(&b))
+        // IETF protocol assignments, 192.0.0.0/24
+        || (a == 192 && b == 0 && c == 0)
+        // Benchmarking, 198.18.0.0/15
+        || (a == 198 && (b == 18 || b == 19))
+        // Reserved, 240.0.0.0/4
+        || a >= 240)
+}
+
+fn is_public_v6(ip: Ipv6Addr) -> bool {
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        || ip.is_unique_local()
+        || ip.is_unicast_link_local()
+        // Documentation, 2001:db8::/32
+        || (ip.segments()[0] == 0x2001 && ip.segments()[1] == 0xdb8))
+}
+
+// The host of `url` if it is an IP literal (IPv6 hosts come bracketed)
+fn literal_ip(url: &Url) -> Option<IpAddr> {
+    url.host_str()?.trim_start_matches('[').trim_end_matches(']').parse().ok()
+}
+
+// An IP-literal host never reaches the resolver, so it is checked on its own
+fn check_literal(url: &Url) -> Result<(), String> {
+    match literal_ip(url) {
+        Some(ip) if !is_public(ip) => Err(format!("{} is not a public address", ip)),
+        _ => Ok(()),
+    }
+}
+
+// Which webhook targets are allowed. Private targets are for local development only.
+#[derive(Debug, Clone, Copy)]
+pub struct TargetPolicy {
+    pub allow_private: bool,
+}
+
+impl TargetPolicy {
+    // Registration check: http(s), and a host that is, or only resolves to, public addresses
+    pub async fn check(&self, url: &Url) -> Result<(), String> {
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err("Webhook url must be http or https".to_string());
+        }
+        let host = url.host_str().ok_or("Webhook url has no host")?;
+        if self.allow_private {
+            return Ok(());
+        }
+        check_literal(url)?;
+        if literal_ip(url).is_none() {
+            let port = url.port_or_known_default().unwrap_or(443);
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+                .await
+                .map_err(|e| format!("{} does not resolve: {}", host, e))?
+                .collect();
+            if let Some(private) = addrs.iter().find(|addr| !is_public(addr.ip())) {
+                return Err(format!("{} resolves to {}, which is not a public address", host, private.ip()));
+            }
+        }
+        Ok(())
+    }
+
+    // Delivery-time check, for stored webhooks and for hosts re-pointed since registration
+    pub fn check_delivery(&self, url: &Url) -> Result<(), String> {
+        if self.allow_private {
+            return Ok(());
+        }
+        check_literal(url)
+    }
+
+    // Client for deliveries: no redirects, so a receiver cannot bounce a signed delivery to
+    // another host, and unless private targets are allowed, only public resolved addresses
+    pub fn client(&self) -> reqwest::Client {
+        let builder = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .timeout(Duration::from_secs(10));
+        let builder = if self.allow_private { builder } else { builder.dns_resolver(Arc::new(PublicResolver)) };
+        builder.build().expect("webhook HTTP client builds")
+    }
+}
+
+// Drops non-public addresses from every lookup, so a hostname that resolves, or is later
+// re-pointed, to an internal address is not reached
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let host = name.as_str();
+            let addrs: Vec<SocketAddr> =
+                tokio::net::lookup_host((host, 0)).await?.filter(|addr| is_public(addr.ip())).collect();
+            if addrs.is_empty() {
+                return Err(format!("{} has no public address", host).into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_globally_routable_addresses_are_public() {
+        for ip in ["8.8.8.8", "1.1.1.1", "2606:4700:4700::1111", "::ffff:8.8.8.8"] {
+            assert!(is_public(ip.parse().unwrap()), "{} should be public", ip);
+        }
+        for ip in [
+            "127.0.0.1",
+            "10.0.0.1",
+            "172.16.5.4",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "255.255.255.255",
+            "::1",
+            "::",
+            "fe80::1",
+            "fd00::1",
+            "::ffff:127.0.0.1",
+            "::ffff:169.254.169.254",
+        ] {
+            assert!(!is_public(ip.parse().unwrap()), "{} should not be public", ip);
+        }
+    }
+
+    #[tokio::test]
+    async fn registration_rejects_internal_targets() {
+        let policy = TargetPolicy { allow_private: false };
+        for url in [
+            "http://169.254.169.254/latest/meta-data/",
+            "http://127.0.0.1:8080/hook",
+            "http://[::1]/hook",
+            "http://10.1.2.3/hook",
+            "http://localhost:9000/hook",
+            "ftp://8.8.8.8/hook",
+        ] {
+            assert!(policy.check(&Url::parse(url).unwrap()).await.is_err(), "{} should be rejected", url);
+        }
+        assert!(policy.check(&Url::parse("https://8.8.8.8/hook").unwrap()).await.is_ok());
+        assert!(policy.check_delivery(&Url::parse("http://192.168.0.10/hook").unwrap()).is_err());
+
+        let development = TargetPolicy { allow_private: true };
+        assert!(development.check(&Url::parse("http://127.0.0.1:8080/hook").unwrap()).await.is_ok());
+    }
+}
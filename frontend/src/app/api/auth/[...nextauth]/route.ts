@@ -25,7 +25,7 @@ const authOptions: AuthOptions = {
         // Optional: if the DID has multiple verification methods, client might need to specify which one was used.
         // publicKeyId: { label: "Public Key ID", type: "hidden" }, 
       },
-      authorize: async (credentials) => {
+      authorize: async (credentials, req) => {
         if (!credentials?.email || !credentials?.did || !credentials?.challenge || !credentials?.signedChallenge) {
           console.error("[NextAuth] Missing credentials for challenge-response authentication", { email: !!credentials?.email, did: !!credentials?.did, challenge: !!credentials?.challenge, signedChallenge: !!credentials?.signedChallenge });
           // It's often better to throw an error that NextAuth can catch and display or redirect.
@@ -44,10 +44,15 @@ const authOptions: AuthOptions = {
             return null;
           }
 
+          const forwardedFor = req?.headers?.['x-forwarded-for'] as string | undefined;
           // Call the identity-service to verify the signed challenge
           const response = await fetch(`${identityServiceUrl}/api/v1/identity/verify-signature`, {
             method: 'POST',
-            headers: { 'Content-Type': 'application/json' },
+            headers: {
+              'Content-Type': 'application/json',
+              // The identity service rate limits and locks out per client address and DID
+              ...(forwardedFor ? { 'X-Forwarded-For': forwardedFor } : {}),
+            },
             body: JSON.stringify({
               did,
               challenge,
@@ -15,12 +15,24 @@ export async function POST(request: NextRequest) {
       return NextResponse.json({ error: 'Identity service configuration error' }, { status: 500 });
     }
 
+    const forwardedFor = request.headers.get('x-forwarded-for') ?? request.ip;
     const serviceResponse = await fetch(`${identityServiceUrl}/api/v1/identity/initiate-challenge`, {
       method: 'POST',
-      headers: { 'Content-Type': 'application/json' },
+      headers: {
+        'Content-Type': 'application/json',
+        // The identity service rate limits per client address, trusting this header from us
+        ...(forwardedFor ? { 'X-Forwarded-For': forwardedFor } : {}),
+      },
       body: JSON.stringify({ email }),
     });
 
+    if (serviceResponse.status === 429) {
+      return NextResponse.json(
+        { error: 'Too many requests, please try again later' },
+        { status: 429, headers: { 'Retry-After': serviceResponse.headers.get('Retry-After') ?? '60' } }
+      );
+    }
+
     const responseData = await serviceResponse.json();
 
     if (!serviceResponse.ok) {
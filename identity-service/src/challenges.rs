@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::telemetry;
+
+// Challenges outstanding at once; `initiate-challenge` is refused beyond this
+const MAX_OUTSTANDING_CHALLENGES: usize = 100_000;
+
+struct Issued {
+    did: String,
+    expires: Instant,
+}
+
+// Login challenges handed out by `initiate-challenge`. Each one is good for a single
+// `verify-signature` by the DID it was issued for, until it expires. State is per instance
+// and lost on restart, like the rate limiter's.
+pub struct ChallengeStore {
+    ttl: Duration,
+    issued: Mutex<HashMap<String, Issued>>,
+}
+
+impl ChallengeStore {
+    pub fn new(ttl: Duration) -> Self {
+        Self { ttl, issued: Mutex::new(HashMap::new()) }
+    }
+
+    // A fresh challenge for `did`; `None` while too many are outstanding
+    pub fn issue(&self, did: &str) -> Option<String> {
+        self.issue_at(did, Instant::now())
+    }
+
+    fn issue_at(&self, did: &str, now: Instant) -> Option<String> {
+        let mut issued = self.issued.lock().expect("challenge store lock");
+        issued.retain(|_, c| c.expires > now);
+        if issued.len() >= MAX_OUTSTANDING_CHALLENGES {
+            telemetry::challenge_store_size(issued.len());
+            return None;
+        }
+        let challenge = Uuid::new_v4().to_string();
+        issued.insert(challenge.clone(), Issued { did: did.to_string(), expires: now + self.ttl });
+        telemetry::challenge_store_size(issued.len());
+        Some(challenge)
+    }
+
+    // Use up `challenge`; true when it was issued for `did` and has not expired
+    pub fn redeem(&self, challenge: &str, did: &str) -> bool {
+        self.redeem_at(challenge, did, Instant::now())
+    }
+
+    fn redeem_at(&self, challenge: &str, did: &str, now: Instant) -> bool {
+        let mut issued = self.issued.lock().expect("challenge store lock");
+        let redeemed = match issued.get(challenge) {
+            Some(c) if c.expires <= now => {
+                issued.remove(challenge);
+                false
+            }
+            Some(c) if c.did == did => {
+                issued.remove(challenge);
+                true
+            }
+            _ => false,
+        };
+        telemetry::challenge_store_size(issued.len());
+        redeemed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DID: &str = "did:iota:tst:0x00000000000000000000000000000000000000000000000000000000000000a1";
+
+    #[test]
+    fn challenge_is_redeemed_once() {
+        let store = ChallengeStore::new(Duration::from_secs(300));
+        let now = Instant::now();
+        let challenge = store.issue_at(DID, now).unwrap();
+        assert!(!store.redeem_at("not-issued", DID, now));
+        assert!(store.redeem_at(&challenge, DID, now));
+        assert!(!store.redeem_at(&challenge, DID, now));
+    }
+
+    #[test]
+    fn challenge_is_bound_to_its_did() {
+        let store = ChallengeStore::new(Duration::from_secs(300));
+        let now = Instant::now();
+        let challenge = store.issue_at(DID, now).unwrap();
+        assert!(!store.redeem_at(&challenge, "did:iota:tst:0x01", now));
+        // A wrong DID does not use it up
+        assert!(store.redeem_at(&challenge, DID, now));
+    }
+
+    #[test]
+    fn expired_challenges_are_rejected_and_dropped() {
+        let store = ChallengeStore::new(Duration::from_secs(300));
+        let now = Instant::now();
+        let stale = store.issue_at(DID, now).unwrap();
+        assert!(!store.redeem_at(&stale, DID, now + Duration::from_secs(300)));
+
+        let expiring = store.issue_at(DID, now).unwrap();
+        store.issue_at(DID, now + Duration::from_secs(301)).unwrap();
+        let issued = store.issued.lock().unwrap();
+        assert_eq!(issued.len(), 1);
+        assert!(!issued.contains_key(&expiring));
+    }
+}
@@ -1,30 +1,99 @@
-use std::env;
 use anyhow::Context;
+use wot_config::{Network, Settings, SharedConfig};
+
+use crate::ratelimit::RateLimitConfig;
 
 #[derive(Clone, Debug)]
 pub struct AppConfig {
-    pub iota_api_endpoint: String,
+    pub network: Network,
+    pub iota_node_url: String,
     pub iota_identity_pkg_id: String,
+    // Shared `identity::DidRegistry` object; recovery needs it to find Identity objects by DID
+    pub iota_identity_registry_id: Option<String>,
     pub service_port: u16,
+    pub recovery_store_path: String,
+    pub recovery_min_time_lock_secs: u64,
+    pub rotation_store_path: String,
+    pub key_rotation_grace_period_secs: u64,
+    // Upper bound on the grace period a rotation request may ask for
+    pub key_rotation_max_grace_period_secs: u64,
+    pub require_hybrid_signatures: bool,
+    // How long resolved DID documents are reused; 0 resolves every time
+    pub did_cache_ttl_secs: u64,
+    // How long a login challenge from `initiate-challenge` can be redeemed
+    pub challenge_ttl_secs: u64,
+    pub issuer_ml_dsa_kid: Option<String>,
+    pub issuer_ml_dsa_seed: Option<String>,
+    pub issuer_ed25519_kid: Option<String>,
+    pub issuer_ed25519_secret: Option<String>,
+    pub issuer_operators: Vec<String>,
+    pub health_probe_interval_secs: u64,
+    pub health_probe_timeout_ms: u64,
+    pub shutdown_drain_timeout_secs: u64,
+    pub rate_limits: RateLimitConfig,
+    pub email_hash_secret: Option<String>,
+    // PEM certificate chain and key; plain HTTP when unset
+    pub tls_cert_path: Option<String>,
+    pub tls_key_path: Option<String>,
+    // CA whose client certificates are required (mutual TLS)
+    pub tls_client_ca_path: Option<String>,
 }
 
 impl AppConfig {
-    pub fn load() -> Result<Self, anyhow::Error> {
-        dotenvy::dotenv().ok(); // Load .env file if present
-
-        let iota_api_endpoint = env::var("API_ENDPOINT")
-            .with_context(|| "API_ENDPOINT must be set")?;
-        let iota_identity_pkg_id = env::var("IOTA_IDENTITY_PKG_ID")
+    // Read from the layered settings (config file, environment, command line); see `wot_config`
+    pub fn from_settings(settings: &Settings) -> Result<Self, anyhow::Error> {
+        let shared = SharedConfig::from_settings(settings)?;
+        let iota_identity_pkg_id = settings
+            .object_id("IOTA_IDENTITY_PKG_ID")?
             .with_context(|| "IOTA_IDENTITY_PKG_ID must be set")?;
-        let service_port_str = env::var("PORT").unwrap_or_else(|_| "8081".to_string());
-        let service_port = service_port_str
-            .parse::<u16>()
-            .with_context(|| format!("Invalid PORT value: {}", service_port_str))?;
+        let health_probe_interval_secs = settings.parse_or("HEALTH_PROBE_INTERVAL_SECS", 10)?;
+        if health_probe_interval_secs == 0 {
+            anyhow::bail!("Invalid HEALTH_PROBE_INTERVAL_SECS value: 0");
+        }
+
+        let key_rotation_grace_period_secs = settings.parse_or("KEY_ROTATION_GRACE_PERIOD_SECS", 604800)?;
+        let key_rotation_max_grace_period_secs = settings.parse_or("KEY_ROTATION_MAX_GRACE_PERIOD_SECS", 2592000)?;
+        if key_rotation_grace_period_secs > key_rotation_max_grace_period_secs {
+            anyhow::bail!("KEY_ROTATION_GRACE_PERIOD_SECS must not exceed KEY_ROTATION_MAX_GRACE_PERIOD_SECS");
+        }
+
+        let tls_cert_path = settings.var("IDENTITY_SERVICE_TLS_CERT_PATH");
+        let tls_key_path = settings.var("IDENTITY_SERVICE_TLS_KEY_PATH");
+        let tls_client_ca_path = settings.var("IDENTITY_SERVICE_TLS_CLIENT_CA_PATH");
+        if tls_cert_path.is_some() != tls_key_path.is_some() {
+            anyhow::bail!("IDENTITY_SERVICE_TLS_CERT_PATH and IDENTITY_SERVICE_TLS_KEY_PATH must be set together");
+        }
+        if tls_client_ca_path.is_some() && tls_cert_path.is_none() {
+            anyhow::bail!("IDENTITY_SERVICE_TLS_CLIENT_CA_PATH requires IDENTITY_SERVICE_TLS_CERT_PATH");
+        }
 
         Ok(Self {
-            iota_api_endpoint,
+            network: shared.network,
+            iota_node_url: shared.iota_node_url.to_string(),
             iota_identity_pkg_id,
-            service_port,
+            iota_identity_registry_id: settings.object_id("IOTA_IDENTITY_REGISTRY_ID")?,
+            service_port: shared.identity_service_port,
+            recovery_store_path: settings.string_or("RECOVERY_STORE_PATH", "./data/recovery.json"),
+            recovery_min_time_lock_secs: settings.parse_or("RECOVERY_MIN_TIME_LOCK_SECS", 86400)?,
+            rotation_store_path: settings.string_or("ROTATION_STORE_PATH", "./data/rotations.json"),
+            key_rotation_grace_period_secs,
+            key_rotation_max_grace_period_secs,
+            require_hybrid_signatures: settings.parse_or("REQUIRE_HYBRID_SIGNATURES", false)?,
+            did_cache_ttl_secs: settings.parse_or("DID_CACHE_TTL_SECS", 30)?,
+            challenge_ttl_secs: settings.parse_or("CHALLENGE_TTL_SECS", 300)?,
+            issuer_ml_dsa_kid: settings.var("ISSUER_ML_DSA_KID"),
+            issuer_ml_dsa_seed: settings.var("ISSUER_ML_DSA_SEED"),
+            issuer_ed25519_kid: settings.var("ISSUER_ED25519_KID"),
+            issuer_ed25519_secret: settings.var("ISSUER_ED25519_SECRET"),
+            issuer_operators: settings.list("ISSUER_OPERATORS"),
+            health_probe_interval_secs,
+            health_probe_timeout_ms: settings.parse_or("HEALTH_PROBE_TIMEOUT_MS", 3000)?,
+            shutdown_drain_timeout_secs: settings.parse_or("SHUTDOWN_DRAIN_TIMEOUT_SECS", 30)?,
+            rate_limits: RateLimitConfig::from_settings(settings)?,
+            email_hash_secret: settings.var("EMAIL_HASH_SECRET"),
+            tls_cert_path,
+            tls_key_path,
+            tls_client_ca_path,
         })
     }
 }
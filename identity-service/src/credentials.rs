@@ -0,0 +1,425 @@
+use anyhow::Context;
+use axum::{extract::State, http::StatusCode, Json};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD as BASE64URL, Engine};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::sync::Arc;
+use uuid::Uuid;
+use wot_id_types::credentials::{IssueCredentialResponse, VerifyCredentialRequest, VerifyCredentialResponse};
+use wot_id_types::identity::SignedAction;
+use wot_id_types::{ErrorCode, WotDid};
+
+use identity_iota::iota::IotaDocument;
+
+use crate::config::AppConfig;
+use crate::handlers::{parse_iota_did, resolve_document, signature_policy, verify_signed_action, wot_did};
+use crate::rotation::{self, RotationRecord};
+use crate::signatures::{self, JwsSigner, SignaturePolicy};
+use crate::state::AppState;
+use crate::store::now_secs;
+use crate::telemetry;
+
+const VC_CONTEXT: &str = "https://www.w3.org/2018/credentials/v1";
+const VC_TYPE: &str = "VerifiableCredential";
+
+// The issuer DID this service signs credentials as. Credentials always carry an ML-DSA-65
+// proof; with an Ed25519 key configured as well they carry a hybrid proof.
+pub struct CredentialIssuer {
+    pub did: WotDid,
+    signers: Vec<JwsSigner>,
+}
+
+fn secret_bytes(value: &str, name: &str) -> Result<[u8; 32], anyhow::Error> {
+    BASE64URL
+        .decode(value)
+        .with_context(|| format!("{} must be base64url", name))?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("{} must be 32 bytes", name))
+}
+
+impl CredentialIssuer {
+    pub fn from_config(config: &AppConfig) -> Result<Option<Self>, anyhow::Error> {
+        let (Some(kid), Some(seed)) = (&config.issuer_ml_dsa_kid, &config.issuer_ml_dsa_seed) else {
+            return Ok(None);
+        };
+        let did: WotDid = kid
+            .split('#')
+            .next()
+            .unwrap_or_default()
+            .parse()
+            .context("ISSUER_ML_DSA_KID must be a method of a did:iota DID")?;
+        let mut signers = vec![JwsSigner::ml_dsa_65(kid.clone(), secret_bytes(seed, "ISSUER_ML_DSA_SEED")?)];
+        if let (Some(kid), Some(secret)) = (&config.issuer_ed25519_kid, &config.issuer_ed25519_secret) {
+            if !kid.starts_with(&format!("{}#", did)) {
+                anyhow::bail!("ISSUER_ED25519_KID must be a method of {}", did);
+            }
+            signers.push(JwsSigner::ed25519(kid.clone(), secret_bytes(secret, "ISSUER_ED25519_SECRET")?));
+        }
+        for signer in &signers {
+            tracing::info!(kid = %signer.kid, jwk = %signer.public_jwk(), "Credential issuer key loaded.");
+        }
+        Ok(Some(Self { did, signers }))
+    }
+
+    fn is_hybrid(&self) -> bool {
+        self.signers.len() > 1
+    }
+}
+
+#[derive(Deserialize)]
+struct IssueCredentialClaims {
+    subject: WotDid,
+    // Credential types besides `VerifiableCredential`, e.g. `VerifiedHuman`
+    types: Vec<String>,
+    // Extra `credentialSubject` properties
+    #[serde(default)]
+    claims: Map<String, Value>,
+    // Unix seconds; must lie in the future
+    expires_at: Option<u64>,
+}
+
+// Issue a credential with a PQC proof. The request is a `credential.issue` action signed by
+// one of the configured issuer operators. An ML-DSA-only issuer returns a compact VC-JWT. A
+// hybrid issuer returns the same JWT claims as a JWS in general JSON serialization, which is
+// not a VC-JWT: generic JWT libraries cannot parse it, so it is verified here or by another
+// verifier that accepts JSON-serialized JWS.
+#[utoipa::path(
+    post,
+    path = "/api/v1/credentials/issue",
+    tag = "credentials",
+    request_body(content = SignedAction, description = "`credential.issue` action"),
+    responses(
+        (status = 200, description = "A compact VC-JWT, or for a hybrid issuer a general JSON JWS carrying the JWT claims", body = IssueCredentialResponse),
+        (status = 400, description = "Malformed subject DID or claims, or `expires_at` not in the future", body = String),
+        (status = 401, description = "Bad signature, or the action is expired", body = String),
+        (status = 403, description = "The signer is not an issuer operator", body = String),
+        (status = 503, description = "Credential issuance is not configured", body = String),
+    )
+)]
+pub async fn issue_credential_handler(
+    State(app_state): State<Arc<AppState>>,
+    Json(payload): Json<SignedAction>,
+) -> Result<Json<IssueCredentialResponse>, (StatusCode, String)> {
+    let issuer = app_state
+        .issuer
+        .as_ref()
+        .ok_or((StatusCode::SERVICE_UNAVAILABLE, "Credential issuance is not configured".to_string()))?;
+    if !app_state.config.issuer_operators.iter().any(|operator| *operator == payload.signer) {
+        return Err((StatusCode::FORBIDDEN, format!("{} may not issue credentials", payload.signer)));
+    }
+    let claims: IssueCredentialClaims =
+        verify_signed_action(&app_state, &payload.signer, &payload.jws, "credential.issue").await?;
+    let subject = claims.subject.clone();
+    let credential_id = format!("urn:uuid:{}", Uuid::new_v4());
+    let issued = issue(issuer, claims, &credential_id, signature_policy(&app_state), now_secs())?;
+    tracing::info!(
+        target: "identity_service::audit",
+        issuer = %issuer.did,
+        subject = %subject,
+        credential_id = %credential_id,
+        operator = %payload.signer,
+        "Credential issued."
+    );
+    Ok(Json(issued))
+}
+
+// Sign the credential `claims` describe with every issuer key
+fn issue(
+    issuer: &CredentialIssuer,
+    claims: IssueCredentialClaims,
+    credential_id: &str,
+    policy: SignaturePolicy,
+    now: u64,
+) -> Result<IssueCredentialResponse, (StatusCode, String)> {
+    if policy.require_hybrid && !issuer.is_hybrid() {
+        return Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Policy requires hybrid signatures but no Ed25519 issuer key is configured".to_string(),
+        ));
+    }
+    if claims.expires_at.is_some_and(|expires_at| expires_at <= now) {
+        return Err((StatusCode::BAD_REQUEST, "expires_at must be in the future".to_string()));
+    }
+
+    let mut credential_subject = claims.claims;
+    credential_subject.insert("id".to_string(), Value::String(claims.subject.to_string()));
+    let mut types = vec![VC_TYPE.to_string()];
+    types.extend(claims.types.into_iter().filter(|t| t != VC_TYPE));
+
+    let mut jwt_claims = json!({
+        "iss": issuer.did,
+        "sub": claims.subject,
+        "nbf": now,
+        "jti": credential_id,
+        "vc": {
+            "@context": [VC_CONTEXT],
+            "type": types,
+            "credentialSubject": credential_subject,
+        },
+    });
+    if let Some(expires_at) = claims.expires_at {
+        jwt_claims["exp"] = json!(expires_at);
+    }
+
+    let signers: Vec<&JwsSigner> = issuer.signers.iter().collect();
+    Ok(IssueCredentialResponse {
+        credential: signatures::sign_jws(jwt_claims.to_string().as_bytes(), "JWT", &signers),
+        issuer: issuer.did.clone(),
+        algorithms: signers.iter().map(|s| s.alg().to_string()).collect(),
+        hybrid: issuer.is_hybrid(),
+    })
+}
+
+fn rejected(code: ErrorCode, reason: String) -> VerifyCredentialResponse {
+    VerifyCredentialResponse { code: Some(code), reason: Some(reason), ..Default::default() }
+}
+
+fn record(outcome: Result<(), ErrorCode>) {
+    telemetry::verification("credential", outcome);
+}
+
+// Verify a credential signed with EdDSA, ML-DSA (compact VC-JWT) or both (general JSON JWS)
+// against its issuer's DID document, applying the hybrid-signature policy
+#[utoipa::path(
+    post,
+    path = "/api/v1/credentials/verify",
+    tag = "credentials",
+    request_body = VerifyCredentialRequest,
+    responses(
+        (status = 200, description = "`isValid` is false with a `code` and `reason` for bad, expired or retired-key credentials, or a non-integer `nbf` or `exp`", body = VerifyCredentialResponse),
+        (status = 400, description = "Malformed credential or issuer DID", body = String),
+        (status = 500, description = "Issuer DID resolution failed", body = String),
+    )
+)]
+pub async fn verify_credential_handler(
+    State(app_state): State<Arc<AppState>>,
+    Json(payload): Json<VerifyCredentialRequest>,
+) -> Result<Json<VerifyCredentialResponse>, (StatusCode, String)> {
+    let unverified: Value = signatures::unverified_claims(&payload.credential)
+        .and_then(|claims| serde_json::from_slice(&claims).map_err(|e| e.to_string()))
+        .map_err(|e| {
+            record(Err(ErrorCode::MalformedClaims));
+            (StatusCode::BAD_REQUEST, format!("Invalid credential: {}", e))
+        })?;
+    let issuer = unverified.get("iss").and_then(Value::as_str).ok_or_else(|| {
+        record(Err(ErrorCode::MalformedClaims));
+        (StatusCode::BAD_REQUEST, "Credential has no issuer".to_string())
+    })?;
+    let issuer_did = parse_iota_did(issuer).inspect_err(|_| record(Err(ErrorCode::InvalidDid)))?;
+    let document = resolve_document(&app_state, &issuer_did)
+        .await
+        .inspect_err(|_| record(Err(ErrorCode::ResolutionFailed)))?;
+    let rotations = app_state.rotations.lock().await.get(issuer).cloned().unwrap_or_default();
+    let policy = signature_policy(&app_state);
+    verify(&document, &payload.credential, policy, &rotations, now_secs()).map(Json)
+}
+
+// Check a credential against its issuer's resolved `document` and key `rotations`
+fn verify(
+    document: &IotaDocument,
+    credential: &str,
+    policy: SignaturePolicy,
+    rotations: &RotationRecord,
+    now: u64,
+) -> Result<VerifyCredentialResponse, (StatusCode, String)> {
+    let reject = |code, reason| {
+        record(Err(code));
+        Ok(rejected(code, reason))
+    };
+    let verified = match signatures::verify_jws(document, credential, policy) {
+        Ok(verified) => verified,
+        Err(e) => {
+            tracing::warn!(issuer = %document.id(), error = %e, "Credential failed verification.");
+            return reject(ErrorCode::BadSignature, e);
+        }
+    };
+    if let Some(kid) = rotation::retired_in(rotations, &verified, now) {
+        return reject(ErrorCode::RetiredKey, format!("Signed with retired method '{}'", kid));
+    }
+
+    let claims: Value = serde_json::from_slice(&verified.claims).map_err(|e| {
+        record(Err(ErrorCode::MalformedClaims));
+        (StatusCode::BAD_REQUEST, format!("Invalid credential claims: {}", e))
+    })?;
+    // A present but unreadable time would otherwise never take effect
+    let time = |name: &str| match claims.get(name) {
+        None => Ok(None),
+        Some(value) => value.as_u64().map(Some).ok_or(format!("`{}` must be a whole number of seconds", name)),
+    };
+    let (nbf, exp) = match (time("nbf"), time("exp")) {
+        (Ok(nbf), Ok(exp)) => (nbf, exp),
+        (Err(reason), _) | (_, Err(reason)) => return reject(ErrorCode::MalformedClaims, reason),
+    };
+    if nbf.is_some_and(|nbf| nbf > now) {
+        return reject(ErrorCode::NotYetValid, "Credential is not yet valid".to_string());
+    }
+    if exp.is_some_and(|exp| exp <= now) {
+        return reject(ErrorCode::Expired, "Credential has expired".to_string());
+    }
+
+    record(Ok(()));
+    Ok(VerifyCredentialResponse {
+        is_valid: true,
+        issuer: Some(wot_did(document.id())),
+        subject: claims.get("sub").and_then(Value::as_str).map(str::to_string),
+        types: claims
+            .pointer("/vc/type")
+            .and_then(Value::as_array)
+            .map(|types| types.iter().filter_map(Value::as_str).map(str::to_string).collect())
+            .unwrap_or_default(),
+        algorithms: verified.algorithms(),
+        hybrid: verified.is_hybrid(),
+        code: None,
+        reason: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use identity_iota::iota::NetworkName;
+    use identity_iota::verification::jwk::Jwk;
+    use identity_iota::verification::{MethodScope, VerificationMethod};
+    use wot_id_types::rotation::KeyRotation;
+
+    const NOW: u64 = 1_700_000_000;
+    const SUBJECT: &str = "did:iota:tst:0x5b";
+
+    // The issuer's DID document with its keys published, and the issuer itself
+    fn issuer(hybrid: bool) -> (IotaDocument, CredentialIssuer) {
+        let mut document = IotaDocument::new(&NetworkName::try_from("tst").unwrap());
+        let mut signers = vec![JwsSigner::ml_dsa_65(format!("{}#ml-dsa", document.id()), [2; 32])];
+        if hybrid {
+            signers.push(JwsSigner::ed25519(format!("{}#ed25519", document.id()), [1; 32]));
+        }
+        for signer in &signers {
+            let (_, fragment) = signer.kid.split_once('#').unwrap();
+            let jwk: Jwk = serde_json::from_value(signer.public_jwk()).unwrap();
+            let method = VerificationMethod::new_from_jwk(document.id().clone(), jwk, Some(fragment)).unwrap();
+            document.insert_method(method, MethodScope::VerificationMethod).unwrap();
+        }
+        let issuer = CredentialIssuer { did: wot_did(document.id()), signers };
+        (document, issuer)
+    }
+
+    fn claims(expires_at: Option<u64>) -> IssueCredentialClaims {
+        IssueCredentialClaims {
+            subject: SUBJECT.parse().unwrap(),
+            types: vec!["VerifiedHuman".to_string()],
+            claims: Map::new(),
+            expires_at,
+        }
+    }
+
+    fn lenient() -> SignaturePolicy {
+        SignaturePolicy { require_hybrid: false }
+    }
+
+    fn hybrid() -> SignaturePolicy {
+        SignaturePolicy { require_hybrid: true }
+    }
+
+    fn issued(issuer: &CredentialIssuer, expires_at: Option<u64>) -> String {
+        issue(issuer, claims(expires_at), "urn:uuid:1", lenient(), NOW).unwrap().credential
+    }
+
+    // Sign arbitrary JWT claims with the issuer's keys, bypassing `issue`'s checks
+    fn signed(issuer: &CredentialIssuer, claims: Value) -> String {
+        let signers: Vec<&JwsSigner> = issuer.signers.iter().collect();
+        signatures::sign_jws(claims.to_string().as_bytes(), "JWT", &signers)
+    }
+
+    fn rejection(response: VerifyCredentialResponse) -> ErrorCode {
+        assert!(!response.is_valid);
+        response.code.unwrap()
+    }
+
+    #[test]
+    fn issued_credential_verifies_until_it_expires() {
+        let (document, issuer) = issuer(false);
+        let response = issue(&issuer, claims(Some(NOW + 60)), "urn:uuid:1", lenient(), NOW).unwrap();
+        assert_eq!((response.issuer.as_str(), response.hybrid), (issuer.did.as_str(), false));
+        assert_eq!(response.algorithms, vec![signatures::ALG_ML_DSA_65.to_string()]);
+
+        let verified = verify(&document, &response.credential, lenient(), &RotationRecord::default(), NOW).unwrap();
+        assert!(verified.is_valid);
+        assert_eq!(verified.issuer, Some(issuer.did.clone()));
+        assert_eq!(verified.subject.as_deref(), Some(SUBJECT));
+        assert_eq!(verified.types, vec!["VerifiableCredential", "VerifiedHuman"]);
+
+        let later = verify(&document, &response.credential, lenient(), &RotationRecord::default(), NOW + 60).unwrap();
+        assert_eq!(rejection(later), ErrorCode::Expired);
+    }
+
+    #[test]
+    fn expiry_must_be_in_the_future() {
+        let (_, issuer) = issuer(false);
+        for expires_at in [0, NOW - 1, NOW] {
+            let err = issue(&issuer, claims(Some(expires_at)), "urn:uuid:1", lenient(), NOW).unwrap_err();
+            assert_eq!(err.0, StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[test]
+    fn subject_must_be_an_iota_did() {
+        let action = json!({ "subject": "did:web:example.com", "types": [] });
+        assert!(serde_json::from_value::<IssueCredentialClaims>(action).is_err());
+    }
+
+    #[test]
+    fn hybrid_policy_needs_a_hybrid_issuer() {
+        let (document, issuer) = self::issuer(false);
+        let err = issue(&issuer, claims(None), "urn:uuid:1", hybrid(), NOW).unwrap_err();
+        assert_eq!(err.0, StatusCode::SERVICE_UNAVAILABLE);
+        // Nor is an ML-DSA-only credential accepted under it
+        let response = verify(&document, &issued(&issuer, None), hybrid(), &RotationRecord::default(), NOW).unwrap();
+        assert_eq!(rejection(response), ErrorCode::BadSignature);
+
+        let (document, issuer) = self::issuer(true);
+        let response = issue(&issuer, claims(None), "urn:uuid:1", hybrid(), NOW).unwrap();
+        assert!(response.hybrid);
+        let verified = verify(&document, &response.credential, hybrid(), &RotationRecord::default(), NOW).unwrap();
+        assert!(verified.is_valid && verified.hybrid);
+    }
+
+    #[test]
+    fn times_that_are_not_whole_seconds_are_rejected() {
+        let (document, issuer) = issuer(false);
+        for (name, value) in [("exp", json!("never")), ("exp", json!(-1)), ("exp", json!(NOW as f64 + 0.5)), ("nbf", json!(null))] {
+            let mut claims = json!({ "iss": issuer.did, "sub": SUBJECT });
+            claims[name] = value;
+            let response = verify(&document, &signed(&issuer, claims), lenient(), &RotationRecord::default(), NOW).unwrap();
+            assert_eq!(rejection(response), ErrorCode::MalformedClaims, "{}", name);
+        }
+        let early = json!({ "iss": issuer.did, "sub": SUBJECT, "nbf": NOW + 1 });
+        let response = verify(&document, &signed(&issuer, early), lenient(), &RotationRecord::default(), NOW).unwrap();
+        assert_eq!(rejection(response), ErrorCode::NotYetValid);
+    }
+
+    #[test]
+    fn tampered_or_retired_credentials_are_rejected() {
+        let (document, issuer) = issuer(false);
+        let credential = issued(&issuer, None);
+
+        // Flip one signature character
+        let mut tampered = credential.clone().into_bytes();
+        let at = credential.rfind('.').unwrap() + 10;
+        tampered[at] = if tampered[at] == b'A' { b'B' } else { b'A' };
+        let tampered = String::from_utf8(tampered).unwrap();
+        let response = verify(&document, &tampered, lenient(), &RotationRecord::default(), NOW).unwrap();
+        assert_eq!(rejection(response), ErrorCode::BadSignature);
+
+        let rotations = RotationRecord {
+            rotations: vec![KeyRotation {
+                id: "r1".to_string(),
+                old_method: "ml-dsa".to_string(),
+                new_method: "ml-dsa-2".to_string(),
+                started_at: NOW - 20,
+                retire_at: NOW - 10,
+            }],
+            versions: Vec::new(),
+        };
+        let response = verify(&document, &credential, lenient(), &rotations, NOW).unwrap();
+        assert_eq!(rejection(response), ErrorCode::RetiredKey);
+    }
+}
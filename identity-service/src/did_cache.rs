@@ -0,0 +1,92 @@
+use identity_iota::iota::IotaDocument;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::telemetry;
+
+// Documents kept at once; expired entries are dropped first, then new ones are not cached
+const MAX_CACHED_DOCUMENTS: usize = 10_000;
+
+struct Cached {
+    document: IotaDocument,
+    expires: Instant,
+}
+
+// Resolved DID documents, kept for `ttl` so repeated verifications for one DID do not each
+// hit the node. A method removed on-chain keeps verifying for up to `ttl`; a TTL of zero
+// turns the cache off.
+pub struct DidCache {
+    ttl: Duration,
+    documents: Mutex<HashMap<String, Cached>>,
+}
+
+impl DidCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self { ttl, documents: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn get(&self, did: &str) -> Option<IotaDocument> {
+        self.get_at(did, Instant::now())
+    }
+
+    fn get_at(&self, did: &str, now: Instant) -> Option<IotaDocument> {
+        if self.ttl.is_zero() {
+            return None;
+        }
+        let documents = self.documents.lock().expect("DID cache lock");
+        let document = documents.get(did).filter(|c| c.expires > now).map(|c| c.document.clone());
+        telemetry::did_cache_lookup(document.is_some());
+        document
+    }
+
+    pub fn insert(&self, did: &str, document: IotaDocument) {
+        self.insert_at(did, document, Instant::now());
+    }
+
+    fn insert_at(&self, did: &str, document: IotaDocument, now: Instant) {
+        if self.ttl.is_zero() {
+            return;
+        }
+        let mut documents = self.documents.lock().expect("DID cache lock");
+        if documents.len() >= MAX_CACHED_DOCUMENTS {
+            documents.retain(|_, c| c.expires > now);
+        }
+        if documents.len() < MAX_CACHED_DOCUMENTS || documents.contains_key(did) {
+            documents.insert(did.to_string(), Cached { document, expires: now + self.ttl });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use identity_iota::iota::NetworkName;
+
+    fn document() -> IotaDocument {
+        IotaDocument::new(&NetworkName::try_from("tst").unwrap())
+    }
+
+    #[test]
+    fn documents_expire_after_the_ttl() {
+        let cache = DidCache::new(Duration::from_secs(30));
+        let now = Instant::now();
+        let document = document();
+        let did = document.id().to_string();
+        assert!(cache.get_at(&did, now).is_none());
+
+        cache.insert_at(&did, document.clone(), now);
+        assert_eq!(cache.get_at(&did, now + Duration::from_secs(29)), Some(document));
+        assert!(cache.get_at(&did, now + Duration::from_secs(30)).is_none());
+    }
+
+    #[test]
+    fn zero_ttl_disables_the_cache() {
+        let cache = DidCache::new(Duration::ZERO);
+        let document = document();
+        let did = document.id().to_string();
+        cache.insert(&did, document);
+        assert!(cache.get(&did).is_none());
+        assert!(cache.documents.lock().unwrap().is_empty());
+    }
+}
@@ -1,106 +1,279 @@
-use axum::{extract::State, http::StatusCode, Json};
-use serde::{Deserialize, Serialize};
+use axum::{extract::{Path, State}, http::StatusCode, Extension, Json};
+use anyhow::Context;
+use iota_sdk::types::base_types::ObjectID;
+use iota_sdk::types::dynamic_field::DynamicFieldName;
+use iota_sdk::types::TypeTag;
+use serde::{de::DeserializeOwned, Deserialize};
+use std::str::FromStr;
 use std::sync::Arc;
-use uuid::Uuid;
+use std::time::Instant;
+use tracing::Instrument;
+use wot_id_types::identity::{
+    InitiateChallengeRequest, InitiateChallengeResponse, KeyAgreementMethod, KeyAgreementResponse, UserInfo,
+    VerifyActionRequest, VerifyActionResponse, VerifySignatureRequest, VerifySignatureResponse,
+};
+use wot_id_types::{ErrorCode, WotDid};
 
+use crate::nonces::NonceUse;
+use crate::ratelimit::ClientIp;
+use crate::rotation;
+use crate::signatures::{self, SignaturePolicy};
 use crate::state::AppState;
+use crate::store::now_secs;
+use crate::telemetry;
 use identity_iota::did::CoreDID;
 use identity_iota::prelude::IotaDID;
 use identity_iota::iota::IotaDocument;
-use identity_iota::document::verifiable::JwsVerificationOptions;
-use identity_eddsa_verifier::EdDSAJwsVerifier;
+use identity_iota::verification::{MethodScope, VerificationMethod};
 use identity_iota::iota::rebased::client::{IdentityClientReadOnly};
+use identity_iota::iota_interaction::IotaClient as SdkClient;
 
-// Health Check
-#[derive(Serialize)]
-pub struct HealthResponse {
-    status: String,
+// The shared DID type for a parsed IotaDID, whose string form is always valid `did:iota` syntax
+pub(crate) fn wot_did(did: &IotaDID) -> WotDid {
+    did.to_string().parse().expect("IotaDID renders valid did:iota syntax")
 }
 
-pub async fn health_check_handler() -> Json<HealthResponse> {
-    Json(HealthResponse { status: "UP".to_string() })
+// Parse a DID string into an IotaDID, mapping failures to 400 responses
+pub(crate) fn parse_iota_did(did: &str) -> Result<IotaDID, (StatusCode, String)> {
+    let core_did: CoreDID = match CoreDID::parse(did) {
+        Ok(did) => did,
+        Err(e) => {
+            eprintln!("Error parsing CoreDID: {}", e);
+            return Err((StatusCode::BAD_REQUEST, format!("Invalid CoreDID format: {}", e)));
+        }
+    };
+
+    match IotaDID::try_from(core_did.clone()) {
+        Ok(iota_did) => Ok(iota_did),
+        Err(e) => {
+            eprintln!("Error converting CoreDID to IotaDID for {}: {}", core_did, e);
+            Err((StatusCode::BAD_REQUEST, format!("Invalid DID for IOTA context: {}", e)))
+        }
+    }
+}
+
+// The DID document, from the cache when it was resolved within `DID_CACHE_TTL_SECS`
+pub(crate) async fn resolve_document(app_state: &AppState, did: &IotaDID) -> Result<IotaDocument, (StatusCode, String)> {
+    match app_state.did_cache.get(&did.to_string()) {
+        Some(document) => Ok(document),
+        None => resolve_current_document(app_state, did).await,
+    }
+}
+
+// The DID document as published now, for callers that build an update on top of it
+pub(crate) async fn resolve_current_document(app_state: &AppState, did: &IotaDID) -> Result<IotaDocument, (StatusCode, String)> {
+    let started = Instant::now();
+    let span = tracing::info_span!("did_resolution", did = %did, otel.status_code = tracing::field::Empty);
+    let resolved = app_state.identity_client.resolve_did(did).instrument(span.clone()).await;
+    telemetry::did_resolution(started, resolved.is_ok());
+    telemetry::iota_rpc("resolve_did", started, resolved.is_ok());
+    if resolved.is_err() {
+        span.record("otel.status_code", "ERROR");
+    }
+    match resolved {
+        Ok(doc) => {
+            app_state.did_cache.insert(&did.to_string(), doc.clone());
+            Ok(doc)
+        }
+        Err(e) => {
+            eprintln!("Error resolving DID document for {}: {}", did, e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to resolve DID document: {}", e)))
+        }
+    }
 }
 
-// Initiate Challenge
+// Signature policy from configuration: whether hybrid Ed25519 + ML-DSA signatures are required
+pub(crate) fn signature_policy(app_state: &AppState) -> SignaturePolicy {
+    SignaturePolicy { require_hybrid: app_state.config.require_hybrid_signatures }
+}
+
+// Verify a JWS (EdDSA, ML-DSA or hybrid) against the resolved DID document of `did` and return its
+// raw claims; failures are counted under the verification `kind`
+pub(crate) async fn verify_did_jws(
+    app_state: &AppState,
+    did: &str,
+    jws: &str,
+    kind: &'static str,
+) -> Result<Vec<u8>, (StatusCode, String)> {
+    let did_to_resolve = parse_iota_did(did).inspect_err(|_| telemetry::verification(kind, Err(ErrorCode::InvalidDid)))?;
+    let resolved_document = resolve_document(app_state, &did_to_resolve)
+        .await
+        .inspect_err(|_| telemetry::verification(kind, Err(ErrorCode::ResolutionFailed)))?;
+
+    let verified = signatures::verify_jws(&resolved_document, jws, signature_policy(app_state)).map_err(|e| {
+        telemetry::verification(kind, Err(ErrorCode::BadSignature));
+        tracing::warn!(did = %did_to_resolve, error = %e, "JWS verification failed.");
+        (StatusCode::UNAUTHORIZED, format!("JWS verification failed for {}: {}", did_to_resolve, e))
+    })?;
+    if let Some(kid) = rotation::retired_signer(app_state, did, &verified).await {
+        telemetry::verification(kind, Err(ErrorCode::RetiredKey));
+        tracing::warn!(did = %did_to_resolve, kid = %kid, "JWS was signed with a retired method.");
+        return Err((StatusCode::UNAUTHORIZED, format!("{} signed with retired method '{}'", did_to_resolve, kid)));
+    }
+    Ok(verified.claims)
+}
+
+// Signed workflow actions older (or newer) than this are rejected; within it, each `jti` is
+// accepted once
+pub(crate) const MAX_ACTION_AGE_SECS: u64 = 300;
+const MAX_JTI_LEN: usize = 128;
+
 #[derive(Deserialize)]
-pub struct InitiateChallengeRequest {
-    pub email: String,
+struct ActionEnvelope {
+    action: String,
+    iat: u64,
+    jti: String,
 }
 
-#[derive(Serialize)]
-pub struct InitiateChallengeResponse {
-    pub did: String,
-    pub challenge: String,
+// Verify a JWS-signed workflow action (`{"action": ..., "iat": ..., "jti": ..., ...}`) and
+// decode its claims
+pub(crate) async fn verify_signed_action<T: DeserializeOwned>(
+    app_state: &AppState,
+    signer: &str,
+    jws: &str,
+    expected_action: &str,
+) -> Result<T, (StatusCode, String)> {
+    let claims = verify_did_jws(app_state, signer, jws, "action").await?;
+    let envelope: ActionEnvelope = serde_json::from_slice(&claims).map_err(|e| {
+        telemetry::verification("action", Err(ErrorCode::MalformedClaims));
+        (StatusCode::BAD_REQUEST, format!("Invalid action claims: {}", e))
+    })?;
+
+    if envelope.action != expected_action {
+        telemetry::verification("action", Err(ErrorCode::WrongAction));
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Expected a '{}' action, got '{}'", expected_action, envelope.action),
+        ));
+    }
+    if envelope.jti.is_empty() || envelope.jti.len() > MAX_JTI_LEN {
+        telemetry::verification("action", Err(ErrorCode::MalformedClaims));
+        return Err((StatusCode::BAD_REQUEST, format!("jti must be 1 to {} characters", MAX_JTI_LEN)));
+    }
+    let now = now_secs();
+    if now.abs_diff(envelope.iat) > MAX_ACTION_AGE_SECS {
+        telemetry::verification("action", Err(ErrorCode::Expired));
+        return Err((StatusCode::UNAUTHORIZED, "Signed action is expired or issued in the future".to_string()));
+    }
+    let claims = serde_json::from_slice(&claims).map_err(|e| {
+        telemetry::verification("action", Err(ErrorCode::MalformedClaims));
+        (StatusCode::BAD_REQUEST, format!("Invalid action claims: {}", e))
+    })?;
+    match app_state.action_nonces.use_nonce(signer, &envelope.jti, envelope.iat, now) {
+        NonceUse::First => {}
+        NonceUse::Replayed => {
+            telemetry::verification("action", Err(ErrorCode::Replayed));
+            tracing::warn!(did = %signer, jti = %envelope.jti, "Signed action was replayed.");
+            return Err((StatusCode::UNAUTHORIZED, format!("Signed action '{}' was already used", envelope.jti)));
+        }
+        NonceUse::Full => {
+            tracing::warn!("Too many recent signed actions; refusing more until some expire.");
+            return Err((StatusCode::SERVICE_UNAVAILABLE, "Too many recent signed actions, try again later".to_string()));
+        }
+    }
+    telemetry::verification("action", Ok(()));
+    Ok(claims)
 }
 
-// Placeholder for the custom Move contract interaction
+// Placeholder for the custom Move contract interaction; `None` when the email is not registered
 async fn resolve_email_via_move_contract(
     _client: &IdentityClientReadOnly,
     email: &str,
     _package_id: &str,
-) -> Result<String, anyhow::Error> {
+) -> Result<Option<WotDid>, anyhow::Error> {
     // TODO: Implement actual Move contract call
     // This function will use `_client` to:
     // - Construct a call to your specific Move contract function that maps emails to DIDs.
     // - This might involve `client.call_contract_view_function(...)` or similar,
     //   depending on how `IdentityClient` or `iota-sdk` exposes Move contract calls.
     // - Parse the result to get the DID string.
-    println!("Attempting to resolve email: {}", email);
-    // For now, returning a placeholder DID if email is recognized, otherwise error
+    // For now, returning a placeholder DID if email is recognized
     if email == "user@example.com" {
-        Ok("did:iota:tst:0xplaceholderdidforuseratolecom".to_string())
+        Ok(Some("did:iota:tst:0x00000000000000000000000000000000000000000000000000000000000000a1".parse()?))
     } else {
-        Err(anyhow::anyhow!("Email not found or Move contract email resolution not implemented for this email"))
+        Ok(None)
     }
 }
 
+// Object ID of the `wot_id::identity::Identity` for a DID, read from the shared
+// `identity::DidRegistry`; `None` when no Identity was created for the DID
+pub async fn resolve_identity_object_via_move_contract(
+    client: &SdkClient,
+    did: &WotDid,
+    registry_id: &str,
+) -> Result<Option<String>, anyhow::Error> {
+    let name = DynamicFieldName {
+        type_: TypeTag::from_str("0x1::string::String")?,
+        value: serde_json::Value::String(did.to_string()),
+    };
+    let started = Instant::now();
+    let response = client
+        .read_api()
+        .get_dynamic_field_object(ObjectID::from_hex_literal(registry_id)?, name)
+        .await;
+    telemetry::iota_rpc("get_dynamic_field_object", started, response.is_ok());
+    let response = response?;
+    let Some(content) = response.data.and_then(|data| data.content) else {
+        return Ok(None);
+    };
+    // The entry is a `dynamic_field::Field<String, ID>`; the Identity object ID is its value
+    let content = serde_json::to_value(content)?;
+    let object_id = content
+        .pointer("/fields/value")
+        .and_then(|value| value.as_str())
+        .with_context(|| format!("Unexpected DidRegistry entry for {}", did))?;
+    Ok(Some(object_id.to_string()))
+}
+
+// Unregistered emails get a well-formed DID derived from the keyed email hash, stable across
+// requests, so the response does not reveal whether an email is registered. `verify-signature`
+// answers for it like for any other bad signature.
+fn decoy_did(app_state: &AppState, email: &str) -> WotDid {
+    wot_did(&IotaDID::new(&app_state.limiter.email_hash(email), app_state.identity_client.network()))
+}
+
+// Email lookup; unregistered emails get a decoy DID, see `decoy_did`
+#[utoipa::path(
+    post,
+    path = "/api/v1/identity/initiate-challenge",
+    tag = "identity",
+    request_body = InitiateChallengeRequest,
+    responses(
+        (status = 200, body = InitiateChallengeResponse),
+        (status = 429, description = "Rate limit exceeded", body = String),
+        (status = 503, description = "Email lookup is unavailable, or too many challenges are outstanding", body = String),
+    )
+)]
 pub async fn initiate_challenge_handler(
     State(app_state): State<Arc<AppState>>,
     Json(payload): Json<InitiateChallengeRequest>,
 ) -> Result<Json<InitiateChallengeResponse>, (StatusCode, String)> {
-    let did_str = match resolve_email_via_move_contract(
+    let did = match resolve_email_via_move_contract(
         &app_state.identity_client, 
         &payload.email,
         &app_state.config.iota_identity_pkg_id,
     ).await {
-        Ok(did) => did,
+        Ok(Some(did)) => did,
+        Ok(None) => decoy_did(&app_state, &payload.email),
         Err(e) => {
-            eprintln!("Error resolving email to DID: {}", e);
-            return Err((StatusCode::NOT_FOUND, e.to_string()));
+            tracing::error!(error = %e, "Email lookup failed.");
+            return Err((StatusCode::SERVICE_UNAVAILABLE, "Email lookup is unavailable".to_string()));
         }
     };
 
-    let challenge = Uuid::new_v4().to_string();
+    let challenge = app_state.challenges.issue(did.as_str()).ok_or_else(|| {
+        tracing::warn!("Challenge store is full.");
+        (StatusCode::SERVICE_UNAVAILABLE, "Too many challenges are outstanding".to_string())
+    })?;
 
     Ok(Json(InitiateChallengeResponse {
-        did: did_str,
+        did,
         challenge,
     }))
 }
 
 
 // Verify Signature
-#[derive(Deserialize)]
-pub struct VerifySignatureRequest {
-    pub did: String,
-    pub challenge: String,
-    pub signature: String, // This is the JWS
-}
-
-#[derive(Serialize)]
-pub struct VerifySignatureResponse {
-    #[serde(rename = "isValid")]
-    pub is_valid: bool,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub user: Option<UserInfo>, 
-}
-
-#[derive(Serialize)]
-pub struct UserInfo {
-    pub email: Option<String>,
-    pub name: Option<String>,
-}
-
 #[derive(Deserialize, Debug)]
 struct JwsClaims {
     iss: String, 
@@ -110,75 +283,74 @@ struct JwsClaims {
 
 // JwsClaims struct defined above this point.
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/identity/verify-signature",
+    tag = "identity",
+    request_body = VerifySignatureRequest,
+    responses(
+        (status = 200, description = "`isValid` is false for bad signatures, unresolvable DIDs and challenges that were not issued for the DID, expired or were already used", body = VerifySignatureResponse),
+        (status = 400, description = "Malformed DID", body = String),
+        (status = 429, description = "Rate limit exceeded, or the DID is locked out for this client", body = String),
+    )
+)]
 pub async fn verify_signature_handler(
     State(app_state): State<Arc<AppState>>,
+    Extension(ClientIp(client_ip)): Extension<ClientIp>,
     Json(payload): Json<VerifySignatureRequest>,
 ) -> Result<Json<VerifySignatureResponse>, (StatusCode, String)> {
-    let core_did: CoreDID = match CoreDID::parse(&payload.did) {
-        Ok(did) => did,
-        Err(e) => {
-            eprintln!("Error parsing CoreDID: {}", e);
-            return Err((StatusCode::BAD_REQUEST, format!("Invalid CoreDID format: {}", e)));
+    let did_to_resolve = parse_iota_did(&payload.did).inspect_err(|_| telemetry::verification("signature", Err(ErrorCode::InvalidDid)))?;
+    let resolved_document = match resolve_document(&app_state, &did_to_resolve).await {
+        Ok(document) => document,
+        Err((_, e)) => {
+            // Answered like a bad signature, so decoy DIDs from `initiate-challenge` are not told apart
+            telemetry::verification("signature", Err(ErrorCode::ResolutionFailed));
+            app_state.limiter.record_failure(&payload.did, client_ip);
+            tracing::warn!(did = %did_to_resolve, error = %e, "DID resolution failed during signature verification.");
+            return Ok(Json(VerifySignatureResponse { is_valid: false, user: None }));
         }
     };
-
-    let did_to_resolve: IotaDID = match IotaDID::try_from(core_did.clone()) {
-        Ok(iota_did) => iota_did,
-        Err(e) => {
-            eprintln!("Error converting CoreDID to IotaDID for {}: {}", core_did, e);
-            return Err((StatusCode::BAD_REQUEST, format!("Invalid DID for IOTA context: {}", e)));
-        }
-    };
-
-    let resolved_document: IotaDocument = match app_state.identity_client.resolve_did(&did_to_resolve).await {
-        Ok(doc) => doc,
-        Err(e) => {
-            eprintln!("Error resolving DID document for {}: {}", did_to_resolve, e);
-            return Err((StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to resolve DID document: {}", e)));
-        }
-    };
-    
-        // The JWS is taken directly from payload.signature
-    // // let jws_to_verify = match Jws::from_encoded_jws(&payload.signature) {
-    //     // Ok(jws) => jws,
-    //     // Err(e) => {
-    //         // eprintln!("Error parsing JWS from string: {}", e);
-    //         // return Err((StatusCode::BAD_REQUEST, format!("Invalid JWS format: {}", e)));
-    //     // }
-    // // };
     
-    let verification_options = JwsVerificationOptions::default();
-    let verification_result = resolved_document.core_document().verify_jws(
-        &payload.signature, // Full JWS string
-        None, // Assuming challenge is in JWS payload, not detached
-        &EdDSAJwsVerifier::default(), // Verifier
-        &verification_options // Verification options
-    );
+    // EdDSA, ML-DSA or hybrid; the configured policy decides whether a single algorithm suffices
+    let verification_result = signatures::verify_jws(&resolved_document, &payload.signature, signature_policy(&app_state));
 
     let mut is_valid = false; // Default to false
+    let mut outcome = Err(ErrorCode::BadSignature);
 
     match verification_result {
         Ok(decoded_jws) => {
             // JWS signature itself is cryptographically valid.
-            // Now, deserialize claims and check the challenge.
-            match serde_json::from_slice::<JwsClaims>(&decoded_jws.claims) {
-                Ok(claims) => {
-                    if claims.challenge == payload.challenge {
-                        // Optional: Further check if claims.iss matches payload.did
-                        // For now, matching challenge is the primary concern for this step.
-                        is_valid = true;
-                        println!("JWS claims successfully verified. Challenge matched for DID {}.
+            // A key past its rotation grace period no longer counts, even while still published.
+            if let Some(signing_kid) = rotation::retired_signer(&app_state, &payload.did, &decoded_jws).await {
+                outcome = Err(ErrorCode::RetiredKey);
+                tracing::warn!(did = %did_to_resolve, kid = %signing_kid, "JWS was signed with a retired method.");
+            } else {
+                // Now, deserialize claims and check the challenge.
+                match serde_json::from_slice::<JwsClaims>(&decoded_jws.claims) {
+                    Ok(claims) => {
+                        // Issued for this DID by `initiate-challenge`, unexpired and unused
+                        if claims.challenge == payload.challenge
+                            && app_state.challenges.redeem(&payload.challenge, &payload.did)
+                        {
+                            // Optional: Further check if claims.iss matches payload.did
+                            // For now, matching challenge is the primary concern for this step.
+                            is_valid = true;
+                            outcome = Ok(());
+                            println!("JWS claims successfully verified. Challenge matched for DID {}.
 JWS: {}
 Challenge: {}", did_to_resolve, payload.signature, payload.challenge);
-                    } else {
-                        eprintln!(
-                            "JWS challenge mismatch. Expected: '{}', Got: '{}' in JWS claims for DID {}",
-                            payload.challenge, claims.challenge, did_to_resolve
-                        );
+                        } else {
+                            outcome = Err(ErrorCode::ChallengeMismatch);
+                            eprintln!(
+                                "JWS challenge mismatch. Expected: '{}', Got: '{}' in JWS claims for DID {}",
+                                payload.challenge, claims.challenge, did_to_resolve
+                            );
+                        }
+                    }
+                    Err(e) => {
+                        outcome = Err(ErrorCode::MalformedClaims);
+                        eprintln!("Failed to deserialize JWS claims for DID {}: {}. Claims data: {:?}", did_to_resolve, e, String::from_utf8_lossy(&decoded_jws.claims));
                     }
-                }
-                Err(e) => {
-                    eprintln!("Failed to deserialize JWS claims for DID {}: {}. Claims data: {:?}", did_to_resolve, e, String::from_utf8_lossy(&decoded_jws.claims));
                 }
             }
         },
@@ -189,6 +361,13 @@ Challenge: {}", did_to_resolve, payload.signature, payload.challenge);
         }
     };
 
+    telemetry::verification("signature", outcome);
+    // Repeated failures lock the DID out for this client for a while; see `ratelimit`
+    if is_valid {
+        app_state.limiter.record_success(&payload.did, client_ip);
+    } else {
+        app_state.limiter.record_failure(&payload.did, client_ip);
+    }
     if is_valid {
         Ok(Json(VerifySignatureResponse {
             is_valid: true,
@@ -198,3 +377,93 @@ Challenge: {}", did_to_resolve, payload.signature, payload.challenge);
         Ok(Json(VerifySignatureResponse { is_valid: false, user: None }))
     }
 }
+
+// Verify a signed workflow action on behalf of another service and return its claims. The
+// caller is responsible for checking the action-specific claims.
+#[utoipa::path(
+    post,
+    path = "/api/v1/identity/verify-action",
+    tag = "identity",
+    request_body = VerifyActionRequest,
+    responses(
+        (status = 200, body = VerifyActionResponse),
+        (status = 400, description = "Malformed DID or claims, or a different action", body = String),
+        (status = 401, description = "Bad signature, or the action is expired or was already used", body = String),
+        (status = 500, description = "DID resolution failed", body = String),
+        (status = 503, description = "Too many recent signed actions", body = String),
+    )
+)]
+pub async fn verify_action_handler(
+    State(app_state): State<Arc<AppState>>,
+    Json(payload): Json<VerifyActionRequest>,
+) -> Result<Json<VerifyActionResponse>, (StatusCode, String)> {
+    let claims = verify_signed_action(&app_state, &payload.signer, &payload.jws, &payload.action).await?;
+    Ok(Json(VerifyActionResponse { signer: payload.signer, claims }))
+}
+
+
+// AKP (draft-ietf-cose-dilithium style) JWKs are read from the method's JSON form
+fn akp_key_agreement_method(method: &VerificationMethod) -> Option<KeyAgreementMethod> {
+    let method_json = serde_json::to_value(method).ok()?;
+    let jwk = method_json.get("publicKeyJwk")?;
+    if jwk.get("kty")?.as_str()? != "AKP" {
+        return None;
+    }
+    Some(KeyAgreementMethod {
+        id: method.id().to_string(),
+        kty: "AKP".to_string(),
+        crv: None,
+        alg: Some(jwk.get("alg")?.as_str()?.to_string()),
+        x: jwk.get("pub")?.as_str()?.to_string(),
+    })
+}
+
+// Returns the OKP and AKP (ML-KEM) key-agreement methods of a DID so other services can encrypt to it
+#[utoipa::path(
+    get,
+    path = "/api/v1/identity/{did}/key-agreement",
+    tag = "identity",
+    params(("did" = String, Path)),
+    responses(
+        (status = 200, body = KeyAgreementResponse),
+        (status = 400, description = "Malformed DID", body = String),
+        (status = 404, description = "The DID has no key-agreement methods", body = String),
+        (status = 500, description = "DID resolution failed", body = String),
+    )
+)]
+pub async fn key_agreement_handler(
+    State(app_state): State<Arc<AppState>>,
+    Path(did): Path<WotDid>,
+) -> Result<Json<KeyAgreementResponse>, (StatusCode, String)> {
+    let did_to_resolve = parse_iota_did(&did)?;
+    let resolved_document = resolve_document(&app_state, &did_to_resolve).await?;
+
+    let methods: Vec<KeyAgreementMethod> = resolved_document
+        .core_document()
+        .methods(Some(MethodScope::key_agreement()))
+        .into_iter()
+        .filter_map(|method| {
+            let okp = method
+                .data()
+                .public_key_jwk()
+                .and_then(|jwk| jwk.try_okp_params().ok())
+                .map(|params| KeyAgreementMethod {
+                    id: method.id().to_string(),
+                    kty: "OKP".to_string(),
+                    crv: Some(params.crv.clone()),
+                    alg: None,
+                    x: params.x.clone(),
+                });
+            okp.or_else(|| akp_key_agreement_method(method))
+        })
+        .collect();
+
+    if methods.is_empty() {
+        return Err((StatusCode::NOT_FOUND, format!("DID {} has no key-agreement methods", did_to_resolve)));
+    }
+
+    Ok(Json(KeyAgreementResponse {
+        did,
+        methods,
+    }))
+}
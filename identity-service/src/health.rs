@@ -0,0 +1,264 @@
+use axum::{extract::State, http::StatusCode, Json};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use utoipa::ToSchema;
+
+use crate::lifecycle::Shutdown;
+use crate::state::AppState;
+use crate::store::now_secs;
+use crate::telemetry;
+
+pub const IOTA_NODE: &str = "iota_node";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProbeStatus {
+    Up,
+    Down,
+    // Not probed yet, or not within the cache TTL
+    Unknown,
+}
+
+// Why a probe failed
+#[derive(Clone, Debug, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case", tag = "kind")]
+pub enum ProbeError {
+    // No answer within the probe timeout
+    Timeout,
+    // The node could not be reached or answered with an error
+    Node { detail: String },
+}
+
+// Latest probe result of one dependency
+#[derive(Clone, Debug, Serialize, ToSchema)]
+pub struct ComponentHealth {
+    pub status: ProbeStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_checked_at: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_success_at: Option<u64>,
+    pub consecutive_failures: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ProbeError>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct HealthReport {
+    // `up` only when every component is
+    pub status: ProbeStatus,
+    pub uptime_secs: u64,
+    pub components: BTreeMap<&'static str, ComponentHealth>,
+}
+
+// Dependency health, refreshed by `run_probes`; requests only read the cached results so
+// health checks never reach the node themselves
+pub struct HealthMonitor {
+    started: Instant,
+    // Results older than this are reported as unknown, so a stalled probe task fails readiness
+    ttl: Duration,
+    components: RwLock<BTreeMap<&'static str, ComponentHealth>>,
+}
+
+impl HealthMonitor {
+    pub fn new(names: &[&'static str], ttl: Duration) -> Self {
+        let unknown = ComponentHealth {
+            status: ProbeStatus::Unknown,
+            latency_ms: None,
+            last_checked_at: None,
+            last_success_at: None,
+            consecutive_failures: 0,
+            error: None,
+        };
+        Self {
+            started: Instant::now(),
+            ttl,
+            components: RwLock::new(names.iter().map(|&name| (name, unknown.clone())).collect()),
+        }
+    }
+
+    async fn record(&self, name: &'static str, result: Result<(), ProbeError>, latency: Duration) {
+        let now = now_secs();
+        let mut components = self.components.write().await;
+        let Some(component) = components.get_mut(name) else {
+            return;
+        };
+        component.latency_ms = Some(latency.as_millis() as u64);
+        component.last_checked_at = Some(now);
+        match result {
+            Ok(()) => {
+                if component.status == ProbeStatus::Down {
+                    tracing::info!(component = name, "Dependency is up again.");
+                }
+                component.status = ProbeStatus::Up;
+                component.last_success_at = Some(now);
+                component.consecutive_failures = 0;
+                component.error = None;
+            }
+            Err(error) => {
+                if component.status != ProbeStatus::Down {
+                    tracing::warn!(component = name, error = ?error, "Dependency is down.");
+                }
+                component.status = ProbeStatus::Down;
+                component.consecutive_failures += 1;
+                component.error = Some(error);
+            }
+        }
+    }
+
+    pub async fn report(&self) -> HealthReport {
+        self.report_at(now_secs()).await
+    }
+
+    async fn report_at(&self, now: u64) -> HealthReport {
+        let mut components = self.components.read().await.clone();
+        for component in components.values_mut() {
+            if component.last_checked_at.is_some_and(|checked| now.saturating_sub(checked) > self.ttl.as_secs()) {
+                component.status = ProbeStatus::Unknown;
+            }
+        }
+        let status = if components.values().all(|c| c.status == ProbeStatus::Up) {
+            ProbeStatus::Up
+        } else {
+            ProbeStatus::Down
+        };
+        HealthReport {
+            status,
+            uptime_secs: self.started.elapsed().as_secs(),
+            components,
+        }
+    }
+}
+
+// Long-running task: probe the node once per interval and cache the result until shutdown
+pub async fn run_probes(app_state: Arc<AppState>, shutdown: Shutdown) {
+    let interval_secs = app_state.config.health_probe_interval_secs;
+    let timeout = Duration::from_millis(app_state.config.health_probe_timeout_ms);
+    let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            () = shutdown.requested() => return,
+        }
+        run_probe(&app_state, IOTA_NODE, timeout, probe_node(&app_state)).await;
+    }
+}
+
+async fn run_probe(
+    app_state: &AppState,
+    name: &'static str,
+    timeout: Duration,
+    probe: impl Future<Output = Result<(), ProbeError>>,
+) {
+    let started = Instant::now();
+    let result = tokio::time::timeout(timeout, probe).await.unwrap_or(Err(ProbeError::Timeout));
+    app_state.health.record(name, result, started.elapsed()).await;
+}
+
+async fn probe_node(app_state: &AppState) -> Result<(), ProbeError> {
+    let started = Instant::now();
+    let checkpoint = app_state.sdk_client.read_api().get_latest_checkpoint_sequence_number().await;
+    telemetry::iota_rpc("get_latest_checkpoint_sequence_number", started, checkpoint.is_ok());
+    checkpoint.map_err(|e| ProbeError::Node { detail: e.to_string() })?;
+    Ok(())
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct LivenessResponse {
+    pub status: ProbeStatus,
+    pub uptime_secs: u64,
+}
+
+// The process is serving requests; the node is not consulted
+#[utoipa::path(get, path = "/livez", tag = "health", responses((status = 200, body = LivenessResponse)))]
+pub async fn livez_handler(State(app_state): State<Arc<AppState>>) -> Json<LivenessResponse> {
+    Json(LivenessResponse {
+        status: ProbeStatus::Up,
+        uptime_secs: app_state.health.started.elapsed().as_secs(),
+    })
+}
+
+// 503 until the last node probe succeeded, and again once that result is older than the cache TTL
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    tag = "health",
+    responses(
+        (status = 200, description = "The last node probe succeeded", body = HealthReport),
+        (status = 503, description = "The node is down or not probed recently", body = HealthReport),
+    )
+)]
+pub async fn readyz_handler(State(app_state): State<Arc<AppState>>) -> (StatusCode, Json<HealthReport>) {
+    let report = app_state.health.report().await;
+    (readiness(&report), Json(report))
+}
+
+fn readiness(report: &HealthReport) -> StatusCode {
+    if report.status == ProbeStatus::Up { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE }
+}
+
+// Same report as `/readyz`, always with 200, for dashboards
+#[utoipa::path(get, path = "/health", tag = "health", responses((status = 200, body = HealthReport)))]
+pub async fn health_check_handler(State(app_state): State<Arc<AppState>>) -> Json<HealthReport> {
+    Json(app_state.health.report().await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROBED: Duration = Duration::from_millis(5);
+
+    fn monitor() -> HealthMonitor {
+        HealthMonitor::new(&[IOTA_NODE], Duration::from_secs(33))
+    }
+
+    #[tokio::test]
+    async fn not_ready_until_the_node_answered() {
+        let monitor = monitor();
+        let report = monitor.report().await;
+        assert_eq!(report.components[IOTA_NODE].status, ProbeStatus::Unknown);
+        assert_eq!(readiness(&report), StatusCode::SERVICE_UNAVAILABLE);
+
+        monitor.record(IOTA_NODE, Ok(()), PROBED).await;
+        assert_eq!(readiness(&monitor.report().await), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn a_failed_probe_fails_readiness_until_the_next_success() {
+        let monitor = monitor();
+        monitor.record(IOTA_NODE, Ok(()), PROBED).await;
+        monitor.record(IOTA_NODE, Err(ProbeError::Timeout), PROBED).await;
+        monitor.record(IOTA_NODE, Err(ProbeError::Node { detail: "refused".to_string() }), PROBED).await;
+        let report = monitor.report().await;
+        assert_eq!(report.status, ProbeStatus::Down);
+        assert_eq!(readiness(&report), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(report.components[IOTA_NODE].consecutive_failures, 2);
+        assert!(report.components[IOTA_NODE].last_success_at.is_some());
+
+        monitor.record(IOTA_NODE, Ok(()), PROBED).await;
+        let report = monitor.report().await;
+        assert_eq!(readiness(&report), StatusCode::OK);
+        assert_eq!(report.components[IOTA_NODE].consecutive_failures, 0);
+    }
+
+    #[tokio::test]
+    async fn results_older_than_the_ttl_are_unknown() {
+        let monitor = monitor();
+        monitor.record(IOTA_NODE, Ok(()), PROBED).await;
+        let checked = monitor.report().await.components[IOTA_NODE].last_checked_at.unwrap();
+
+        assert_eq!(readiness(&monitor.report_at(checked + 33).await), StatusCode::OK);
+        let stale = monitor.report_at(checked + 34).await;
+        assert_eq!(stale.components[IOTA_NODE].status, ProbeStatus::Unknown);
+        assert_eq!(readiness(&stale), StatusCode::SERVICE_UNAVAILABLE);
+        // The last result is still shown
+        assert_eq!(stale.components[IOTA_NODE].last_success_at, Some(checked));
+    }
+}
@@ -0,0 +1,106 @@
+use std::sync::Arc;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::watch;
+use wot_config::Settings;
+use wot_telemetry::LogFilter;
+
+use crate::ratelimit::{RateLimitConfig, RateLimiter};
+use crate::state::AppState;
+use crate::telemetry::DEFAULT_LOG_FILTER;
+
+// Handed to background tasks so they stop once shutdown starts.
+#[derive(Clone)]
+pub struct Shutdown(watch::Receiver<bool>);
+
+impl Shutdown {
+    pub fn channel() -> (watch::Sender<bool>, Self) {
+        let (sender, receiver) = watch::channel(false);
+        (sender, Self(receiver))
+    }
+
+    pub async fn requested(&self) {
+        let _ = self.0.clone().wait_for(|&requested| requested).await;
+    }
+}
+
+// Resolves with the name of the first SIGTERM or SIGINT.
+pub async fn terminate_signal() -> &'static str {
+    let mut sigterm = signal(SignalKind::terminate()).expect("SIGTERM handler can be installed");
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => "SIGINT",
+        _ = sigterm.recv() => "SIGTERM",
+    }
+}
+
+// Re-reads the configuration on each SIGHUP and applies the settings that can change without a
+// restart. The environment of a running process is fixed, so changes come from the config file.
+pub async fn reload_on_hangup(log_filter: LogFilter, app_state: Arc<AppState>) {
+    let mut hangups = signal(SignalKind::hangup()).expect("SIGHUP handler can be installed");
+    while hangups.recv().await.is_some() {
+        match reload(&log_filter, &app_state) {
+            Ok(()) => tracing::info!("Configuration reloaded."),
+            Err(e) => tracing::error!(error = %e, "Configuration reload failed, keeping current settings."),
+        }
+    }
+}
+
+fn reload(log_filter: &LogFilter, app_state: &AppState) -> anyhow::Result<()> {
+    apply(&Settings::load()?, log_filter, &app_state.limiter)
+}
+
+// Everything is validated before anything is applied, so a bad file changes nothing
+fn apply(settings: &Settings, log_filter: &LogFilter, limiter: &RateLimiter) -> anyhow::Result<()> {
+    let rate_limits = RateLimitConfig::from_settings(settings)?;
+    log_filter.set(&settings.string_or("RUST_LOG", DEFAULT_LOG_FILTER)).map_err(anyhow::Error::msg)?;
+    limiter.set_config(rate_limits);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn settings(vars: &[(&str, &str)]) -> Settings {
+        let vars: HashMap<String, String> = vars.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect();
+        Settings::from_parts(Vec::new(), vars).unwrap()
+    }
+
+    // The log filter can only be changed while its layer, the first item, is alive
+    fn started() -> (impl Sized, LogFilter, RateLimiter) {
+        let (layer, log_filter) = LogFilter::new(DEFAULT_LOG_FILTER);
+        let limiter = RateLimiter::new(RateLimitConfig::from_settings(&settings(&[])).unwrap(), b"key".to_vec());
+        (layer, log_filter, limiter)
+    }
+
+    #[test]
+    fn reload_applies_log_level_and_rate_limits() {
+        let (_layer, log_filter, limiter) = started();
+        let reloaded = settings(&[
+            ("RUST_LOG", "identity_service=trace,warn"),
+            ("RATE_LIMIT_IP_PER_MINUTE", "120"),
+            ("VERIFY_LOCKOUT_THRESHOLD", "3"),
+            ("RATE_LIMIT_TRUSTED_PROXIES", "10.0.0.2"),
+        ]);
+        apply(&reloaded, &log_filter, &limiter).unwrap();
+        assert_eq!(log_filter.current().as_deref(), Some("identity_service=trace,warn"));
+        let config = limiter.config();
+        assert_eq!((config.ip_per_minute, config.lockout_threshold), (120, 3));
+        assert_eq!(config.trusted_proxies, vec!["10.0.0.2".parse::<std::net::IpAddr>().unwrap()]);
+    }
+
+    #[test]
+    fn an_invalid_reload_changes_nothing() {
+        let (_layer, log_filter, limiter) = started();
+        let (log_before, limits_before) = (log_filter.current(), limiter.config());
+        for invalid in [
+            [("RUST_LOG", "identity_service=loud"), ("RATE_LIMIT_IP_PER_MINUTE", "120")],
+            [("RUST_LOG", "debug"), ("RATE_LIMIT_IP_PER_MINUTE", "lots")],
+            [("RUST_LOG", "debug"), ("RATE_LIMIT_TRUSTED_PROXIES", "frontend")],
+        ] {
+            assert!(apply(&settings(&invalid), &log_filter, &limiter).is_err());
+        }
+        assert_eq!(log_filter.current(), log_before);
+        assert_eq!(limiter.config(), limits_before);
+    }
+}
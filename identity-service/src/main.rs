@@ -1,35 +1,56 @@
-use axum::{routing::{get, post}, Router};
+use axum::middleware;
+use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::net::TcpListener;
 use tower_http::trace::TraceLayer;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
+mod challenges;
 mod config;
+mod credentials;
+mod did_cache;
 mod handlers;
+mod health;
+mod lifecycle;
+mod nonces;
+mod openapi;
+mod ratelimit;
+mod recovery;
+mod rotation;
+mod signatures;
 mod state;
+mod store;
+mod telemetry;
+mod tls;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    // Initialize tracing (adapted from existing main.rs)
-    tracing_subscriber::registry()
-        .with(tracing_subscriber::EnvFilter::new(
-            std::env::var("RUST_LOG")
-                .unwrap_or_else(|_| "identity_service=debug,tower_http=debug,info".into()),
-        ))
-        .with(tracing_subscriber::fmt::layer())
-        .init();
+    // Loaded before tracing so `.env` can set `RUST_LOG` and `OTEL_*`
+    dotenvy::dotenv().ok();
+    // Logs, plus span export when `OTEL_TRACES_EXPORTER` is set
+    let (tracer_provider, log_filter) = wot_telemetry::init("identity-service", telemetry::DEFAULT_LOG_FILTER);
 
     tracing::info!("Starting identity-service...");
 
-    // Load configuration
-    let app_config = match config::AppConfig::load() {
-        Ok(cfg) => cfg,
+    // Installed first so metrics recorded during startup are kept
+    let metrics = telemetry::install_recorder()?;
+
+    // Load configuration: config file, environment and command line
+    let app_config = match load_config(&log_filter) {
+        Ok(Some(cfg)) => cfg,
+        // `--print-config`
+        Ok(None) => return Ok(()),
         Err(e) => {
             tracing::error!(error = %e, "Failed to load application configuration.");
             return Err(e);
         }
     };
-    tracing::info!(port = app_config.service_port, iota_node = %app_config.iota_api_endpoint, "Application configuration loaded.");
+    tracing::info!(
+        port = app_config.service_port,
+        network = %app_config.network,
+        iota_node = %app_config.iota_node_url,
+        "Application configuration loaded."
+    );
 
     // Build application state
     let shared_state = match state::build_app_state(app_config.clone()).await {
@@ -41,13 +62,29 @@ async fn main() -> anyhow::Result<()> {
     };
     tracing::info!("Application state built successfully.");
 
-    // Define routes
-    let app = Router::new()
-        .route("/health", get(handlers::health_check_handler))
-        .route("/api/v1/identity/initiate-challenge", post(handlers::initiate_challenge_handler))
-        .route("/api/v1/identity/verify-signature", post(handlers::verify_signature_handler))
+    // Background tasks stop once shutdown starts
+    let (shutdown_tx, shutdown) = lifecycle::Shutdown::channel();
+    // Probe the node in the background; health endpoints only read the cached results
+    let probes = tokio::spawn(health::run_probes(shared_state.clone(), shutdown.clone()));
+    // Log level and rate limits follow the config file on SIGHUP
+    tokio::spawn(lifecycle::reload_on_hangup(log_filter, shared_state.clone()));
+
+    // Challenge and verification are limited per client IP, email and DID
+    let limited = openapi::limited_routes()
+        .route_layer(middleware::from_fn_with_state(shared_state.clone(), ratelimit::limit));
+
+    // Define routes; the OpenAPI document is built from the same routes
+    let (api_routes, api) = openapi::routes(metrics).merge(limited).split_for_parts();
+    let app = api_routes
+        .route_layer(TraceLayer::new_for_http().make_span_with(telemetry::make_span))
         .with_state(shared_state)
-        .layer(TraceLayer::new_for_http());
+        .merge(openapi::docs(api))
+        .layer(middleware::from_fn(telemetry::track_http));
+
+    // HTTPS, with client certificates required when a client CA is configured
+    let tls_config = tls::server_config(&app_config).inspect_err(|e| {
+        tracing::error!(error = %e, "Failed to load TLS certificates.");
+    })?;
 
     let addr_str = format!("0.0.0.0:{}", app_config.service_port);
     tracing::info!("Attempting to bind to TCP listener at: {}", addr_str);
@@ -65,15 +102,74 @@ async fn main() -> anyhow::Result<()> {
     };
     
     let actual_addr = listener.local_addr().expect("Listener has no local address after bind");
-    tracing::info!("Identity service listening on {}", actual_addr);
+    let transport = match (&tls_config, &app_config.tls_client_ca_path) {
+        (None, _) => "http",
+        (Some(_), None) => "https",
+        (Some(_), Some(_)) => "https with client certificates",
+    };
+    tracing::info!(transport, "Identity service listening on {}", actual_addr);
 
-    axum::serve(listener, app.into_make_service())
-        .await
-        .map_err(|e| {
+    // On SIGTERM/SIGINT stop accepting connections and give in-flight requests the drain timeout
+    let drain_timeout = Duration::from_secs(app_config.shutdown_drain_timeout_secs);
+    let stopped = async move {
+        shutdown.requested().await;
+    };
+    let server = async move {
+        match tls_config {
+            Some(config) => {
+                // Rate limiting reads the peer address like with a plain listener
+                wot_tls::serve(listener, config, app, axum::extract::ConnectInfo::<SocketAddr>, stopped).await;
+                Ok(())
+            }
+            None => {
+                axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+                    .with_graceful_shutdown(stopped)
+                    .await
+            }
+        }
+    };
+    let drain = async {
+        let signal = lifecycle::terminate_signal().await;
+        tracing::info!(signal, drain_timeout_secs = drain_timeout.as_secs(), "Shutting down, draining connections.");
+        shutdown_tx.send_replace(true);
+        tokio::time::sleep(drain_timeout).await;
+    };
+    tokio::select! {
+        result = server => result.map_err(|e| {
             tracing::error!(error = %e, "Axum server error.");
             anyhow::anyhow!(e)
-        })?;
+        })?,
+        () = drain => tracing::warn!("Drain timeout reached, dropping remaining connections."),
+    }
 
     tracing::info!("Axum server stopped gracefully.");
+    // Also reached if the server stopped on its own
+    shutdown_tx.send_replace(true);
+    probes.await.ok();
+    if let Some(provider) = tracer_provider {
+        if let Err(e) = provider.shutdown() {
+            eprintln!("Failed to flush traces: {}", e);
+        }
+    }
     Ok(())
 }
+
+// `None` when the resolved configuration was printed instead
+fn load_config(log_filter: &wot_telemetry::LogFilter) -> anyhow::Result<Option<config::AppConfig>> {
+    let mut settings = wot_config::Settings::load()?;
+    // Names used before the configuration was shared with the backend
+    settings.rename_env("API_ENDPOINT", "IOTA_NODE_URL");
+    settings.rename_env("PORT", "IDENTITY_SERVICE_PORT");
+
+    let app_config = config::AppConfig::from_settings(&settings)?;
+    // `RUST_LOG` may also come from the config file, which is read after logging starts
+    log_filter.set(&settings.string_or("RUST_LOG", telemetry::DEFAULT_LOG_FILTER)).map_err(anyhow::Error::msg)?;
+    if settings.print_requested() {
+        print!("{}", settings.render());
+        return Ok(None);
+    }
+    for (key, source) in settings.unused() {
+        tracing::warn!(key = %key, source = %source, "Setting is not used by identity-service.");
+    }
+    Ok(Some(app_config))
+}
@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+// `jti`s remembered at once; signed actions are refused beyond this rather than let through unchecked
+const MAX_REMEMBERED_NONCES: usize = 100_000;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum NonceUse {
+    First,
+    // The signer already used this `jti` within the freshness window
+    Replayed,
+    Full,
+}
+
+// `jti`s of signed actions accepted while their `iat` is still within the freshness window,
+// so a captured action cannot be submitted again. Once the window has passed the freshness
+// check rejects it anyway and the entry is dropped. State is per instance and lost on
+// restart, like the challenge store's.
+pub struct ActionNonces {
+    window_secs: u64,
+    // (signer, jti) -> Unix seconds after which the action is stale
+    seen: Mutex<HashMap<(String, String), u64>>,
+}
+
+impl ActionNonces {
+    pub fn new(window_secs: u64) -> Self {
+        Self { window_secs, seen: Mutex::new(HashMap::new()) }
+    }
+
+    // Record `jti` for an action by `signer` issued at `iat`
+    pub fn use_nonce(&self, signer: &str, jti: &str, iat: u64, now: u64) -> NonceUse {
+        let mut seen = self.seen.lock().expect("action nonce lock");
+        seen.retain(|_, stale_after| *stale_after >= now);
+        let key = (signer.to_string(), jti.to_string());
+        if seen.contains_key(&key) {
+            return NonceUse::Replayed;
+        }
+        if seen.len() >= MAX_REMEMBERED_NONCES {
+            return NonceUse::Full;
+        }
+        seen.insert(key, iat + self.window_secs);
+        NonceUse::First
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DID: &str = "did:iota:tst:0x00000000000000000000000000000000000000000000000000000000000000a1";
+    const OTHER: &str = "did:iota:tst:0x00000000000000000000000000000000000000000000000000000000000000a2";
+
+    #[test]
+    fn a_jti_is_accepted_once_per_signer() {
+        let nonces = ActionNonces::new(300);
+        assert_eq!(nonces.use_nonce(DID, "n-1", 1_000, 1_000), NonceUse::First);
+        assert_eq!(nonces.use_nonce(DID, "n-1", 1_000, 1_100), NonceUse::Replayed);
+        assert_eq!(nonces.use_nonce(DID, "n-2", 1_000, 1_100), NonceUse::First);
+        assert_eq!(nonces.use_nonce(OTHER, "n-1", 1_000, 1_100), NonceUse::First);
+    }
+
+    #[test]
+    fn a_jti_is_remembered_for_the_whole_window() {
+        let nonces = ActionNonces::new(300);
+        // Signed slightly in the future, still fresh until 300 s after `iat`
+        assert_eq!(nonces.use_nonce(DID, "n-1", 1_100, 1_000), NonceUse::First);
+        assert_eq!(nonces.use_nonce(DID, "n-1", 1_100, 1_400), NonceUse::Replayed);
+        // Past the window the entry is dropped; the freshness check rejects the action by then
+        assert_eq!(nonces.use_nonce(DID, "n-2", 1_500, 1_401), NonceUse::First);
+        assert_eq!(nonces.seen.lock().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,118 @@
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Redirect, Response},
+    routing::get,
+    Json, Router,
+};
+use metrics_exporter_prometheus::PrometheusHandle;
+use std::sync::Arc;
+use utoipa::OpenApi;
+use utoipa_axum::{router::OpenApiRouter, routes};
+use utoipa_swagger_ui::Config;
+
+use crate::state::AppState;
+use crate::{credentials, handlers, health, recovery, rotation, telemetry};
+
+// Version of the HTTP API, not of the crate. Any change to the generated document needs a bump
+// here, and `openapi.json` regenerated (see the test below).
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "wot.id identity service",
+        version = "2.0.7",
+        description = "DID authentication, signed action verification, key rotation, recovery and credential APIs of the wot.id identity service"
+    ),
+    tags(
+        (name = "health", description = "Liveness and node health"),
+        (name = "identity", description = "Challenge login, signature and signed action verification, key agreement"),
+        (name = "recovery", description = "Guardian-based controller recovery"),
+        (name = "rotation", description = "Key rotation and verification against past DID documents"),
+        (name = "credentials", description = "Verifiable credentials with post-quantum proofs"),
+        (name = "telemetry", description = "Prometheus metrics"),
+    )
+)]
+pub struct ApiDoc;
+
+// Routes behind the per-client, per-email and per-DID rate limiter; see `ratelimit`
+pub fn limited_routes() -> OpenApiRouter<Arc<AppState>> {
+    OpenApiRouter::new()
+        .routes(routes!(handlers::initiate_challenge_handler))
+        .routes(routes!(handlers::verify_signature_handler))
+}
+
+// Every other route, each documented by its handler's annotation
+pub fn routes(metrics: PrometheusHandle) -> OpenApiRouter<Arc<AppState>> {
+    let mut doc = ApiDoc::openapi();
+    // utoipa fills in the crate's `license`, which is not declared
+    doc.info.license = None;
+    OpenApiRouter::with_openapi(doc)
+        .routes(routes!(health::livez_handler))
+        .routes(routes!(health::readyz_handler))
+        .routes(routes!(health::health_check_handler))
+        .merge(telemetry::routes(metrics))
+        .routes(routes!(handlers::verify_action_handler))
+        .routes(routes!(handlers::key_agreement_handler))
+        .routes(routes!(recovery::recovery_status_handler))
+        .routes(routes!(recovery::configure_recovery_handler))
+        .routes(routes!(recovery::initiate_recovery_handler))
+        .routes(routes!(recovery::approve_recovery_handler))
+        .routes(routes!(recovery::cancel_recovery_handler))
+        .routes(routes!(recovery::execute_recovery_handler))
+        .routes(routes!(rotation::rotations_handler))
+        .routes(routes!(rotation::rotate_key_handler))
+        .routes(routes!(rotation::verify_historical_handler))
+        .routes(routes!(credentials::issue_credential_handler))
+        .routes(routes!(credentials::verify_credential_handler))
+}
+
+// The document at `/openapi.json`, browsable at `/swagger-ui`. The UI is served from the
+// assets bundled in `utoipa-swagger-ui`, whose axum integration needs a newer axum.
+pub fn docs(api: utoipa::openapi::OpenApi) -> Router {
+    Router::new()
+        .route("/openapi.json", get(move || std::future::ready(Json(api))))
+        .route("/swagger-ui", get(|| std::future::ready(Redirect::permanent("/swagger-ui/"))))
+        .route("/swagger-ui/", get(swagger_ui))
+        .route("/swagger-ui/*file", get(swagger_ui))
+        .with_state(Arc::new(Config::new(["/openapi.json"])))
+}
+
+async fn swagger_ui(State(config): State<Arc<Config<'static>>>, file: Option<Path<String>>) -> Response {
+    let file = file.map(|Path(file)| file).unwrap_or_default();
+    match utoipa_swagger_ui::serve(&file, config) {
+        Ok(Some(file)) => ([(header::CONTENT_TYPE, file.content_type)], file.bytes.into_owned()).into_response(),
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use metrics_exporter_prometheus::PrometheusBuilder;
+    use serde_json::Value;
+    use std::path::Path;
+
+    // `openapi.json` is the published document. When the generated one differs, its version must
+    // differ too; `UPDATE_OPENAPI=1 cargo test` then rewrites the file.
+    #[test]
+    fn spec_changes_come_with_a_version_bump() {
+        let router = routes(PrometheusBuilder::new().build_recorder().handle()).merge(limited_routes());
+        let (_, api) = router.split_for_parts();
+        let generated = serde_json::to_value(&api).unwrap();
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("openapi.json");
+        let published: Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        if generated == published {
+            return;
+        }
+        assert_ne!(
+            generated["info"]["version"], published["info"]["version"],
+            "The OpenAPI document changed; bump `version` in src/openapi.rs"
+        );
+        assert!(
+            std::env::var_os("UPDATE_OPENAPI").is_some(),
+            "openapi.json is outdated; regenerate it with UPDATE_OPENAPI=1 cargo test"
+        );
+        std::fs::write(&path, api.to_pretty_json().unwrap() + "\n").unwrap();
+    }
+}
@@ -0,0 +1,367 @@
+use axum::{
+    body::{to_bytes, Body},
+    extract::{ConnectInfo, Request, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use wot_config::Settings;
+
+use crate::state::AppState;
+use crate::telemetry;
+
+// Largest request body the limiter buffers to read the email or DID
+const MAX_BODY_BYTES: usize = 64 * 1024;
+// Idle buckets are dropped once this many keys are tracked
+const MAX_TRACKED_KEYS: usize = 100_000;
+
+// What a quota is counted against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Scope {
+    Ip,
+    Email,
+    Did,
+}
+
+impl Scope {
+    fn as_str(self) -> &'static str {
+        match self {
+            Scope::Ip => "ip",
+            Scope::Email => "email",
+            Scope::Did => "did",
+        }
+    }
+}
+
+// Quotas and lockout policy; re-read on SIGHUP
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimitConfig {
+    // Requests per minute per key, also the burst size; 0 disables the scope
+    pub ip_per_minute: u32,
+    pub email_per_minute: u32,
+    pub did_per_minute: u32,
+    // Failed verifications within the window after which a DID is locked out
+    pub lockout_threshold: u32,
+    pub lockout_window: Duration,
+    pub lockout_duration: Duration,
+    // Peers whose `X-Forwarded-For` is believed, e.g. the frontend server
+    pub trusted_proxies: Vec<IpAddr>,
+}
+
+impl RateLimitConfig {
+    pub fn from_settings(settings: &Settings) -> Result<Self, anyhow::Error> {
+        let trusted_proxies = settings
+            .list("RATE_LIMIT_TRUSTED_PROXIES")
+            .iter()
+            .map(|ip| ip.parse().map_err(|_| anyhow::anyhow!("Invalid RATE_LIMIT_TRUSTED_PROXIES entry: {}", ip)))
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            ip_per_minute: settings.parse_or("RATE_LIMIT_IP_PER_MINUTE", 60)?,
+            email_per_minute: settings.parse_or("RATE_LIMIT_EMAIL_PER_MINUTE", 5)?,
+            did_per_minute: settings.parse_or("RATE_LIMIT_DID_PER_MINUTE", 10)?,
+            lockout_threshold: settings.parse_or("VERIFY_LOCKOUT_THRESHOLD", 5)?,
+            lockout_window: Duration::from_secs(settings.parse_or("VERIFY_LOCKOUT_WINDOW_SECS", 900)?),
+            lockout_duration: Duration::from_secs(settings.parse_or("VERIFY_LOCKOUT_SECS", 900)?),
+            trusted_proxies,
+        })
+    }
+
+    fn per_minute(&self, scope: Scope) -> u32 {
+        match scope {
+            Scope::Ip => self.ip_per_minute,
+            Scope::Email => self.email_per_minute,
+            Scope::Did => self.did_per_minute,
+        }
+    }
+
+    // The address of the client: the peer itself, or the last address in `X-Forwarded-For`
+    // that was not added by a trusted proxy
+    fn client_ip(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        if !self.trusted_proxies.contains(&peer) {
+            return peer;
+        }
+        let forwarded: Vec<IpAddr> = headers
+            .get_all("x-forwarded-for")
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .filter_map(|entry| entry.trim().parse().ok())
+            .collect();
+        forwarded
+            .iter()
+            .rev()
+            .find(|ip| !self.trusted_proxies.contains(ip))
+            .or(forwarded.first())
+            .copied()
+            .unwrap_or(peer)
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn refill(&mut self, per_minute: u32, now: Instant) {
+        let capacity = f64::from(per_minute);
+        let refilled = now.duration_since(self.updated).as_secs_f64() * capacity / 60.0;
+        self.tokens = (self.tokens + refilled).min(capacity);
+        self.updated = now;
+    }
+}
+
+struct Failures {
+    count: u32,
+    since: Instant,
+    locked_until: Option<Instant>,
+}
+
+// Why a request was refused, and when to try again
+#[derive(Debug)]
+pub struct Limited {
+    scope: Scope,
+    retry_after: Duration,
+}
+
+impl IntoResponse for Limited {
+    fn into_response(self) -> Response {
+        // Whole seconds, rounded up so a prompt retry is not refused again
+        let secs = self.retry_after.as_secs() + u64::from(self.retry_after.subsec_nanos() > 0);
+        let mut response = (
+            StatusCode::TOO_MANY_REQUESTS,
+            format!("Too many requests for this {}; retry in {} s", self.scope.as_str(), secs),
+        )
+            .into_response();
+        response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(secs.max(1)));
+        response
+    }
+}
+
+// Address of the client as found by the `limit` middleware, for handlers that record lockouts
+#[derive(Debug, Clone, Copy)]
+pub struct ClientIp(pub IpAddr);
+
+// In-memory token buckets and verification lockouts; state is per instance and lost on restart
+pub struct RateLimiter {
+    config: RwLock<RateLimitConfig>,
+    buckets: Mutex<HashMap<(Scope, String), Bucket>>,
+    // Per DID and client IP, so failures from one address cannot lock the DID out for everyone
+    failures: Mutex<HashMap<(String, IpAddr), Failures>>,
+    // Key for email hashes, so plain addresses are never kept and decoy DIDs cannot be precomputed
+    email_key: Vec<u8>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig, email_key: Vec<u8>) -> Self {
+        Self {
+            config: RwLock::new(config),
+            buckets: Mutex::new(HashMap::new()),
+            failures: Mutex::new(HashMap::new()),
+            email_key,
+        }
+    }
+
+    pub fn set_config(&self, config: RateLimitConfig) {
+        *self.config.write().expect("rate limit config lock") = config;
+    }
+
+    #[cfg(test)]
+    pub fn config(&self) -> RateLimitConfig {
+        self.config.read().expect("rate limit config lock").clone()
+    }
+
+    // Keyed SHA-256 of the normalized address
+    pub fn email_hash(&self, email: &str) -> [u8; 32] {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.email_key).expect("HMAC accepts any key length");
+        mac.update(email.trim().to_lowercase().as_bytes());
+        mac.finalize().into_bytes().into()
+    }
+
+    // Take one token from the key's bucket
+    fn take(&self, scope: Scope, key: &str, now: Instant) -> Result<(), Limited> {
+        let config = self.config.read().expect("rate limit config lock");
+        let per_minute = config.per_minute(scope);
+        if per_minute == 0 {
+            return Ok(());
+        }
+        let mut buckets = self.buckets.lock().expect("rate limit bucket lock");
+        if buckets.len() >= MAX_TRACKED_KEYS {
+            // A full bucket behaves exactly like a missing one
+            buckets.retain(|(scope, _), bucket| {
+                let per_minute = config.per_minute(*scope);
+                bucket.refill(per_minute, now);
+                bucket.tokens < f64::from(per_minute)
+            });
+        }
+        let bucket = buckets
+            .entry((scope, key.to_string()))
+            .or_insert_with(|| Bucket { tokens: f64::from(per_minute), updated: now });
+        bucket.refill(per_minute, now);
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return Ok(());
+        }
+        let wait = (1.0 - bucket.tokens) * 60.0 / f64::from(per_minute);
+        Err(Limited { scope, retry_after: Duration::from_secs_f64(wait) })
+    }
+
+    fn check_lockout(&self, did: &str, ip: IpAddr, now: Instant) -> Result<(), Limited> {
+        let failures = self.failures.lock().expect("lockout lock");
+        match failures.get(&(did.to_string(), ip)).and_then(|f| f.locked_until) {
+            Some(until) if until > now => Err(Limited { scope: Scope::Did, retry_after: until - now }),
+            _ => Ok(()),
+        }
+    }
+
+    // Count a failed verification; locks the DID out for this client once the threshold is reached
+    pub fn record_failure(&self, did: &str, ip: IpAddr) {
+        self.record_failure_at(did, ip, Instant::now());
+    }
+
+    fn record_failure_at(&self, did: &str, ip: IpAddr, now: Instant) {
+        let config = self.config.read().expect("rate limit config lock").clone();
+        if config.lockout_threshold == 0 {
+            return;
+        }
+        let mut failures = self.failures.lock().expect("lockout lock");
+        failures.retain(|_, f| {
+            f.locked_until.map_or(now.duration_since(f.since) < config.lockout_window, |until| until > now)
+        });
+        let entry = failures.entry((did.to_string(), ip)).or_insert(Failures { count: 0, since: now, locked_until: None });
+        entry.count += 1;
+        if entry.count >= config.lockout_threshold && entry.locked_until.is_none() {
+            entry.locked_until = Some(now + config.lockout_duration);
+            tracing::warn!(did = %did, client_ip = %ip, failures = entry.count, "DID locked out for client after repeated failed verifications.");
+        }
+    }
+
+    pub fn record_success(&self, did: &str, ip: IpAddr) {
+        self.failures.lock().expect("lockout lock").remove(&(did.to_string(), ip));
+    }
+}
+
+// Middleware for the challenge and verification routes: limits by client IP, and by the `email`
+// or `did` field of the JSON body when present. A DID locked out for this client is refused
+// before verification. Handlers get the client IP as a `ClientIp` extension.
+pub async fn limit(
+    State(app_state): State<Arc<AppState>>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let (mut parts, body) = request.into_parts();
+    let Ok(bytes) = to_bytes(body, MAX_BODY_BYTES).await else {
+        return (StatusCode::PAYLOAD_TOO_LARGE, "Request body too large".to_string()).into_response();
+    };
+    let limiter = &app_state.limiter;
+    let client_ip = limiter.config.read().expect("rate limit config lock").client_ip(peer.ip(), &parts.headers);
+    let fields: serde_json::Value = serde_json::from_slice(&bytes).unwrap_or_default();
+    let field = |name: &str| fields.get(name).and_then(serde_json::Value::as_str);
+
+    let now = Instant::now();
+    let mut checked = limiter.take(Scope::Ip, &client_ip.to_string(), now);
+    if let (Ok(()), Some(email)) = (&checked, field("email")) {
+        checked = limiter.take(Scope::Email, &hex::encode(limiter.email_hash(email)), now);
+    }
+    if let (Ok(()), Some(did)) = (&checked, field("did")) {
+        checked = limiter.check_lockout(did, client_ip, now).and_then(|()| limiter.take(Scope::Did, did, now));
+    }
+    if let Err(limited) = checked {
+        telemetry::rate_limited(limited.scope.as_str());
+        tracing::debug!(client_ip = %client_ip, scope = limited.scope.as_str(), "Request rate limited.");
+        return limited.into_response();
+    }
+    parts.extensions.insert(ClientIp(client_ip));
+    next.run(Request::from_parts(parts, Body::from(bytes))).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> RateLimitConfig {
+        RateLimitConfig {
+            ip_per_minute: 2,
+            email_per_minute: 1,
+            did_per_minute: 0,
+            lockout_threshold: 3,
+            lockout_window: Duration::from_secs(60),
+            lockout_duration: Duration::from_secs(300),
+            trusted_proxies: vec!["10.0.0.1".parse().unwrap()],
+        }
+    }
+
+    #[test]
+    fn bucket_refills_at_the_quota_rate() {
+        let limiter = RateLimiter::new(config(), b"key".to_vec());
+        let start = Instant::now();
+        assert!(limiter.take(Scope::Ip, "a", start).is_ok());
+        assert!(limiter.take(Scope::Ip, "a", start).is_ok());
+        let limited = limiter.take(Scope::Ip, "a", start).unwrap_err();
+        assert_eq!(limited.retry_after, Duration::from_secs(30));
+        assert!(limiter.take(Scope::Ip, "b", start).is_ok());
+        assert!(limiter.take(Scope::Ip, "a", start + Duration::from_secs(30)).is_ok());
+        // A quota of 0 disables the scope
+        for _ in 0..10 {
+            assert!(limiter.take(Scope::Did, "did:iota:0x1", start).is_ok());
+        }
+    }
+
+    #[test]
+    fn lockout_after_repeated_failures() {
+        let limiter = RateLimiter::new(config(), b"key".to_vec());
+        let ip: IpAddr = "1.1.1.1".parse().unwrap();
+        let start = Instant::now();
+        limiter.record_failure_at("did:iota:0x1", ip, start);
+        limiter.record_failure_at("did:iota:0x1", ip, start);
+        assert!(limiter.check_lockout("did:iota:0x1", ip, start).is_ok());
+        limiter.record_failure_at("did:iota:0x1", ip, start);
+        let limited = limiter.check_lockout("did:iota:0x1", ip, start + Duration::from_secs(100)).unwrap_err();
+        assert_eq!(limited.retry_after, Duration::from_secs(200));
+        assert!(limiter.check_lockout("did:iota:0x1", ip, start + Duration::from_secs(300)).is_ok());
+        limiter.record_success("did:iota:0x2", ip);
+        assert!(limiter.check_lockout("did:iota:0x2", ip, start).is_ok());
+    }
+
+    // Someone guessing from one address cannot lock the owner out elsewhere
+    #[test]
+    fn lockout_is_per_client() {
+        let limiter = RateLimiter::new(config(), b"key".to_vec());
+        let (attacker, owner): (IpAddr, IpAddr) = ("6.6.6.6".parse().unwrap(), "1.1.1.1".parse().unwrap());
+        let start = Instant::now();
+        for _ in 0..3 {
+            limiter.record_failure_at("did:iota:0x1", attacker, start);
+        }
+        assert!(limiter.check_lockout("did:iota:0x1", attacker, start).is_err());
+        assert!(limiter.check_lockout("did:iota:0x1", owner, start).is_ok());
+        // The owner's success does not lift the attacker's lockout
+        limiter.record_success("did:iota:0x1", owner);
+        assert!(limiter.check_lockout("did:iota:0x1", attacker, start).is_err());
+    }
+
+    #[test]
+    fn forwarded_for_only_from_trusted_proxies() {
+        let config = config();
+        let mut headers = HeaderMap::new();
+        headers.insert("x-forwarded-for", HeaderValue::from_static("1.1.1.1, 2.2.2.2, 10.0.0.1"));
+        let proxy: IpAddr = "10.0.0.1".parse().unwrap();
+        let stranger: IpAddr = "3.3.3.3".parse().unwrap();
+        assert_eq!(config.client_ip(proxy, &headers), "2.2.2.2".parse::<IpAddr>().unwrap());
+        assert_eq!(config.client_ip(stranger, &headers), stranger);
+        assert_eq!(config.client_ip(proxy, &HeaderMap::new()), proxy);
+    }
+
+    #[test]
+    fn email_hash_ignores_case_and_whitespace() {
+        let limiter = RateLimiter::new(config(), b"key".to_vec());
+        assert_eq!(limiter.email_hash(" User@Example.com"), limiter.email_hash("user@example.com"));
+        assert_ne!(limiter.email_hash("user@example.com"), RateLimiter::new(config(), b"other".to_vec()).email_hash("user@example.com"));
+    }
+}
@@ -0,0 +1,639 @@
+use axum::{extract::{Path, State}, http::StatusCode, Json};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use uuid::Uuid;
+use wot_id_types::identity::SignedAction;
+use wot_id_types::recovery::{
+    AuditEvent, ControllerRotationPlan, RecoveryConfig, RecoveryRecord, RecoveryRequest, RecoveryStatus,
+    RecoveryStatusResponse,
+};
+use wot_id_types::WotDid;
+
+use crate::handlers::{resolve_identity_object_via_move_contract, verify_signed_action};
+use crate::state::AppState;
+use crate::store::{now_secs, JsonStore};
+
+// The recovery types are shared wire types; the audit trail is only appended to here
+trait AuditTrail {
+    fn audit(&mut self, kind: &str, actor: &str, request_id: Option<&str>);
+}
+
+impl AuditTrail for RecoveryRecord {
+    fn audit(&mut self, kind: &str, actor: &str, request_id: Option<&str>) {
+        let event = AuditEvent {
+            timestamp: now_secs(),
+            kind: kind.to_string(),
+            actor: actor.to_string(),
+            request_id: request_id.map(str::to_string),
+        };
+        tracing::info!(
+            target: "identity_service::audit",
+            kind = %event.kind,
+            actor = %event.actor,
+            request_id = ?event.request_id,
+            "Recovery audit event."
+        );
+        self.audit.push(event);
+    }
+}
+
+pub type RecoveryStore = JsonStore<RecoveryRecord>;
+
+type Rejection = (StatusCode, String);
+
+// The shared `0x6` clock object taken by the time-locked `identity` entry functions
+const CLOCK_OBJECT_ID: &str = "0x6";
+
+#[derive(Deserialize)]
+struct RecoveryClaims {
+    // DID of the identity being recovered
+    subject: WotDid,
+    #[serde(default)]
+    guardians: Vec<WotDid>,
+    threshold: Option<usize>,
+    time_lock_secs: Option<u64>,
+    new_controller: Option<String>,
+    #[serde(default)]
+    remove_controllers: Vec<String>,
+    request_id: Option<String>,
+}
+
+async fn verified_claims(
+    app_state: &AppState,
+    payload: &SignedAction,
+    expected_action: &str,
+) -> Result<RecoveryClaims, Rejection> {
+    verify_signed_action(app_state, &payload.signer, &payload.jws, expected_action).await
+}
+
+// The chain enforces recovery, so every step needs the `wot_id` Identity objects involved
+async fn identity_object(app_state: &AppState, did: &WotDid) -> Result<String, Rejection> {
+    let registry_id = app_state.config.iota_identity_registry_id.as_deref().ok_or((
+        StatusCode::SERVICE_UNAVAILABLE,
+        "Recovery is unavailable: IOTA_IDENTITY_REGISTRY_ID is not set".to_string(),
+    ))?;
+    match resolve_identity_object_via_move_contract(&app_state.sdk_client, did, registry_id).await {
+        Ok(Some(object_id)) => Ok(object_id),
+        Ok(None) => Err((StatusCode::NOT_FOUND, format!("No wot_id Identity is registered for {}", did))),
+        Err(e) => {
+            tracing::error!(error = %e, did = %did, "Identity object lookup failed.");
+            Err((StatusCode::SERVICE_UNAVAILABLE, "Identity object lookup is unavailable".to_string()))
+        }
+    }
+}
+
+fn identity_call(app_state: &AppState, function: &str, arguments: Vec<Value>) -> Value {
+    json!({
+        "package": app_state.config.iota_identity_pkg_id,
+        "module": "identity",
+        "function": function,
+        "arguments": arguments,
+    })
+}
+
+fn status_response(did: &WotDid, record: &RecoveryRecord, call: Value) -> Json<RecoveryStatusResponse> {
+    Json(RecoveryStatusResponse { did: did.clone(), record: record.clone(), calls: vec![call] })
+}
+
+// Validated guardian settings; the threshold defaults to a majority of the guardians
+fn recovery_config(
+    subject: &WotDid,
+    mut guardians: Vec<WotDid>,
+    threshold: Option<usize>,
+    time_lock_secs: Option<u64>,
+    min_time_lock_secs: u64,
+) -> Result<RecoveryConfig, Rejection> {
+    guardians.sort();
+    guardians.dedup();
+    let threshold = threshold.unwrap_or(guardians.len() / 2 + 1);
+    let time_lock_secs = time_lock_secs.unwrap_or(min_time_lock_secs);
+
+    if guardians.contains(subject) {
+        return Err((StatusCode::BAD_REQUEST, "An identity cannot be its own guardian".to_string()));
+    }
+    if threshold == 0 || threshold > guardians.len() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Threshold must be between 1 and the number of guardians ({})", guardians.len()),
+        ));
+    }
+    if time_lock_secs < min_time_lock_secs {
+        return Err((StatusCode::BAD_REQUEST, format!("Time lock must be at least {} seconds", min_time_lock_secs)));
+    }
+    Ok(RecoveryConfig { guardians, threshold, time_lock_secs })
+}
+
+fn configure(record: &mut RecoveryRecord, config: RecoveryConfig, owner: &WotDid) -> Result<(), Rejection> {
+    if record.open_request().is_some() {
+        return Err((StatusCode::CONFLICT, "Cannot change guardians while a recovery is in progress".to_string()));
+    }
+    record.config = Some(config);
+    record.audit("configured", owner, None);
+    Ok(())
+}
+
+fn guardian_config(record: &RecoveryRecord, subject: &WotDid, guardian: &WotDid) -> Result<RecoveryConfig, Rejection> {
+    let config = record
+        .config
+        .clone()
+        .ok_or((StatusCode::NOT_FOUND, format!("No recovery configured for {}", subject)))?;
+    if !config.guardians.contains(guardian) {
+        return Err((StatusCode::FORBIDDEN, format!("{} is not a guardian of {}", guardian, subject)));
+    }
+    Ok(config)
+}
+
+fn start_time_lock(record: &mut RecoveryRecord, config: &RecoveryConfig, guardian: &WotDid, now: u64) {
+    let Some(request) = record.active.as_mut() else { return };
+    if request.status != RecoveryStatus::Pending || request.approvals.len() < config.threshold {
+        return;
+    }
+    request.status = RecoveryStatus::TimeLocked;
+    request.unlocks_at = Some(now + config.time_lock_secs);
+    let request_id = request.id.clone();
+    record.audit("time_lock_started", guardian, Some(&request_id));
+}
+
+fn initiate(
+    record: &mut RecoveryRecord,
+    subject: &WotDid,
+    guardian: &WotDid,
+    new_controller: String,
+    remove_controllers: Vec<String>,
+    now: u64,
+) -> Result<(), Rejection> {
+    let config = guardian_config(record, subject, guardian)?;
+    if record.open_request().is_some() {
+        return Err((StatusCode::CONFLICT, "A recovery is already in progress".to_string()));
+    }
+
+    let request = RecoveryRequest {
+        id: Uuid::new_v4().to_string(),
+        new_controller,
+        remove_controllers,
+        approvals: vec![guardian.clone()],
+        status: RecoveryStatus::Pending,
+        created_at: now,
+        unlocks_at: None,
+    };
+    let request_id = request.id.clone();
+    record.active = Some(request);
+    record.audit("initiated", guardian, Some(&request_id));
+    start_time_lock(record, &config, guardian, now);
+    Ok(())
+}
+
+fn approve(
+    record: &mut RecoveryRecord,
+    subject: &WotDid,
+    guardian: &WotDid,
+    request_id: Option<&str>,
+    now: u64,
+) -> Result<(), Rejection> {
+    let config = guardian_config(record, subject, guardian)?;
+    let request = record
+        .active
+        .as_mut()
+        .filter(|r| r.status == RecoveryStatus::Pending && Some(r.id.as_str()) == request_id)
+        .ok_or((StatusCode::NOT_FOUND, "No pending recovery request with that id".to_string()))?;
+    if request.approvals.contains(guardian) {
+        return Err((StatusCode::CONFLICT, "Guardian has already approved this request".to_string()));
+    }
+    request.approvals.push(guardian.clone());
+    let request_id = request.id.clone();
+    record.audit("approved", guardian, Some(&request_id));
+    start_time_lock(record, &config, guardian, now);
+    Ok(())
+}
+
+fn cancel(record: &mut RecoveryRecord, owner: &WotDid, request_id: Option<&str>) -> Result<(), Rejection> {
+    let request = record
+        .active
+        .as_mut()
+        .filter(|r| {
+            matches!(r.status, RecoveryStatus::Pending | RecoveryStatus::TimeLocked)
+                && Some(r.id.as_str()) == request_id
+        })
+        .ok_or((StatusCode::NOT_FOUND, "No open recovery request with that id".to_string()))?;
+    request.status = RecoveryStatus::Cancelled;
+    let request_id = request.id.clone();
+    record.audit("cancelled", owner, Some(&request_id));
+    Ok(())
+}
+
+// Marks the request executed. Asking again for an executed request returns it unchanged, so a
+// guardian whose response was lost can fetch the transaction again.
+fn execute(
+    record: &mut RecoveryRecord,
+    subject: &WotDid,
+    guardian: &WotDid,
+    request_id: Option<&str>,
+    now: u64,
+) -> Result<RecoveryRequest, Rejection> {
+    guardian_config(record, subject, guardian)?;
+    let request = record
+        .active
+        .as_mut()
+        .filter(|r| Some(r.id.as_str()) == request_id)
+        .ok_or((StatusCode::NOT_FOUND, "No recovery request with that id".to_string()))?;
+    match request.status {
+        RecoveryStatus::Executed => return Ok(request.clone()),
+        RecoveryStatus::TimeLocked => {}
+        _ => return Err((StatusCode::CONFLICT, "The recovery request has not reached its threshold".to_string())),
+    }
+    let unlocks_at = request.unlocks_at.unwrap_or(u64::MAX);
+    if now < unlocks_at {
+        return Err((
+            StatusCode::CONFLICT,
+            format!("Recovery is time-locked until {} (unix seconds)", unlocks_at),
+        ));
+    }
+    request.status = RecoveryStatus::Executed;
+    let request = request.clone();
+    record.audit("executed", guardian, Some(&request.id));
+    Ok(request)
+}
+
+// Owner sets (or replaces) guardians, threshold and time lock
+#[utoipa::path(
+    post,
+    path = "/api/v1/identity/recovery/configure",
+    tag = "recovery",
+    request_body(content = SignedAction, description = "`recovery.configure` action"),
+    responses(
+        (status = 200, description = "Includes the `configure_recovery` call the owner submits", body = RecoveryStatusResponse),
+        (status = 400, description = "Malformed DID or claims", body = String),
+        (status = 401, description = "Bad signature, or the action is expired", body = String),
+        (status = 403, description = "The signer is not the identity owner", body = String),
+        (status = 404, description = "The identity or a guardian has no wot_id Identity object", body = String),
+        (status = 409, description = "A recovery is in progress", body = String),
+        (status = 503, description = "Identity objects cannot be looked up", body = String),
+    )
+)]
+pub async fn configure_recovery_handler(
+    State(app_state): State<Arc<AppState>>,
+    Json(payload): Json<SignedAction>,
+) -> Result<Json<RecoveryStatusResponse>, (StatusCode, String)> {
+    let claims = verified_claims(&app_state, &payload, "recovery.configure").await?;
+    if claims.subject != payload.signer {
+        return Err((StatusCode::FORBIDDEN, "Only the identity owner can configure recovery".to_string()));
+    }
+    let config = recovery_config(
+        &claims.subject,
+        claims.guardians,
+        claims.threshold,
+        claims.time_lock_secs,
+        app_state.config.recovery_min_time_lock_secs,
+    )?;
+
+    let identity = identity_object(&app_state, &claims.subject).await?;
+    let mut guardian_objects = Vec::with_capacity(config.guardians.len());
+    for guardian in &config.guardians {
+        guardian_objects.push(identity_object(&app_state, guardian).await?);
+    }
+    let call = identity_call(
+        &app_state,
+        "configure_recovery",
+        vec![
+            json!(identity),
+            json!(guardian_objects),
+            json!(config.threshold),
+            json!(config.time_lock_secs.saturating_mul(1000)),
+        ],
+    );
+
+    let mut records = app_state.recovery.lock().await;
+    let record = records.entry(claims.subject.to_string()).or_default();
+    configure(record, config, &payload.signer)?;
+    let response = status_response(&claims.subject, record, call);
+    app_state.recovery.persist(&records).await?;
+    Ok(response)
+}
+
+// A guardian opens a recovery request naming the new controller; this counts as their approval
+#[utoipa::path(
+    post,
+    path = "/api/v1/identity/recovery/initiate",
+    tag = "recovery",
+    request_body(content = SignedAction, description = "`recovery.initiate` action"),
+    responses(
+        (status = 200, description = "Includes the `initiate_recovery` call the guardian submits", body = RecoveryStatusResponse),
+        (status = 400, description = "Malformed DID or claims", body = String),
+        (status = 401, description = "Bad signature, or the action is expired", body = String),
+        (status = 403, description = "The signer is not a guardian", body = String),
+        (status = 404, description = "No recovery configured, or no wot_id Identity object", body = String),
+        (status = 409, description = "A recovery is already in progress", body = String),
+        (status = 503, description = "Identity objects cannot be looked up", body = String),
+    )
+)]
+pub async fn initiate_recovery_handler(
+    State(app_state): State<Arc<AppState>>,
+    Json(payload): Json<SignedAction>,
+) -> Result<Json<RecoveryStatusResponse>, (StatusCode, String)> {
+    let claims = verified_claims(&app_state, &payload, "recovery.initiate").await?;
+    let new_controller = claims
+        .new_controller
+        .ok_or((StatusCode::BAD_REQUEST, "new_controller is required".to_string()))?;
+    let identity = identity_object(&app_state, &claims.subject).await?;
+    let guardian = identity_object(&app_state, &payload.signer).await?;
+    let call = identity_call(
+        &app_state,
+        "initiate_recovery",
+        vec![
+            json!(identity),
+            json!(guardian),
+            json!(new_controller),
+            json!(claims.remove_controllers),
+            json!(CLOCK_OBJECT_ID),
+        ],
+    );
+
+    let mut records = app_state.recovery.lock().await;
+    let record = records
+        .get_mut(claims.subject.as_str())
+        .ok_or((StatusCode::NOT_FOUND, format!("No recovery configured for {}", claims.subject)))?;
+    initiate(record, &claims.subject, &payload.signer, new_controller, claims.remove_controllers, now_secs())?;
+    let response = status_response(&claims.subject, record, call);
+    app_state.recovery.persist(&records).await?;
+    Ok(response)
+}
+
+// Another guardian co-signs the open request; reaching the threshold starts the time lock
+#[utoipa::path(
+    post,
+    path = "/api/v1/identity/recovery/approve",
+    tag = "recovery",
+    request_body(content = SignedAction, description = "`recovery.approve` action"),
+    responses(
+        (status = 200, description = "Includes the `approve_recovery` call the guardian submits", body = RecoveryStatusResponse),
+        (status = 400, description = "Malformed DID or claims", body = String),
+        (status = 401, description = "Bad signature, or the action is expired", body = String),
+        (status = 403, description = "The signer is not a guardian", body = String),
+        (status = 404, description = "No recovery configured, no pending request with that id, or no wot_id Identity object", body = String),
+        (status = 409, description = "The guardian already approved", body = String),
+        (status = 503, description = "Identity objects cannot be looked up", body = String),
+    )
+)]
+pub async fn approve_recovery_handler(
+    State(app_state): State<Arc<AppState>>,
+    Json(payload): Json<SignedAction>,
+) -> Result<Json<RecoveryStatusResponse>, (StatusCode, String)> {
+    let claims = verified_claims(&app_state, &payload, "recovery.approve").await?;
+    let identity = identity_object(&app_state, &claims.subject).await?;
+    let guardian = identity_object(&app_state, &payload.signer).await?;
+    let call = identity_call(
+        &app_state,
+        "approve_recovery",
+        vec![json!(identity), json!(guardian), json!(CLOCK_OBJECT_ID)],
+    );
+
+    let mut records = app_state.recovery.lock().await;
+    let record = records
+        .get_mut(claims.subject.as_str())
+        .ok_or((StatusCode::NOT_FOUND, format!("No recovery configured for {}", claims.subject)))?;
+    approve(record, &claims.subject, &payload.signer, claims.request_id.as_deref(), now_secs())?;
+    let response = status_response(&claims.subject, record, call);
+    app_state.recovery.persist(&records).await?;
+    Ok(response)
+}
+
+// The owner can still cancel a recovery they did not ask for until it has been executed
+#[utoipa::path(
+    post,
+    path = "/api/v1/identity/recovery/cancel",
+    tag = "recovery",
+    request_body(content = SignedAction, description = "`recovery.cancel` action"),
+    responses(
+        (status = 200, description = "Includes the `cancel_recovery` call the owner submits", body = RecoveryStatusResponse),
+        (status = 400, description = "Malformed DID or claims", body = String),
+        (status = 401, description = "Bad signature, or the action is expired", body = String),
+        (status = 403, description = "The signer is not the identity owner", body = String),
+        (status = 404, description = "No recovery configured, no open request with that id, or no wot_id Identity object", body = String),
+        (status = 503, description = "Identity objects cannot be looked up", body = String),
+    )
+)]
+pub async fn cancel_recovery_handler(
+    State(app_state): State<Arc<AppState>>,
+    Json(payload): Json<SignedAction>,
+) -> Result<Json<RecoveryStatusResponse>, (StatusCode, String)> {
+    let claims = verified_claims(&app_state, &payload, "recovery.cancel").await?;
+    if claims.subject != payload.signer {
+        return Err((StatusCode::FORBIDDEN, "Only the identity owner can cancel a recovery".to_string()));
+    }
+    let identity = identity_object(&app_state, &claims.subject).await?;
+    let call = identity_call(&app_state, "cancel_recovery", vec![json!(identity)]);
+
+    let mut records = app_state.recovery.lock().await;
+    let record = records
+        .get_mut(claims.subject.as_str())
+        .ok_or((StatusCode::NOT_FOUND, format!("No recovery configured for {}", claims.subject)))?;
+    cancel(record, &payload.signer, claims.request_id.as_deref())?;
+    let response = status_response(&claims.subject, record, call);
+    app_state.recovery.persist(&records).await?;
+    Ok(response)
+}
+
+// Once the time lock has elapsed, a guardian finalizes the request and gets the transaction the
+// new controller sends; `recover_controller` checks the approvals and time lock again on chain
+#[utoipa::path(
+    post,
+    path = "/api/v1/identity/recovery/execute",
+    tag = "recovery",
+    request_body(content = SignedAction, description = "`recovery.execute` action"),
+    responses(
+        (status = 200, description = "The `recover_controller` call the new controller submits from its wallet", body = ControllerRotationPlan),
+        (status = 400, description = "Malformed DID or claims", body = String),
+        (status = 401, description = "Bad signature, or the action is expired", body = String),
+        (status = 403, description = "The signer is not a guardian", body = String),
+        (status = 404, description = "No recovery configured, no request with that id, or no wot_id Identity object", body = String),
+        (status = 409, description = "The request has not reached its threshold, or the time lock has not elapsed", body = String),
+        (status = 503, description = "Identity objects cannot be looked up", body = String),
+    )
+)]
+pub async fn execute_recovery_handler(
+    State(app_state): State<Arc<AppState>>,
+    Json(payload): Json<SignedAction>,
+) -> Result<Json<ControllerRotationPlan>, (StatusCode, String)> {
+    let claims = verified_claims(&app_state, &payload, "recovery.execute").await?;
+    let identity_object_id = identity_object(&app_state, &claims.subject).await?;
+
+    let mut records = app_state.recovery.lock().await;
+    let record = records
+        .get_mut(claims.subject.as_str())
+        .ok_or((StatusCode::NOT_FOUND, format!("No recovery configured for {}", claims.subject)))?;
+    let request = execute(record, &claims.subject, &payload.signer, claims.request_id.as_deref(), now_secs())?;
+    let call = identity_call(
+        &app_state,
+        "recover_controller",
+        vec![json!(identity_object_id), json!(CLOCK_OBJECT_ID)],
+    );
+    let plan = ControllerRotationPlan {
+        did: claims.subject.clone(),
+        identity_object_id,
+        request_id: request.id,
+        approvals: request.approvals,
+        calls: vec![call],
+    };
+    app_state.recovery.persist(&records).await?;
+    Ok(Json(plan))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/identity/{did}/recovery",
+    tag = "recovery",
+    params(("did" = String, Path)),
+    responses(
+        (status = 200, body = RecoveryStatusResponse),
+        (status = 404, description = "No recovery configured", body = String),
+    )
+)]
+pub async fn recovery_status_handler(
+    State(app_state): State<Arc<AppState>>,
+    Path(did): Path<WotDid>,
+) -> Result<Json<RecoveryStatusResponse>, (StatusCode, String)> {
+    let records = app_state.recovery.lock().await;
+    let record = records
+        .get(did.as_str())
+        .ok_or((StatusCode::NOT_FOUND, format!("No recovery configured for {}", did)))?;
+    Ok(Json(RecoveryStatusResponse { did, record: record.clone(), calls: Vec::new() }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: u64 = 86400;
+
+    fn did(tag: &str) -> WotDid {
+        format!("did:iota:tst:0x{}", tag).parse().unwrap()
+    }
+
+    // Owner 0x01 guarded by 0xa1, 0xa2 and 0xa3
+    fn configured(threshold: usize) -> (WotDid, RecoveryRecord) {
+        let owner = did("01");
+        let config = recovery_config(&owner, vec![did("a1"), did("a2"), did("a3")], Some(threshold), None, DAY).unwrap();
+        let mut record = RecoveryRecord::default();
+        configure(&mut record, config, &owner).unwrap();
+        (owner, record)
+    }
+
+    fn open(record: &mut RecoveryRecord, owner: &WotDid, now: u64) -> String {
+        initiate(record, owner, &did("a1"), "0xnew".to_string(), vec!["0xlost".to_string()], now).unwrap();
+        record.active.as_ref().unwrap().id.clone()
+    }
+
+    #[test]
+    fn config_is_validated() {
+        let owner = did("01");
+        let guardians = vec![did("a1"), did("a2"), did("a1")];
+
+        let config = recovery_config(&owner, guardians.clone(), None, None, DAY).unwrap();
+        assert_eq!(config.guardians, vec![did("a1"), did("a2")]);
+        assert_eq!((config.threshold, config.time_lock_secs), (2, DAY));
+
+        for threshold in [0, 3] {
+            let err = recovery_config(&owner, guardians.clone(), Some(threshold), None, DAY).unwrap_err();
+            assert_eq!(err.0, StatusCode::BAD_REQUEST);
+        }
+        assert!(recovery_config(&owner, guardians.clone(), None, Some(DAY - 1), DAY).is_err());
+        assert!(recovery_config(&owner, vec![did("a1"), owner.clone()], None, None, DAY).is_err());
+    }
+
+    #[test]
+    fn threshold_of_approvals_starts_the_time_lock() {
+        let (owner, mut record) = configured(2);
+        let id = open(&mut record, &owner, 1000);
+        assert_eq!(record.active.as_ref().unwrap().status, RecoveryStatus::Pending);
+
+        let err = approve(&mut record, &owner, &did("a1"), Some(&id), 1000).unwrap_err();
+        assert_eq!(err.0, StatusCode::CONFLICT);
+        let err = approve(&mut record, &owner, &did("b1"), Some(&id), 1000).unwrap_err();
+        assert_eq!(err.0, StatusCode::FORBIDDEN);
+        let err = approve(&mut record, &owner, &did("a2"), Some("other"), 1000).unwrap_err();
+        assert_eq!(err.0, StatusCode::NOT_FOUND);
+
+        approve(&mut record, &owner, &did("a2"), Some(&id), 2000).unwrap();
+        let request = record.active.as_ref().unwrap();
+        assert_eq!(request.status, RecoveryStatus::TimeLocked);
+        assert_eq!(request.unlocks_at, Some(2000 + DAY));
+
+        // Late approvals do not move the time lock
+        let err = approve(&mut record, &owner, &did("a3"), Some(&id), 3000).unwrap_err();
+        assert_eq!(err.0, StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn single_guardian_threshold_locks_on_initiate() {
+        let (owner, mut record) = configured(1);
+        open(&mut record, &owner, 1000);
+        let request = record.active.as_ref().unwrap();
+        assert_eq!(request.status, RecoveryStatus::TimeLocked);
+        assert_eq!(request.unlocks_at, Some(1000 + DAY));
+
+        let err = initiate(&mut record, &owner, &did("a2"), "0xother".to_string(), Vec::new(), 1000).unwrap_err();
+        assert_eq!(err.0, StatusCode::CONFLICT);
+    }
+
+    #[test]
+    fn execute_waits_for_threshold_and_time_lock() {
+        let (owner, mut record) = configured(2);
+        let id = open(&mut record, &owner, 1000);
+        let err = execute(&mut record, &owner, &did("a1"), Some(&id), 1000 + 2 * DAY).unwrap_err();
+        assert_eq!(err.0, StatusCode::CONFLICT);
+
+        approve(&mut record, &owner, &did("a2"), Some(&id), 1000).unwrap();
+        let err = execute(&mut record, &owner, &did("a1"), Some(&id), 1000 + DAY - 1).unwrap_err();
+        assert_eq!(err.0, StatusCode::CONFLICT);
+        let err = execute(&mut record, &owner, &did("b1"), Some(&id), 1000 + DAY).unwrap_err();
+        assert_eq!(err.0, StatusCode::FORBIDDEN);
+
+        let request = execute(&mut record, &owner, &did("a1"), Some(&id), 1000 + DAY).unwrap();
+        assert_eq!(request.status, RecoveryStatus::Executed);
+        assert_eq!(request.new_controller, "0xnew");
+        assert_eq!(request.approvals, vec![did("a1"), did("a2")]);
+    }
+
+    #[test]
+    fn execute_is_idempotent() {
+        let (owner, mut record) = configured(1);
+        let id = open(&mut record, &owner, 1000);
+        let first = execute(&mut record, &owner, &did("a1"), Some(&id), 1000 + DAY).unwrap();
+        let audited = record.audit.len();
+
+        let again = execute(&mut record, &owner, &did("a2"), Some(&id), 1000 + 2 * DAY).unwrap();
+        assert_eq!((again.id, again.status), (first.id, RecoveryStatus::Executed));
+        assert_eq!(record.audit.len(), audited);
+        let err = cancel(&mut record, &owner, Some(&id)).unwrap_err();
+        assert_eq!(err.0, StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn cancel_stops_an_open_request() {
+        let (owner, mut record) = configured(1);
+        let id = open(&mut record, &owner, 1000);
+        let err = cancel(&mut record, &owner, Some("other")).unwrap_err();
+        assert_eq!(err.0, StatusCode::NOT_FOUND);
+
+        cancel(&mut record, &owner, Some(&id)).unwrap();
+        assert_eq!(record.active.as_ref().unwrap().status, RecoveryStatus::Cancelled);
+        assert!(record.open_request().is_none());
+        let err = execute(&mut record, &owner, &did("a1"), Some(&id), 1000 + DAY).unwrap_err();
+        assert_eq!(err.0, StatusCode::CONFLICT);
+
+        // The guardians can be changed again, and a new request opened
+        let config = recovery_config(&owner, vec![did("a1")], None, None, DAY).unwrap();
+        configure(&mut record, config, &owner).unwrap();
+        open(&mut record, &owner, 2000);
+    }
+
+    #[test]
+    fn guardians_cannot_change_during_a_recovery() {
+        let (owner, mut record) = configured(2);
+        open(&mut record, &owner, 1000);
+        let config = recovery_config(&owner, vec![did("a1")], None, None, DAY).unwrap();
+        let err = configure(&mut record, config, &owner).unwrap_err();
+        assert_eq!(err.0, StatusCode::CONFLICT);
+    }
+}
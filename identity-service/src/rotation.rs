@@ -0,0 +1,345 @@
+use axum::{extract::{Path, State}, http::StatusCode, Json};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+use uuid::Uuid;
+use wot_id_types::identity::SignedAction;
+use wot_id_types::rotation::{
+    KeyRotation, RotateKeyResponse, RotationStatus, RotationsResponse, VerifyHistoricalRequest,
+    VerifyHistoricalResponse, VersionSummary,
+};
+use wot_id_types::WotDid;
+
+use identity_iota::iota::IotaDocument;
+use identity_iota::verification::jwk::Jwk;
+use identity_iota::verification::{MethodRelationship, MethodScope, VerificationMethod};
+
+use crate::handlers::{parse_iota_did, resolve_current_document, signature_policy, verify_signed_action};
+use crate::signatures::{self, VerifiedJws};
+use crate::state::AppState;
+use crate::store::{now_secs, JsonStore};
+
+// Snapshot of a DID document taken before it changed, kept so signatures made with
+// since-removed keys remain verifiable for history and audit
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DocumentVersion {
+    pub version: u32,
+    pub captured_at: u64,
+    pub reason: String,
+    pub document: Value,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RotationRecord {
+    pub rotations: Vec<KeyRotation>,
+    pub versions: Vec<DocumentVersion>,
+}
+
+// DID documents go over the wire, and into the store, as their JSON form
+fn document_json(document: &IotaDocument) -> Result<Value, (StatusCode, String)> {
+    serde_json::to_value(document)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to serialize DID document: {}", e)))
+}
+
+impl RotationRecord {
+    fn capture(&mut self, document: &IotaDocument, reason: String) -> Result<u32, (StatusCode, String)> {
+        let document = document_json(document)?;
+        let version = self.versions.last().map(|v| v.version + 1).unwrap_or(1);
+        self.versions.push(DocumentVersion { version, captured_at: now_secs(), reason, document });
+        Ok(version)
+    }
+}
+
+pub type RotationStore = JsonStore<RotationRecord>;
+
+// `kid` may be a full DID URL or a bare `#fragment`; rotations are tracked by fragment
+fn fragment_of(method_ref: &str) -> &str {
+    method_ref.rsplit('#').next().unwrap_or(method_ref)
+}
+
+// The first of `kids` naming a method whose rotation grace period has ended at `now`
+fn retired_method<'a>(record: &RotationRecord, mut kids: impl Iterator<Item = &'a str>, now: u64) -> Option<&'a str> {
+    kids.find(|kid| {
+        record
+            .rotations
+            .iter()
+            .any(|r| r.old_method == fragment_of(kid) && r.is_retired(now))
+    })
+}
+
+// The method of `did` that signed `verified` and has since been retired, if any. Such a key no
+// longer counts, even while it is still published.
+pub async fn retired_signer(app_state: &AppState, did: &str, verified: &VerifiedJws) -> Option<String> {
+    let records = app_state.rotations.lock().await;
+    retired_in(records.get(did)?, verified, now_secs())
+}
+
+// The method that signed `verified` and is retired in `record` at `now`, if any
+pub fn retired_in(record: &RotationRecord, verified: &VerifiedJws, now: u64) -> Option<String> {
+    retired_method(record, verified.signatures.iter().map(|s| s.kid.as_str()), now).map(str::to_string)
+}
+
+// A method can only be in one rotation at a time; once retired it may be rotated again
+fn ensure_not_rotating(record: &RotationRecord, old_fragment: &str, now: u64) -> Result<(), (StatusCode, String)> {
+    if record.rotations.iter().any(|r| r.old_method == old_fragment && !r.is_retired(now)) {
+        return Err((StatusCode::CONFLICT, format!("Method '{}' is already being rotated", old_fragment)));
+    }
+    Ok(())
+}
+
+// When the old key retires: after the requested grace period, or the default, capped at `max`
+fn retire_at(requested: Option<u64>, default: u64, max: u64, now: u64) -> Result<u64, (StatusCode, String)> {
+    let grace_period = requested.unwrap_or(default).min(max);
+    now.checked_add(grace_period)
+        .ok_or((StatusCode::BAD_REQUEST, format!("A grace period of {}s ends too far in the future", grace_period)))
+}
+
+#[derive(Deserialize)]
+struct RotateKeyClaims {
+    subject: WotDid,
+    old_method: String,
+    new_method: String,
+    // Public key of the new method as a JWK (no private parameters)
+    new_public_key: Jwk,
+    grace_period_secs: Option<u64>,
+}
+
+// Start a rotation: returns the document with the new method added. The request must be
+// signed by the owner with a key that is currently in the document (usually the old one).
+#[utoipa::path(
+    post,
+    path = "/api/v1/identity/keys/rotate",
+    tag = "rotation",
+    request_body(content = SignedAction, description = "`key.rotate` action"),
+    responses(
+        (status = 200, body = RotateKeyResponse),
+        (status = 400, description = "Malformed DID, claims or new key, or a grace period that overflows", body = String),
+        (status = 401, description = "Bad signature, or the action is expired", body = String),
+        (status = 403, description = "The signer is not the identity owner", body = String),
+        (status = 404, description = "The old method is not in the document", body = String),
+        (status = 409, description = "The new method exists, or the old one is already being rotated", body = String),
+    )
+)]
+pub async fn rotate_key_handler(
+    State(app_state): State<Arc<AppState>>,
+    Json(payload): Json<SignedAction>,
+) -> Result<Json<RotateKeyResponse>, (StatusCode, String)> {
+    let claims: RotateKeyClaims = verify_signed_action(&app_state, &payload.signer, &payload.jws, "key.rotate").await?;
+    if claims.subject != payload.signer {
+        return Err((StatusCode::FORBIDDEN, "Only the identity owner can rotate its keys".to_string()));
+    }
+    if claims.new_public_key.is_private() {
+        return Err((StatusCode::BAD_REQUEST, "new_public_key must not contain private key material".to_string()));
+    }
+    let old_fragment = fragment_of(&claims.old_method).to_string();
+    let new_fragment = fragment_of(&claims.new_method).to_string();
+
+    let did = parse_iota_did(&claims.subject)?;
+    let current = resolve_current_document(&app_state, &did).await?;
+    let method_url = |fragment: &str| {
+        did.to_url()
+            .join(format!("#{}", fragment))
+            .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid method fragment '{}': {}", fragment, e)))
+    };
+    let old_url = method_url(&old_fragment)?;
+    let new_url = method_url(&new_fragment)?;
+    if current.resolve_method(&old_url, None).is_none() {
+        return Err((StatusCode::NOT_FOUND, format!("{} has no method '{}'", did, old_fragment)));
+    }
+    if current.resolve_method(&new_url, None).is_some() {
+        return Err((StatusCode::CONFLICT, format!("{} already has a method '{}'", did, new_fragment)));
+    }
+
+    let mut updated = current.clone();
+    let method = VerificationMethod::new_from_jwk(did.clone(), claims.new_public_key, Some(&new_fragment))
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid new_public_key: {}", e)))?;
+    updated
+        .insert_method(method, MethodScope::VerificationMethod)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Failed to add method: {}", e)))?;
+    updated
+        .attach_method_relationship(&new_url, MethodRelationship::Authentication)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Failed to attach authentication relationship: {}", e)))?;
+
+    let now = now_secs();
+    let config = &app_state.config;
+    let rotation = KeyRotation {
+        id: Uuid::new_v4().to_string(),
+        old_method: old_fragment.clone(),
+        new_method: new_fragment,
+        started_at: now,
+        retire_at: retire_at(
+            claims.grace_period_secs,
+            config.key_rotation_grace_period_secs,
+            config.key_rotation_max_grace_period_secs,
+            now,
+        )?,
+    };
+
+    let mut records = app_state.rotations.lock().await;
+    let record = records.entry(claims.subject.to_string()).or_default();
+    ensure_not_rotating(record, &old_fragment, now)?;
+    let previous_version = record.capture(&current, format!("before rotation {}", rotation.id))?;
+    let response = RotateKeyResponse {
+        rotation_id: rotation.id.clone(),
+        retire_at: rotation.retire_at,
+        previous_version,
+        document: document_json(&updated)?,
+    };
+    tracing::info!(
+        target: "identity_service::audit",
+        did = %claims.subject,
+        rotation_id = %rotation.id,
+        old_method = %rotation.old_method,
+        new_method = %rotation.new_method,
+        retire_at = rotation.retire_at,
+        "Key rotation started."
+    );
+    record.rotations.push(rotation);
+    app_state.rotations.persist(&records).await?;
+
+    Ok(Json(response))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/identity/{did}/rotations",
+    tag = "rotation",
+    params(("did" = String, Path)),
+    responses(
+        (status = 200, body = RotationsResponse),
+        (status = 400, description = "Malformed DID", body = String),
+        (status = 500, description = "DID resolution failed", body = String),
+    )
+)]
+pub async fn rotations_handler(
+    State(app_state): State<Arc<AppState>>,
+    Path(did_str): Path<WotDid>,
+) -> Result<Json<RotationsResponse>, (StatusCode, String)> {
+    let did = parse_iota_did(&did_str)?;
+    let record = app_state.rotations.lock().await.get(did_str.as_str()).cloned().unwrap_or_default();
+    let now = now_secs();
+
+    let mut finalize_document = None;
+    let retired: Vec<&KeyRotation> = record.rotations.iter().filter(|r| r.is_retired(now)).collect();
+    if !retired.is_empty() {
+        let mut current = resolve_current_document(&app_state, &did).await?;
+        let mut changed = false;
+        for rotation in retired {
+            if let Ok(url) = did.to_url().join(format!("#{}", rotation.old_method)) {
+                changed |= current.remove_method(&url).is_some();
+            }
+        }
+        if changed {
+            finalize_document = Some(document_json(&current)?);
+        }
+    }
+
+    Ok(Json(RotationsResponse {
+        did: did_str,
+        rotations: record
+            .rotations
+            .iter()
+            .map(|r| RotationStatus { rotation: r.clone(), retired: r.is_retired(now) })
+            .collect(),
+        versions: record
+            .versions
+            .iter()
+            .map(|v| VersionSummary { version: v.version, captured_at: v.captured_at, reason: v.reason.clone() })
+            .collect(),
+        finalize_document,
+    }))
+}
+
+// Audit check: was this JWS valid under some recorded version of the DID document? Retirement
+// is deliberately not enforced here; the hybrid-signature policy is.
+#[utoipa::path(
+    post,
+    path = "/api/v1/identity/verify-historical",
+    tag = "rotation",
+    request_body = VerifyHistoricalRequest,
+    responses((status = 200, body = VerifyHistoricalResponse))
+)]
+pub async fn verify_historical_handler(
+    State(app_state): State<Arc<AppState>>,
+    Json(payload): Json<VerifyHistoricalRequest>,
+) -> Result<Json<VerifyHistoricalResponse>, (StatusCode, String)> {
+    let record = app_state.rotations.lock().await.get(payload.did.as_str()).cloned().unwrap_or_default();
+
+    for version in record.versions.iter().rev() {
+        if payload.version.is_some_and(|v| v != version.version) {
+            continue;
+        }
+        let document: IotaDocument = match serde_json::from_value(version.document.clone()) {
+            Ok(doc) => doc,
+            Err(e) => {
+                tracing::warn!(
+                    did = %payload.did,
+                    version = version.version,
+                    error = %e,
+                    "Stored document version is unreadable."
+                );
+                continue;
+            }
+        };
+        if let Ok(verified) = signatures::verify_jws(&document, &payload.jws, signature_policy(&app_state)) {
+            return Ok(Json(VerifyHistoricalResponse {
+                is_valid: true,
+                version: Some(version.version),
+                claims: serde_json::from_slice(&verified.claims).ok(),
+            }));
+        }
+    }
+
+    Ok(Json(VerifyHistoricalResponse { is_valid: false, version: None, claims: None }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rotation(old_method: &str, started_at: u64, retire_at: u64) -> KeyRotation {
+        KeyRotation {
+            id: Uuid::new_v4().to_string(),
+            old_method: old_method.to_string(),
+            new_method: format!("{}-next", old_method),
+            started_at,
+            retire_at,
+        }
+    }
+
+    #[test]
+    fn old_key_retires_exactly_at_the_end_of_the_grace_period() {
+        let record = RotationRecord { rotations: vec![rotation("key-1", 1000, 2000)], versions: Vec::new() };
+        let kids = || ["did:iota:tst:0x01#key-1"].into_iter();
+
+        assert_eq!(retired_method(&record, kids(), 1999), None);
+        assert_eq!(retired_method(&record, kids(), 2000), Some("did:iota:tst:0x01#key-1"));
+        assert_eq!(retired_method(&record, ["#key-1"].into_iter(), 2000), Some("#key-1"));
+        // The new key and unrelated keys are unaffected
+        assert_eq!(retired_method(&record, ["#key-1-next", "#key-2"].into_iter(), 5000), None);
+        // A hybrid signature is rejected if either of its keys is retired
+        assert_eq!(retired_method(&record, ["#key-2", "#key-1"].into_iter(), 5000), Some("#key-1"));
+    }
+
+    #[test]
+    fn method_cannot_be_rotated_twice_at_once() {
+        let mut record = RotationRecord::default();
+        assert!(ensure_not_rotating(&record, "key-1", 1000).is_ok());
+        record.rotations.push(rotation("key-1", 1000, 2000));
+
+        let err = ensure_not_rotating(&record, "key-1", 1999).unwrap_err();
+        assert_eq!(err.0, StatusCode::CONFLICT);
+        assert!(ensure_not_rotating(&record, "key-2", 1999).is_ok());
+        assert!(ensure_not_rotating(&record, "key-1", 2000).is_ok());
+    }
+
+    #[test]
+    fn grace_period_is_capped_and_cannot_overflow() {
+        assert_eq!(retire_at(None, 100, 1000, 5000), Ok(5100));
+        assert_eq!(retire_at(Some(500), 100, 1000, 5000), Ok(5500));
+        assert_eq!(retire_at(Some(u64::MAX), 100, 1000, 5000), Ok(6000));
+
+        let err = retire_at(Some(u64::MAX), 100, u64::MAX, 5000).unwrap_err();
+        assert_eq!(err.0, StatusCode::BAD_REQUEST);
+    }
+}
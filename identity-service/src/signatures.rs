@@ -0,0 +1,370 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD as BASE64URL, Engine};
+use ml_dsa::signature::{Signer as _, Verifier as _};
+use ml_dsa::{EncodedSignature, EncodedVerifyingKey, KeyGen, KeyPair, MlDsa44, MlDsa65, MlDsa87, MlDsaParams, Signature, VerifyingKey, B32};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use identity_eddsa_verifier::EdDSAJwsVerifier;
+use identity_iota::document::verifiable::JwsVerificationOptions;
+use identity_iota::iota::IotaDocument;
+use identity_iota::verification::VerificationMethod;
+
+pub const ALG_EDDSA: &str = "EdDSA";
+pub const ALG_ML_DSA_44: &str = "ML-DSA-44";
+pub const ALG_ML_DSA_65: &str = "ML-DSA-65";
+pub const ALG_ML_DSA_87: &str = "ML-DSA-87";
+
+// JWK key type for ML-DSA public keys (draft-ietf-cose-dilithium); the key is in `pub`
+const KTY_AKP: &str = "AKP";
+
+fn is_ml_dsa(alg: &str) -> bool {
+    matches!(alg, ALG_ML_DSA_44 | ALG_ML_DSA_65 | ALG_ML_DSA_87)
+}
+
+// Which signatures are acceptable. A hybrid JWS carries an Ed25519 and an ML-DSA signature
+// over the same payload, so it stays secure as long as either algorithm holds.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SignaturePolicy {
+    pub require_hybrid: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct VerifiedSignature {
+    pub kid: String,
+    pub alg: String,
+}
+
+pub struct VerifiedJws {
+    pub claims: Vec<u8>,
+    pub signatures: Vec<VerifiedSignature>,
+}
+
+impl VerifiedJws {
+    pub fn is_hybrid(&self) -> bool {
+        self.signatures.iter().any(|s| s.alg == ALG_EDDSA) && self.signatures.iter().any(|s| is_ml_dsa(&s.alg))
+    }
+
+    pub fn algorithms(&self) -> Vec<String> {
+        self.signatures.iter().map(|s| s.alg.clone()).collect()
+    }
+}
+
+// One signature of a JWS in general JSON serialization (RFC 7515 section 7.2.1)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct JwsSignature {
+    pub protected: String,
+    pub signature: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct GeneralJws {
+    payload: String,
+    signatures: Vec<JwsSignature>,
+}
+
+#[derive(Deserialize)]
+struct ProtectedHeader {
+    alg: String,
+    kid: Option<String>,
+}
+
+fn decode(value: &str, what: &str) -> Result<Vec<u8>, String> {
+    BASE64URL.decode(value).map_err(|e| format!("Invalid base64url in {}: {}", what, e))
+}
+
+// Split a JWS into its payload and signatures. Single-algorithm signatures use compact
+// serialization; hybrid signatures use general JSON serialization.
+fn parse_jws(jws: &str) -> Result<(String, Vec<JwsSignature>), String> {
+    let jws = jws.trim();
+    if jws.starts_with('{') {
+        let general: GeneralJws = serde_json::from_str(jws).map_err(|e| format!("Invalid JSON JWS: {}", e))?;
+        if general.signatures.is_empty() {
+            return Err("JWS has no signatures".to_string());
+        }
+        return Ok((general.payload, general.signatures));
+    }
+    let mut parts = jws.split('.');
+    match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(protected), Some(payload), Some(signature), None) => Ok((
+            payload.to_string(),
+            vec![JwsSignature { protected: protected.to_string(), signature: signature.to_string() }],
+        )),
+        _ => Err("JWS is neither compact nor JSON serialized".to_string()),
+    }
+}
+
+// Decode the claims of a JWS without verifying it, e.g. to find the issuer to resolve
+pub fn unverified_claims(jws: &str) -> Result<Vec<u8>, String> {
+    let (payload, _) = parse_jws(jws)?;
+    decode(&payload, "payload")
+}
+
+// Verify every signature of `jws` against methods of `document`, then apply `policy`
+pub fn verify_jws(document: &IotaDocument, jws: &str, policy: SignaturePolicy) -> Result<VerifiedJws, String> {
+    let _span = tracing::info_span!("jws_verification", did = %document.id()).entered();
+    let (payload, signatures) = parse_jws(jws)?;
+    let signatures = signatures
+        .iter()
+        .map(|s| verify_signature(document, &s.protected, &payload, &s.signature))
+        .collect::<Result<Vec<_>, String>>()?;
+    let verified = VerifiedJws { claims: decode(&payload, "payload")?, signatures };
+
+    if policy.require_hybrid && !verified.is_hybrid() {
+        return Err("Policy requires a hybrid Ed25519 + ML-DSA signature".to_string());
+    }
+    Ok(verified)
+}
+
+fn verify_signature(
+    document: &IotaDocument,
+    protected: &str,
+    payload: &str,
+    signature: &str,
+) -> Result<VerifiedSignature, String> {
+    let header: ProtectedHeader = serde_json::from_slice(&decode(protected, "protected header")?)
+        .map_err(|e| format!("Invalid protected header: {}", e))?;
+
+    match header.alg.as_str() {
+        ALG_EDDSA => {
+            let compact = format!("{}.{}.{}", protected, payload, signature);
+            let decoded = document
+                .core_document()
+                .verify_jws(&compact, None, &EdDSAJwsVerifier::default(), &JwsVerificationOptions::default())
+                .map_err(|e| format!("EdDSA signature verification failed: {}", e))?;
+            Ok(VerifiedSignature {
+                kid: decoded.protected.kid().unwrap_or_default().to_string(),
+                alg: header.alg,
+            })
+        }
+        alg if is_ml_dsa(alg) => {
+            let kid = header.kid.ok_or("ML-DSA signature has no kid")?;
+            let method = document
+                .core_document()
+                .resolve_method(kid.as_str(), None)
+                .ok_or_else(|| format!("{} is not a method of {}", kid, document.id()))?;
+            let public_key = ml_dsa_public_key(method, alg)?;
+            let signing_input = format!("{}.{}", protected, payload);
+            verify_ml_dsa(alg, &public_key, signing_input.as_bytes(), &decode(signature, "signature")?)?;
+            Ok(VerifiedSignature { kid: method.id().to_string(), alg: header.alg })
+        }
+        other => Err(format!("Unsupported JWS algorithm {}", other)),
+    }
+}
+
+// Raw ML-DSA public key from the method's `publicKeyJwk` (`kty: AKP`)
+fn ml_dsa_public_key(method: &VerificationMethod, alg: &str) -> Result<Vec<u8>, String> {
+    let method_json = serde_json::to_value(method).map_err(|e| format!("Unreadable verification method: {}", e))?;
+    let jwk = method_json
+        .get("publicKeyJwk")
+        .ok_or_else(|| format!("Method {} has no publicKeyJwk", method.id()))?;
+    if jwk.get("kty").and_then(Value::as_str) != Some(KTY_AKP) {
+        return Err(format!("Method {} is not an ML-DSA key", method.id()));
+    }
+    if jwk.get("alg").and_then(Value::as_str).is_some_and(|key_alg| key_alg != alg) {
+        return Err(format!("Method {} is not a {} key", method.id(), alg));
+    }
+    let public_key = jwk
+        .get("pub")
+        .and_then(Value::as_str)
+        .ok_or_else(|| format!("Method {} has no public key", method.id()))?;
+    decode(public_key, "publicKeyJwk.pub")
+}
+
+fn verify_ml_dsa(alg: &str, public_key: &[u8], message: &[u8], signature: &[u8]) -> Result<(), String> {
+    match alg {
+        ALG_ML_DSA_44 => verify_ml_dsa_with::<MlDsa44>(public_key, message, signature),
+        ALG_ML_DSA_65 => verify_ml_dsa_with::<MlDsa65>(public_key, message, signature),
+        ALG_ML_DSA_87 => verify_ml_dsa_with::<MlDsa87>(public_key, message, signature),
+        other => Err(format!("Unsupported ML-DSA parameter set {}", other)),
+    }
+}
+
+fn verify_ml_dsa_with<P: MlDsaParams>(public_key: &[u8], message: &[u8], signature: &[u8]) -> Result<(), String> {
+    let public_key = EncodedVerifyingKey::<P>::try_from(public_key).map_err(|_| "ML-DSA public key has the wrong length")?;
+    let signature = EncodedSignature::<P>::try_from(signature).map_err(|_| "ML-DSA signature has the wrong length")?;
+    let signature = Signature::<P>::decode(&signature).ok_or("ML-DSA signature is malformed")?;
+    VerifyingKey::<P>::decode(&public_key)
+        .verify(message, &signature)
+        .map_err(|_| "ML-DSA signature verification failed".to_string())
+}
+
+enum SigningKey {
+    Ed25519(ed25519_dalek::SigningKey),
+    MlDsa65(Box<KeyPair<MlDsa65>>),
+}
+
+// A private key held by this service, used to issue credentials
+pub struct JwsSigner {
+    pub kid: String,
+    key: SigningKey,
+}
+
+impl JwsSigner {
+    pub fn ed25519(kid: String, secret: [u8; 32]) -> Self {
+        Self { kid, key: SigningKey::Ed25519(ed25519_dalek::SigningKey::from_bytes(&secret)) }
+    }
+
+    // ML-DSA-65 key pair derived deterministically from a 32-byte seed (FIPS 204 ML-DSA.KeyGen_internal)
+    pub fn ml_dsa_65(kid: String, seed: [u8; 32]) -> Self {
+        Self { kid, key: SigningKey::MlDsa65(Box::new(MlDsa65::key_gen_internal(&B32::from(seed)))) }
+    }
+
+    pub fn alg(&self) -> &'static str {
+        match self.key {
+            SigningKey::Ed25519(_) => ALG_EDDSA,
+            SigningKey::MlDsa65(_) => ALG_ML_DSA_65,
+        }
+    }
+
+    // Public key as the JWK to publish in the issuer's DID document
+    pub fn public_jwk(&self) -> Value {
+        match &self.key {
+            SigningKey::Ed25519(key) => json!({
+                "kty": "OKP",
+                "crv": "Ed25519",
+                "x": BASE64URL.encode(key.verifying_key().as_bytes()),
+            }),
+            SigningKey::MlDsa65(key) => json!({
+                "kty": KTY_AKP,
+                "alg": ALG_ML_DSA_65,
+                "pub": BASE64URL.encode(key.verifying_key().encode()),
+            }),
+        }
+    }
+
+    fn sign(&self, signing_input: &[u8]) -> Vec<u8> {
+        match &self.key {
+            SigningKey::Ed25519(key) => ed25519_dalek::Signer::sign(key, signing_input).to_bytes().to_vec(),
+            SigningKey::MlDsa65(key) => key.signing_key().sign(signing_input).encode().to_vec(),
+        }
+    }
+}
+
+// Sign `payload` with every signer: compact serialization for one, general JSON for several
+pub fn sign_jws(payload: &[u8], typ: &str, signers: &[&JwsSigner]) -> String {
+    let payload = BASE64URL.encode(payload);
+    let signatures: Vec<JwsSignature> = signers
+        .iter()
+        .map(|signer| {
+            let header = json!({ "alg": signer.alg(), "kid": signer.kid, "typ": typ });
+            let protected = BASE64URL.encode(header.to_string());
+            let signature = BASE64URL.encode(signer.sign(format!("{}.{}", protected, payload).as_bytes()));
+            JwsSignature { protected, signature }
+        })
+        .collect();
+
+    match signatures.as_slice() {
+        [single] => format!("{}.{}.{}", single.protected, payload, single.signature),
+        _ => serde_json::to_string(&GeneralJws { payload, signatures }).expect("JWS serializes to JSON"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use identity_iota::iota::NetworkName;
+    use identity_iota::verification::jwk::Jwk;
+    use identity_iota::verification::MethodScope;
+
+    const CLAIMS: &[u8] = br#"{"iss":"issuer","sub":"subject"}"#;
+
+    fn document() -> IotaDocument {
+        IotaDocument::new(&NetworkName::try_from("tst").unwrap())
+    }
+
+    fn signers(document: &IotaDocument) -> (JwsSigner, JwsSigner) {
+        (
+            JwsSigner::ed25519(format!("{}#ed25519", document.id()), [1; 32]),
+            JwsSigner::ml_dsa_65(format!("{}#ml-dsa", document.id()), [2; 32]),
+        )
+    }
+
+    // Publish each signer's public key as a verification method of `document`
+    fn publish(document: &mut IotaDocument, signers: &[&JwsSigner]) {
+        for signer in signers {
+            let (_, fragment) = signer.kid.split_once('#').unwrap();
+            let jwk: Jwk = serde_json::from_value(signer.public_jwk()).unwrap();
+            let method = VerificationMethod::new_from_jwk(document.id().clone(), jwk, Some(fragment)).unwrap();
+            document.insert_method(method, MethodScope::VerificationMethod).unwrap();
+        }
+    }
+
+    fn hybrid_policy() -> SignaturePolicy {
+        SignaturePolicy { require_hybrid: true }
+    }
+
+    #[test]
+    fn eddsa_only_round_trip() {
+        let mut document = document();
+        let (ed25519, ml_dsa) = signers(&document);
+        publish(&mut document, &[&ed25519, &ml_dsa]);
+
+        let jws = sign_jws(CLAIMS, "JWT", &[&ed25519]);
+        assert_eq!(jws.split('.').count(), 3);
+        let verified = verify_jws(&document, &jws, SignaturePolicy::default()).unwrap();
+        assert_eq!(verified.claims, CLAIMS);
+        assert_eq!(verified.algorithms(), vec![ALG_EDDSA]);
+        assert!(!verified.is_hybrid());
+        assert!(verify_jws(&document, &jws, hybrid_policy()).is_err());
+    }
+
+    #[test]
+    fn ml_dsa_only_round_trip() {
+        let mut document = document();
+        let (ed25519, ml_dsa) = signers(&document);
+        publish(&mut document, &[&ed25519, &ml_dsa]);
+
+        let jws = sign_jws(CLAIMS, "JWT", &[&ml_dsa]);
+        let verified = verify_jws(&document, &jws, SignaturePolicy::default()).unwrap();
+        assert_eq!(verified.claims, CLAIMS);
+        assert_eq!(verified.algorithms(), vec![ALG_ML_DSA_65]);
+        assert_eq!(verified.signatures[0].kid, ml_dsa.kid);
+        assert!(!verified.is_hybrid());
+        assert!(verify_jws(&document, &jws, hybrid_policy()).is_err());
+    }
+
+    #[test]
+    fn hybrid_round_trip_satisfies_the_policy() {
+        let mut document = document();
+        let (ed25519, ml_dsa) = signers(&document);
+        publish(&mut document, &[&ed25519, &ml_dsa]);
+
+        let jws = sign_jws(CLAIMS, "JWT", &[&ml_dsa, &ed25519]);
+        assert!(jws.starts_with('{'));
+        let verified = verify_jws(&document, &jws, hybrid_policy()).unwrap();
+        assert_eq!(verified.claims, CLAIMS);
+        assert!(verified.is_hybrid());
+        assert_eq!(verified.algorithms(), vec![ALG_ML_DSA_65, ALG_EDDSA]);
+    }
+
+    #[test]
+    fn hybrid_is_rejected_if_either_signature_fails() {
+        let mut document = document();
+        let (ed25519, ml_dsa) = signers(&document);
+        publish(&mut document, &[&ed25519, &ml_dsa]);
+        let mut general: GeneralJws = serde_json::from_str(&sign_jws(CLAIMS, "JWT", &[&ml_dsa, &ed25519])).unwrap();
+
+        // Swapping in a signature over other claims breaks only the ML-DSA half
+        let other = sign_jws(br#"{"iss":"issuer","sub":"mallory"}"#, "JWT", &[&ml_dsa]);
+        general.signatures[0].signature = other.rsplit('.').next().unwrap().to_string();
+        let tampered = serde_json::to_string(&general).unwrap();
+        assert!(verify_jws(&document, &tampered, SignaturePolicy::default()).is_err());
+
+        // A key the document does not publish cannot take part either
+        let stranger = JwsSigner::ml_dsa_65(format!("{}#stranger", document.id()), [3; 32]);
+        let jws = sign_jws(CLAIMS, "JWT", &[&stranger, &ed25519]);
+        assert!(verify_jws(&document, &jws, hybrid_policy()).is_err());
+    }
+
+    #[test]
+    fn require_hybrid_rejects_two_signatures_of_one_kind() {
+        let mut document = document();
+        let (ed25519, _) = signers(&document);
+        let second = JwsSigner::ed25519(format!("{}#ed25519-2", document.id()), [4; 32]);
+        publish(&mut document, &[&ed25519, &second]);
+
+        let jws = sign_jws(CLAIMS, "JWT", &[&ed25519, &second]);
+        assert!(verify_jws(&document, &jws, SignaturePolicy::default()).is_ok());
+        assert!(verify_jws(&document, &jws, hybrid_policy()).is_err());
+    }
+}
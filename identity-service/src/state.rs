@@ -1,29 +1,81 @@
 use std::sync::Arc;
+use std::time::Duration;
 use anyhow::Context;
 use identity_iota::iota::rebased::client::IdentityClientReadOnly;
 use identity_iota::iota_interaction::{IotaClient as SdkClient, IotaClientBuilder}; // Correct builder and client type
 
+use crate::challenges::ChallengeStore;
 use crate::config::AppConfig;
+use crate::credentials::CredentialIssuer;
+use crate::did_cache::DidCache;
+use crate::handlers::MAX_ACTION_AGE_SECS;
+use crate::health::{self, HealthMonitor};
+use crate::nonces::ActionNonces;
+use crate::ratelimit::RateLimiter;
+use crate::recovery::RecoveryStore;
+use crate::rotation::RotationStore;
 
 #[derive(Clone)]
 pub struct AppState {
     pub identity_client: Arc<IdentityClientReadOnly>,
+    // Raw node client, used by the health probes
+    pub sdk_client: SdkClient,
     pub config: Arc<AppConfig>,
+    pub recovery: Arc<RecoveryStore>,
+    pub rotations: Arc<RotationStore>,
+    pub issuer: Option<Arc<CredentialIssuer>>,
+    pub health: Arc<HealthMonitor>,
+    pub limiter: Arc<RateLimiter>,
+    pub did_cache: Arc<DidCache>,
+    pub challenges: Arc<ChallengeStore>,
+    pub action_nonces: Arc<ActionNonces>,
 }
 
 pub async fn build_app_state(config: AppConfig) -> Result<AppState, anyhow::Error> {
     // Construct the SdkClient (identity_iota::iota_interaction::IotaClient)
     // using the IotaClientBuilder from identity_iota::iota_interaction.
     let sdk_client: SdkClient = IotaClientBuilder::default()
-        .build(&config.iota_api_endpoint)
+        .build(&config.iota_node_url)
         .await
-        .with_context(|| format!("Failed to build SdkClient for node {}", config.iota_api_endpoint))?;
+        .with_context(|| format!("Failed to build SdkClient for node {}", config.iota_node_url))?;
 
     let identity_client = IdentityClientReadOnly::new(sdk_client.clone()).await
         .context("Failed to create IdentityClientReadOnly")?;
 
+    let recovery = RecoveryStore::load(&config.recovery_store_path).await
+        .with_context(|| format!("Failed to load recovery store from {}", config.recovery_store_path))?;
+    let rotations = RotationStore::load(&config.rotation_store_path).await
+        .with_context(|| format!("Failed to load rotation store from {}", config.rotation_store_path))?;
+    let issuer = CredentialIssuer::from_config(&config)
+        .context("Invalid credential issuer configuration")?;
+    if issuer.is_none() {
+        tracing::warn!("ISSUER_ML_DSA_KID/ISSUER_ML_DSA_SEED not set; credential issuance is disabled");
+    }
+
+    let email_key = match &config.email_hash_secret {
+        Some(secret) => secret.as_bytes().to_vec(),
+        None => {
+            tracing::warn!("EMAIL_HASH_SECRET not set; using a random key, so decoy DIDs change on restart");
+            rand::random::<[u8; 32]>().to_vec()
+        }
+    };
+    let limiter = RateLimiter::new(config.rate_limits.clone(), email_key);
+    let did_cache = DidCache::new(Duration::from_secs(config.did_cache_ttl_secs));
+    let challenges = ChallengeStore::new(Duration::from_secs(config.challenge_ttl_secs));
+    // A probe result stays valid for three missed rounds
+    let health_ttl = Duration::from_secs(config.health_probe_interval_secs * 3) + Duration::from_millis(config.health_probe_timeout_ms);
+
     Ok(AppState {
         identity_client: Arc::new(identity_client),
+        sdk_client,
         config: Arc::new(config),
+        recovery: Arc::new(recovery),
+        rotations: Arc::new(rotations),
+        issuer: issuer.map(Arc::new),
+        health: Arc::new(HealthMonitor::new(&[health::IOTA_NODE], health_ttl)),
+        limiter: Arc::new(limiter),
+        did_cache: Arc::new(did_cache),
+        challenges: Arc::new(challenges),
+        action_nonces: Arc::new(ActionNonces::new(MAX_ACTION_AGE_SECS)),
     })
 }
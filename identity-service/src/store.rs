@@ -0,0 +1,50 @@
+use axum::http::StatusCode;
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::{Mutex, MutexGuard};
+
+// Records keyed by DID, persisted as one JSON file so workflow state (time locks,
+// grace periods) survives restarts
+pub struct JsonStore<V> {
+    path: PathBuf,
+    records: Mutex<HashMap<String, V>>,
+}
+
+impl<V: Serialize + DeserializeOwned> JsonStore<V> {
+    pub async fn load(path: impl Into<PathBuf>) -> Result<Self, anyhow::Error> {
+        let path = path.into();
+        let records = match tokio::fs::read(&path).await {
+            Ok(bytes) => serde_json::from_slice(&bytes)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self { path, records: Mutex::new(records) })
+    }
+
+    pub async fn lock(&self) -> MutexGuard<'_, HashMap<String, V>> {
+        self.records.lock().await
+    }
+
+    // Write the locked records back; callers pass the guard they mutated
+    pub async fn persist(&self, records: &HashMap<String, V>) -> Result<(), (StatusCode, String)> {
+        let write = async {
+            if let Some(parent) = self.path.parent() {
+                tokio::fs::create_dir_all(parent).await?;
+            }
+            let tmp = self.path.with_extension("json.tmp");
+            tokio::fs::write(&tmp, serde_json::to_vec_pretty(records)?).await?;
+            tokio::fs::rename(&tmp, &self.path).await?;
+            Ok::<_, anyhow::Error>(())
+        };
+        write.await.map_err(|e| {
+            tracing::error!(error = %e, path = %self.path.display(), "Failed to persist store.");
+            (StatusCode::INTERNAL_SERVER_ERROR, "Failed to persist state".to_string())
+        })
+    }
+}
+
+pub fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
@@ -0,0 +1,110 @@
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use std::time::Instant;
+use tracing::Span;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use utoipa_axum::{router::OpenApiRouter, routes};
+use wot_id_types::ErrorCode;
+
+// Latency buckets in seconds, shared by HTTP and DID resolution histograms
+const LATENCY_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+// Install the global Prometheus recorder; metrics recorded before this are dropped
+pub fn install_recorder() -> anyhow::Result<PrometheusHandle> {
+    let handle = PrometheusBuilder::new()
+        .set_buckets_for_metric(Matcher::Suffix("duration_seconds".to_string()), LATENCY_BUCKETS)?
+        .install_recorder()?;
+    Ok(handle)
+}
+
+pub fn routes<S: Clone + Send + Sync + 'static>(handle: PrometheusHandle) -> OpenApiRouter<S> {
+    OpenApiRouter::new().routes(routes!(render)).with_state(handle)
+}
+
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "telemetry",
+    responses((status = 200, description = "Prometheus text exposition format", body = String, content_type = "text/plain"))
+)]
+async fn render(State(handle): State<PrometheusHandle>) -> String {
+    handle.render()
+}
+
+// Middleware: count requests and record their latency per route template, so path
+// parameters such as DIDs do not become labels
+pub async fn track_http(request: Request, next: Next) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or_else(|| "unmatched".to_string(), |path| path.as_str().to_string());
+    let method = request.method().to_string();
+    let started = Instant::now();
+    let response = next.run(request).await;
+
+    let status = response.status().as_u16().to_string();
+    metrics::histogram!("http_request_duration_seconds", "method" => method.clone(), "route" => route.clone())
+        .record(started.elapsed().as_secs_f64());
+    metrics::counter!("http_requests_total", "method" => method, "route" => route, "status" => status).increment(1);
+    response
+}
+
+// Outcome of a signature, action or credential verification: `valid`, or why it failed
+pub fn verification(kind: &'static str, outcome: Result<(), ErrorCode>) {
+    let outcome = outcome.map_or_else(ErrorCode::as_str, |()| "valid");
+    metrics::counter!("verifications_total", "kind" => kind, "outcome" => outcome).increment(1);
+}
+
+// A request refused by the rate limiter, by the scope whose quota ran out
+pub fn rate_limited(scope: &'static str) {
+    metrics::counter!("rate_limited_requests_total", "scope" => scope).increment(1);
+}
+
+pub fn did_resolution(started: Instant, ok: bool) {
+    let outcome = if ok { "ok" } else { "error" };
+    metrics::histogram!("did_resolution_duration_seconds", "outcome" => outcome).record(started.elapsed().as_secs_f64());
+    metrics::counter!("did_resolutions_total", "outcome" => outcome).increment(1);
+}
+
+// One call to the IOTA node, by SDK method; DID resolutions are counted here too. Same names as
+// the backend's JSON-RPC metrics, so one query covers both services.
+pub fn iota_rpc(method: &'static str, started: Instant, ok: bool) {
+    metrics::histogram!("iota_rpc_duration_seconds", "method" => method).record(started.elapsed().as_secs_f64());
+    if !ok {
+        metrics::counter!("iota_rpc_errors_total", "method" => method).increment(1);
+    }
+}
+
+// A lookup in the DID document cache; the hit rate is hits over all lookups
+pub fn did_cache_lookup(hit: bool) {
+    let result = if hit { "hit" } else { "miss" };
+    metrics::counter!("did_cache_lookups_total", "result" => result).increment(1);
+}
+
+// Login challenges issued and not yet redeemed or expired
+pub fn challenge_store_size(size: usize) {
+    metrics::gauge!("challenge_store_size").set(size as f64);
+}
+
+// Log filter when `RUST_LOG` is not set
+pub const DEFAULT_LOG_FILTER: &str = "identity_service=debug,tower_http=debug,info";
+
+// Request span for `TraceLayer`, continuing the caller's W3C `traceparent`; named after the
+// route template, so the layer must run after routing (`Router::route_layer`)
+pub fn make_span(request: &Request) -> Span {
+    let route = request.extensions().get::<MatchedPath>().map_or("unmatched", |path| path.as_str());
+    let span = tracing::info_span!(
+        "http_request",
+        otel.name = format!("{} {}", request.method(), route),
+        otel.kind = "server",
+        http.request.method = %request.method(),
+        http.route = route,
+        url.path = %request.uri().path(),
+    );
+    let _ = span.set_parent(wot_telemetry::remote_context(request.headers()));
+    span
+}
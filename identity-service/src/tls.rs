@@ -0,0 +1,135 @@
+use anyhow::Context;
+use rustls::pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer};
+use rustls::server::WebPkiClientVerifier;
+use rustls::{RootCertStore, ServerConfig};
+use std::sync::Arc;
+
+use crate::config::AppConfig;
+
+fn load_certs(path: &str) -> anyhow::Result<Vec<CertificateDer<'static>>> {
+    let certs = CertificateDer::pem_file_iter(path)
+        .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+        .with_context(|| format!("Failed to read certificates from {}", path))?;
+    if certs.is_empty() {
+        anyhow::bail!("No certificates found in {}", path);
+    }
+    Ok(certs)
+}
+
+// TLS for the listener, or `None` for plain HTTP. With a client CA, only clients presenting a
+// certificate it issued (the backend's) can connect.
+pub fn server_config(config: &AppConfig) -> anyhow::Result<Option<Arc<ServerConfig>>> {
+    let (Some(cert_path), Some(key_path)) = (&config.tls_cert_path, &config.tls_key_path) else {
+        return Ok(None);
+    };
+    load_server_config(cert_path, key_path, config.tls_client_ca_path.as_deref()).map(Some)
+}
+
+fn load_server_config(cert_path: &str, key_path: &str, client_ca_path: Option<&str>) -> anyhow::Result<Arc<ServerConfig>> {
+    let certs = load_certs(cert_path)?;
+    let key = PrivateKeyDer::from_pem_file(key_path).with_context(|| format!("Failed to read private key from {}", key_path))?;
+    // Always ring, so the result does not depend on which rustls features other crates enable
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let builder = ServerConfig::builder_with_provider(provider.clone()).with_safe_default_protocol_versions()?;
+    let builder = match client_ca_path {
+        Some(ca_path) => {
+            let mut roots = RootCertStore::empty();
+            for cert in load_certs(ca_path)? {
+                roots.add(cert).with_context(|| format!("Invalid CA certificate in {}", ca_path))?;
+            }
+            let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider).build()?;
+            builder.with_client_cert_verifier(verifier)
+        }
+        None => builder.with_no_client_auth(),
+    };
+    let mut server_config = builder.with_single_cert(certs, key).context("Certificate and private key do not match")?;
+    server_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(Arc::new(server_config))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{extract::ConnectInfo, routing::get, Router};
+    use rcgen::{BasicConstraints, Certificate, CertificateParams, ExtendedKeyUsagePurpose, IsCa};
+    use std::path::PathBuf;
+    use tokio::net::TcpListener;
+
+    // A CA with a server certificate for `localhost` and a client certificate, written as PEM
+    // files into a directory that is removed again when the test ends, passed or failed
+    struct TestPki {
+        dir: PathBuf,
+    }
+
+    impl TestPki {
+        fn generate() -> Self {
+            let dir = std::env::temp_dir().join(format!("wot-identity-tls-{}", std::process::id()));
+            std::fs::create_dir_all(&dir).unwrap();
+            let pki = Self { dir };
+            let mut ca_params = CertificateParams::new(Vec::new());
+            ca_params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+            let ca = Certificate::from_params(ca_params).unwrap();
+            let server = Certificate::from_params(CertificateParams::new(vec!["localhost".to_string()])).unwrap();
+            let mut client_params = CertificateParams::new(Vec::new());
+            client_params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ClientAuth];
+            let client = Certificate::from_params(client_params).unwrap();
+
+            let write = |file: &str, pem: String| std::fs::write(pki.dir.join(file), pem).unwrap();
+            write("ca.pem", ca.serialize_pem().unwrap());
+            write("server.pem", server.serialize_pem_with_signer(&ca).unwrap());
+            write("server.key", server.serialize_private_key_pem());
+            // The client PEM holds key and certificate, as `reqwest::Identity::from_pem` expects
+            write("client.pem", client.serialize_private_key_pem() + &client.serialize_pem_with_signer(&ca).unwrap());
+            pki
+        }
+
+        fn path(&self, file: &str) -> String {
+            self.dir.join(file).to_string_lossy().into_owned()
+        }
+
+        fn read(&self, file: &str) -> Vec<u8> {
+            std::fs::read(self.dir.join(file)).unwrap()
+        }
+    }
+
+    impl Drop for TestPki {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    fn client(ca: &[u8], identity: Option<&[u8]>) -> reqwest::Client {
+        let builder = reqwest::Client::builder()
+            .use_rustls_tls()
+            .tls_built_in_root_certs(false)
+            .add_root_certificate(reqwest::Certificate::from_pem(ca).unwrap());
+        match identity {
+            Some(pem) => builder.identity(reqwest::Identity::from_pem(pem).unwrap()),
+            None => builder,
+        }
+        .build()
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn mutual_tls_requires_a_client_certificate_from_the_ca() {
+        let pki = TestPki::generate();
+        let tls = load_server_config(&pki.path("server.pem"), &pki.path("server.key"), Some(&pki.path("ca.pem"))).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("https://localhost:{}/livez", listener.local_addr().unwrap().port());
+        let app = Router::new().route("/livez", get(|| async { "ok" }));
+        let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+        let served = tokio::spawn(wot_tls::serve(listener, tls, app, ConnectInfo, async move {
+            stopped.await.ok();
+        }));
+
+        let ca = pki.read("ca.pem");
+        let identity = pki.read("client.pem");
+        let response = client(&ca, Some(&identity)).get(&url).send().await.unwrap();
+        assert_eq!(response.text().await.unwrap(), "ok");
+        assert!(client(&ca, None).get(&url).send().await.is_err());
+
+        stop.send(()).unwrap();
+        served.await.unwrap();
+    }
+}
@@ -3,11 +3,29 @@ module wot_id::credentials {
 
     use iota::hash;
     use iota::bcs;
+    use iota::event;
     use wot_id::identity::{Self, Identity};
 
     // Errors
     const E_NOT_AUTHORIZED: u64 = 1;
 
+    /// Emitted when a credential is issued
+    public struct CredentialIssued has copy, drop {
+        object_id: ID,
+        credential_id: String,
+        issuer: String,
+        holder: String,
+    }
+
+    /// Emitted when a credential is revoked
+    public struct CredentialRevoked has copy, drop {
+        object_id: ID,
+        credential_id: String,
+        issuer: String,
+        holder: String,
+        revocation_id: String,
+    }
+
     /// A claim within a credential
     public struct Claim has store, drop {
         name: String,
@@ -105,6 +123,13 @@ module wot_id::credentials {
             nonce: create_nonce(ctx),
         };
         
+        event::emit(CredentialIssued {
+            object_id,
+            credential_id: cred_id,
+            issuer: issuer_did,
+            holder,
+        });
+
         // Create credential
         let credential = Credential {
             id,
@@ -130,6 +155,14 @@ module wot_id::credentials {
     ) {
         credential.status = 2; // Revoked
         credential.revocation_id = option::some(revocation_id);
+
+        event::emit(CredentialRevoked {
+            object_id: object::id(credential),
+            credential_id: credential.credential_id,
+            issuer: credential.issuer,
+            holder: credential.holder,
+            revocation_id,
+        });
     }
 
     /// Verify a credential
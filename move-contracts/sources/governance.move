@@ -0,0 +1,163 @@
+module wot_id::governance {
+    use std::string::{Self, String};
+
+    use iota::clock::{Self, Clock};
+    use iota::event;
+
+    // Errors
+    const E_VOTING_NOT_ENDED: u64 = 1;
+    const E_INVALID_HASH: u64 = 2;
+    const E_INVALID_THRESHOLD: u64 = 3;
+    const E_INVALID_WEIGHTING: u64 = 4;
+
+    const HASH_LENGTH: u64 = 32;
+
+    // Events
+
+    /// Lets its holder anchor results. Created once, for the publisher, who hands it to the
+    /// address the backend anchors with.
+    public struct AnchorCap has key, store {
+        id: UID,
+    }
+
+    /// Emitted when the result of an off-chain vote is anchored
+    public struct ProposalResultAnchored has copy, drop {
+        object_id: ID,
+        proposal_id: String,
+        proposal_hash: vector<u8>,
+        ballots_hash: vector<u8>,
+        outcome: String,
+        votes_for: u64,
+        votes_against: u64,
+        votes_abstain: u64,
+        anchored_by: address,
+    }
+
+    /// Immutable record of a finished governance vote. Proposals and DID-signed ballots are
+    /// collected by the backend; the hashes commit to the proposer's signed proposal and to
+    /// the ballots, so anyone holding them can check the tally.
+    public struct GovernanceResult has key {
+        id: UID,
+        proposal_id: String,
+        // SHA-256 of the proposer's signed proposal (JWS)
+        proposal_hash: vector<u8>,
+        // SHA-256 of the JSON array of ballot JWSs ordered by voter DID
+        ballots_hash: vector<u8>,
+        // `one_did_one_vote` or `trust_weighted`
+        weighting: String,
+        votes_for: u64,
+        votes_against: u64,
+        votes_abstain: u64,
+        voters: u64,
+        quorum: u64,
+        threshold_pct: u8,
+        // `passed`, `rejected` or `no_quorum`, recomputed here from the tally
+        outcome: String,
+        voting_ends: u64,
+        anchored: u64,
+        anchored_by: address,
+    }
+
+    fun init(ctx: &mut TxContext) {
+        transfer::transfer(AnchorCap { id: object::new(ctx) }, tx_context::sender(ctx));
+    }
+
+    /// Anchor the tally of a proposal whose voting period has ended
+    public entry fun anchor_result(
+        _cap: &AnchorCap,
+        proposal_id: vector<u8>,
+        proposal_hash: vector<u8>,
+        ballots_hash: vector<u8>,
+        weighting: vector<u8>,
+        votes_for: u64,
+        votes_against: u64,
+        votes_abstain: u64,
+        voters: u64,
+        quorum: u64,
+        threshold_pct: u8,
+        voting_ends: u64,
+        clock: &Clock,
+        ctx: &mut TxContext
+    ) {
+        let now = clock::timestamp_ms(clock);
+        assert!(voting_ends <= now, E_VOTING_NOT_ENDED);
+        assert!(vector::length(&proposal_hash) == HASH_LENGTH, E_INVALID_HASH);
+        assert!(vector::length(&ballots_hash) == HASH_LENGTH, E_INVALID_HASH);
+        assert!(threshold_pct <= 100, E_INVALID_THRESHOLD);
+        let weighting = string::utf8(weighting);
+        assert!(
+            weighting == string::utf8(b"one_did_one_vote") || weighting == string::utf8(b"trust_weighted"),
+            E_INVALID_WEIGHTING
+        );
+
+        let outcome = compute_outcome(votes_for, votes_against, votes_abstain, quorum, threshold_pct);
+        let id = object::new(ctx);
+        let proposal_id = string::utf8(proposal_id);
+        let anchored_by = tx_context::sender(ctx);
+
+        event::emit(ProposalResultAnchored {
+            object_id: object::uid_to_inner(&id),
+            proposal_id,
+            proposal_hash,
+            ballots_hash,
+            outcome,
+            votes_for,
+            votes_against,
+            votes_abstain,
+            anchored_by,
+        });
+
+        transfer::freeze_object(GovernanceResult {
+            id,
+            proposal_id,
+            proposal_hash,
+            ballots_hash,
+            weighting,
+            votes_for,
+            votes_against,
+            votes_abstain,
+            voters,
+            quorum,
+            threshold_pct,
+            outcome,
+            voting_ends,
+            anchored: now,
+            anchored_by,
+        });
+    }
+
+    /// Quorum counts every cast vote; `for` must exceed `threshold_pct` of for + against
+    fun compute_outcome(
+        votes_for: u64,
+        votes_against: u64,
+        votes_abstain: u64,
+        quorum: u64,
+        threshold_pct: u8
+    ): String {
+        let cast = (votes_for as u128) + (votes_against as u128) + (votes_abstain as u128);
+        if (cast < (quorum as u128)) {
+            return string::utf8(b"no_quorum")
+        };
+        let decided = (votes_for as u128) + (votes_against as u128);
+        if ((votes_for as u128) * 100 > (threshold_pct as u128) * decided) {
+            string::utf8(b"passed")
+        } else {
+            string::utf8(b"rejected")
+        }
+    }
+
+    /// Get the anchored outcome of a result
+    public fun outcome(result: &GovernanceResult): String {
+        result.outcome
+    }
+
+    /// Get the proposal a result belongs to
+    public fun proposal_id(result: &GovernanceResult): String {
+        result.proposal_id
+    }
+
+    #[test_only]
+    public fun init_for_testing(ctx: &mut TxContext) {
+        init(ctx);
+    }
+}
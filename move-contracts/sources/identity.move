@@ -2,13 +2,69 @@ module wot_id::identity {
     use std::string::{Self, String};
 
     use iota::bcs;
-
+    use iota::clock::{Self, Clock};
+    use iota::dynamic_field;
+    use iota::event;
 
     // Errors
     const E_NOT_AUTHORIZED: u64 = 1;
     const E_INVALID_CONTROLLER: u64 = 2;
     const E_LAST_CONTROLLER: u64 = 3;
     const E_NOT_FOUND: u64 = 4;
+    const E_NOT_GUARDIAN: u64 = 5;
+    const E_INVALID_THRESHOLD: u64 = 6;
+    const E_RECOVERY_IN_PROGRESS: u64 = 7;
+    const E_NO_RECOVERY: u64 = 8;
+    const E_ALREADY_APPROVED: u64 = 9;
+    const E_TIME_LOCKED: u64 = 10;
+
+    // Events
+
+    /// Emitted when a new Identity is created
+    public struct IdentityCreated has copy, drop {
+        object_id: ID,
+        did: String,
+    }
+
+    /// Emitted whenever controllers, verification methods or privacy settings change
+    public struct IdentityUpdated has copy, drop {
+        object_id: ID,
+        did: String,
+        change: String,
+    }
+
+    /// Emitted whenever a recovery request is opened, approved, cancelled or executed
+    public struct RecoveryUpdated has copy, drop {
+        object_id: ID,
+        did: String,
+        change: String,
+    }
+
+    /// Shared index from DID to Identity object ID, filled in by `create_identity`.
+    /// Entries are dynamic fields keyed by the DID string.
+    public struct DidRegistry has key {
+        id: UID,
+    }
+
+    /// Key of the `Recovery` dynamic field of an Identity
+    public struct RecoveryKey has copy, drop, store {}
+
+    /// Guardian recovery settings and the open request, if any
+    public struct Recovery has store, drop {
+        // Guardian Identity object IDs
+        guardians: vector<ID>,
+        threshold: u64,
+        time_lock_ms: u64,
+        request: Option<RecoveryRequest>,
+    }
+
+    /// A recovery request; `unlocks_at_ms` is set once `threshold` guardians approved
+    public struct RecoveryRequest has store, drop {
+        new_controller: address,
+        remove_controllers: vector<address>,
+        approvals: vector<ID>,
+        unlocks_at_ms: Option<u64>,
+    }
 
     /// Attribute policy for selective disclosure
     public struct AttributePolicy has store, drop {
@@ -56,6 +112,10 @@ module wot_id::identity {
         updated: u64,
     }
 
+    fun init(ctx: &mut TxContext) {
+        transfer::share_object(DidRegistry { id: object::new(ctx) });
+    }
+
     /// Create a new DID from the provided object ID
     fun create_did_from_object_id(object_id: address): String {
         let mut did = string::utf8(b"did:iota:0x");
@@ -102,6 +162,7 @@ module wot_id::identity {
 
     /// Create a new Identity with DID Document
     public entry fun create_identity(
+        registry: &mut DidRegistry,
         ctx: &mut TxContext
     ) {
         let sender = tx_context::sender(ctx);
@@ -114,6 +175,9 @@ module wot_id::identity {
         // Create DID from object ID
         let did = create_did_from_object_id(object::id_to_address(&object_id));
         
+        event::emit(IdentityCreated { object_id, did });
+        dynamic_field::add(&mut registry.id, did, object_id);
+
         // Create Identity object
         let identity = Identity {
             id,
@@ -158,6 +222,7 @@ module wot_id::identity {
         
         // Update the last updated timestamp
         identity.updated = tx_context::epoch(ctx);
+        emit_updated(identity, b"verification_method_added");
     }
 
     /// Check if an address is a controller of the Identity
@@ -199,6 +264,16 @@ module wot_id::identity {
         
         // Update the last updated timestamp
         identity.updated = tx_context::epoch(ctx);
+        emit_updated(identity, b"controller_added");
+    }
+
+    /// Emit an IdentityUpdated event describing the change
+    fun emit_updated(identity: &Identity, change: vector<u8>) {
+        event::emit(IdentityUpdated {
+            object_id: object::id(identity),
+            did: identity.did,
+            change: string::utf8(change),
+        });
     }
 
     /// Get the DID of an Identity
@@ -226,6 +301,7 @@ module wot_id::identity {
         
         // Update the last updated timestamp
         identity.updated = tx_context::epoch(ctx);
+        emit_updated(identity, b"privacy_settings_updated");
     }
 
     /// Removes a verification method from an Identity.
@@ -238,11 +314,11 @@ module wot_id::identity {
         let sender = tx_context::sender(ctx);
         assert_is_controller(identity, sender);
 
-        let i = 0;
-        let found = false;
-        let len = vector::length(&identity.verification_method);
+        let mut i = 0;
+        let mut found = false;
+        let len = vector::length(&identity.verification_methods);
         while (i < len) {
-            let vm = vector::borrow(&identity.verification_method, i);
+            let vm = vector::borrow(&identity.verification_methods, i);
             if (vm.id == method_id) {
                 found = true;
                 break
@@ -252,9 +328,10 @@ module wot_id::identity {
 
         assert!(found, E_NOT_FOUND);
 
-        vector::remove(&mut identity.verification_method, i);
+        vector::remove(&mut identity.verification_methods, i);
 
         identity.updated = tx_context::epoch(ctx);
+        emit_updated(identity, b"verification_method_removed");
     }
 
     /// Removes a controller from an Identity.
@@ -269,14 +346,187 @@ module wot_id::identity {
         assert_is_controller(identity, sender);
 
         // Ensure we are not removing the last controller
-        assert!(vector::length(&identity.controller) > 1, E_LAST_CONTROLLER);
+        assert!(vector::length(&identity.controllers) > 1, E_LAST_CONTROLLER);
 
-        let maybe_index = vector::index_of(&identity.controller, &controller_to_remove);
-        assert!(option::is_some(&maybe_index), E_NOT_FOUND);
-        
-        let index = option::destroy_some(maybe_index);
-        vector::remove(&mut identity.controller, index);
+        let (found, index) = vector::index_of(&identity.controllers, &controller_to_remove);
+        assert!(found, E_NOT_FOUND);
+        vector::remove(&mut identity.controllers, index);
 
         identity.updated = tx_context::epoch(ctx);
+        emit_updated(identity, b"controller_removed");
+    }
+
+    // Guardian recovery. Guardians are other Identities; each approval is a transaction sent by
+    // a controller of the guardian Identity. Once `threshold` guardians approved, the request
+    // unlocks after `time_lock_ms`, during which any controller can cancel it. The new
+    // controller then calls `recover_controller` itself, since it is not a controller yet.
+
+    /// Set or replace the guardians; not allowed while a recovery request is open
+    public entry fun configure_recovery(
+        identity: &mut Identity,
+        guardians: vector<ID>,
+        threshold: u64,
+        time_lock_ms: u64,
+        ctx: &mut TxContext
+    ) {
+        assert_is_controller(identity, tx_context::sender(ctx));
+        assert!(threshold > 0 && threshold <= vector::length(&guardians), E_INVALID_THRESHOLD);
+        assert!(!vector::contains(&guardians, &object::id(identity)), E_INVALID_THRESHOLD);
+
+        let key = RecoveryKey {};
+        if (dynamic_field::exists_(&identity.id, key)) {
+            let old: Recovery = dynamic_field::remove(&mut identity.id, key);
+            assert!(option::is_none(&old.request), E_RECOVERY_IN_PROGRESS);
+        };
+        dynamic_field::add(&mut identity.id, key, Recovery {
+            guardians,
+            threshold,
+            time_lock_ms,
+            request: option::none(),
+        });
+
+        identity.updated = tx_context::epoch(ctx);
+        emit_updated(identity, b"recovery_configured");
+    }
+
+    /// Open a recovery request as `guardian`; this counts as its approval
+    public entry fun initiate_recovery(
+        identity: &mut Identity,
+        guardian: &Identity,
+        new_controller: address,
+        remove_controllers: vector<address>,
+        clock: &Clock,
+        ctx: &mut TxContext
+    ) {
+        let guardian_id = assert_is_guardian(identity, guardian, ctx);
+        let recovery = recovery_mut(identity);
+        assert!(option::is_none(&recovery.request), E_RECOVERY_IN_PROGRESS);
+
+        let mut request = RecoveryRequest {
+            new_controller,
+            remove_controllers,
+            approvals: vector::singleton(guardian_id),
+            unlocks_at_ms: option::none(),
+        };
+        start_time_lock(&mut request, recovery.threshold, recovery.time_lock_ms, clock);
+        option::fill(&mut recovery.request, request);
+
+        emit_recovery(identity, b"recovery_initiated");
+    }
+
+    /// Approve the open request as `guardian`; reaching the threshold starts the time lock
+    public entry fun approve_recovery(
+        identity: &mut Identity,
+        guardian: &Identity,
+        clock: &Clock,
+        ctx: &mut TxContext
+    ) {
+        let guardian_id = assert_is_guardian(identity, guardian, ctx);
+        let recovery = recovery_mut(identity);
+        let (threshold, time_lock_ms) = (recovery.threshold, recovery.time_lock_ms);
+        assert!(option::is_some(&recovery.request), E_NO_RECOVERY);
+        let request = option::borrow_mut(&mut recovery.request);
+        assert!(!vector::contains(&request.approvals, &guardian_id), E_ALREADY_APPROVED);
+
+        vector::push_back(&mut request.approvals, guardian_id);
+        start_time_lock(request, threshold, time_lock_ms, clock);
+
+        emit_recovery(identity, b"recovery_approved");
+    }
+
+    /// Any controller can cancel the open request until it has been executed
+    public entry fun cancel_recovery(
+        identity: &mut Identity,
+        ctx: &mut TxContext
+    ) {
+        assert_is_controller(identity, tx_context::sender(ctx));
+        let recovery = recovery_mut(identity);
+        assert!(option::is_some(&recovery.request), E_NO_RECOVERY);
+        option::extract(&mut recovery.request);
+
+        emit_recovery(identity, b"recovery_cancelled");
+    }
+
+    /// Sent by the request's new controller once the time lock has elapsed: adds it as a
+    /// controller and removes the controllers the request names
+    public entry fun recover_controller(
+        identity: &mut Identity,
+        clock: &Clock,
+        ctx: &mut TxContext
+    ) {
+        let sender = tx_context::sender(ctx);
+        let recovery = recovery_mut(identity);
+        assert!(option::is_some(&recovery.request), E_NO_RECOVERY);
+        let request = option::extract(&mut recovery.request);
+        assert!(request.new_controller == sender, E_NOT_AUTHORIZED);
+        assert!(option::is_some(&request.unlocks_at_ms), E_TIME_LOCKED);
+        assert!(clock::timestamp_ms(clock) >= *option::borrow(&request.unlocks_at_ms), E_TIME_LOCKED);
+
+        if (!is_controller(identity, sender)) {
+            vector::push_back(&mut identity.controllers, sender);
+        };
+        let RecoveryRequest { new_controller: _, remove_controllers, approvals: _, unlocks_at_ms: _ } = request;
+        let mut i = 0;
+        let len = vector::length(&remove_controllers);
+        while (i < len) {
+            let controller = *vector::borrow(&remove_controllers, i);
+            let (found, index) = vector::index_of(&identity.controllers, &controller);
+            if (found && controller != sender) {
+                vector::remove(&mut identity.controllers, index);
+            };
+            i = i + 1;
+        };
+
+        identity.updated = tx_context::epoch(ctx);
+        emit_recovery(identity, b"recovery_executed");
+        emit_updated(identity, b"controller_recovered");
+    }
+
+    /// Look up the Identity object ID registered for a DID
+    public fun lookup_did(registry: &DidRegistry, did: String): Option<ID> {
+        if (dynamic_field::exists_(&registry.id, did)) {
+            option::some(*dynamic_field::borrow(&registry.id, did))
+        } else {
+            option::none()
+        }
+    }
+
+    /// Asserts that the sender controls `guardian` and that it guards `identity`
+    fun assert_is_guardian(identity: &Identity, guardian: &Identity, ctx: &TxContext): ID {
+        assert_is_controller(guardian, tx_context::sender(ctx));
+        assert!(dynamic_field::exists_(&identity.id, RecoveryKey {}), E_NO_RECOVERY);
+        let recovery: &Recovery = dynamic_field::borrow(&identity.id, RecoveryKey {});
+        let guardian_id = object::id(guardian);
+        assert!(vector::contains(&recovery.guardians, &guardian_id), E_NOT_GUARDIAN);
+        guardian_id
+    }
+
+    fun recovery_mut(identity: &mut Identity): &mut Recovery {
+        assert!(dynamic_field::exists_(&identity.id, RecoveryKey {}), E_NO_RECOVERY);
+        dynamic_field::borrow_mut(&mut identity.id, RecoveryKey {})
+    }
+
+    fun start_time_lock(request: &mut RecoveryRequest, threshold: u64, time_lock_ms: u64, clock: &Clock) {
+        if (option::is_none(&request.unlocks_at_ms) && vector::length(&request.approvals) >= threshold) {
+            option::fill(&mut request.unlocks_at_ms, clock::timestamp_ms(clock) + time_lock_ms);
+        };
+    }
+
+    fun emit_recovery(identity: &Identity, change: vector<u8>) {
+        event::emit(RecoveryUpdated {
+            object_id: object::id(identity),
+            did: identity.did,
+            change: string::utf8(change),
+        });
+    }
+
+    #[test_only]
+    public fun init_for_testing(ctx: &mut TxContext) {
+        init(ctx);
+    }
+
+    #[test_only]
+    public fun controllers(identity: &Identity): vector<address> {
+        identity.controllers
     }
 }
@@ -0,0 +1,190 @@
+module wot_id::mailbox {
+    use std::string::String;
+
+    use iota::dynamic_field;
+    use iota::dynamic_object_field as dof;
+    use iota::event;
+    use iota::transfer::Receiving;
+    use wot_id::identity::{Self, Identity};
+
+    // Errors
+    const E_NOT_OWNER: u64 = 1;
+    const E_WRONG_RECIPIENT: u64 = 2;
+    const E_PAYLOAD_TOO_LARGE: u64 = 3;
+
+    // Encrypted payloads are kept small; larger content belongs in off-chain storage
+    const MAX_PAYLOAD_SIZE: u64 = 16384;
+
+    // Events
+
+    /// Emitted when a DID opens a mailbox, so senders can find it by DID
+    public struct MailboxCreated has copy, drop {
+        object_id: ID,
+        did: String,
+    }
+
+    /// Emitted when a message is sent to a mailbox
+    public struct MessageSent has copy, drop {
+        object_id: ID,
+        mailbox_id: ID,
+        sender_did: String,
+        recipient_did: String,
+    }
+
+    /// Shared index from DID to its current Mailbox object ID, filled in by `create_mailbox`.
+    /// Entries are dynamic fields keyed by the DID string.
+    public struct MailboxDirectory has key {
+        id: UID,
+    }
+
+    /// Asynchronous inbox for a DID. Messages are transferred to the mailbox's address and
+    /// stay pending until the owner claims them into dynamic object fields.
+    public struct Mailbox has key {
+        id: UID,
+        owner_did: String,
+        claimed: u64,
+    }
+
+    /// Authorizes claiming and deleting messages of one mailbox
+    public struct MailboxOwnerCap has key, store {
+        id: UID,
+        mailbox_id: ID,
+    }
+
+    /// A message end-to-end encrypted to the recipient's key-agreement method
+    public struct MessageObject has key, store {
+        id: UID,
+        sender_did: String,
+        recipient_did: String,
+        // Serialized encryption envelope; opaque to the contract
+        payload: vector<u8>,
+        sent: u64,
+    }
+
+    fun init(ctx: &mut TxContext) {
+        transfer::share_object(MailboxDirectory { id: object::new(ctx) });
+    }
+
+    /// Open a mailbox for an Identity; the owner cap goes to the calling controller.
+    /// A newer mailbox replaces the DID's directory entry.
+    public entry fun create_mailbox(
+        directory: &mut MailboxDirectory,
+        identity: &Identity,
+        ctx: &mut TxContext
+    ) {
+        let sender = tx_context::sender(ctx);
+        identity::assert_is_controller(identity, sender);
+
+        let id = object::new(ctx);
+        let mailbox_id = object::uid_to_inner(&id);
+        let owner_did = identity::get_did(identity);
+
+        event::emit(MailboxCreated { object_id: mailbox_id, did: owner_did });
+        if (dynamic_field::exists_(&directory.id, owner_did)) {
+            let _: ID = dynamic_field::remove(&mut directory.id, owner_did);
+        };
+        dynamic_field::add(&mut directory.id, owner_did, mailbox_id);
+
+        transfer::share_object(Mailbox { id, owner_did, claimed: 0 });
+        transfer::public_transfer(MailboxOwnerCap { id: object::new(ctx), mailbox_id }, sender);
+    }
+
+    /// Send a message by transferring it to the recipient's mailbox object. The sender DID is
+    /// taken from the sending Identity, whose controller must sign the transaction.
+    public entry fun send_message(
+        mailbox: &Mailbox,
+        sender: &Identity,
+        payload: vector<u8>,
+        ctx: &mut TxContext
+    ) {
+        identity::assert_is_controller(sender, tx_context::sender(ctx));
+        assert!(vector::length(&payload) <= MAX_PAYLOAD_SIZE, E_PAYLOAD_TOO_LARGE);
+
+        let id = object::new(ctx);
+        let mailbox_id = object::id(mailbox);
+        let sender_did = identity::get_did(sender);
+
+        event::emit(MessageSent {
+            object_id: object::uid_to_inner(&id),
+            mailbox_id,
+            sender_did,
+            recipient_did: mailbox.owner_did,
+        });
+
+        let message = MessageObject {
+            id,
+            sender_did,
+            recipient_did: mailbox.owner_did,
+            payload,
+            sent: tx_context::epoch(ctx),
+        };
+        transfer::public_transfer(message, object::id_to_address(&mailbox_id));
+    }
+
+    /// Claim a pending message into the mailbox's dynamic object fields
+    public entry fun claim_message(
+        mailbox: &mut Mailbox,
+        cap: &MailboxOwnerCap,
+        message: Receiving<MessageObject>,
+    ) {
+        assert!(cap.mailbox_id == object::id(mailbox), E_NOT_OWNER);
+
+        let message = transfer::public_receive(&mut mailbox.id, message);
+        assert!(message.recipient_did == mailbox.owner_did, E_WRONG_RECIPIENT);
+
+        let message_id = object::id(&message);
+        dof::add(&mut mailbox.id, message_id, message);
+        mailbox.claimed = mailbox.claimed + 1;
+    }
+
+    /// Delete a claimed message
+    public entry fun delete_message(
+        mailbox: &mut Mailbox,
+        cap: &MailboxOwnerCap,
+        message_id: ID,
+    ) {
+        assert!(cap.mailbox_id == object::id(mailbox), E_NOT_OWNER);
+
+        let MessageObject { id, sender_did: _, recipient_did: _, payload: _, sent: _ } =
+            dof::remove(&mut mailbox.id, message_id);
+        object::delete(id);
+        mailbox.claimed = mailbox.claimed - 1;
+    }
+
+    /// Delete a message straight from the pending queue without claiming it
+    public entry fun reject_message(
+        mailbox: &mut Mailbox,
+        cap: &MailboxOwnerCap,
+        message: Receiving<MessageObject>,
+    ) {
+        assert!(cap.mailbox_id == object::id(mailbox), E_NOT_OWNER);
+
+        let MessageObject { id, sender_did: _, recipient_did: _, payload: _, sent: _ } =
+            transfer::public_receive(&mut mailbox.id, message);
+        object::delete(id);
+    }
+
+    /// Get the DID that owns a mailbox
+    public fun owner_did(mailbox: &Mailbox): String {
+        mailbox.owner_did
+    }
+
+    /// Look up the current mailbox of a DID
+    public fun mailbox_of(directory: &MailboxDirectory, did: String): Option<ID> {
+        if (dynamic_field::exists_(&directory.id, did)) {
+            option::some(*dynamic_field::borrow(&directory.id, did))
+        } else {
+            option::none()
+        }
+    }
+
+    /// Get the sender DID of a message
+    public fun message_sender(message: &MessageObject): String {
+        message.sender_did
+    }
+
+    #[test_only]
+    public fun init_for_testing(ctx: &mut TxContext) {
+        init(ctx);
+    }
+}
@@ -1,6 +1,8 @@
 module wot_id::trust {
     use std::string::{Self, String};
 
+    use iota::event;
+
     use wot_id::identity::{Self, Identity};
     
     // Error codes
@@ -15,6 +17,29 @@ module wot_id::trust {
 
     const MAX_TRUST_LEVEL: u64 = 100000; // Represents 100.000 (scaled for precision)
     
+    // === Events ===
+
+    /// Emitted when a trust relationship is established, updated or gains evidence
+    public struct TrustChanged has copy, drop {
+        object_id: ID,
+        source: String,
+        target: String,
+        context: String,
+        previous_value: u64,
+        new_value: u64,
+        change: String,
+    }
+
+    /// Emitted when claim-level trust is established, attested or updated
+    public struct ClaimTrustChanged has copy, drop {
+        object_id: ID,
+        credential_id: String,
+        claim_name: String,
+        actor: String,
+        trust_value: u64,
+        change: String,
+    }
+
     /// Trust level with universal scale (0.000-100.000)
     /// Internally represented as u64 (0-100000) for precision
     public struct TrustLevel has copy, drop, store {
@@ -234,6 +259,8 @@ module wot_id::trust {
             max_path_length,
         };
         
+        emit_trust_changed(&relationship, 0, b"established");
+
         // Share the trust relationship object
         transfer::share_object(relationship);
     }
@@ -275,9 +302,13 @@ module wot_id::trust {
         // Add to history
         vector::push_back(&mut relationship.trust_history, update);
         
+        let previous_value = relationship.trust_level.value;
+
         // Update trust level
         relationship.trust_level = TrustLevel { value: new_trust_value };
         relationship.last_updated = now;
+
+        emit_trust_changed(relationship, previous_value, b"updated");
     }
     
     /// Add evidence to a trust relationship
@@ -315,6 +346,8 @@ module wot_id::trust {
         // Add to evidence collection
         vector::push_back(&mut relationship.evidence, evidence);
         relationship.last_updated = now;
+
+        emit_trust_changed(relationship, relationship.trust_level.value, b"evidence_added");
     }
     
     /// Verify if trust level meets a minimum threshold
@@ -388,6 +421,8 @@ module wot_id::trust {
             verifier: verifier_did,
         };
         
+        emit_claim_trust_changed(&claim_trust, verifier_did, b"established");
+
         // Share the claim trust object
         transfer::share_object(claim_trust);
     }
@@ -423,6 +458,8 @@ module wot_id::trust {
         // Add to attestations
         vector::push_back(&mut claim_trust.attestations, attestation);
         claim_trust.last_updated = now;
+
+        emit_claim_trust_changed(claim_trust, attestor_did, b"attestation_added");
     }
     
     /// Update trust level for a claim
@@ -452,8 +489,35 @@ module wot_id::trust {
         // Update trust level
         claim_trust.trust_level = TrustLevel { value: new_trust_value };
         claim_trust.last_updated = now;
+
+        emit_claim_trust_changed(claim_trust, verifier_did, b"updated");
     }
     
+    // === Event Helpers ===
+
+    fun emit_trust_changed(relationship: &TrustRelationship, previous_value: u64, change: vector<u8>) {
+        event::emit(TrustChanged {
+            object_id: object::id(relationship),
+            source: relationship.source,
+            target: relationship.target,
+            context: relationship.context,
+            previous_value,
+            new_value: relationship.trust_level.value,
+            change: string::utf8(change),
+        });
+    }
+
+    fun emit_claim_trust_changed(claim_trust: &ClaimTrust, actor: String, change: vector<u8>) {
+        event::emit(ClaimTrustChanged {
+            object_id: object::id(claim_trust),
+            credential_id: claim_trust.credential_id,
+            claim_name: claim_trust.claim_name,
+            actor,
+            trust_value: claim_trust.trust_level.value,
+            change: string::utf8(change),
+        });
+    }
+
     // === Trust Path and Verification Functions ===
     
     /// Calculate direct trust between source and target in a specific context
@@ -0,0 +1,134 @@
+#[test_only]
+module wot_id::governance_tests {
+    use std::string;
+
+    use iota::clock::{Self, Clock};
+    use iota::test_scenario::{Self as ts, Scenario};
+    use wot_id::governance::{Self, AnchorCap, GovernanceResult};
+
+    const PUBLISHER: address = @0xA;
+    const MALLORY: address = @0xC;
+
+    const VOTING_ENDS: u64 = 2_000;
+
+    fun setup(): Scenario {
+        let mut scenario = ts::begin(PUBLISHER);
+        governance::init_for_testing(ts::ctx(&mut scenario));
+        scenario
+    }
+
+    fun clock_at(scenario: &mut Scenario, now: u64): Clock {
+        let mut clock = clock::create_for_testing(ts::ctx(scenario));
+        clock::set_for_testing(&mut clock, now);
+        clock
+    }
+
+    fun hash(): vector<u8> {
+        let mut hash = vector[];
+        let mut i = 0;
+        while (i < 32) {
+            vector::push_back(&mut hash, i);
+            i = i + 1;
+        };
+        hash
+    }
+
+    // Anchor a tally for quorum 3 and a 50% threshold with the publisher's cap
+    fun anchor(scenario: &mut Scenario, now: u64, votes_for: u64, votes_against: u64, votes_abstain: u64, weighting: vector<u8>) {
+        ts::next_tx(scenario, PUBLISHER);
+        let cap = ts::take_from_sender<AnchorCap>(scenario);
+        let clock = clock_at(scenario, now);
+        governance::anchor_result(
+            &cap,
+            b"proposal-1",
+            hash(),
+            hash(),
+            weighting,
+            votes_for,
+            votes_against,
+            votes_abstain,
+            votes_for + votes_against + votes_abstain,
+            3,
+            50,
+            VOTING_ENDS,
+            &clock,
+            ts::ctx(scenario),
+        );
+        clock::destroy_for_testing(clock);
+        ts::return_to_sender(scenario, cap);
+    }
+
+    fun anchored_outcome(scenario: &mut Scenario): string::String {
+        ts::next_tx(scenario, MALLORY);
+        let result = ts::take_immutable<GovernanceResult>(scenario);
+        assert!(governance::proposal_id(&result) == string::utf8(b"proposal-1"));
+        let outcome = governance::outcome(&result);
+        ts::return_immutable(result);
+        outcome
+    }
+
+    #[test]
+    fun only_the_publisher_holds_the_anchor_cap() {
+        let mut scenario = setup();
+        ts::next_tx(&mut scenario, MALLORY);
+        assert!(ts::has_most_recent_for_address<AnchorCap>(PUBLISHER));
+        assert!(!ts::has_most_recent_for_address<AnchorCap>(MALLORY));
+        ts::end(scenario);
+    }
+
+    #[test]
+    fun outcome_is_recomputed_from_the_tally() {
+        let mut scenario = setup();
+        anchor(&mut scenario, VOTING_ENDS, 3, 1, 0, b"one_did_one_vote");
+        assert!(anchored_outcome(&mut scenario) == string::utf8(b"passed"));
+        ts::end(scenario);
+    }
+
+    #[test]
+    fun a_tie_at_the_threshold_is_rejected() {
+        let mut scenario = setup();
+        anchor(&mut scenario, VOTING_ENDS, 2, 2, 1, b"trust_weighted");
+        assert!(anchored_outcome(&mut scenario) == string::utf8(b"rejected"));
+        ts::end(scenario);
+    }
+
+    #[test]
+    fun abstentions_count_towards_quorum_only() {
+        let mut scenario = setup();
+        anchor(&mut scenario, VOTING_ENDS, 1, 0, 1, b"one_did_one_vote");
+        assert!(anchored_outcome(&mut scenario) == string::utf8(b"no_quorum"));
+        ts::end(scenario);
+    }
+
+    #[test]
+    #[expected_failure(abort_code = wot_id::governance::E_VOTING_NOT_ENDED)]
+    fun cannot_anchor_before_voting_ends() {
+        let mut scenario = setup();
+        anchor(&mut scenario, VOTING_ENDS - 1, 3, 0, 0, b"one_did_one_vote");
+        ts::end(scenario);
+    }
+
+    #[test]
+    #[expected_failure(abort_code = wot_id::governance::E_INVALID_WEIGHTING)]
+    fun unknown_weighting_is_rejected() {
+        let mut scenario = setup();
+        anchor(&mut scenario, VOTING_ENDS, 3, 0, 0, b"plutocracy");
+        ts::end(scenario);
+    }
+
+    #[test]
+    #[expected_failure(abort_code = wot_id::governance::E_INVALID_HASH)]
+    fun hashes_must_be_sha256() {
+        let mut scenario = setup();
+        ts::next_tx(&mut scenario, PUBLISHER);
+        let cap = ts::take_from_sender<AnchorCap>(&scenario);
+        let clock = clock_at(&mut scenario, VOTING_ENDS);
+        governance::anchor_result(
+            &cap, b"proposal-1", b"short", hash(), b"one_did_one_vote", 3, 0, 0, 3, 3, 50, VOTING_ENDS, &clock,
+            ts::ctx(&mut scenario),
+        );
+        clock::destroy_for_testing(clock);
+        ts::return_to_sender(&scenario, cap);
+        ts::end(scenario);
+    }
+}
@@ -0,0 +1,243 @@
+#[test_only]
+module wot_id::identity_tests {
+    use iota::clock::{Self, Clock};
+    use iota::test_scenario::{Self as ts, Scenario};
+    use wot_id::identity::{Self, DidRegistry, Identity};
+
+    const ALICE: address = @0xA;
+    const MALLORY: address = @0xC;
+    // Wallet Alice recovers control with
+    const NEW: address = @0xD;
+    const GUARDIAN_1: address = @0x1;
+    const GUARDIAN_2: address = @0x2;
+    const GUARDIAN_3: address = @0x3;
+
+    const TIME_LOCK_MS: u64 = 1_000;
+
+    // Alice's Identity, guarded by the Identities of three other wallets
+    public struct Guarded has drop {
+        alice: ID,
+        guardians: vector<ID>,
+    }
+
+    fun setup(): Scenario {
+        let mut scenario = ts::begin(ALICE);
+        identity::init_for_testing(ts::ctx(&mut scenario));
+        scenario
+    }
+
+    fun clock_at(scenario: &mut Scenario, now: u64): Clock {
+        let mut clock = clock::create_for_testing(ts::ctx(scenario));
+        clock::set_for_testing(&mut clock, now);
+        clock
+    }
+
+    // Create an Identity controlled by `controller` and return its object ID
+    fun create_identity(scenario: &mut Scenario, controller: address): ID {
+        ts::next_tx(scenario, controller);
+        let mut registry = ts::take_shared<DidRegistry>(scenario);
+        identity::create_identity(&mut registry, ts::ctx(scenario));
+        ts::return_shared(registry);
+        ts::next_tx(scenario, controller);
+        option::destroy_some(ts::most_recent_id_shared<Identity>())
+    }
+
+    fun guarded(scenario: &mut Scenario, threshold: u64): Guarded {
+        let alice = create_identity(scenario, ALICE);
+        let guardians = vector[
+            create_identity(scenario, GUARDIAN_1),
+            create_identity(scenario, GUARDIAN_2),
+            create_identity(scenario, GUARDIAN_3),
+        ];
+        configure(scenario, alice, guardians, threshold);
+        Guarded { alice, guardians }
+    }
+
+    fun nth_guardian(guarded: &Guarded, index: u64): ID {
+        *vector::borrow(&guarded.guardians, index)
+    }
+
+    fun configure(scenario: &mut Scenario, alice: ID, guardians: vector<ID>, threshold: u64) {
+        ts::next_tx(scenario, ALICE);
+        let mut identity = ts::take_shared_by_id<Identity>(scenario, alice);
+        identity::configure_recovery(&mut identity, guardians, threshold, TIME_LOCK_MS, ts::ctx(scenario));
+        ts::return_shared(identity);
+    }
+
+    // Guardian `index` of `guarded`, acting through `sender`, opens a request moving control to NEW
+    fun initiate(scenario: &mut Scenario, guarded: &Guarded, index: u64, sender: address, now: u64) {
+        ts::next_tx(scenario, sender);
+        let mut identity = ts::take_shared_by_id<Identity>(scenario, guarded.alice);
+        let guardian = ts::take_shared_by_id<Identity>(scenario, nth_guardian(guarded, index));
+        let clock = clock_at(scenario, now);
+        identity::initiate_recovery(&mut identity, &guardian, NEW, vector[ALICE], &clock, ts::ctx(scenario));
+        clock::destroy_for_testing(clock);
+        ts::return_shared(guardian);
+        ts::return_shared(identity);
+    }
+
+    fun approve(scenario: &mut Scenario, alice: ID, guardian_id: ID, sender: address, now: u64) {
+        ts::next_tx(scenario, sender);
+        let mut identity = ts::take_shared_by_id<Identity>(scenario, alice);
+        let guardian = ts::take_shared_by_id<Identity>(scenario, guardian_id);
+        let clock = clock_at(scenario, now);
+        identity::approve_recovery(&mut identity, &guardian, &clock, ts::ctx(scenario));
+        clock::destroy_for_testing(clock);
+        ts::return_shared(guardian);
+        ts::return_shared(identity);
+    }
+
+    fun cancel(scenario: &mut Scenario, alice: ID, sender: address) {
+        ts::next_tx(scenario, sender);
+        let mut identity = ts::take_shared_by_id<Identity>(scenario, alice);
+        identity::cancel_recovery(&mut identity, ts::ctx(scenario));
+        ts::return_shared(identity);
+    }
+
+    fun recover(scenario: &mut Scenario, alice: ID, sender: address, now: u64) {
+        ts::next_tx(scenario, sender);
+        let mut identity = ts::take_shared_by_id<Identity>(scenario, alice);
+        let clock = clock_at(scenario, now);
+        identity::recover_controller(&mut identity, &clock, ts::ctx(scenario));
+        clock::destroy_for_testing(clock);
+        ts::return_shared(identity);
+    }
+
+    fun controllers(scenario: &mut Scenario, alice: ID): vector<address> {
+        ts::next_tx(scenario, MALLORY);
+        let identity = ts::take_shared_by_id<Identity>(scenario, alice);
+        let controllers = identity::controllers(&identity);
+        ts::return_shared(identity);
+        controllers
+    }
+
+    #[test]
+    fun threshold_of_guardians_hands_over_control_after_the_time_lock() {
+        let mut scenario = setup();
+        let guarded = guarded(&mut scenario, 2);
+        initiate(&mut scenario, &guarded, 0, GUARDIAN_1, 0);
+        // The time lock starts with the second approval
+        approve(&mut scenario, guarded.alice, nth_guardian(&guarded, 1), GUARDIAN_2, 100);
+
+        recover(&mut scenario, guarded.alice, NEW, 100 + TIME_LOCK_MS);
+        assert!(controllers(&mut scenario, guarded.alice) == vector[NEW]);
+        ts::end(scenario);
+    }
+
+    #[test]
+    #[expected_failure(abort_code = wot_id::identity::E_TIME_LOCKED)]
+    fun below_the_threshold_the_request_never_unlocks() {
+        let mut scenario = setup();
+        let guarded = guarded(&mut scenario, 2);
+        initiate(&mut scenario, &guarded, 0, GUARDIAN_1, 0);
+        recover(&mut scenario, guarded.alice, NEW, 100 * TIME_LOCK_MS);
+        ts::end(scenario);
+    }
+
+    #[test]
+    #[expected_failure(abort_code = wot_id::identity::E_TIME_LOCKED)]
+    fun cannot_recover_before_the_time_lock_ends() {
+        let mut scenario = setup();
+        let guarded = guarded(&mut scenario, 2);
+        initiate(&mut scenario, &guarded, 0, GUARDIAN_1, 0);
+        approve(&mut scenario, guarded.alice, nth_guardian(&guarded, 1), GUARDIAN_2, 100);
+        recover(&mut scenario, guarded.alice, NEW, 100 + TIME_LOCK_MS - 1);
+        ts::end(scenario);
+    }
+
+    #[test]
+    #[expected_failure(abort_code = wot_id::identity::E_ALREADY_APPROVED)]
+    fun a_guardian_counts_once() {
+        let mut scenario = setup();
+        let guarded = guarded(&mut scenario, 2);
+        initiate(&mut scenario, &guarded, 0, GUARDIAN_1, 0);
+        approve(&mut scenario, guarded.alice, nth_guardian(&guarded, 0), GUARDIAN_1, 100);
+        ts::end(scenario);
+    }
+
+    #[test]
+    #[expected_failure(abort_code = wot_id::identity::E_NOT_GUARDIAN)]
+    fun other_identities_cannot_approve() {
+        let mut scenario = setup();
+        let guarded = guarded(&mut scenario, 2);
+        let mallory = create_identity(&mut scenario, MALLORY);
+        initiate(&mut scenario, &guarded, 0, GUARDIAN_1, 0);
+        approve(&mut scenario, guarded.alice, mallory, MALLORY, 100);
+        ts::end(scenario);
+    }
+
+    #[test]
+    #[expected_failure(abort_code = wot_id::identity::E_NOT_AUTHORIZED)]
+    fun approvals_come_from_the_guardians_controller() {
+        let mut scenario = setup();
+        let guarded = guarded(&mut scenario, 2);
+        initiate(&mut scenario, &guarded, 0, GUARDIAN_1, 0);
+        approve(&mut scenario, guarded.alice, nth_guardian(&guarded, 1), MALLORY, 100);
+        ts::end(scenario);
+    }
+
+    #[test]
+    #[expected_failure(abort_code = wot_id::identity::E_NOT_AUTHORIZED)]
+    fun only_the_new_controller_executes() {
+        let mut scenario = setup();
+        let guarded = guarded(&mut scenario, 1);
+        initiate(&mut scenario, &guarded, 0, GUARDIAN_1, 0);
+        recover(&mut scenario, guarded.alice, MALLORY, TIME_LOCK_MS);
+        ts::end(scenario);
+    }
+
+    #[test]
+    #[expected_failure(abort_code = wot_id::identity::E_NO_RECOVERY)]
+    fun a_controller_can_cancel_during_the_time_lock() {
+        let mut scenario = setup();
+        let guarded = guarded(&mut scenario, 2);
+        initiate(&mut scenario, &guarded, 0, GUARDIAN_1, 0);
+        approve(&mut scenario, guarded.alice, nth_guardian(&guarded, 1), GUARDIAN_2, 100);
+        cancel(&mut scenario, guarded.alice, ALICE);
+        recover(&mut scenario, guarded.alice, NEW, 100 + TIME_LOCK_MS);
+        ts::end(scenario);
+    }
+
+    #[test]
+    #[expected_failure(abort_code = wot_id::identity::E_NOT_AUTHORIZED)]
+    fun only_a_controller_can_cancel() {
+        let mut scenario = setup();
+        let guarded = guarded(&mut scenario, 2);
+        initiate(&mut scenario, &guarded, 0, GUARDIAN_1, 0);
+        cancel(&mut scenario, guarded.alice, MALLORY);
+        ts::end(scenario);
+    }
+
+    #[test]
+    fun a_cancelled_request_can_be_opened_again() {
+        let mut scenario = setup();
+        let guarded = guarded(&mut scenario, 2);
+        initiate(&mut scenario, &guarded, 0, GUARDIAN_1, 0);
+        cancel(&mut scenario, guarded.alice, ALICE);
+
+        // Earlier approvals are gone: the new request needs the threshold again
+        initiate(&mut scenario, &guarded, 2, GUARDIAN_3, 500);
+        approve(&mut scenario, guarded.alice, nth_guardian(&guarded, 0), GUARDIAN_1, 600);
+        recover(&mut scenario, guarded.alice, NEW, 600 + TIME_LOCK_MS);
+        assert!(controllers(&mut scenario, guarded.alice) == vector[NEW]);
+        ts::end(scenario);
+    }
+
+    #[test]
+    #[expected_failure(abort_code = wot_id::identity::E_RECOVERY_IN_PROGRESS)]
+    fun guardians_cannot_be_replaced_while_a_request_is_open() {
+        let mut scenario = setup();
+        let guarded = guarded(&mut scenario, 2);
+        initiate(&mut scenario, &guarded, 0, GUARDIAN_1, 0);
+        configure(&mut scenario, guarded.alice, guarded.guardians, 1);
+        ts::end(scenario);
+    }
+
+    #[test]
+    #[expected_failure(abort_code = wot_id::identity::E_INVALID_THRESHOLD)]
+    fun threshold_cannot_exceed_the_guardians() {
+        let mut scenario = setup();
+        guarded(&mut scenario, 4);
+        ts::end(scenario);
+    }
+}
@@ -0,0 +1,96 @@
+#[test_only]
+module wot_id::mailbox_tests {
+    use iota::test_scenario::{Self as ts, Scenario};
+    use wot_id::identity::{Self, DidRegistry, Identity};
+    use wot_id::mailbox::{Self, Mailbox, MailboxDirectory, MessageObject};
+
+    const ALICE: address = @0xA;
+    const BOB: address = @0xB;
+    const MALLORY: address = @0xC;
+
+    fun setup(): Scenario {
+        let mut scenario = ts::begin(ALICE);
+        identity::init_for_testing(ts::ctx(&mut scenario));
+        mailbox::init_for_testing(ts::ctx(&mut scenario));
+        scenario
+    }
+
+    // Create an Identity controlled by `controller` and return its object ID
+    fun create_identity(scenario: &mut Scenario, controller: address): ID {
+        ts::next_tx(scenario, controller);
+        let mut registry = ts::take_shared<DidRegistry>(scenario);
+        identity::create_identity(&mut registry, ts::ctx(scenario));
+        ts::return_shared(registry);
+        ts::next_tx(scenario, controller);
+        option::destroy_some(ts::most_recent_id_shared<Identity>())
+    }
+
+    // Open a mailbox for the Identity `identity_id` and return the mailbox's object ID
+    fun create_mailbox(scenario: &mut Scenario, controller: address, identity_id: ID): ID {
+        ts::next_tx(scenario, controller);
+        let mut directory = ts::take_shared<MailboxDirectory>(scenario);
+        let identity = ts::take_shared_by_id<Identity>(scenario, identity_id);
+        mailbox::create_mailbox(&mut directory, &identity, ts::ctx(scenario));
+        ts::return_shared(identity);
+        ts::return_shared(directory);
+        ts::next_tx(scenario, controller);
+        option::destroy_some(ts::most_recent_id_shared<Mailbox>())
+    }
+
+    fun send(scenario: &mut Scenario, signer: address, mailbox_id: ID, sender_id: ID) {
+        ts::next_tx(scenario, signer);
+        let mailbox = ts::take_shared_by_id<Mailbox>(scenario, mailbox_id);
+        let sender = ts::take_shared_by_id<Identity>(scenario, sender_id);
+        mailbox::send_message(&mailbox, &sender, b"sealed", ts::ctx(scenario));
+        ts::return_shared(sender);
+        ts::return_shared(mailbox);
+    }
+
+    #[test]
+    fun directory_resolves_the_newest_mailbox() {
+        let mut scenario = setup();
+        let alice = create_identity(&mut scenario, ALICE);
+        let first = create_mailbox(&mut scenario, ALICE, alice);
+        let second = create_mailbox(&mut scenario, ALICE, alice);
+        assert!(first != second);
+
+        ts::next_tx(&mut scenario, BOB);
+        let directory = ts::take_shared<MailboxDirectory>(&scenario);
+        let identity = ts::take_shared_by_id<Identity>(&scenario, alice);
+        let did = identity::get_did(&identity);
+        assert!(mailbox::mailbox_of(&directory, did) == option::some(second));
+        assert!(option::is_none(&mailbox::mailbox_of(&directory, std::string::utf8(b"did:iota:0xunknown"))));
+        ts::return_shared(identity);
+        ts::return_shared(directory);
+        ts::end(scenario);
+    }
+
+    #[test]
+    fun message_carries_the_sending_identity() {
+        let mut scenario = setup();
+        let alice = create_identity(&mut scenario, ALICE);
+        let bob = create_identity(&mut scenario, BOB);
+        let mailbox_id = create_mailbox(&mut scenario, ALICE, alice);
+        send(&mut scenario, BOB, mailbox_id, bob);
+
+        ts::next_tx(&mut scenario, BOB);
+        let message = ts::take_from_address<MessageObject>(&scenario, object::id_to_address(&mailbox_id));
+        let sender = ts::take_shared_by_id<Identity>(&scenario, bob);
+        assert!(mailbox::message_sender(&message) == identity::get_did(&sender));
+        ts::return_shared(sender);
+        ts::return_to_address(object::id_to_address(&mailbox_id), message);
+        ts::end(scenario);
+    }
+
+    // Mallory cannot send as Bob: the signer must control the sending Identity
+    #[test]
+    #[expected_failure(abort_code = wot_id::identity::E_NOT_AUTHORIZED)]
+    fun sender_must_control_the_sending_identity() {
+        let mut scenario = setup();
+        let alice = create_identity(&mut scenario, ALICE);
+        let bob = create_identity(&mut scenario, BOB);
+        let mailbox_id = create_mailbox(&mut scenario, ALICE, alice);
+        send(&mut scenario, MALLORY, mailbox_id, bob);
+        ts::end(scenario);
+    }
+}
@@ -0,0 +1,349 @@
+use futures::future::BoxFuture;
+use futures::stream::{FuturesUnordered, StreamExt};
+use futures::FutureExt;
+use libp2p::core::transport::PortUse;
+use libp2p::core::Endpoint;
+use libp2p::request_response::{self, ProtocolSupport, ResponseChannel};
+use libp2p::swarm::{
+    CloseConnection, ConnectionDenied, ConnectionId, FromSwarm, NetworkBehaviour, THandler, THandlerInEvent,
+    THandlerOutEvent, ToSwarm,
+};
+use libp2p::{Multiaddr, PeerId};
+use rand::RngCore;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::protocol::{HandshakeRequest, HandshakeResponse, Presentation, PROTOCOL_NAME};
+use crate::verify::{PresentationSigner, PresentationVerifier, VerifiedPeer, VerifiedPresentation};
+use crate::HandshakeError;
+
+type Inner = request_response::json::Behaviour<HandshakeRequest, HandshakeResponse>;
+
+pub struct Config {
+    pub signer: Arc<dyn PresentationSigner>,
+    pub verifier: Arc<dyn PresentationVerifier>,
+    // Presentations older (or newer) than this are rejected
+    pub max_presentation_age: Duration,
+    pub request_timeout: Duration,
+}
+
+impl Config {
+    pub fn new(signer: Arc<dyn PresentationSigner>, verifier: Arc<dyn PresentationVerifier>) -> Self {
+        Self {
+            signer,
+            verifier,
+            max_presentation_age: Duration::from_secs(300),
+            request_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Event {
+    // The remote peer's presentation verified and it accepted ours
+    Verified { peer_id: PeerId, peer: VerifiedPeer },
+    // The handshake failed and the connection is being closed
+    Rejected { peer_id: PeerId, error: HandshakeError },
+}
+
+// Where one peer's handshake stands
+enum Session {
+    // Dialer: sent our challenge, waiting for the listener's presentation
+    AwaitingPresentation { nonce: String },
+    // Dialer: listener verified, waiting for it to accept our presentation
+    AwaitingVerdict { peer: VerifiedPeer },
+    // Listener: sent our presentation and challenge, waiting for the dialer's presentation
+    AwaitingCounterPresentation { nonce: String },
+    Verified,
+}
+
+// Results of signing/verification work, which runs off the swarm's poll loop
+enum Task {
+    // Listener signed its presentation in answer to a challenge
+    Answered { peer_id: PeerId, nonce: String, channel: ResponseChannel<HandshakeResponse>, jws: Result<String, HandshakeError> },
+    // Dialer verified the listener's presentation and signed its own
+    DialerVerified { peer_id: PeerId, result: Result<(VerifiedPeer, String), HandshakeError> },
+    // Listener verified the dialer's presentation
+    ListenerVerified { peer_id: PeerId, channel: ResponseChannel<HandshakeResponse>, result: Result<VerifiedPeer, HandshakeError> },
+}
+
+// libp2p behaviour running the VC-gated handshake on every new connection. Dial
+// as usual; wait for `Event::Verified` before trusting the peer.
+pub struct Behaviour {
+    inner: Inner,
+    local_peer_id: PeerId,
+    config: Config,
+    sessions: HashMap<PeerId, Session>,
+    verified: HashMap<PeerId, VerifiedPeer>,
+    tasks: FuturesUnordered<BoxFuture<'static, Task>>,
+    pending: VecDeque<ToSwarm<Event, THandlerInEvent<Self>>>,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+fn new_nonce() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+impl Behaviour {
+    pub fn new(local_peer_id: PeerId, config: Config) -> Self {
+        let inner = request_response::json::Behaviour::new(
+            [(PROTOCOL_NAME, ProtocolSupport::Full)],
+            request_response::Config::default().with_request_timeout(config.request_timeout),
+        );
+        Self {
+            inner,
+            local_peer_id,
+            config,
+            sessions: HashMap::new(),
+            verified: HashMap::new(),
+            tasks: FuturesUnordered::new(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    pub fn verified_peer(&self, peer_id: &PeerId) -> Option<&VerifiedPeer> {
+        self.verified.get(peer_id)
+    }
+
+    // Sign a presentation of our credentials addressed to `audience` and bound to its challenge
+    fn sign_presentation(&self, audience: PeerId, challenge: String) -> BoxFuture<'static, Result<String, HandshakeError>> {
+        let presentation = Presentation {
+            did: self.config.signer.did().to_string(),
+            peer_id: self.local_peer_id.to_string(),
+            audience: audience.to_string(),
+            challenge,
+            credentials: self.config.signer.credentials(),
+            iat: now_secs(),
+        };
+        let payload = serde_json::to_vec(&presentation).expect("presentation serializes to JSON");
+        self.config.signer.sign(payload)
+    }
+
+    // Verify a presentation from `peer_id` and check it is bound to this session
+    fn verify_presentation(
+        &self,
+        peer_id: PeerId,
+        jws: String,
+        challenge: String,
+    ) -> BoxFuture<'static, Result<VerifiedPeer, HandshakeError>> {
+        let verify = self.config.verifier.verify(jws);
+        let local_peer_id = self.local_peer_id;
+        let max_age = self.config.max_presentation_age.as_secs();
+        async move {
+            let VerifiedPresentation { presentation, credential_types } = verify.await?;
+            if presentation.peer_id != peer_id.to_string() {
+                return Err(HandshakeError::BindingMismatch(format!(
+                    "presentation is for peer {}, connection is with {}",
+                    presentation.peer_id, peer_id
+                )));
+            }
+            if presentation.audience != local_peer_id.to_string() {
+                return Err(HandshakeError::BindingMismatch("presentation is addressed to another peer".to_string()));
+            }
+            if presentation.challenge != challenge {
+                return Err(HandshakeError::BindingMismatch("challenge does not match".to_string()));
+            }
+            if now_secs().abs_diff(presentation.iat) > max_age {
+                return Err(HandshakeError::BindingMismatch("presentation is stale".to_string()));
+            }
+            Ok(VerifiedPeer { did: presentation.did, credential_types })
+        }
+        .boxed()
+    }
+
+    fn reject(&mut self, peer_id: PeerId, error: HandshakeError) {
+        tracing::warn!(target: "p2p_handshake", %peer_id, %error, "Handshake failed; closing connection.");
+        self.sessions.remove(&peer_id);
+        self.verified.remove(&peer_id);
+        self.pending.push_back(ToSwarm::GenerateEvent(Event::Rejected { peer_id, error }));
+        self.pending.push_back(ToSwarm::CloseConnection { peer_id, connection: CloseConnection::All });
+    }
+
+    fn accept(&mut self, peer_id: PeerId, peer: VerifiedPeer) {
+        tracing::info!(target: "p2p_handshake", %peer_id, did = %peer.did, "Peer verified.");
+        self.sessions.insert(peer_id, Session::Verified);
+        self.verified.insert(peer_id, peer.clone());
+        self.pending.push_back(ToSwarm::GenerateEvent(Event::Verified { peer_id, peer }));
+    }
+
+    fn start_handshake(&mut self, peer_id: PeerId) {
+        let nonce = new_nonce();
+        self.inner.send_request(&peer_id, HandshakeRequest::Challenge { nonce: nonce.clone() });
+        self.sessions.insert(peer_id, Session::AwaitingPresentation { nonce });
+    }
+
+    fn on_request(&mut self, peer_id: PeerId, request: HandshakeRequest, channel: ResponseChannel<HandshakeResponse>) {
+        match (request, self.sessions.get(&peer_id)) {
+            (HandshakeRequest::Challenge { nonce: challenge }, None) => {
+                let nonce = new_nonce();
+                let sign = self.sign_presentation(peer_id, challenge);
+                self.tasks.push(
+                    async move { Task::Answered { peer_id, nonce, channel, jws: sign.await } }.boxed(),
+                );
+            }
+            (HandshakeRequest::Presentation { jws }, Some(Session::AwaitingCounterPresentation { nonce })) => {
+                let verify = self.verify_presentation(peer_id, jws, nonce.clone());
+                self.tasks.push(
+                    async move { Task::ListenerVerified { peer_id, channel, result: verify.await } }.boxed(),
+                );
+            }
+            (request, _) => {
+                let reason = format!("unexpected {:?} at this stage", request);
+                let _ = self.inner.send_response(channel, HandshakeResponse::Rejected { reason: reason.clone() });
+                self.reject(peer_id, HandshakeError::Protocol(reason));
+            }
+        }
+    }
+
+    fn on_response(&mut self, peer_id: PeerId, response: HandshakeResponse) {
+        match (response, self.sessions.remove(&peer_id)) {
+            (HandshakeResponse::Presentation { jws, nonce: counter_challenge }, Some(Session::AwaitingPresentation { nonce })) => {
+                let verify = self.verify_presentation(peer_id, jws, nonce);
+                let sign = self.sign_presentation(peer_id, counter_challenge);
+                self.tasks.push(
+                    async move {
+                        let result = match verify.await {
+                            Ok(peer) => sign.await.map(|jws| (peer, jws)),
+                            Err(e) => Err(e),
+                        };
+                        Task::DialerVerified { peer_id, result }
+                    }
+                    .boxed(),
+                );
+            }
+            (HandshakeResponse::Accepted, Some(Session::AwaitingVerdict { peer })) => self.accept(peer_id, peer),
+            (HandshakeResponse::Rejected { reason }, _) => self.reject(peer_id, HandshakeError::RemoteRejected(reason)),
+            (response, _) => self.reject(peer_id, HandshakeError::Protocol(format!("unexpected {:?}", response))),
+        }
+    }
+
+    fn on_task(&mut self, task: Task) {
+        match task {
+            Task::Answered { peer_id, nonce, channel, jws } => match jws {
+                Ok(jws) => {
+                    let response = HandshakeResponse::Presentation { jws, nonce: nonce.clone() };
+                    if self.inner.send_response(channel, response).is_ok() {
+                        self.sessions.insert(peer_id, Session::AwaitingCounterPresentation { nonce });
+                    }
+                }
+                Err(e) => self.reject(peer_id, e),
+            },
+            Task::DialerVerified { peer_id, result } => match result {
+                Ok((peer, jws)) => {
+                    self.inner.send_request(&peer_id, HandshakeRequest::Presentation { jws });
+                    self.sessions.insert(peer_id, Session::AwaitingVerdict { peer });
+                }
+                Err(e) => self.reject(peer_id, e),
+            },
+            Task::ListenerVerified { peer_id, channel, result } => match result {
+                Ok(peer) => {
+                    if self.inner.send_response(channel, HandshakeResponse::Accepted).is_ok() {
+                        self.accept(peer_id, peer);
+                    }
+                }
+                Err(e) => {
+                    let _ = self.inner.send_response(channel, HandshakeResponse::Rejected { reason: e.to_string() });
+                    self.reject(peer_id, e);
+                }
+            },
+        }
+    }
+
+    fn on_inner_event(&mut self, event: request_response::Event<HandshakeRequest, HandshakeResponse>) {
+        match event {
+            request_response::Event::Message { peer, message, .. } => match message {
+                request_response::Message::Request { request, channel, .. } => self.on_request(peer, request, channel),
+                request_response::Message::Response { response, .. } => self.on_response(peer, response),
+            },
+            request_response::Event::OutboundFailure { peer, error, .. } => {
+                if self.sessions.contains_key(&peer) && !self.verified.contains_key(&peer) {
+                    self.reject(peer, HandshakeError::Protocol(format!("request failed: {}", error)));
+                }
+            }
+            request_response::Event::InboundFailure { peer, error, .. } => {
+                if !self.verified.contains_key(&peer) {
+                    self.reject(peer, HandshakeError::Protocol(format!("inbound request failed: {}", error)));
+                }
+            }
+            request_response::Event::ResponseSent { .. } => {}
+        }
+    }
+}
+
+impl NetworkBehaviour for Behaviour {
+    type ConnectionHandler = <Inner as NetworkBehaviour>::ConnectionHandler;
+    type ToSwarm = Event;
+
+    fn handle_established_inbound_connection(
+        &mut self,
+        connection_id: ConnectionId,
+        peer: PeerId,
+        local_addr: &Multiaddr,
+        remote_addr: &Multiaddr,
+    ) -> Result<THandler<Self>, ConnectionDenied> {
+        self.inner
+            .handle_established_inbound_connection(connection_id, peer, local_addr, remote_addr)
+    }
+
+    fn handle_established_outbound_connection(
+        &mut self,
+        connection_id: ConnectionId,
+        peer: PeerId,
+        addr: &Multiaddr,
+        role_override: Endpoint,
+        port_use: PortUse,
+    ) -> Result<THandler<Self>, ConnectionDenied> {
+        self.inner
+            .handle_established_outbound_connection(connection_id, peer, addr, role_override, port_use)
+    }
+
+    fn on_swarm_event(&mut self, event: FromSwarm) {
+        match &event {
+            // The dialer drives the handshake, once per peer
+            FromSwarm::ConnectionEstablished(established)
+                if established.endpoint.is_dialer() && established.other_established == 0 =>
+            {
+                self.start_handshake(established.peer_id);
+            }
+            FromSwarm::ConnectionClosed(closed) if closed.remaining_established == 0 => {
+                self.sessions.remove(&closed.peer_id);
+                self.verified.remove(&closed.peer_id);
+            }
+            _ => {}
+        }
+        self.inner.on_swarm_event(event);
+    }
+
+    fn on_connection_handler_event(
+        &mut self,
+        peer_id: PeerId,
+        connection_id: ConnectionId,
+        event: THandlerOutEvent<Self>,
+    ) {
+        self.inner.on_connection_handler_event(peer_id, connection_id, event);
+    }
+
+    fn poll(&mut self, cx: &mut Context<'_>) -> Poll<ToSwarm<Self::ToSwarm, THandlerInEvent<Self>>> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Poll::Ready(event);
+            }
+            if let Poll::Ready(Some(task)) = self.tasks.poll_next_unpin(cx) {
+                self.on_task(task);
+                continue;
+            }
+            match self.inner.poll(cx) {
+                Poll::Ready(ToSwarm::GenerateEvent(event)) => self.on_inner_event(event),
+                Poll::Ready(other) => return Poll::Ready(other.map_out(|_| unreachable!("generated events are handled above"))),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
@@ -0,0 +1,45 @@
+//! VC-gated handshake for wot.id peer-to-peer sessions.
+//!
+//! Before two peers exchange application traffic over libp2p, each presents a JWS-signed
+//! presentation containing its DID and "Verified Human" credentials (VC-JWTs). The
+//! presentation is bound to both peer IDs and a fresh challenge from the other side, so it
+//! cannot be replayed on another connection. Verification uses the same DID resolution and
+//! EdDSA JWS verification as identity-service. A peer that fails verification is disconnected.
+//!
+//! Flow (`A` dialed `B`):
+//!
+//! 1. `A -> B`: `Challenge { nonce_a }`
+//! 2. `B -> A`: `Presentation { jws_b(nonce_a), nonce_b }`
+//! 3. `A` verifies `B`, then `A -> B`: `Presentation { jws_a(nonce_b) }`
+//! 4. `B` verifies `A` and answers `Accepted` or `Rejected`
+
+mod behaviour;
+mod protocol;
+mod verify;
+
+pub use behaviour::{Behaviour, Config, Event};
+pub use protocol::{HandshakeRequest, HandshakeResponse, Presentation, PROTOCOL_NAME};
+pub use verify::{
+    CredentialPolicy, DidVerifier, DocumentResolver, IotaResolver, PresentationSigner, PresentationVerifier,
+    StaticResolver, StorageSigner, VerifiedPeer, VerifiedPresentation, VERIFIED_HUMAN_TYPE,
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum HandshakeError {
+    #[error("failed to resolve {did}: {reason}")]
+    Resolution { did: String, reason: String },
+    #[error("presentation signature is invalid: {0}")]
+    InvalidSignature(String),
+    #[error("presentation is malformed: {0}")]
+    MalformedPresentation(String),
+    #[error("presentation is not bound to this session: {0}")]
+    BindingMismatch(String),
+    #[error("credential rejected: {0}")]
+    CredentialRejected(String),
+    #[error("failed to sign presentation: {0}")]
+    Signing(String),
+    #[error("peer rejected our presentation: {0}")]
+    RemoteRejected(String),
+    #[error("handshake protocol violation: {0}")]
+    Protocol(String),
+}
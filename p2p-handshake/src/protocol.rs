@@ -0,0 +1,37 @@
+use libp2p::StreamProtocol;
+use serde::{Deserialize, Serialize};
+
+pub const PROTOCOL_NAME: StreamProtocol = StreamProtocol::new("/wot-id/vc-handshake/1.0.0");
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HandshakeRequest {
+    // Sent by the dialer to open the handshake
+    Challenge { nonce: String },
+    // The dialer's presentation, answering the listener's challenge
+    Presentation { jws: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HandshakeResponse {
+    // The listener's presentation plus its own challenge for the dialer
+    Presentation { jws: String, nonce: String },
+    Accepted,
+    Rejected { reason: String },
+}
+
+// Claims signed by each side (compact JWS, signed with a method of `did`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Presentation {
+    pub did: String,
+    // The presenter's own libp2p peer ID
+    pub peer_id: String,
+    // The peer the presentation is addressed to
+    pub audience: String,
+    // The audience's challenge nonce
+    pub challenge: String,
+    // VC-JWTs whose subject is `did`
+    pub credentials: Vec<String>,
+    pub iat: u64,
+}
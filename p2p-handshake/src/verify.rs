@@ -0,0 +1,251 @@
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use identity_eddsa_verifier::EdDSAJwsVerifier;
+use identity_iota::core::Object;
+use identity_iota::credential::{
+    FailFast, Jwt, JwtCredentialValidationOptions, JwtCredentialValidator, JwtCredentialValidatorUtils,
+};
+use identity_iota::did::CoreDID;
+use identity_iota::document::verifiable::JwsVerificationOptions;
+use identity_iota::document::CoreDocument;
+use identity_iota::iota::rebased::client::IdentityClientReadOnly;
+use identity_iota::iota::IotaDID;
+use identity_iota::storage::{JwkDocumentExt, JwkStorage, JwsSignatureOptions, KeyIdStorage, Storage};
+
+use crate::protocol::Presentation;
+use crate::HandshakeError;
+
+// Credential type a peer must hold unless the policy says otherwise
+pub const VERIFIED_HUMAN_TYPE: &str = "VerifiedHuman";
+
+// Resolves DID documents for presentation and credential verification
+pub trait DocumentResolver: Send + Sync + 'static {
+    fn resolve(&self, did: &str) -> BoxFuture<'static, Result<CoreDocument, HandshakeError>>;
+}
+
+// Resolves `did:iota` documents on-chain, like identity-service does
+pub struct IotaResolver {
+    client: Arc<IdentityClientReadOnly>,
+}
+
+impl IotaResolver {
+    pub fn new(client: Arc<IdentityClientReadOnly>) -> Self {
+        Self { client }
+    }
+}
+
+impl DocumentResolver for IotaResolver {
+    fn resolve(&self, did: &str) -> BoxFuture<'static, Result<CoreDocument, HandshakeError>> {
+        let client = self.client.clone();
+        let did = did.to_string();
+        async move {
+            let iota_did = IotaDID::parse(&did)
+                .map_err(|e| HandshakeError::Resolution { did: did.clone(), reason: e.to_string() })?;
+            let document = client
+                .resolve_did(&iota_did)
+                .await
+                .map_err(|e| HandshakeError::Resolution { did: did.clone(), reason: e.to_string() })?;
+            Ok(document.core_document().clone())
+        }
+        .boxed()
+    }
+}
+
+// Fixed set of documents, for tests and closed deployments
+#[derive(Default, Clone)]
+pub struct StaticResolver {
+    documents: HashMap<String, CoreDocument>,
+}
+
+impl StaticResolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, document: CoreDocument) {
+        self.documents.insert(document.id().to_string(), document);
+    }
+}
+
+impl DocumentResolver for StaticResolver {
+    fn resolve(&self, did: &str) -> BoxFuture<'static, Result<CoreDocument, HandshakeError>> {
+        let result = self.documents.get(did).cloned().ok_or_else(|| HandshakeError::Resolution {
+            did: did.to_string(),
+            reason: "unknown DID".to_string(),
+        });
+        futures::future::ready(result).boxed()
+    }
+}
+
+// Signs this peer's presentations
+pub trait PresentationSigner: Send + Sync + 'static {
+    fn did(&self) -> &str;
+    // VC-JWTs to present, with this peer's DID as subject
+    fn credentials(&self) -> Vec<String>;
+    fn sign(&self, payload: Vec<u8>) -> BoxFuture<'static, Result<String, HandshakeError>>;
+}
+
+// Signs with a verification method of `document` whose key lives in an identity.rs `Storage`
+pub struct StorageSigner<K, I> {
+    document: CoreDocument,
+    did: String,
+    storage: Arc<Storage<K, I>>,
+    fragment: String,
+    credentials: Vec<String>,
+}
+
+impl<K, I> StorageSigner<K, I> {
+    pub fn new(document: CoreDocument, storage: Arc<Storage<K, I>>, fragment: String, credentials: Vec<String>) -> Self {
+        Self { did: document.id().to_string(), document, storage, fragment, credentials }
+    }
+}
+
+impl<K, I> PresentationSigner for StorageSigner<K, I>
+where
+    K: JwkStorage + Send + Sync + 'static,
+    I: KeyIdStorage + Send + Sync + 'static,
+{
+    fn did(&self) -> &str {
+        &self.did
+    }
+
+    fn credentials(&self) -> Vec<String> {
+        self.credentials.clone()
+    }
+
+    fn sign(&self, payload: Vec<u8>) -> BoxFuture<'static, Result<String, HandshakeError>> {
+        let document = self.document.clone();
+        let storage = self.storage.clone();
+        let fragment = self.fragment.clone();
+        async move {
+            document
+                .create_jws(&storage, &fragment, &payload, &JwsSignatureOptions::default())
+                .await
+                .map(|jws| jws.as_str().to_string())
+                .map_err(|e| HandshakeError::Signing(e.to_string()))
+        }
+        .boxed()
+    }
+}
+
+// Which credentials a peer must present
+#[derive(Debug, Clone)]
+pub struct CredentialPolicy {
+    // Every one of these types must be held (in one credential or across several)
+    pub required_types: Vec<String>,
+    // Issuer DIDs accepted for required credentials; empty accepts any resolvable issuer
+    pub trusted_issuers: Vec<String>,
+}
+
+impl Default for CredentialPolicy {
+    fn default() -> Self {
+        Self { required_types: vec![VERIFIED_HUMAN_TYPE.to_string()], trusted_issuers: Vec::new() }
+    }
+}
+
+// A presentation whose signature and credentials checked out. Session binding (peer IDs,
+// challenge, freshness) is checked by the behaviour.
+#[derive(Debug, Clone)]
+pub struct VerifiedPresentation {
+    pub presentation: Presentation,
+    pub credential_types: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct VerifiedPeer {
+    pub did: String,
+    pub credential_types: Vec<String>,
+}
+
+pub trait PresentationVerifier: Send + Sync + 'static {
+    fn verify(&self, jws: String) -> BoxFuture<'static, Result<VerifiedPresentation, HandshakeError>>;
+}
+
+// Verifies presentations against resolved DID documents with EdDSA, and their VC-JWTs
+// against the issuers' documents
+pub struct DidVerifier {
+    resolver: Arc<dyn DocumentResolver>,
+    policy: CredentialPolicy,
+}
+
+impl DidVerifier {
+    pub fn new(resolver: Arc<dyn DocumentResolver>, policy: CredentialPolicy) -> Self {
+        Self { resolver, policy }
+    }
+}
+
+impl PresentationVerifier for DidVerifier {
+    fn verify(&self, jws: String) -> BoxFuture<'static, Result<VerifiedPresentation, HandshakeError>> {
+        let resolver = self.resolver.clone();
+        let policy = self.policy.clone();
+        async move {
+            // The presenter's DID is read from the unverified payload, then the whole
+            // presentation is checked against that DID's document
+            let claimed = unverified_payload(&jws)?;
+            let document = resolver.resolve(&claimed.did).await?;
+            let decoded = document
+                .verify_jws(&jws, None, &EdDSAJwsVerifier::default(), &JwsVerificationOptions::default())
+                .map_err(|e| HandshakeError::InvalidSignature(e.to_string()))?;
+            let presentation: Presentation = serde_json::from_slice(&decoded.claims)
+                .map_err(|e| HandshakeError::MalformedPresentation(e.to_string()))?;
+
+            let mut credential_types = Vec::new();
+            for credential in &presentation.credentials {
+                credential_types.extend(verify_credential(resolver.as_ref(), &policy, &presentation.did, credential).await?);
+            }
+            if let Some(missing) = policy.required_types.iter().find(|t| !credential_types.contains(t)) {
+                return Err(HandshakeError::CredentialRejected(format!("no valid {} credential presented", missing)));
+            }
+            credential_types.sort();
+            credential_types.dedup();
+
+            Ok(VerifiedPresentation { presentation, credential_types })
+        }
+        .boxed()
+    }
+}
+
+fn unverified_payload(jws: &str) -> Result<Presentation, HandshakeError> {
+    use base64::Engine;
+    let payload = jws
+        .split('.')
+        .nth(1)
+        .ok_or_else(|| HandshakeError::MalformedPresentation("not a compact JWS".to_string()))?;
+    let bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(payload)
+        .map_err(|e| HandshakeError::MalformedPresentation(e.to_string()))?;
+    serde_json::from_slice(&bytes).map_err(|e| HandshakeError::MalformedPresentation(e.to_string()))
+}
+
+// Validate one VC-JWT for `holder` and return its types
+async fn verify_credential(
+    resolver: &dyn DocumentResolver,
+    policy: &CredentialPolicy,
+    holder: &str,
+    credential: &str,
+) -> Result<Vec<String>, HandshakeError> {
+    let jwt = Jwt::from(credential.to_string());
+    let issuer: CoreDID = JwtCredentialValidatorUtils::extract_issuer_from_jwt(&jwt)
+        .map_err(|e| HandshakeError::CredentialRejected(e.to_string()))?;
+    if !policy.trusted_issuers.is_empty() && !policy.trusted_issuers.contains(&issuer.to_string()) {
+        return Err(HandshakeError::CredentialRejected(format!("issuer {} is not trusted", issuer)));
+    }
+    let issuer_document = resolver.resolve(&issuer.to_string()).await?;
+
+    let decoded = JwtCredentialValidator::with_signature_verifier(EdDSAJwsVerifier::default())
+        .validate::<_, Object>(&jwt, &issuer_document, &JwtCredentialValidationOptions::default(), FailFast::FirstError)
+        .map_err(|e| HandshakeError::CredentialRejected(e.to_string()))?;
+
+    let is_subject = decoded
+        .credential
+        .credential_subject
+        .iter()
+        .any(|subject| subject.id.as_ref().is_some_and(|id| id.as_str() == holder));
+    if !is_subject {
+        return Err(HandshakeError::CredentialRejected(format!("credential from {} is not about {}", issuer, holder)));
+    }
+    Ok(decoded.credential.types)
+}
@@ -0,0 +1,225 @@
+// Two in-process peers on loopback TCP running the full handshake
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::StreamExt;
+use identity_iota::core::{FromJson, Object, Url};
+use identity_iota::credential::{Credential, CredentialBuilder, Subject};
+use identity_iota::did::CoreDID;
+use identity_iota::document::CoreDocument;
+use identity_iota::storage::{JwkDocumentExt, JwkMemStore, JwsSignatureOptions, KeyIdMemstore, Storage};
+use identity_iota::verification::jws::JwsAlgorithm;
+use identity_iota::verification::MethodScope;
+use libp2p::swarm::SwarmEvent;
+use libp2p::{noise, tcp, yamux, Swarm, SwarmBuilder};
+use p2p_handshake::{
+    Behaviour, Config, CredentialPolicy, DidVerifier, Event, HandshakeError, StaticResolver, StorageSigner,
+    VerifiedPeer, VERIFIED_HUMAN_TYPE,
+};
+
+type MemStorage = Storage<JwkMemStore, KeyIdMemstore>;
+
+struct TestIdentity {
+    document: CoreDocument,
+    storage: Arc<MemStorage>,
+    fragment: String,
+}
+
+impl TestIdentity {
+    async fn new(name: &str) -> Self {
+        let storage = Storage::new(JwkMemStore::new(), KeyIdMemstore::new());
+        let mut document = CoreDocument::builder(Object::new())
+            .id(CoreDID::parse(format!("did:example:{}", name)).unwrap())
+            .build()
+            .unwrap();
+        let fragment = document
+            .generate_method(&storage, JwkMemStore::ED25519_KEY_TYPE, JwsAlgorithm::EdDSA, None, MethodScope::VerificationMethod)
+            .await
+            .unwrap();
+        Self { document, storage: Arc::new(storage), fragment }
+    }
+
+    fn did(&self) -> String {
+        self.document.id().to_string()
+    }
+
+    async fn issue(&self, holder: &str, credential_type: &str) -> String {
+        let credential: Credential = CredentialBuilder::default()
+            .issuer(Url::parse(self.did()).unwrap())
+            .type_(credential_type)
+            .subject(Subject::from_json_value(serde_json::json!({ "id": holder })).unwrap())
+            .build()
+            .unwrap();
+        self.document
+            .create_credential_jwt(&credential, &self.storage, &self.fragment, &JwsSignatureOptions::default(), None)
+            .await
+            .unwrap()
+            .as_str()
+            .to_string()
+    }
+
+    fn swarm(&self, credentials: Vec<String>, verifier: Arc<DidVerifier>) -> Swarm<Behaviour> {
+        let signer = Arc::new(StorageSigner::new(
+            self.document.clone(),
+            self.storage.clone(),
+            self.fragment.clone(),
+            credentials,
+        ));
+        SwarmBuilder::with_new_identity()
+            .with_tokio()
+            .with_tcp(tcp::Config::default(), noise::Config::new, yamux::Config::default)
+            .unwrap()
+            .with_behaviour(|key| Behaviour::new(key.public().to_peer_id(), Config::new(signer, verifier)))
+            .unwrap()
+            .with_swarm_config(|c| c.with_idle_connection_timeout(Duration::from_secs(30)))
+            .build()
+    }
+}
+
+struct Fixture {
+    issuer: TestIdentity,
+    rogue_issuer: TestIdentity,
+    alice: TestIdentity,
+    bob: TestIdentity,
+    verifier: Arc<DidVerifier>,
+}
+
+// Alice, Bob and two issuers, all resolvable; only `issuer` is trusted
+async fn fixture() -> Fixture {
+    let issuer = TestIdentity::new("issuer").await;
+    let rogue_issuer = TestIdentity::new("rogue").await;
+    let alice = TestIdentity::new("alice").await;
+    let bob = TestIdentity::new("bob").await;
+
+    let mut resolver = StaticResolver::new();
+    for identity in [&issuer, &rogue_issuer, &alice, &bob] {
+        resolver.insert(identity.document.clone());
+    }
+    let policy = CredentialPolicy { trusted_issuers: vec![issuer.did()], ..CredentialPolicy::default() };
+    let verifier = Arc::new(DidVerifier::new(Arc::new(resolver), policy));
+
+    Fixture { issuer, rogue_issuer, alice, bob, verifier }
+}
+
+#[derive(Debug)]
+enum Outcome {
+    Verified(VerifiedPeer),
+    Rejected(HandshakeError),
+    // The other side closed the connection without a verdict for us
+    Closed,
+}
+
+// Let `dialer` connect to `listener` and drive both swarms until each has an outcome
+async fn run_handshake(mut dialer: Swarm<Behaviour>, mut listener: Swarm<Behaviour>) -> (Outcome, Outcome) {
+    listener.listen_on("/ip4/127.0.0.1/tcp/0".parse().unwrap()).unwrap();
+    let address = loop {
+        if let SwarmEvent::NewListenAddr { address, .. } = listener.select_next_some().await {
+            break address;
+        }
+    };
+    dialer.dial(address).unwrap();
+
+    fn outcome(event: SwarmEvent<Event>) -> Option<Outcome> {
+        match event {
+            SwarmEvent::Behaviour(Event::Verified { peer, .. }) => Some(Outcome::Verified(peer)),
+            SwarmEvent::Behaviour(Event::Rejected { error, .. }) => Some(Outcome::Rejected(error)),
+            SwarmEvent::ConnectionClosed { num_established: 0, .. } => Some(Outcome::Closed),
+            _ => None,
+        }
+    }
+
+    let mut dialer_outcome = None;
+    let mut listener_outcome = None;
+    tokio::time::timeout(Duration::from_secs(30), async {
+        while dialer_outcome.is_none() || listener_outcome.is_none() {
+            tokio::select! {
+                event = dialer.select_next_some() => {
+                    if let Some(o) = outcome(event) {
+                        dialer_outcome.get_or_insert(o);
+                    }
+                }
+                event = listener.select_next_some() => {
+                    if let Some(o) = outcome(event) {
+                        listener_outcome.get_or_insert(o);
+                    }
+                }
+            }
+        }
+    })
+    .await
+    .expect("handshake did not finish");
+
+    (dialer_outcome.unwrap(), listener_outcome.unwrap())
+}
+
+#[tokio::test]
+async fn peers_with_verified_human_credentials_are_mutually_verified() {
+    let f = fixture().await;
+    let alice_vc = f.issuer.issue(&f.alice.did(), VERIFIED_HUMAN_TYPE).await;
+    let bob_vc = f.issuer.issue(&f.bob.did(), VERIFIED_HUMAN_TYPE).await;
+
+    let (alice_saw, bob_saw) = run_handshake(
+        f.alice.swarm(vec![alice_vc], f.verifier.clone()),
+        f.bob.swarm(vec![bob_vc], f.verifier.clone()),
+    )
+    .await;
+
+    match (alice_saw, bob_saw) {
+        (Outcome::Verified(bob), Outcome::Verified(alice)) => {
+            assert_eq!(bob.did, f.bob.did());
+            assert_eq!(alice.did, f.alice.did());
+            assert!(alice.credential_types.iter().any(|t| t == VERIFIED_HUMAN_TYPE));
+        }
+        other => panic!("expected both peers verified, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn listener_without_verified_human_credential_is_rejected() {
+    let f = fixture().await;
+    let alice_vc = f.issuer.issue(&f.alice.did(), VERIFIED_HUMAN_TYPE).await;
+    let bob_vc = f.issuer.issue(&f.bob.did(), "EmailCredential").await;
+
+    let (alice_saw, bob_saw) = run_handshake(
+        f.alice.swarm(vec![alice_vc], f.verifier.clone()),
+        f.bob.swarm(vec![bob_vc], f.verifier.clone()),
+    )
+    .await;
+
+    assert!(matches!(alice_saw, Outcome::Rejected(HandshakeError::CredentialRejected(_))), "{:?}", alice_saw);
+    assert!(!matches!(bob_saw, Outcome::Verified(_)), "{:?}", bob_saw);
+}
+
+#[tokio::test]
+async fn dialer_with_untrusted_issuer_is_rejected() {
+    let f = fixture().await;
+    let alice_vc = f.rogue_issuer.issue(&f.alice.did(), VERIFIED_HUMAN_TYPE).await;
+    let bob_vc = f.issuer.issue(&f.bob.did(), VERIFIED_HUMAN_TYPE).await;
+
+    let (alice_saw, bob_saw) = run_handshake(
+        f.alice.swarm(vec![alice_vc], f.verifier.clone()),
+        f.bob.swarm(vec![bob_vc], f.verifier.clone()),
+    )
+    .await;
+
+    assert!(matches!(bob_saw, Outcome::Rejected(HandshakeError::CredentialRejected(_))), "{:?}", bob_saw);
+    assert!(!matches!(alice_saw, Outcome::Verified(_)), "{:?}", alice_saw);
+}
+
+#[tokio::test]
+async fn credential_about_another_did_is_rejected() {
+    let f = fixture().await;
+    // Alice replays Bob's credential as her own
+    let bobs_vc = f.issuer.issue(&f.bob.did(), VERIFIED_HUMAN_TYPE).await;
+    let bob_vc = f.issuer.issue(&f.bob.did(), VERIFIED_HUMAN_TYPE).await;
+
+    let (alice_saw, bob_saw) = run_handshake(
+        f.alice.swarm(vec![bobs_vc], f.verifier.clone()),
+        f.bob.swarm(vec![bob_vc], f.verifier.clone()),
+    )
+    .await;
+
+    assert!(matches!(bob_saw, Outcome::Rejected(HandshakeError::CredentialRejected(_))), "{:?}", bob_saw);
+    assert!(!matches!(alice_saw, Outcome::Verified(_)), "{:?}", alice_saw);
+}
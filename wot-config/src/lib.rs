@@ -0,0 +1,34 @@
+//! Layered configuration shared by the wot.id services.
+//!
+//! Every setting has one name, the environment variable name (`IOTA_NODE_URL`, `BACKEND_PORT`,
+//! ...). A value is looked up in these layers, highest precedence first:
+//!
+//! 1. Command line flags: `--iota-node-url https://...` or `--iota-node-url=https://...`
+//! 2. Environment variables (including a `.env` file loaded by the service)
+//! 3. The `[profiles.<network>]` table of the TOML config file
+//! 4. Top-level keys of the TOML config file, in lower case (`iota_node_url = "..."`)
+//! 5. Built-in defaults of the selected network profile
+//!
+//! The config file is named by `--config <path>` or `WOT_CONFIG`; the network by `--network`,
+//! `WOT_NETWORK` or `network` in the file, and defaults to `localnet`. `--print-config` asks the
+//! service to print its resolved configuration, secrets redacted, and exit.
+
+mod network;
+mod settings;
+mod shared;
+
+pub use network::Network;
+pub use settings::{Settings, Source};
+pub use shared::SharedConfig;
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("invalid command line: {0}")]
+    Cli(String),
+    #[error("failed to read config file {path}: {reason}")]
+    File { path: String, reason: String },
+    #[error("{0} must be set")]
+    Missing(String),
+    #[error("invalid {key} value '{value}': {reason}")]
+    Invalid { key: String, value: String, reason: String },
+}
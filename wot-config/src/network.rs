@@ -0,0 +1,63 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::ConfigError;
+
+// Named IOTA network; selects the node a service talks to unless `IOTA_NODE_URL` overrides it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Network {
+    // Node started with `iota start` on this machine
+    #[default]
+    Localnet,
+    Devnet,
+    Testnet,
+    Mainnet,
+}
+
+impl Network {
+    pub const ALL: [Network; 4] = [Network::Localnet, Network::Devnet, Network::Testnet, Network::Mainnet];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Network::Localnet => "localnet",
+            Network::Devnet => "devnet",
+            Network::Testnet => "testnet",
+            Network::Mainnet => "mainnet",
+        }
+    }
+
+    pub fn node_url(self) -> &'static str {
+        match self {
+            Network::Localnet => "http://127.0.0.1:19000",
+            Network::Devnet => "https://api.devnet.iota.cafe",
+            Network::Testnet => "https://api.testnet.iota.cafe",
+            Network::Mainnet => "https://api.mainnet.iota.cafe",
+        }
+    }
+
+    // Lowest-precedence values for this network
+    pub(crate) fn defaults(self) -> Vec<(String, String)> {
+        vec![("IOTA_NODE_URL".to_string(), self.node_url().to_string())]
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Network {
+    type Err = ConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Network::ALL
+            .into_iter()
+            .find(|network| network.as_str().eq_ignore_ascii_case(s.trim()))
+            .ok_or_else(|| ConfigError::Invalid {
+                key: "WOT_NETWORK".to_string(),
+                value: s.to_string(),
+                reason: "expected localnet, devnet, testnet or mainnet".to_string(),
+            })
+    }
+}
@@ -0,0 +1,38 @@
+use url::Url;
+
+use crate::{ConfigError, Network, Settings};
+
+// Settings both services read, under the same names
+#[derive(Clone, Debug)]
+pub struct SharedConfig {
+    pub network: Network,
+    pub iota_node_url: Url,
+    pub backend_port: u16,
+    pub identity_service_port: u16,
+    // Where the backend reaches the identity service; defaults to localhost on its port, over
+    // HTTPS when `IDENTITY_SERVICE_TLS_CERT_PATH` is set
+    pub identity_service_url: Url,
+}
+
+impl SharedConfig {
+    pub fn from_settings(settings: &Settings) -> Result<Self, ConfigError> {
+        let iota_node_url = settings.url("IOTA_NODE_URL")?.ok_or_else(|| ConfigError::Missing("IOTA_NODE_URL".to_string()))?;
+        let backend_port = settings.parse_or("BACKEND_PORT", 8080u16)?;
+        let identity_service_port = settings.parse_or("IDENTITY_SERVICE_PORT", 8081u16)?;
+        let identity_service_url = match settings.url("IDENTITY_SERVICE_URL")? {
+            Some(url) => url,
+            None => {
+                // Certificates name hosts, so HTTPS goes to `localhost` rather than the address
+                let base = if settings.var("IDENTITY_SERVICE_TLS_CERT_PATH").is_some() { "https://localhost" } else { "http://127.0.0.1" };
+                Url::parse(&format!("{}:{}", base, identity_service_port)).expect("localhost URL is valid")
+            }
+        };
+        Ok(Self {
+            network: settings.network(),
+            iota_node_url,
+            backend_port,
+            identity_service_port,
+            identity_service_url,
+        })
+    }
+}
@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::{ErrorCode, WotDid};
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct IssueCredentialResponse {
+    // Compact VC-JWT for ML-DSA only. A hybrid proof needs two signatures, so it is a JWS in
+    // general JSON serialization over the same JWT claims, and not a VC-JWT.
+    pub credential: String,
+    pub issuer: WotDid,
+    pub algorithms: Vec<String>,
+    pub hybrid: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct VerifyCredentialRequest {
+    pub credential: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct VerifyCredentialResponse {
+    #[serde(rename = "isValid")]
+    pub is_valid: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issuer: Option<WotDid>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub types: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub algorithms: Vec<String>,
+    pub hybrid: bool,
+    // Set with `reason` when the credential is rejected
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<ErrorCode>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
@@ -0,0 +1,149 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::Deref;
+use std::str::FromStr;
+use utoipa::ToSchema;
+
+use crate::TypeError;
+
+// Longest network name the IOTA DID method allows
+const MAX_NETWORK_LEN: usize = 6;
+// An object ID is 32 bytes
+const MAX_TAG_DIGITS: usize = 64;
+
+// A `did:iota` DID, checked for the method's syntax: `did:iota:<network>:0x<object id>`, or
+// `did:iota:0x<object id>` on mainnet. Only the canonical lower-case form is accepted, so two
+// DIDs for the same identity compare equal as strings. Whether it resolves is up to the node.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(try_from = "String", into = "String")]
+#[schema(value_type = String, example = "did:iota:tst:0x8f2a4c1e0b6d3f597a2c4e6f8091b3d5c7e9f1a3b5d7c9e1f3a5b7c9d1e3f5a7")]
+pub struct WotDid(String);
+
+impl WotDid {
+    pub fn parse(did: &str) -> Result<Self, TypeError> {
+        let invalid = || TypeError::InvalidDid(did.to_string());
+        let rest = did.strip_prefix("did:iota:").ok_or_else(invalid)?;
+        let tag = match rest.split_once(':') {
+            Some((network, tag)) => {
+                let valid_network = (1..=MAX_NETWORK_LEN).contains(&network.len())
+                    && network.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit());
+                if !valid_network {
+                    return Err(invalid());
+                }
+                tag
+            }
+            None => rest,
+        };
+        let digits = tag.strip_prefix("0x").ok_or_else(invalid)?;
+        let valid_tag = (1..=MAX_TAG_DIGITS).contains(&digits.len())
+            && digits.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b));
+        if !valid_tag {
+            return Err(invalid());
+        }
+        Ok(Self(did.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for WotDid {
+    type Err = TypeError;
+
+    fn from_str(did: &str) -> Result<Self, Self::Err> {
+        Self::parse(did)
+    }
+}
+
+impl TryFrom<String> for WotDid {
+    type Error = TypeError;
+
+    fn try_from(did: String) -> Result<Self, Self::Error> {
+        Self::parse(&did)
+    }
+}
+
+impl From<WotDid> for String {
+    fn from(did: WotDid) -> Self {
+        did.0
+    }
+}
+
+// Lets a `WotDid` be passed wherever a `&str` DID is expected
+impl Deref for WotDid {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for WotDid {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for WotDid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+// DIDs kept as plain strings (claims, stored records) compare against a `WotDid` directly
+impl PartialEq<str> for WotDid {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<String> for WotDid {
+    fn eq(&self, other: &String) -> bool {
+        &self.0 == other
+    }
+}
+
+impl PartialEq<WotDid> for String {
+    fn eq(&self, other: &WotDid) -> bool {
+        *self == other.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TAG: &str = "0x8f2a4c1e0b6d3f597a2c4e6f8091b3d5c7e9f1a3b5d7c9e1f3a5b7c9d1e3f5a7";
+
+    #[test]
+    fn accepts_network_and_mainnet_forms() {
+        for did in [format!("did:iota:{}", TAG), format!("did:iota:tst:{}", TAG), "did:iota:smr:0x1".to_string()] {
+            assert_eq!(WotDid::parse(&did).unwrap().as_str(), did);
+        }
+    }
+
+    #[test]
+    fn rejects_other_syntax() {
+        for did in [
+            "".to_string(),
+            format!("did:key:{}", TAG),
+            format!("did:iota:{}", &TAG[2..]),
+            format!("did:iota:tst:{}", TAG.to_uppercase().replace("0X", "0x")),
+            format!("did:iota:toolong:{}", TAG),
+            format!("did:iota::{}", TAG),
+            format!("did:iota:tst:{}0", TAG),
+            "did:iota:tst:0x".to_string(),
+            format!("did:iota:tst:{}#key-1", TAG),
+        ] {
+            assert_eq!(WotDid::parse(&did), Err(TypeError::InvalidDid(did.clone())), "{}", did);
+        }
+    }
+
+    #[test]
+    fn deserializing_validates() {
+        let did: WotDid = serde_json::from_str(&format!("\"did:iota:tst:{}\"", TAG)).unwrap();
+        assert_eq!(serde_json::to_string(&did).unwrap(), format!("\"did:iota:tst:{}\"", TAG));
+        assert!(serde_json::from_str::<WotDid>("\"did:iota:tst:0xnothex\"").is_err());
+    }
+}
@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+// Why a signature, signed action or credential failed verification. The identity service
+// reports it in verification responses and as the `outcome` label of `verifications_total`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    // Not a well-formed IOTA DID
+    InvalidDid,
+    // The DID document could not be resolved
+    ResolutionFailed,
+    // No signature verifies against the DID document, or the signature policy is not met
+    BadSignature,
+    // Signed with a method past its rotation grace period
+    RetiredKey,
+    // The signed challenge is not the one issued
+    ChallengeMismatch,
+    MalformedClaims,
+    // A signed action other than the one expected
+    WrongAction,
+    // Past `exp`, or a signed action older than the allowed age
+    Expired,
+    // Before `nbf`
+    NotYetValid,
+    // A signed action whose `jti` was already used
+    Replayed,
+}
+
+impl ErrorCode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::InvalidDid => "invalid_did",
+            ErrorCode::ResolutionFailed => "resolution_failed",
+            ErrorCode::BadSignature => "bad_signature",
+            ErrorCode::RetiredKey => "retired_key",
+            ErrorCode::ChallengeMismatch => "challenge_mismatch",
+            ErrorCode::MalformedClaims => "malformed_claims",
+            ErrorCode::WrongAction => "wrong_action",
+            ErrorCode::Expired => "expired",
+            ErrorCode::NotYetValid => "not_yet_valid",
+            ErrorCode::Replayed => "replayed",
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::ToSchema;
+
+use crate::WotDid;
+
+// A workflow action (compact or JSON JWS) signed by `signer`'s DID
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct SignedAction {
+    pub signer: WotDid,
+    pub jws: String,
+}
+
+// Initiate Challenge
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct InitiateChallengeRequest {
+    pub email: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct InitiateChallengeResponse {
+    pub did: WotDid,
+    pub challenge: String,
+}
+
+// Verify Signature
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct VerifySignatureRequest {
+    pub did: WotDid,
+    pub challenge: String,
+    pub signature: String, // This is the JWS
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct VerifySignatureResponse {
+    #[serde(rename = "isValid")]
+    pub is_valid: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<UserInfo>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct UserInfo {
+    pub email: Option<String>,
+    pub name: Option<String>,
+}
+
+// Verify Action
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct VerifyActionRequest {
+    pub signer: WotDid,
+    pub jws: String,
+    // The action the caller expects, e.g. `governance.vote`
+    pub action: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct VerifyActionResponse {
+    pub signer: WotDid,
+    pub claims: Value,
+}
+
+// Key Agreement
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct KeyAgreementMethod {
+    pub id: String,
+    pub kty: String,
+    // Curve of OKP keys (`X25519`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crv: Option<String>,
+    // Algorithm of AKP keys (`ML-KEM-768`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alg: Option<String>,
+    pub x: String, // base64url public key from the method's JWK (`x`, or `pub` for AKP keys)
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct KeyAgreementResponse {
+    pub did: WotDid,
+    pub methods: Vec<KeyAgreementMethod>,
+}
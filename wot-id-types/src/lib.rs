@@ -0,0 +1,30 @@
+//! Wire types shared by the wot.id services.
+//!
+//! The identity service's request and response bodies live here, grouped by the service module
+//! that handles them, so the service and its clients (the backend's `IdentityClient`) cannot
+//! disagree on a field. Alongside them are the newtypes for values that cross service boundaries:
+//! DIDs ([`WotDid`]), trust levels ([`TrustValue`]) and verification error codes ([`ErrorCode`]).
+//!
+//! Everything derives `serde` and `utoipa::ToSchema` and nothing else, so the crate builds
+//! against both services' tokio and axum versions.
+
+mod did;
+mod error;
+mod trust;
+
+pub mod credentials;
+pub mod identity;
+pub mod recovery;
+pub mod rotation;
+
+pub use did::WotDid;
+pub use error::ErrorCode;
+pub use trust::TrustValue;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum TypeError {
+    #[error("invalid DID '{0}': expected did:iota:[<network>:]0x<object id>")]
+    InvalidDid(String),
+    #[error("trust value {0} is out of range (0..={max})", max = TrustValue::MAX)]
+    TrustValueOutOfRange(u64),
+}
@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::ToSchema;
+
+use crate::WotDid;
+
+// Guardians chosen by the identity owner. `threshold` guardian approvals start the time lock.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct RecoveryConfig {
+    pub guardians: Vec<WotDid>,
+    pub threshold: usize,
+    pub time_lock_secs: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RecoveryStatus {
+    // Collecting guardian approvals
+    Pending,
+    // Threshold reached; executable once `unlocks_at` has passed unless the owner cancels
+    TimeLocked,
+    Cancelled,
+    Executed,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct RecoveryRequest {
+    pub id: String,
+    // Controller addresses, as taken by `identity::initiate_recovery`
+    pub new_controller: String,
+    pub remove_controllers: Vec<String>,
+    pub approvals: Vec<WotDid>,
+    pub status: RecoveryStatus,
+    pub created_at: u64,
+    pub unlocks_at: Option<u64>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct AuditEvent {
+    pub timestamp: u64,
+    pub kind: String,
+    pub actor: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct RecoveryRecord {
+    pub config: Option<RecoveryConfig>,
+    pub active: Option<RecoveryRequest>,
+    pub audit: Vec<AuditEvent>,
+}
+
+impl RecoveryRecord {
+    pub fn open_request(&self) -> Option<&RecoveryRequest> {
+        self.active
+            .as_ref()
+            .filter(|r| matches!(r.status, RecoveryStatus::Pending | RecoveryStatus::TimeLocked))
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct RecoveryStatusResponse {
+    pub did: WotDid,
+    #[serde(flatten)]
+    pub record: RecoveryRecord,
+    // `wot_id::identity` call the signer submits from its wallet, so the chain, which enforces
+    // the threshold and time lock, records the same step
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub calls: Vec<Value>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct ControllerRotationPlan {
+    pub did: WotDid,
+    pub identity_object_id: String,
+    pub request_id: String,
+    pub approvals: Vec<WotDid>,
+    // The `wot_id::identity::recover_controller` call, sent by the new controller: the chain
+    // checks the guardian approvals and the time lock again before rotating the controllers
+    pub calls: Vec<Value>,
+}
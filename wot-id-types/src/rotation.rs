@@ -0,0 +1,77 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::ToSchema;
+
+use crate::WotDid;
+
+// One key rotation. The old method keeps verifying until `retire_at`, after which
+// verify-signature rejects it even if the controller has not yet published its removal.
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct KeyRotation {
+    pub id: String,
+    pub old_method: String,
+    pub new_method: String,
+    pub started_at: u64,
+    pub retire_at: u64,
+}
+
+impl KeyRotation {
+    pub fn is_retired(&self, now: u64) -> bool {
+        now >= self.retire_at
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct RotateKeyResponse {
+    pub rotation_id: String,
+    pub retire_at: u64,
+    // Document version snapshotted before the rotation
+    pub previous_version: u32,
+    // DID document with both keys; the controller publishes this now
+    #[schema(value_type = Object)]
+    pub document: Value,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct RotationStatus {
+    #[serde(flatten)]
+    pub rotation: KeyRotation,
+    pub retired: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct VersionSummary {
+    pub version: u32,
+    pub captured_at: u64,
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct RotationsResponse {
+    pub did: WotDid,
+    pub rotations: Vec<RotationStatus>,
+    pub versions: Vec<VersionSummary>,
+    // Present once a retired method is still in the published document: the
+    // document to publish with those methods removed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schema(value_type = Option<Object>)]
+    pub finalize_document: Option<Value>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct VerifyHistoricalRequest {
+    pub did: WotDid,
+    pub jws: String,
+    // Check only this version; otherwise the newest matching version wins
+    pub version: Option<u32>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, ToSchema)]
+pub struct VerifyHistoricalResponse {
+    #[serde(rename = "isValid")]
+    pub is_valid: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub claims: Option<Value>,
+}
@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use utoipa::openapi::schema::{KnownFormat, ObjectBuilder, Schema, SchemaFormat, Type};
+use utoipa::openapi::RefOr;
+use utoipa::{PartialSchema, ToSchema};
+
+use crate::TypeError;
+
+// Trust level of one relationship or claim assessment in trust.move units: 0..=100000 stands for
+// 0.000..=100.000. Sums of levels, such as aggregate scores, are plain `u64`s.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "u64", into = "u64")]
+pub struct TrustValue(u64);
+
+impl TrustValue {
+    // Mirrors MAX_TRUST_LEVEL in trust.move
+    pub const MAX: TrustValue = TrustValue(100_000);
+
+    pub fn new(value: u64) -> Result<Self, TypeError> {
+        if value > Self::MAX.0 {
+            return Err(TypeError::TrustValueOutOfRange(value));
+        }
+        Ok(Self(value))
+    }
+
+    pub const fn get(self) -> u64 {
+        self.0
+    }
+}
+
+// Written out because the derive cannot put the range on a newtype
+impl PartialSchema for TrustValue {
+    fn schema() -> RefOr<Schema> {
+        ObjectBuilder::new()
+            .schema_type(Type::Integer)
+            .format(Some(SchemaFormat::KnownFormat(KnownFormat::Int64)))
+            .minimum(Some(0))
+            .maximum(Some(Self::MAX.0))
+            .into()
+    }
+}
+
+impl ToSchema for TrustValue {}
+
+impl TryFrom<u64> for TrustValue {
+    type Error = TypeError;
+
+    fn try_from(value: u64) -> Result<Self, Self::Error> {
+        Self::new(value)
+    }
+}
+
+impl From<TrustValue> for u64 {
+    fn from(value: TrustValue) -> Self {
+        value.0
+    }
+}
+
+impl fmt::Display for TrustValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_the_full_range() {
+        assert_eq!(TrustValue::new(0).unwrap().get(), 0);
+        assert_eq!(TrustValue::new(100_000).unwrap(), TrustValue::MAX);
+        assert_eq!(TrustValue::new(100_001), Err(TypeError::TrustValueOutOfRange(100_001)));
+        assert_eq!(TrustValue::new(u64::MAX), Err(TypeError::TrustValueOutOfRange(u64::MAX)));
+    }
+
+    #[test]
+    fn deserializing_validates() {
+        let value: TrustValue = serde_json::from_str("100000").unwrap();
+        assert_eq!(serde_json::to_string(&value).unwrap(), "100000");
+        assert_eq!(serde_json::from_str::<TrustValue>("0").unwrap(), TrustValue::default());
+        let err = serde_json::from_str::<TrustValue>("100001").unwrap_err();
+        assert!(err.to_string().contains("out of range"), "{}", err);
+        assert!(serde_json::from_str::<TrustValue>("-1").is_err());
+    }
+}
@@ -0,0 +1,279 @@
+//! Logging and trace export shared by the wot.id services.
+//!
+//! [`init`] installs the global subscriber, exporting spans as configured by
+//! `OTEL_TRACES_EXPORTER`. Calls between the services carry W3C trace context:
+//! [`trace_headers`] writes it into an outgoing request and [`remote_context`] reads it back
+//! from an incoming one, so a request keeps one trace across both services.
+
+use http::header::{HeaderMap, HeaderName, HeaderValue};
+use opentelemetry::propagation::{Extractor, Injector};
+use opentelemetry::trace::{TraceContextExt, TracerProvider as _};
+use opentelemetry::{global, Context};
+use opentelemetry_sdk::error::{OTelSdkError, OTelSdkResult};
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::{SdkTracerProvider, SpanData, SpanExporter};
+use opentelemetry_sdk::Resource;
+use serde_json::{json, Map, Value};
+use std::io::Write;
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+use tracing::Span;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::{layer::SubscriberExt, reload, util::SubscriberInitExt, EnvFilter, Registry};
+
+/// Where finished spans go, from `OTEL_TRACES_EXPORTER`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TraceExporter {
+    None,
+    // OTLP over HTTP/protobuf; the endpoint comes from `OTEL_EXPORTER_OTLP_ENDPOINT`
+    Otlp,
+    // One JSON object per span, for local runs and tests without a collector
+    Stdout,
+    File(String),
+}
+
+impl TraceExporter {
+    pub fn from_env() -> Self {
+        match std::env::var("OTEL_TRACES_EXPORTER").ok().as_deref().map(str::trim) {
+            None | Some("") | Some("none") => TraceExporter::None,
+            Some("otlp") => TraceExporter::Otlp,
+            Some("stdout") | Some("console") => TraceExporter::Stdout,
+            Some(other) => match other.strip_prefix("file:") {
+                Some(path) => TraceExporter::File(path.to_string()),
+                None => {
+                    eprintln!("Unknown OTEL_TRACES_EXPORTER '{}'; trace export is disabled", other);
+                    TraceExporter::None
+                }
+            },
+        }
+    }
+}
+
+/// Log filter in `RUST_LOG` syntax, replaceable while running.
+#[derive(Clone)]
+pub struct LogFilter(reload::Handle<EnvFilter, Registry>);
+
+impl LogFilter {
+    /// A filter starting at `directives` and the layer that applies it. Directives that do not
+    /// parse are skipped, as with `RUST_LOG`.
+    pub fn new(directives: &str) -> (reload::Layer<EnvFilter, Registry>, Self) {
+        let (layer, handle) = reload::Layer::new(EnvFilter::new(directives));
+        (layer, LogFilter(handle))
+    }
+
+    /// The directives in effect; `None` once the layer is gone.
+    pub fn current(&self) -> Option<String> {
+        self.0.with_current(|filter| filter.to_string()).ok()
+    }
+
+    /// Leaves the current filter in place when `directives` do not parse.
+    pub fn set(&self, directives: &str) -> Result<(), String> {
+        let filter = EnvFilter::try_new(directives).map_err(|e| e.to_string())?;
+        self.0.reload(filter).map_err(|e| e.to_string())
+    }
+}
+
+/// Install the global subscriber: fmt logs filtered by `RUST_LOG` (else `default_filter`) and,
+/// when an exporter is configured, OpenTelemetry spans. The returned provider flushes on
+/// shutdown.
+pub fn init(service_name: &'static str, default_filter: &str) -> (Option<SdkTracerProvider>, LogFilter) {
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    let exporter = TraceExporter::from_env();
+    let provider = match build_provider(&exporter, service_name) {
+        Ok(provider) => provider,
+        Err(e) => {
+            eprintln!("Failed to set up {:?} trace export: {}", exporter, e);
+            None
+        }
+    };
+    let otel_layer = provider
+        .as_ref()
+        .map(|provider| tracing_opentelemetry::layer().with_tracer(provider.tracer(service_name)));
+
+    let (filter, log_filter) = LogFilter::new(&std::env::var("RUST_LOG").unwrap_or_else(|_| default_filter.to_string()));
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(tracing_subscriber::fmt::layer())
+        .with(otel_layer)
+        .init();
+    if provider.is_some() {
+        tracing::info!("Exporting traces to {:?}", exporter);
+    }
+    (provider, log_filter)
+}
+
+fn build_provider(exporter: &TraceExporter, service_name: &'static str) -> anyhow::Result<Option<SdkTracerProvider>> {
+    let service_name = std::env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| service_name.to_string());
+    let builder = SdkTracerProvider::builder().with_resource(Resource::builder().with_service_name(service_name).build());
+    let provider = match exporter {
+        TraceExporter::None => return Ok(None),
+        TraceExporter::Otlp => {
+            builder.with_batch_exporter(opentelemetry_otlp::SpanExporter::builder().with_http().build()?)
+        }
+        TraceExporter::Stdout => builder.with_simple_exporter(JsonLinesExporter::new(Box::new(std::io::stdout()))),
+        TraceExporter::File(path) => {
+            let file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+            builder.with_simple_exporter(JsonLinesExporter::new(Box::new(file)))
+        }
+    };
+    Ok(Some(provider.build()))
+}
+
+/// Writes each finished span as one JSON line.
+pub struct JsonLinesExporter {
+    out: Mutex<Box<dyn Write + Send>>,
+}
+
+impl JsonLinesExporter {
+    pub fn new(out: Box<dyn Write + Send>) -> Self {
+        Self { out: Mutex::new(out) }
+    }
+}
+
+impl std::fmt::Debug for JsonLinesExporter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("JsonLinesExporter")
+    }
+}
+
+impl SpanExporter for JsonLinesExporter {
+    async fn export(&self, batch: Vec<SpanData>) -> OTelSdkResult {
+        let mut out = self.out.lock().map_err(|_| OTelSdkError::InternalFailure("span writer poisoned".to_string()))?;
+        for span in batch {
+            let line = span_json(&span).to_string();
+            writeln!(out, "{}", line).map_err(|e| OTelSdkError::InternalFailure(e.to_string()))?;
+        }
+        out.flush().map_err(|e| OTelSdkError::InternalFailure(e.to_string()))
+    }
+}
+
+fn span_json(span: &SpanData) -> Value {
+    let unix_nanos = |t: std::time::SystemTime| t.duration_since(UNIX_EPOCH).map(|d| d.as_nanos() as u64).unwrap_or(0);
+    let attributes: Map<String, Value> = span
+        .attributes
+        .iter()
+        .map(|kv| (kv.key.to_string(), Value::String(kv.value.to_string())))
+        .collect();
+    json!({
+        "trace_id": span.span_context.trace_id().to_string(),
+        "span_id": span.span_context.span_id().to_string(),
+        "parent_span_id": span.parent_span_id.to_string(),
+        "name": span.name,
+        "kind": format!("{:?}", span.span_kind),
+        "start_unix_nano": unix_nanos(span.start_time),
+        "end_unix_nano": unix_nanos(span.end_time),
+        "status": format!("{:?}", span.status),
+        "attributes": attributes,
+    })
+}
+
+struct HeaderInjector<'a>(&'a mut HeaderMap);
+
+impl Injector for HeaderInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        if let (Ok(name), Ok(value)) = (HeaderName::from_bytes(key.as_bytes()), HeaderValue::from_str(&value)) {
+            self.0.insert(name, value);
+        }
+    }
+}
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0.keys().map(HeaderName::as_str).collect()
+    }
+}
+
+/// W3C `traceparent` (and `tracestate`) headers for an outgoing request made within `span`.
+pub fn trace_headers(span: &Span) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    let context = span.context();
+    if context.span().span_context().is_valid() {
+        global::get_text_map_propagator(|propagator| propagator.inject_context(&context, &mut HeaderInjector(&mut headers)));
+    }
+    headers
+}
+
+/// Remote parent from an incoming request's `traceparent`, if any.
+pub fn remote_context(headers: &HeaderMap) -> Context {
+    global::get_text_map_propagator(|propagator| propagator.extract(&HeaderExtractor(headers)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    // Exported span lines, readable after the writer is handed to the exporter
+    #[derive(Clone, Default)]
+    struct Lines(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Lines {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Lines {
+        fn span(&self, name: &str) -> Value {
+            let lines = String::from_utf8(self.0.lock().unwrap().clone()).unwrap();
+            lines
+                .lines()
+                .map(|line| serde_json::from_str::<Value>(line).unwrap())
+                .find(|span| span["name"] == name)
+                .unwrap_or_else(|| panic!("no span named {}", name))
+        }
+    }
+
+    // The backend's identity client sends `trace_headers` of its client span; identity-service
+    // parents its request span on `remote_context` of what arrives
+    #[test]
+    fn traceparent_from_the_backend_continues_the_trace_in_identity_service() {
+        global::set_text_map_propagator(TraceContextPropagator::new());
+        let lines = Lines::default();
+        let provider = SdkTracerProvider::builder().with_simple_exporter(JsonLinesExporter::new(Box::new(lines.clone()))).build();
+        let subscriber = tracing_subscriber::registry().with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let client = tracing::info_span!("identity_service_request", otel.kind = "client");
+            let headers = trace_headers(&client);
+            assert!(headers.contains_key("traceparent"));
+
+            let server = tracing::info_span!("http_request", otel.kind = "server");
+            let _ = server.set_parent(remote_context(&headers));
+            drop(server);
+            drop(client);
+        });
+
+        let (client, server) = (lines.span("identity_service_request"), lines.span("http_request"));
+        assert_eq!(server["trace_id"], client["trace_id"]);
+        assert_eq!(server["parent_span_id"], client["span_id"]);
+        assert_eq!(server["kind"], "Server");
+    }
+
+    #[test]
+    fn log_filter_keeps_its_directives_when_new_ones_do_not_parse() {
+        let (_layer, log_filter) = LogFilter::new("info");
+        log_filter.set("backend=debug,warn").unwrap();
+        assert_eq!(log_filter.current().as_deref(), Some("backend=debug,warn"));
+        assert!(log_filter.set("backend=loud").is_err());
+        assert_eq!(log_filter.current().as_deref(), Some("backend=debug,warn"));
+    }
+
+    #[test]
+    fn no_headers_without_a_recorded_span() {
+        assert!(trace_headers(&Span::none()).is_empty());
+        let context = remote_context(&HeaderMap::new());
+        assert!(!context.span().span_context().is_valid());
+    }
+}
@@ -0,0 +1,88 @@
+//! The HTTPS listener shared by the wot.id services.
+//!
+//! Each service builds its own rustls [`ServerConfig`] from its settings; [`serve`] runs the
+//! accept loop on top of it. It takes any tower service rather than an axum `Router`, so it
+//! builds against both services' axum versions.
+
+use hyper::body::{Body, Incoming};
+use hyper::{Request, Response};
+use hyper_util::rt::{TokioExecutor, TokioIo};
+use hyper_util::server::{conn::auto, graceful::GracefulShutdown};
+use rustls::ServerConfig;
+use std::error::Error as StdError;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio_rustls::TlsAcceptor;
+use tower::{Service, ServiceExt};
+
+type BoxError = Box<dyn StdError + Send + Sync>;
+
+// Clients that have not finished the handshake by then are dropped
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Serve `app` over TLS until `shutdown` resolves, then wait for open connections to finish.
+///
+/// Every request carries `connect_info(peer)` as an extension, so handlers and middleware read
+/// the peer address as they would behind a plain listener; the services pass axum's
+/// `ConnectInfo`. Handshakes run on the connection's task, so a slow client does not hold up
+/// others.
+pub async fn serve<S, B, C>(
+    listener: TcpListener,
+    config: Arc<ServerConfig>,
+    app: S,
+    connect_info: fn(SocketAddr) -> C,
+    shutdown: impl Future<Output = ()>,
+) where
+    S: Service<Request<Incoming>, Response = Response<B>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    S::Error: Into<BoxError>,
+    B: Body + Send + 'static,
+    B::Data: Send,
+    B::Error: Into<BoxError>,
+    C: Clone + Send + Sync + 'static,
+{
+    let acceptor = TlsAcceptor::from(config);
+    let connections = GracefulShutdown::new();
+    let builder = auto::Builder::new(TokioExecutor::new());
+    let mut shutdown = std::pin::pin!(shutdown);
+    loop {
+        let (stream, peer) = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    // Typically out of file descriptors; back off instead of spinning
+                    tracing::warn!(error = %e, "Failed to accept connection.");
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                    continue;
+                }
+            },
+            () = &mut shutdown => break,
+        };
+        let acceptor = acceptor.clone();
+        let app = app.clone();
+        let builder = builder.clone();
+        let watcher = connections.watcher();
+        tokio::spawn(async move {
+            let stream = match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                Ok(Ok(stream)) => stream,
+                Ok(Err(e)) => {
+                    tracing::debug!(peer = %peer, error = %e, "TLS handshake failed.");
+                    return;
+                }
+                Err(_) => return,
+            };
+            let service = hyper::service::service_fn(move |mut request: Request<Incoming>| {
+                request.extensions_mut().insert(connect_info(peer));
+                app.clone().oneshot(request)
+            });
+            let connection = builder.serve_connection_with_upgrades(TokioIo::new(stream), service);
+            if let Err(e) = watcher.watch(connection.into_owned()).await {
+                tracing::debug!(peer = %peer, error = %e, "Connection ended with an error.");
+            }
+        });
+    }
+    connections.shutdown().await;
+}